@@ -0,0 +1,125 @@
+use chrono::{DateTime, Utc};
+use std::cell::RefCell;
+use std::sync::Arc;
+
+/// Source of wall-clock time for timestamps written to the project
+/// (`created_at`, `updated_at`, task events, ...). Production code always
+/// uses `SystemClock`; tests swap in a `TestClock` with `freeze`.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Manually driven clock: time only moves via `set` / `advance_ms`.
+#[cfg(test)]
+pub struct TestClock {
+    now: std::sync::Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl TestClock {
+    pub fn new(start: DateTime<Utc>) -> Self {
+        Self { now: std::sync::Mutex::new(start) }
+    }
+
+    pub fn set(&self, t: DateTime<Utc>) {
+        *self.now.lock().unwrap() = t;
+    }
+
+    pub fn advance_ms(&self, ms: i64) {
+        let mut now = self.now.lock().unwrap();
+        *now += chrono::Duration::milliseconds(ms);
+    }
+}
+
+#[cfg(test)]
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+thread_local! {
+    // Per-thread so parallel tests can't see each other's frozen time.
+    static OVERRIDE: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+}
+
+pub fn now() -> DateTime<Utc> {
+    OVERRIDE
+        .with(|o| o.borrow().as_ref().map(|c| c.now()))
+        .unwrap_or_else(|| SystemClock.now())
+}
+
+/// `now()` formatted the way project.json stores timestamps.
+pub fn now_rfc3339() -> String {
+    now().to_rfc3339()
+}
+
+/// Use `clock` for `now()` on the current thread until the guard is dropped.
+#[cfg(test)]
+pub fn install(clock: Arc<dyn Clock>) -> ClockGuard {
+    let previous = OVERRIDE.with(|o| o.borrow_mut().replace(clock));
+    ClockGuard { previous }
+}
+
+/// Freeze the current thread's clock at `start`; returns the clock so the
+/// test can advance it, and a guard that restores the previous clock.
+#[cfg(test)]
+pub fn freeze(start: DateTime<Utc>) -> (Arc<TestClock>, ClockGuard) {
+    let clock = Arc::new(TestClock::new(start));
+    let guard = install(clock.clone());
+    (clock, guard)
+}
+
+#[cfg(test)]
+pub struct ClockGuard {
+    previous: Option<Arc<dyn Clock>>,
+}
+
+#[cfg(test)]
+impl Drop for ClockGuard {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        OVERRIDE.with(|o| *o.borrow_mut() = previous);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn t(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn frozen_clock_only_moves_when_advanced() {
+        let (clock, _guard) = freeze(t("2026-01-01T00:00:00Z"));
+        assert_eq!(now(), t("2026-01-01T00:00:00Z"));
+        assert_eq!(now(), t("2026-01-01T00:00:00Z"));
+        clock.advance_ms(1500);
+        assert_eq!(now_rfc3339(), "2026-01-01T00:00:01.500+00:00");
+        clock.set(t("2026-06-01T12:00:00Z"));
+        assert_eq!(now(), t("2026-06-01T12:00:00Z"));
+    }
+
+    #[test]
+    fn guard_restores_previous_clock() {
+        let (_outer, outer_guard) = freeze(t("2026-01-01T00:00:00Z"));
+        {
+            let (_inner, _inner_guard) = freeze(t("2030-01-01T00:00:00Z"));
+            assert_eq!(now(), t("2030-01-01T00:00:00Z"));
+        }
+        assert_eq!(now(), t("2026-01-01T00:00:00Z"));
+        drop(outer_guard);
+        let before = Utc::now();
+        assert!(now() >= before);
+    }
+}
@@ -0,0 +1,230 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Instant;
+use tokio::process::Command;
+
+/// Credential ref used (and removed again) by the secrets round-trip.
+const SELF_TEST_SECRET_REF: &str = "cutline_self_test";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CheckStatus {
+    Pass,
+    Fail,
+    /// Not applicable right now (e.g. no project open, or a prerequisite failed).
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CheckResult {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SelfTestReport {
+    /// True when no check failed; skipped checks don't count against it.
+    pub ok: bool,
+    pub checks: Vec<CheckResult>,
+}
+
+impl SelfTestReport {
+    fn new(checks: Vec<CheckResult>) -> Self {
+        let ok = checks.iter().all(|c| c.status != CheckStatus::Fail);
+        Self { ok, checks }
+    }
+}
+
+fn record(name: &str, started: Instant, result: Result<String, String>) -> CheckResult {
+    let (status, detail) = match result {
+        Ok(detail) => (CheckStatus::Pass, detail),
+        Err(detail) => (CheckStatus::Fail, detail),
+    };
+    CheckResult {
+        name: name.to_string(),
+        status,
+        detail,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+    }
+}
+
+fn skipped(name: &str, reason: &str) -> CheckResult {
+    CheckResult {
+        name: name.to_string(),
+        status: CheckStatus::Skip,
+        detail: reason.to_string(),
+        elapsed_ms: 0,
+    }
+}
+
+/// Run every environment check. `scratch_dir` is where temporary files go:
+/// the open project's cache dir, or `None` to skip the workspace check and
+/// use the system temp dir for the thumbnail check.
+pub async fn run_self_test(scratch_dir: Option<PathBuf>) -> SelfTestReport {
+    let mut checks = Vec::new();
+
+    let started = Instant::now();
+    let ffmpeg = tool_version("ffmpeg", crate::paths::ffmpeg()).await;
+    let ffmpeg_ok = ffmpeg.is_ok();
+    checks.push(record("ffmpeg", started, ffmpeg));
+
+    let started = Instant::now();
+    let ffprobe = tool_version("ffprobe", crate::paths::ffprobe()).await;
+    let ffprobe_ok = ffprobe.is_ok();
+    checks.push(record("ffprobe", started, ffprobe));
+
+    match &scratch_dir {
+        Some(dir) => {
+            let started = Instant::now();
+            checks.push(record("workspace", started, check_workspace(dir)));
+        }
+        None => checks.push(skipped("workspace", "no project open")),
+    }
+
+    let started = Instant::now();
+    checks.push(record("secrets", started, check_secrets()));
+
+    if ffmpeg_ok && ffprobe_ok {
+        let dir = scratch_dir.unwrap_or_else(std::env::temp_dir);
+        let started = Instant::now();
+        checks.push(record("thumbnail", started, check_thumbnail(&dir).await));
+    } else {
+        checks.push(skipped("thumbnail", "requires ffmpeg and ffprobe"));
+    }
+
+    SelfTestReport::new(checks)
+}
+
+/// First line of `<tool> -version`, e.g. "ffmpeg version 6.1.1 ...".
+async fn tool_version(tool: &str, bin: &Path) -> Result<String, String> {
+    let output = Command::new(bin)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| format!("{} not found ({}): {}", tool, bin.display(), e))?;
+    if !output.status.success() {
+        return Err(format!("{} -version exited {:?}", tool, output.status.code()));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().to_string())
+}
+
+fn check_workspace(dir: &Path) -> Result<String, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+    let probe = dir.join(format!(".cutline_self_test_{}", uuid::Uuid::new_v4()));
+    let payload = b"cutline self-test";
+    let result = std::fs::write(&probe, payload)
+        .map_err(|e| format!("cannot write {}: {}", probe.display(), e))
+        .and_then(|_| std::fs::read(&probe).map_err(|e| format!("cannot read {}: {}", probe.display(), e)));
+    let _ = std::fs::remove_file(&probe);
+    match result? {
+        read if read == payload => Ok(format!("{} is writable", dir.display())),
+        _ => Err(format!("{}: read back different content", probe.display())),
+    }
+}
+
+fn check_secrets() -> Result<String, String> {
+    let value = uuid::Uuid::new_v4().to_string();
+    crate::secrets::set_secret(SELF_TEST_SECRET_REF, &value)?;
+    let read = crate::secrets::get_secret(SELF_TEST_SECRET_REF);
+    crate::secrets::delete_secret(SELF_TEST_SECRET_REF)?;
+    if read? != Some(value) {
+        return Err("secret read back different value".to_string());
+    }
+    if crate::secrets::get_secret(SELF_TEST_SECRET_REF)?.is_some() {
+        return Err("secret still present after delete".to_string());
+    }
+    Ok("set/get/delete round-trip ok".to_string())
+}
+
+/// Render a 1s color-source clip, probe it, and grab a thumbnail from it —
+/// the same ffmpeg/ffprobe steps an imported video goes through.
+async fn check_thumbnail(dir: &Path) -> Result<String, String> {
+    let work = dir.join(format!("self_test_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]));
+    std::fs::create_dir_all(&work).map_err(|e| format!("cannot create {}: {}", work.display(), e))?;
+    let result = render_and_thumbnail(&work).await;
+    let _ = std::fs::remove_dir_all(&work);
+    result
+}
+
+async fn render_and_thumbnail(work: &Path) -> Result<String, String> {
+    let clip = work.join("color.mp4");
+    let thumb = work.join("thumb.jpg");
+    run_ffmpeg(&[
+        "-f", "lavfi",
+        "-i", "color=c=blue:s=64x64:d=1:r=24",
+        "-c:v", "libx264",
+        "-pix_fmt", "yuv420p",
+        &clip.to_string_lossy(),
+    ])
+    .await?;
+
+    let probe = crate::media::probe::ffprobe(&clip).map_err(|e| format!("ffprobe failed on the test clip: {}", e))?;
+    let meta = crate::media::probe::extract_video_meta(&probe);
+    let duration_ms = crate::project::model::meta_duration_ms(&meta).unwrap_or(0);
+    if duration_ms <= 0 {
+        return Err("ffprobe reported no duration for the test clip".to_string());
+    }
+
+    run_ffmpeg(&[
+        "-ss", "0.5",
+        "-i", &clip.to_string_lossy(),
+        "-vframes", "1",
+        &thumb.to_string_lossy(),
+    ])
+    .await?;
+    let size = std::fs::metadata(&thumb).map(|m| m.len()).unwrap_or(0);
+    if size == 0 {
+        return Err("ffmpeg wrote an empty thumbnail".to_string());
+    }
+    Ok(format!("{}ms clip -> {} byte thumbnail", duration_ms, size))
+}
+
+async fn run_ffmpeg(args: &[&str]) -> Result<(), String> {
+    let output = Command::new(crate::paths::ffmpeg())
+        .arg("-y")
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last_line = stderr.lines().last().unwrap_or_default();
+        return Err(format!("ffmpeg exited {:?}: {}", output.status.code(), last_line));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skipped_checks_do_not_fail_the_report() {
+        let pass = record("a", Instant::now(), Ok("fine".to_string()));
+        let report = SelfTestReport::new(vec![pass.clone(), skipped("b", "n/a")]);
+        assert!(report.ok);
+
+        let fail = record("c", Instant::now(), Err("broken".to_string()));
+        let report = SelfTestReport::new(vec![pass, fail]);
+        assert!(!report.ok);
+        assert_eq!(report.checks[1].status, CheckStatus::Fail);
+    }
+
+    #[test]
+    fn workspace_check_round_trips_and_cleans_up() {
+        let dir = std::env::temp_dir().join(format!("cutline_selftest_{}", uuid::Uuid::new_v4()));
+        assert!(check_workspace(&dir).is_ok());
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
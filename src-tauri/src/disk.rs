@@ -0,0 +1,107 @@
+//! Free-space checks, so a long export or download fails up front with the
+//! numbers instead of midway with an opaque IO error.
+
+use std::path::Path;
+
+use crate::project::model::TaskError;
+
+/// Room left free on top of an estimate (temp files, the project save, the OS).
+pub const HEADROOM_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Bytes available to us on the volume holding `path`. `path` need not
+/// exist yet; its nearest existing ancestor is measured.
+pub fn disk_free(path: &Path) -> Result<u64, String> {
+    let dir = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| format!("No existing directory above {}", path.display()))?;
+    query_free(dir)
+}
+
+#[cfg(unix)]
+fn query_free(dir: &Path) -> Result<u64, String> {
+    let output = std::process::Command::new("df")
+        .arg("-Pk")
+        .arg(dir)
+        .output()
+        .map_err(|e| format!("Failed to run df: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("df failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    parse_df_available(&String::from_utf8_lossy(&output.stdout))
+        .ok_or_else(|| "Unexpected df output".to_string())
+}
+
+/// Honors per-user quotas, like `df`'s `Available` column.
+#[cfg(windows)]
+fn query_free(dir: &Path) -> Result<u64, String> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = dir.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated and outlives the call; the totals we
+    // don't need may be null.
+    let ok = unsafe { GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut()) };
+    if ok == 0 {
+        return Err(format!("GetDiskFreeSpaceExW failed: {}", std::io::Error::last_os_error()));
+    }
+    Ok(available)
+}
+
+/// `Available` column of POSIX `df -Pk` output, in bytes.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn parse_df_available(stdout: &str) -> Option<u64> {
+    let line = stdout.lines().nth(1)?;
+    let kib: u64 = line.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Refuse with `insufficient_disk_space` unless `dir`'s volume can take
+/// `needed` bytes plus `HEADROOM_BYTES`. When free space can't be measured
+/// the write goes ahead; the check only exists to fail earlier.
+pub fn ensure_space(dir: &Path, needed: u64) -> Result<(), TaskError> {
+    let available = match disk_free(dir) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::warn!("Skipping free space check for {}: {}", dir.display(), e);
+            return Ok(());
+        }
+    };
+    let required = needed.saturating_add(HEADROOM_BYTES);
+    if available < required {
+        return Err(TaskError::new(
+            "insufficient_disk_space",
+            format!(
+                "Not enough disk space in {}: need about {} bytes, {} available",
+                dir.display(),
+                required,
+                available
+            ),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_available_column_of_df() {
+        let out = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                   /dev/sda1        102400000  40960000  61440000      40% /\n";
+        assert_eq!(parse_df_available(out), Some(61_440_000 * 1024));
+        assert_eq!(parse_df_available("Filesystem\n"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn measures_nearest_existing_dir_and_refuses_impossible_sizes() {
+        let missing = std::env::temp_dir().join(format!("cutline_disk_{}", uuid::Uuid::new_v4())).join("out.mp4");
+        assert!(disk_free(&missing).unwrap() > 0);
+
+        let err = ensure_space(&std::env::temp_dir(), u64::MAX / 2).unwrap_err();
+        assert_eq!(err.code, "insufficient_disk_space");
+    }
+}
@@ -1,5 +1,9 @@
 mod asset;
+mod clock;
+mod diagnostics;
+mod disk;
 mod media;
+mod paths;
 mod project;
 mod provider;
 mod providers;
@@ -8,12 +12,12 @@ mod state;
 mod task;
 
 use project::model::{
-    Asset, Clip, DraftTrackIds, Indexes, Marker, ProjectFile, ProjectMeta, ProjectPaths,
+    snap_to_frame, Asset, Clip, DraftTrackIds, Indexes, Marker, ProjectFile, ProjectMeta, ProjectPaths,
     ProjectSettings, Resolution, Task, TaskError, TaskEvent, TaskRetries, Timeline, Timebase, Track,
 };
+use project::layout::{WorkspaceLayout, WorkspaceSubdir};
 use state::{AppState, LoadedProject};
 use std::collections::HashMap;
-use tauri::Manager;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tauri::Emitter;
@@ -34,13 +38,11 @@ async fn create_project(
             .map_err(|e| format!("创建项目目录失败: {}", e))?;
     }
 
-    project::io::ensure_workspace_dirs(&project_dir)?;
-
     let timeline_id = format!("tl_{}", uuid::Uuid::new_v4());
     let video_track_id = format!("trk_v_{}", uuid::Uuid::new_v4());
     let audio_track_id = format!("trk_a_{}", uuid::Uuid::new_v4());
     let text_track_id = format!("trk_t_{}", uuid::Uuid::new_v4());
-    let now = chrono::Utc::now().to_rfc3339();
+    let now = crate::clock::now_rfc3339();
 
     let pf = ProjectFile {
         schema_version: "0.2".to_string(),
@@ -58,6 +60,15 @@ async fn create_project(
                 aspect_ratio: "16:9".to_string(),
                 sample_rate: 48000,
                 generation: None,
+                snap_to_frame: false,
+                save_debounce_ms: None,
+                max_task_events: None,
+                compress_on_disk: None,
+                import_defaults: None,
+                scale_mode: None,
+                still_duration_ms: None,
+                proxy_keyframe_interval_ms: None,
+                limits: None,
             },
             paths: ProjectPaths {
                 workspace_root: "./workspace".to_string(),
@@ -71,6 +82,7 @@ async fn create_project(
                 audio: audio_track_id.clone(),
                 text: text_track_id.clone(),
             },
+            view_state: None,
         },
         assets: vec![],
         tasks: vec![],
@@ -112,16 +124,29 @@ async fn create_project(
         },
     };
 
+    let workspace_dir =
+        project::io::resolve_workspace_root(&project_dir, &pf.project.paths.workspace_root);
+    project::io::ensure_workspace_dirs(&WorkspaceLayout::new(
+        &project_dir,
+        &workspace_dir,
+        &pf.project.paths,
+    ))?;
+
     let project_json_path = project_dir.join("project.json");
+    let lock = project::lock::acquire_guarded(&project_json_path, false)?;
     project::io::write_project_atomic(&project_json_path, &pf)?;
+    lock.keep();
 
     // Load into AppState
+    state.apply_settings(&pf.project.settings);
     let mut guard = state.inner.lock().await;
+    release_previous(&guard, &project_json_path);
     *guard = Some(LoadedProject {
         project: pf.clone(),
         json_path: project_json_path,
         project_dir,
         dirty: false,
+        read_only: false,
     });
 
     Ok(pf)
@@ -130,28 +155,84 @@ async fn create_project(
 #[tauri::command]
 async fn open_project(
     project_json_path: String,
+    force: Option<bool>,
+    read_only: Option<bool>,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<ProjectFile, String> {
-    let path = PathBuf::from(&project_json_path);
-    let mut pf = project::io::read_project(&path)?;
+) -> Result<OpenedProject, String> {
+    // Compressed projects may be opened via project.json.gz.
+    let path = project::io::logical_json_path(Path::new(&project_json_path));
+    open_project_at(&path, &path, force.unwrap_or(false), read_only.unwrap_or(false), &state).await
+}
 
-    // Crash recovery: mark running tasks as failed
-    let now = chrono::Utc::now().to_rfc3339();
-    for task in &mut pf.tasks {
-        if task.state == "running" {
-            task.state = "failed".to_string();
-            task.updated_at = now.clone();
-            task.error = Some(TaskError {
-                code: "crash_recovered".to_string(),
-                message: "Task was running when app exited.".to_string(),
-                detail: None,
-            });
-            task.events.push(TaskEvent {
-                t: now.clone(),
-                level: "warn".to_string(),
-                msg: "crash_recovered: task was running when app exited".to_string(),
-            });
+/// Tasks `open_project` had to fail because the app exited while they ran.
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RecoverySummary {
+    crash_recovered_task_ids: Vec<String>,
+}
+
+/// The opened project plus a `recovery` field, so callers that only read
+/// the project fields are unaffected.
+#[derive(serde::Serialize)]
+struct OpenedProject {
+    #[serde(flatten)]
+    project: ProjectFile,
+    recovery: RecoverySummary,
+}
+
+/// Open the project in a folder the user picked, restoring the newest
+/// backup when the project file itself is gone. A read-only open inspects
+/// the backup without restoring it.
+#[tauri::command]
+async fn open_project_dir(
+    dir_path: String,
+    force: Option<bool>,
+    read_only: Option<bool>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<OpenedProject, String> {
+    let (force, read_only) = (force.unwrap_or(false), read_only.unwrap_or(false));
+    match project::io::find_project_in_dir(Path::new(&dir_path))? {
+        project::io::FoundProject::Existing(path) => open_project_at(&path, &path, force, read_only, &state).await,
+        project::io::FoundProject::Backup { json_path, backup } if read_only => {
+            open_project_at(&json_path, &backup, force, read_only, &state).await
         }
+        project::io::FoundProject::Backup { json_path, backup } => {
+            log::warn!("{} 不存在，从备份恢复: {}", json_path.display(), backup.display());
+            project::io::restore_backup(&json_path, &backup)?;
+            open_project_at(&json_path, &json_path, force, read_only, &state).await
+        }
+    }
+}
+
+/// Load the project stored at `source` as the project at `path` (they differ
+/// only when inspecting a backup). Read-only opens take no lock and write
+/// nothing back.
+async fn open_project_at(
+    path: &Path,
+    source: &Path,
+    force: bool,
+    read_only: bool,
+    state: &Arc<AppState>,
+) -> Result<OpenedProject, String> {
+    let path = path.to_path_buf();
+    // Released again if anything below fails
+    let lock = if read_only { None } else { Some(project::lock::acquire_guarded(&path, force)?) };
+    // Only the lock holder may finish an interrupted save
+    let mut pf = if read_only {
+        project::io::read_project_read_only(source)?
+    } else {
+        project::io::read_project(source)?
+    };
+
+    let migrated = pf.migrate_asset_durations();
+    if migrated > 0 {
+        log::info!("Migrated durationSec -> durationMs on {} asset(s)", migrated);
+    }
+
+    // Crash recovery: mark running tasks as failed
+    let crash_recovered_task_ids = pf.recover_crashed_tasks();
+    if !crash_recovered_task_ids.is_empty() {
+        log::warn!("Marked {} interrupted task(s) as crash_recovered", crash_recovered_task_ids.len());
     }
 
     let project_dir = path
@@ -159,33 +240,122 @@ async fn open_project(
         .ok_or("无法获取项目目录")?
         .to_path_buf();
 
-    // Ensure cache dirs exist
-    project::io::ensure_workspace_dirs(&project_dir)?;
+    // An absolute workspace root must already exist; relative ones are created.
+    let workspace_dir =
+        project::io::resolve_workspace_root(&project_dir, &pf.project.paths.workspace_root);
+    if Path::new(&pf.project.paths.workspace_root).is_absolute() {
+        project::io::validate_workspace_root(&workspace_dir)?;
+    }
 
-    // Save crash recovery changes
     pf.rebuild_indexes();
-    project::io::write_project_atomic(&path, &pf)?;
+    if !read_only {
+        // Ensure cache dirs exist
+        project::io::ensure_workspace_dirs(&WorkspaceLayout::new(
+            &project_dir,
+            &workspace_dir,
+            &pf.project.paths,
+        ))?;
+
+        // Save crash recovery changes
+        project::io::write_project_atomic(&path, &pf)?;
+    }
+    if let Some(lock) = lock {
+        lock.keep();
+    }
 
     // Load into AppState
+    state.apply_settings(&pf.project.settings);
     let mut guard = state.inner.lock().await;
+    release_previous(&guard, &path);
+    if read_only {
+        // Reopening our own project for inspection gives up its lock
+        project::lock::release(&path);
+    }
     *guard = Some(LoadedProject {
         project: pf.clone(),
         json_path: path,
         project_dir,
         dirty: false,
+        read_only,
     });
 
+    Ok(OpenedProject {
+        project: pf,
+        recovery: RecoverySummary { crash_recovered_task_ids },
+    })
+}
+
+/// Drop the lock on the project being replaced in AppState, if it's a different file.
+fn release_previous(current: &Option<LoadedProject>, next_json_path: &Path) {
+    if let Some(prev) = current {
+        if prev.json_path != next_json_path {
+            project::lock::release(&prev.json_path);
+        }
+    }
+}
+
+/// Re-read project.json from disk, replacing the in-memory project. Refuses
+/// when there are unsaved edits unless `discard_unsaved` is set.
+#[tauri::command]
+async fn reload_project(
+    discard_unsaved: Option<bool>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<ProjectFile, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+
+    if loaded.dirty {
+        if !discard_unsaved.unwrap_or(false) {
+            return Err("unsaved_changes: 项目有未保存的修改，重新加载会丢弃这些修改".to_string());
+        }
+        log::warn!("Reloading {:?}, discarding unsaved edits", loaded.json_path);
+    }
+
+    let mut pf = if loaded.read_only {
+        project::io::read_project_read_only(&loaded.json_path)?
+    } else {
+        project::io::read_project(&loaded.json_path)?
+    };
+    let migrated = pf.migrate_asset_durations();
+    pf.rebuild_indexes();
+    state.apply_settings(&pf.project.settings);
+    loaded.project = pf.clone();
+    loaded.dirty = migrated > 0 && !loaded.read_only;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+
     Ok(pf)
 }
 
+#[tauri::command]
+async fn close_project(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    // Save while the project is still loaded, so a failed write keeps it
+    // (and its lock) instead of dropping unsaved edits.
+    if let Some(loaded) = guard.as_mut().filter(|l| l.dirty) {
+        loaded.project.rebuild_indexes();
+        loaded.project.project.updated_at = crate::clock::now_rfc3339();
+        project::io::write_project_atomic(&loaded.json_path, &loaded.project)?;
+        loaded.dirty = false;
+    }
+    if let Some(loaded) = guard.take() {
+        project::lock::release(&loaded.json_path);
+    }
+    Ok(())
+}
+
 #[tauri::command]
 async fn save_project(
     state: tauri::State<'_, Arc<AppState>>,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
     loaded.project.rebuild_indexes();
-    loaded.project.project.updated_at = chrono::Utc::now().to_rfc3339();
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
     project::io::write_project_atomic(&loaded.json_path, &loaded.project)?;
     loaded.dirty = false;
     Ok(())
@@ -200,152 +370,336 @@ async fn get_project(
     Ok(loaded.project.clone())
 }
 
+#[tauri::command]
+async fn project_search(
+    query: project::query::ProjectQuery,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<project::query::SearchResults, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    Ok(project::query::search(&loaded.project, &query))
+}
+
+/// Clips and tasks that reference `asset_id`. Read-only.
+#[tauri::command]
+async fn asset_usages(
+    asset_id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<project::query::AssetUsages, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    project::query::asset_usages(&loaded.project, &asset_id)
+}
+
+#[tauri::command]
+async fn project_diff(
+    other_json_path: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<project::diff::ProjectDiff, String> {
+    let other = project::io::read_project_read_only(Path::new(&other_json_path))?;
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    Ok(project::diff::diff_projects(&loaded.project, &other))
+}
+
+/// What happened to one path passed to `import_assets`.
+#[derive(serde::Serialize)]
+#[serde(tag = "status", rename_all = "camelCase", rename_all_fields = "camelCase")]
+enum ImportOutcome {
+    Imported { asset: Box<Asset> },
+    Duplicate { duplicate_of: String },
+    Error { error: String },
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportResult {
+    path: String,
+    #[serde(flatten)]
+    outcome: ImportOutcome,
+}
+
+/// Import each file independently: a missing or unreadable file is reported
+/// in its own result and doesn't stop the rest of the batch. Emits
+/// `import:progress` `{ done, total, currentFile }` before each file and once
+/// more (`currentFile: null`) when the batch is finished. `options` picks the
+/// background tasks enqueued per asset, defaulting to the project's settings.
 #[tauri::command]
 async fn import_assets(
     file_paths: Vec<String>,
+    options: Option<project::model::ImportOverrides>,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<Vec<Asset>, String> {
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<ImportResult>, String> {
+    let (layout, options) = {
+        let mut guard = state.inner.lock().await;
+        // Checked up front so nothing is copied into a read-only workspace
+        let loaded = writable_project(&mut guard)?;
+        let defaults = loaded.project.project.settings.effective_import_options();
+        (loaded.layout(), options.unwrap_or_default().apply(defaults))
+    };
+
+    let total = file_paths.len();
+    let mut results = Vec::with_capacity(total);
+    for (done, file_path_str) in file_paths.into_iter().enumerate() {
+        let _ = app_handle.emit("import:progress", serde_json::json!({
+            "done": done,
+            "total": total,
+            "currentFile": file_path_str,
+        }));
+        let outcome = match import_one(&state, &layout, &file_path_str, options, &app_handle).await {
+            Ok(outcome) => outcome,
+            Err(error) => {
+                log::warn!("Import of {} failed: {}", file_path_str, error);
+                ImportOutcome::Error { error }
+            }
+        };
+        results.push(ImportResult { path: file_path_str, outcome });
+    }
+    let _ = app_handle.emit("import:progress", serde_json::json!({
+        "done": total,
+        "total": total,
+        "currentFile": null,
+    }));
+
+    let imported = results
+        .iter()
+        .any(|r| matches!(r.outcome, ImportOutcome::Imported { .. }));
+    if imported {
+        let mut guard = state.inner.lock().await;
+        let loaded = writable_project(&mut guard)?;
+        loaded.project.rebuild_indexes();
+        loaded.project.project.updated_at = crate::clock::now_rfc3339();
+        loaded.dirty = true;
+
+        // Save immediately after import
+        project::io::write_project_compact(&loaded.json_path, &loaded.project)?;
+        loaded.dirty = false;
+
+        // Notify task runner
+        drop(guard);
+        state.task_notify.notify_one();
+    }
+
+    Ok(results)
+}
+
+/// Import one file. Fingerprinting, copying and probing run on the blocking
+/// pool without holding the project lock, which is only taken briefly to
+/// check for duplicates and to register the asset.
+async fn import_one(
+    state: &Arc<AppState>,
+    layout: &WorkspaceLayout,
+    file_path_str: &str,
+    options: project::model::ImportOptions,
+    app_handle: &tauri::AppHandle,
+) -> Result<ImportOutcome, String> {
+    let source_path = PathBuf::from(file_path_str);
+    if !source_path.exists() {
+        return Err(format!("文件不存在: {}", file_path_str));
+    }
+
+    let fp = {
+        let source_path = source_path.clone();
+        tokio::task::spawn_blocking(move || asset::fingerprint::compute_file_fingerprint(&source_path))
+            .await
+            .map_err(|e| format!("导入任务失败: {}", e))??
+    };
+    if let Some(duplicate_of) = find_duplicate_asset(state, &fp.value).await? {
+        return Ok(ImportOutcome::Duplicate { duplicate_of });
+    }
+
+    let staged = {
+        let layout = layout.clone();
+        tokio::task::spawn_blocking(move || stage_import(&layout, &source_path, fp))
+            .await
+            .map_err(|e| format!("导入任务失败: {}", e))??
+    };
+
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
+    // The same content may have been imported while we were copying.
+    if let Some(existing) = asset::registry::find_duplicate(&loaded.project.assets, &staged.asset.fingerprint.value) {
+        return Ok(ImportOutcome::Duplicate { duplicate_of: existing.asset_id.clone() });
+    }
+    Ok(register_import(loaded, staged, options, app_handle))
+}
+
+async fn find_duplicate_asset(state: &Arc<AppState>, fingerprint: &str) -> Result<Option<String>, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    Ok(asset::registry::find_duplicate(&loaded.project.assets, fingerprint).map(|a| a.asset_id.clone()))
+}
 
-    let mut new_assets: Vec<Asset> = Vec::new();
-    let mut thumb_tasks: Vec<(String, String)> = Vec::new(); // (taskId, assetId)
+/// An imported file copied into the workspace and probed, not yet registered.
+struct StagedImport {
+    asset: Asset,
+    probeable: bool,
+    /// Removes the workspace copy unless the asset gets registered.
+    copy_guard: Option<task::handlers::TempFileGuard>,
+}
 
-    for file_path_str in &file_paths {
-        let source_path = PathBuf::from(file_path_str);
-        if !source_path.exists() {
-            return Err(format!("文件不存在: {}", file_path_str));
-        }
+/// Copy `source_path` into the workspace and build its asset record.
+fn stage_import(
+    layout: &WorkspaceLayout,
+    source_path: &Path,
+    fp: project::model::Fingerprint,
+) -> Result<StagedImport, String> {
+    let asset_type = guess_asset_type(source_path);
+    let sub_dir = WorkspaceSubdir::for_asset_type(&asset_type);
+
+    let file_name = source_path
+        .file_name()
+        .ok_or("无法获取文件名")?
+        .to_string_lossy()
+        .to_string();
+
+    let dest_dir = layout.dir(sub_dir);
+    std::fs::create_dir_all(&dest_dir)
+        .map_err(|e| format!("创建目录失败: {}", e))?;
 
-        let fp = asset::fingerprint::compute_file_fingerprint(&source_path)?;
+    let dest_path = dest_dir.join(&file_name);
 
-        if asset::registry::find_duplicate(&loaded.project.assets, &fp.value).is_some() {
-            continue;
+    let mut copy_guard = None;
+    if !dest_path.exists() {
+        std::fs::copy(source_path, &dest_path)
+            .map_err(|e| format!("复制文件失败: {}", e))?;
+        copy_guard = Some(task::handlers::TempFileGuard::new(dest_path.clone()));
+    }
+
+    let relative_path = layout.stored_path(sub_dir, &file_name);
+
+    let mut meta = match asset_type.as_str() {
+        "video" | "audio" => match media::probe::ffprobe(&dest_path) {
+            Ok(probe_data) => media::probe::extract_video_meta(&probe_data),
+            Err(_) => serde_json::json!({ "kind": asset_type }),
+        },
+        "image" => media::probe::extract_image_meta(&dest_path),
+        "subtitle" => {
+            let content = media::subtitle::read_lossy(&dest_path)
+                .map_err(|e| format!("读取字幕失败: {}", e))?;
+            let ext = dest_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+            let format = media::subtitle::format_for_extension(&ext).unwrap_or("srt");
+            media::subtitle::extract_subtitle_meta(&content, format)
         }
+        _ => serde_json::json!({ "kind": "unknown" }),
+    };
 
-        let asset_type = guess_asset_type(&source_path);
-        let sub_dir = match asset_type.as_str() {
-            "video" => "workspace/assets/video",
-            "audio" => "workspace/assets/audio",
-            "image" => "workspace/assets/images",
-            _ => "workspace/assets/video",
-        };
+    // Keep unprobeable files (so the user sees them) but tag them so
+    // they can't be placed on the timeline or fed to media tasks.
+    let mut tags = vec!["source".to_string()];
+    let probe_error = media::probe::check_usable_meta(&meta).err();
+    if let Some(reason) = &probe_error {
+        log::warn!("Imported {} is unprobeable: {}", file_name, reason);
+        tags.push(media::probe::UNPROBEABLE_TAG.to_string());
+        if let Some(obj) = meta.as_object_mut() {
+            obj.insert("probeError".to_string(), serde_json::json!(reason));
+        }
+    }
 
-        let file_name = source_path
-            .file_name()
-            .ok_or("无法获取文件名")?
-            .to_string_lossy()
-            .to_string();
+    let asset_id = format!(
+        "ast_{}_{}",
+        asset_type,
+        &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
+    );
 
-        let dest_dir = loaded.project_dir.join(sub_dir);
-        std::fs::create_dir_all(&dest_dir)
-            .map_err(|e| format!("创建目录失败: {}", e))?;
+    let asset = Asset {
+        asset_id,
+        asset_type,
+        source: "uploaded".to_string(),
+        fingerprint: fp,
+        path: relative_path,
+        meta,
+        generation: None,
+        tags,
+        created_at: crate::clock::now_rfc3339(),
+    };
+    Ok(StagedImport { asset, probeable: probe_error.is_none(), copy_guard })
+}
 
-        let dest_path = dest_dir.join(&file_name);
+/// Register a staged asset and enqueue the thumb/proxy/waveform tasks `options` asks for.
+fn register_import(
+    loaded: &mut LoadedProject,
+    staged: StagedImport,
+    options: project::model::ImportOptions,
+    app_handle: &tauri::AppHandle,
+) -> ImportOutcome {
+    let StagedImport { asset, probeable, copy_guard } = staged;
+    loaded.project.assets.push(asset.clone());
+    if let Some(mut guard) = copy_guard {
+        guard.disarm();
+    }
 
-        if !dest_path.exists() {
-            std::fs::copy(&source_path, &dest_path)
-                .map_err(|e| format!("复制文件失败: {}", e))?;
-        }
+    if !probeable || options.probe_only {
+        return ImportOutcome::Imported { asset: Box::new(asset) };
+    }
 
-        let relative_path = format!("{}/{}", sub_dir, file_name);
+    let asset_id = &asset.asset_id;
+    let asset_type = asset.asset_type.as_str();
 
-        let meta = match asset_type.as_str() {
-            "video" | "audio" => match media::probe::ffprobe(&dest_path) {
-                Ok(probe_data) => media::probe::extract_video_meta(&probe_data),
-                Err(_) => serde_json::json!({ "kind": asset_type }),
-            },
-            "image" => media::probe::extract_image_meta(&dest_path),
-            _ => serde_json::json!({ "kind": "unknown" }),
-        };
+    // Auto-enqueue thumb task for video/image
+    let mut tasks = Vec::new();
+    let mut thumb_deps = Vec::new();
+    if options.generate_thumb && (asset_type == "video" || asset_type == "image") {
+        let thumb_task = auto_task("thumb", asset_id, vec![], "import");
+        thumb_deps.push(thumb_task.task_id.clone());
+        tasks.push(thumb_task);
+    }
 
-        let asset_id = format!(
-            "ast_{}_{}",
-            asset_type,
-            &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
-        );
-
-        let asset = Asset {
-            asset_id: asset_id.clone(),
-            asset_type: asset_type.clone(),
-            source: "uploaded".to_string(),
-            fingerprint: fp,
-            path: relative_path,
-            meta,
-            generation: None,
-            tags: vec!["source".to_string()],
-            created_at: chrono::Utc::now().to_rfc3339(),
-        };
+    // Video proxies wait on the thumb; audio gets a lightweight preview proxy
+    if options.generate_proxy && (asset_type == "video" || asset_type == "audio") {
+        tasks.push(auto_task("proxy", asset_id, thumb_deps, "import"));
+    }
 
-        loaded.project.assets.push(asset.clone());
-        new_assets.push(asset);
+    if options.generate_waveform && has_audio(&asset) {
+        tasks.push(auto_task("waveform", asset_id, vec![], "import"));
+    }
 
-        // Auto-enqueue thumb task for video/image
-        if asset_type == "video" || asset_type == "image" {
-            let now = chrono::Utc::now().to_rfc3339();
-            let thumb_task_id = format!("task_thumb_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]);
-            let thumb_task = Task {
-                task_id: thumb_task_id.clone(),
-                kind: "thumb".to_string(),
-                state: "queued".to_string(),
-                created_at: now.clone(),
-                updated_at: now.clone(),
-                input: serde_json::json!({ "assetId": asset_id }),
-                output: None,
-                progress: None,
-                error: None,
-                retries: TaskRetries { count: 0, max: 3 },
-                deps: vec![],
-                events: vec![TaskEvent {
-                    t: now.clone(),
-                    level: "info".to_string(),
-                    msg: "Task enqueued (auto: import)".to_string(),
-                }],
-                dedupe_key: Some(format!("thumb:{}", asset_id)),
-            };
-            loaded.project.tasks.push(thumb_task);
-            thumb_tasks.push((thumb_task_id.clone(), asset_id.clone()));
-
-            // Auto-enqueue proxy task for video (depends on thumb)
-            if asset_type == "video" {
-                let proxy_task_id = format!("task_proxy_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]);
-                let proxy_task = Task {
-                    task_id: proxy_task_id,
-                    kind: "proxy".to_string(),
-                    state: "queued".to_string(),
-                    created_at: now.clone(),
-                    updated_at: now.clone(),
-                    input: serde_json::json!({ "assetId": asset_id }),
-                    output: None,
-                    progress: None,
-                    error: None,
-                    retries: TaskRetries { count: 0, max: 3 },
-                    deps: vec![thumb_task_id],
-                    events: vec![TaskEvent {
-                        t: now,
-                        level: "info".to_string(),
-                        msg: "Task enqueued (auto: import)".to_string(),
-                    }],
-                    dedupe_key: Some(format!("proxy:{}", asset_id)),
-                };
-                loaded.project.tasks.push(proxy_task);
-            }
-        }
+    // Needed for playback at all, so not subject to the proxy option
+    if asset_type == "video" && media::protocol::needs_remux(&asset.path) {
+        tasks.push(auto_task("remux", asset_id, vec![], "import"));
     }
 
-    loaded.project.rebuild_indexes();
-    loaded.project.project.updated_at = chrono::Utc::now().to_rfc3339();
-    loaded.dirty = true;
+    // Over the task limit the asset is still imported; rebuild_caches can
+    // queue its artifacts later.
+    match admit_tasks(loaded, tasks.len(), app_handle) {
+        Ok(()) => loaded.project.tasks.extend(tasks),
+        Err(e) => log::warn!("Not enqueueing import tasks for {}: {}", asset_id, e),
+    }
 
-    // Save immediately after import
-    project::io::write_project_atomic(&loaded.json_path, &loaded.project)?;
-    loaded.dirty = false;
+    ImportOutcome::Imported { asset: Box::new(asset) }
+}
 
-    // Notify task runner
-    drop(guard);
-    state.task_notify.notify_one();
+fn has_audio(asset: &Asset) -> bool {
+    asset.asset_type == "audio"
+        || (asset.asset_type == "video" && asset.meta.pointer("/audio/present").and_then(|v| v.as_bool()).unwrap_or(false))
+}
 
-    Ok(new_assets)
+/// A queued per-asset task enqueued on the user's behalf; `origin` names the
+/// command that did it (`import`, `contact sheet`).
+fn auto_task(kind: &str, asset_id: &str, deps: Vec<String>, origin: &str) -> Task {
+    let now = crate::clock::now_rfc3339();
+    Task {
+        task_id: format!("task_{}_{}", kind, &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]),
+        kind: kind.to_string(),
+        state: "queued".to_string(),
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        input: serde_json::json!({ "assetId": asset_id }),
+        output: None,
+        progress: None,
+        error: None,
+        retries: TaskRetries { count: 0, max: 3 },
+        deps,
+        events: vec![TaskEvent {
+            t: now,
+            level: "info".to_string(),
+            msg: format!("Task enqueued (auto: {})", origin),
+        }],
+        dedupe_key: Some(format!("{}:{}", kind, asset_id)),
+        started_at: None,
+        finished_at: None,
+    }
 }
 
 #[tauri::command]
@@ -359,6 +713,23 @@ fn probe_media(file_path: String) -> Result<serde_json::Value, String> {
 // File Access
 // ============================================================
 
+/// Replace the origins allowed to read `media://` responses. `"*"` allows any.
+#[tauri::command]
+fn set_media_allowed_origins(
+    origins: Vec<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    if origins.iter().any(|o| o.trim().is_empty()) {
+        return Err("origin cannot be empty".to_string());
+    }
+    let mut guard = state
+        .media_origins
+        .write()
+        .map_err(|_| "media origins lock poisoned".to_string())?;
+    *guard = origins;
+    Ok(())
+}
+
 #[tauri::command]
 async fn read_file_base64(
     relative_path: String,
@@ -366,14 +737,78 @@ async fn read_file_base64(
 ) -> Result<String, String> {
     let guard = state.inner.lock().await;
     let loaded = guard.as_ref().ok_or("没有打开的项目")?;
-    let abs_path = loaded.project_dir.join(&relative_path);
+    let abs_path = loaded.resolve_path(&relative_path);
     drop(guard);
 
-    let bytes = std::fs::read(&abs_path)
+    let bytes = state
+        .artifact_cache
+        .read(&abs_path)
         .map_err(|e| format!("读取文件失败 {}: {}", abs_path.display(), e))?;
 
     use base64::Engine;
-    Ok(base64::engine::general_purpose::STANDARD.encode(&bytes))
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes.as_slice()))
+}
+
+/// Hit/miss counters of the in-memory artifact cache, for debugging.
+#[tauri::command]
+fn artifact_cache_stats(state: tauri::State<'_, Arc<AppState>>) -> media::cache::CacheStats {
+    state.artifact_cache.stats()
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AssetPaths {
+    path: String,
+    proxy_path: Option<String>,
+    thumb_path: Option<String>,
+    playable_path: Option<String>,
+}
+
+/// Absolute, canonical on-disk paths for an asset and its derived files,
+/// for handing to the opener plugin ("reveal in file manager").
+#[tauri::command]
+async fn asset_abs_path(
+    asset_id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<AssetPaths, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    let asset = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .ok_or(format!("Asset not found: {}", asset_id))?;
+
+    // The cache/exports dirs may be configured outside the workspace.
+    let workspace_dir = loaded.workspace_dir();
+    let roots = loaded.layout().roots();
+    let resolve = |rel: &str| -> Result<String, String> {
+        let path = loaded.resolve_path(rel);
+        let mut result = project::io::canonicalize_within(&path, &workspace_dir);
+        for root in &roots {
+            if result.is_ok() {
+                break;
+            }
+            result = project::io::canonicalize_within(&path, root);
+        }
+        result.map(|p| p.to_string_lossy().to_string())
+    };
+    // Derived files may not exist yet (task pending); report them as absent.
+    let derived = |key: &str| {
+        asset
+            .meta
+            .get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|rel| resolve(rel).ok())
+    };
+
+    Ok(AssetPaths {
+        path: resolve(&asset.path)?,
+        proxy_path: derived("proxyUri"),
+        thumb_path: derived("thumbUri"),
+        playable_path: derived("playableUri"),
+    })
 }
 
 // ============================================================
@@ -387,9 +822,10 @@ async fn task_enqueue(
     deps: Option<Vec<String>>,
     dedupe_key: Option<String>,
     state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
 ) -> Result<String, String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
     // Check deduplication
     if let Some(ref dk) = dedupe_key {
@@ -401,7 +837,7 @@ async fn task_enqueue(
         }
     }
 
-    let now = chrono::Utc::now().to_rfc3339();
+    let now = crate::clock::now_rfc3339();
     let task_id = format!(
         "task_{}_{}",
         kind,
@@ -426,8 +862,11 @@ async fn task_enqueue(
             msg: "Task enqueued".to_string(),
         }],
         dedupe_key,
+        started_at: None,
+        finished_at: None,
     };
 
+    admit_tasks(loaded, 1, &app_handle)?;
     loaded.project.tasks.push(task);
     loaded.project.rebuild_indexes();
     loaded.dirty = true;
@@ -446,7 +885,8 @@ async fn task_retry(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
+    let cap = loaded.project.project.settings.task_event_cap();
 
     let task = loaded
         .project
@@ -460,11 +900,13 @@ async fn task_retry(
     }
 
     task.state = "queued".to_string();
-    task.updated_at = chrono::Utc::now().to_rfc3339();
+    task.updated_at = crate::clock::now_rfc3339();
     task.retries.count += 1;
     task.error = None;
     task.progress = None;
-    task.append_event("info", &format!("Task retried (attempt #{})", task.retries.count));
+    task.started_at = None;
+    task.finished_at = None;
+    task.append_event("info", &format!("Task retried (attempt #{})", task.retries.count), cap);
 
     let snapshot = task.clone();
     loaded.dirty = true;
@@ -484,7 +926,8 @@ async fn task_cancel(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
+    let cap = loaded.project.project.settings.task_event_cap();
 
     let task = loaded
         .project
@@ -495,9 +938,11 @@ async fn task_cancel(
 
     match task.state.as_str() {
         "queued" => {
+            let now = crate::clock::now_rfc3339();
             task.state = "canceled".to_string();
-            task.updated_at = chrono::Utc::now().to_rfc3339();
-            task.append_event("warn", "Task canceled (was queued)");
+            task.updated_at = now.clone();
+            task.finished_at = Some(now);
+            task.append_event("warn", "Task canceled (was queued)", cap);
             let snapshot = task.clone();
             loaded.dirty = true;
             drop(guard);
@@ -518,30 +963,329 @@ async fn task_cancel(
     Ok(())
 }
 
-#[derive(serde::Serialize)]
-#[serde(rename_all = "camelCase")]
-struct TaskSummary {
-    task_id: String,
-    kind: String,
-    state: String,
-    created_at: String,
-    updated_at: String,
-    progress: Option<project::model::TaskProgress>,
-    error: Option<project::model::TaskError>,
-    retries: project::model::TaskRetries,
-}
-
+/// Cancel `task_id` and every queued/running task that transitively depends
+/// on it. Tasks already in a terminal state are skipped. Returns the ids that
+/// were canceled or flagged for cancellation.
 #[tauri::command]
-async fn task_list(
+async fn task_cancel_cascade(
+    task_id: String,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<Vec<TaskSummary>, String> {
-    let guard = state.inner.lock().await;
-    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    if !loaded.project.tasks.iter().any(|t| t.task_id == task_id) {
+        return Err(format!("任务不存在: {}", task_id));
+    }
+
+    let mut targets = vec![task_id.clone()];
+    targets.extend(loaded.project.downstream_tasks(&task_id));
+
+    let now = crate::clock::now_rfc3339();
+    let cap = loaded.project.project.settings.task_event_cap();
+    let mut canceled = Vec::new();
+    let mut running = Vec::new();
+    let mut snapshots = Vec::new();
+    for id in &targets {
+        let Some(task) = loaded.project.tasks.iter_mut().find(|t| &t.task_id == id) else {
+            continue;
+        };
+        match task.state.as_str() {
+            "queued" => {
+                task.state = "canceled".to_string();
+                task.updated_at = now.clone();
+                task.finished_at = Some(now.clone());
+                let msg = if *id == task_id {
+                    "Task canceled (was queued)".to_string()
+                } else {
+                    format!("Task canceled (upstream {} canceled)", task_id)
+                };
+                task.append_event("warn", &msg, cap);
+                snapshots.push(task.clone());
+                canceled.push(id.clone());
+            }
+            "running" => {
+                running.push(id.clone());
+                canceled.push(id.clone());
+            }
+            _ => {}
+        }
+    }
+
+    if !snapshots.is_empty() {
+        loaded.dirty = true;
+    }
+    drop(guard);
+
+    if !running.is_empty() {
+        // Runner and run_ffmpeg poll these flags and kill the child process
+        let mut flags = state.cancel_flags.lock().await;
+        flags.extend(running);
+    }
+    for snapshot in snapshots {
+        let _ = app_handle.emit("task:updated", serde_json::json!({ "task": snapshot }));
+    }
+    if !canceled.is_empty() {
+        state.save_notify.notify_one();
+    }
+
+    Ok(canceled)
+}
 
-    let summaries: Vec<TaskSummary> = loaded
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CancelAllResult {
+    /// Queued tasks flipped to canceled.
+    canceled: usize,
+    /// Running tasks flagged; the runner kills their ffmpeg child.
+    running_killed: usize,
+    /// Canceled tasks removed from the project (`task_clear_queue` only).
+    cleared: usize,
+}
+
+/// Cancel every queued task and flag every running one.
+#[tauri::command]
+async fn task_cancel_all(
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<CancelAllResult, String> {
+    cancel_all_tasks(&state, &app_handle, false).await
+}
+
+/// `task_cancel_all`, then remove the tasks it canceled from the queue.
+/// Running tasks finish canceling on their own and are kept.
+#[tauri::command]
+async fn task_clear_queue(
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<CancelAllResult, String> {
+    cancel_all_tasks(&state, &app_handle, true).await
+}
+
+async fn cancel_all_tasks(
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+    clear: bool,
+) -> Result<CancelAllResult, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let snapshots = loaded.project.cancel_queued_tasks("Task canceled (cancel all)");
+    let running: Vec<String> = loaded
         .project
         .tasks
         .iter()
+        .filter(|t| t.state == "running")
+        .map(|t| t.task_id.clone())
+        .collect();
+    let mut cleared = 0;
+    if clear && !snapshots.is_empty() {
+        let ids: Vec<String> = snapshots.iter().map(|t| t.task_id.clone()).collect();
+        cleared = loaded.project.remove_tasks(&ids);
+        loaded.project.rebuild_indexes();
+    }
+    if !snapshots.is_empty() {
+        loaded.dirty = true;
+    }
+    drop(guard);
+
+    let result = CancelAllResult { canceled: snapshots.len(), running_killed: running.len(), cleared };
+    if !running.is_empty() {
+        state.cancel_flags.lock().await.extend(running);
+    }
+    if clear {
+        if cleared > 0 {
+            let _ = app_handle.emit("project:updated", ());
+        }
+    } else {
+        for snapshot in snapshots {
+            let _ = app_handle.emit("task:updated", serde_json::json!({ "task": snapshot }));
+        }
+    }
+    if result.canceled > 0 {
+        state.save_notify.notify_one();
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+async fn regenerate_thumb(
+    asset_id: String,
+    t_ms: i64,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let asset = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .ok_or(format!("Asset not found: {}", asset_id))?;
+    if asset.asset_type != "video" && asset.asset_type != "image" {
+        return Err(format!("Asset {} has no frames to thumbnail", asset_id));
+    }
+
+    let now = crate::clock::now_rfc3339();
+    let task_id = format!(
+        "task_thumb_{}",
+        &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
+    );
+    let task = Task {
+        task_id: task_id.clone(),
+        kind: "thumb".to_string(),
+        state: "queued".to_string(),
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        input: serde_json::json!({ "assetId": asset_id, "tMs": t_ms.max(0) }),
+        output: None,
+        progress: None,
+        error: None,
+        retries: TaskRetries { count: 0, max: 3 },
+        deps: vec![],
+        events: vec![TaskEvent {
+            t: now,
+            level: "info".to_string(),
+            msg: format!("Task enqueued (regenerate thumb at {}ms)", t_ms),
+        }],
+        dedupe_key: None,
+        started_at: None,
+        finished_at: None,
+    };
+
+    admit_tasks(loaded, 1, &app_handle)?;
+    loaded.project.tasks.push(task.clone());
+    loaded.project.rebuild_indexes();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
+    state.save_notify.notify_one();
+    state.task_notify.notify_one();
+
+    Ok(task_id)
+}
+
+/// Enqueue a proxy rebuild with custom width/crf, overwriting the existing
+/// proxy. If a proxy task for the asset is still queued, its parameters are
+/// updated instead and its id returned.
+#[tauri::command]
+async fn regenerate_proxy(
+    asset_id: String,
+    width: Option<u32>,
+    crf: Option<u32>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let width = width.unwrap_or(960);
+    let crf = crf.unwrap_or(28);
+    if !(160..=3840).contains(&width) {
+        return Err(format!("width must be between 160 and 3840, got {}", width));
+    }
+    if crf > 51 {
+        return Err(format!("crf must be between 0 and 51, got {}", crf));
+    }
+
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let asset = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .ok_or(format!("Asset not found: {}", asset_id))?;
+    if asset.asset_type != "video" && asset.asset_type != "audio" {
+        return Err(format!("Asset {} has no media to proxy", asset_id));
+    }
+
+    let input = serde_json::json!({ "assetId": asset_id, "width": width, "crf": crf });
+    let now = crate::clock::now_rfc3339();
+    let cap = loaded.project.project.settings.task_event_cap();
+
+    let queued = loaded.project.tasks.iter_mut().find(|t| {
+        t.kind == "proxy"
+            && t.state == "queued"
+            && t.input.get("assetId").and_then(|v| v.as_str()) == Some(asset_id.as_str())
+    });
+    let task = match queued {
+        Some(task) => {
+            task.input = input;
+            task.updated_at = now;
+            task.append_event("info", &format!("Proxy parameters updated (width {}, crf {})", width, crf), cap);
+            task.clone()
+        }
+        None => {
+            let task = Task {
+                task_id: format!(
+                    "task_proxy_{}",
+                    &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
+                ),
+                kind: "proxy".to_string(),
+                state: "queued".to_string(),
+                created_at: now.clone(),
+                updated_at: now.clone(),
+                input,
+                output: None,
+                progress: None,
+                error: None,
+                retries: TaskRetries { count: 0, max: 3 },
+                deps: vec![],
+                events: vec![TaskEvent {
+                    t: now,
+                    level: "info".to_string(),
+                    msg: format!("Task enqueued (regenerate proxy, width {}, crf {})", width, crf),
+                }],
+                dedupe_key: None,
+                started_at: None,
+                finished_at: None,
+            };
+            admit_tasks(loaded, 1, &app_handle)?;
+            loaded.project.tasks.push(task.clone());
+            loaded.project.rebuild_indexes();
+            task
+        }
+    };
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
+    state.save_notify.notify_one();
+    state.task_notify.notify_one();
+
+    Ok(task.task_id)
+}
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TaskSummary {
+    task_id: String,
+    kind: String,
+    state: String,
+    created_at: String,
+    updated_at: String,
+    progress: Option<project::model::TaskProgress>,
+    error: Option<project::model::TaskError>,
+    retries: project::model::TaskRetries,
+    started_at: Option<String>,
+    finished_at: Option<String>,
+    duration_ms: Option<i64>,
+}
+
+/// Tasks matching `query` (all of them, in insertion order, by default).
+#[tauri::command]
+async fn task_list(
+    query: Option<project::query::TaskListQuery>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<TaskSummary>, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+
+    let summaries: Vec<TaskSummary> = project::query::list_tasks(&loaded.project, &query.unwrap_or_default())?
+        .into_iter()
         .map(|t| TaskSummary {
             task_id: t.task_id.clone(),
             kind: t.kind.clone(),
@@ -551,40 +1295,340 @@ async fn task_list(
             progress: t.progress.clone(),
             error: t.error.clone(),
             retries: t.retries.clone(),
+            started_at: t.started_at.clone(),
+            finished_at: t.finished_at.clone(),
+            duration_ms: t.duration_ms(),
         })
         .collect();
 
     Ok(summaries)
 }
 
+/// The task's `output` blob (e.g. the asset/clip ids a generation created);
+/// `None` until the task has produced one.
+#[tauri::command]
+async fn task_output(
+    task_id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Option<serde_json::Value>, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+
+    let task = loaded
+        .project
+        .tasks
+        .iter()
+        .find(|t| t.task_id == task_id)
+        .ok_or(format!("任务不存在: {}", task_id))?;
+    Ok(task.output.clone())
+}
+
+/// Assets filtered and ordered by `query` (type, tag, rating, favorite).
+#[tauri::command]
+async fn asset_list(
+    query: Option<project::query::AssetListQuery>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<Vec<Asset>, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+
+    Ok(project::query::list_assets(&loaded.project, &query.unwrap_or_default())?
+        .into_iter()
+        .cloned()
+        .collect())
+}
+
+/// Star an asset (`rating` 1-5, 0 clears it) and/or mark it as a favorite;
+/// an omitted field is left unchanged. Stored in `meta.rating` / `meta.favorite`.
+#[tauri::command]
+async fn asset_set_rating(
+    asset_id: String,
+    rating: Option<u8>,
+    favorite: Option<bool>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Asset, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let asset = loaded
+        .project
+        .assets
+        .iter_mut()
+        .find(|a| a.asset_id == asset_id)
+        .ok_or(format!("Asset not found: {}", asset_id))?;
+    if let Some(rating) = rating {
+        asset.set_rating(rating)?;
+    }
+    if let Some(favorite) = favorite {
+        asset.set_favorite(favorite);
+    }
+    let updated = asset.clone();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(updated)
+}
+
+#[derive(serde::Serialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct TaskStats {
+    queued: usize,
+    running: usize,
+    succeeded: usize,
+    failed: usize,
+    canceled: usize,
+    /// Whether the queue is paused (see `queue_pause`).
+    paused: bool,
+}
+
+#[tauri::command]
+async fn task_stats(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<TaskStats, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+
+    let mut stats = TaskStats { paused: state.is_queue_paused(), ..Default::default() };
+    for task in &loaded.project.tasks {
+        match task.state.as_str() {
+            "queued" => stats.queued += 1,
+            "running" => stats.running += 1,
+            "succeeded" => stats.succeeded += 1,
+            "failed" => stats.failed += 1,
+            "canceled" => stats.canceled += 1,
+            _ => {}
+        }
+    }
+    Ok(stats)
+}
+
+/// Stop starting new tasks. Running tasks are left to finish.
+#[tauri::command]
+fn queue_pause(state: tauri::State<'_, Arc<AppState>>, app_handle: tauri::AppHandle) {
+    if state.set_queue_paused(true) {
+        let _ = app_handle.emit("queue:updated", serde_json::json!({ "paused": true }));
+    }
+}
+
+#[tauri::command]
+fn queue_resume(state: tauri::State<'_, Arc<AppState>>, app_handle: tauri::AppHandle) {
+    if state.set_queue_paused(false) {
+        let _ = app_handle.emit("queue:updated", serde_json::json!({ "paused": false }));
+    }
+}
+
 // ============================================================
 // Timeline Commands
 // ============================================================
 
+/// Error for commands that return a structured `TaskError` instead of a string.
+fn no_project_error() -> TaskError {
+    TaskError::new("no_project", "没有打开的项目")
+}
+
+const READ_ONLY_MESSAGE: &str = "项目以只读方式打开，不能修改";
+
+/// The open project, for commands that edit it. Read-only projects refuse.
+fn writable_project(guard: &mut Option<LoadedProject>) -> Result<&mut LoadedProject, String> {
+    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    if loaded.read_only {
+        return Err(format!("read_only: {}", READ_ONLY_MESSAGE));
+    }
+    Ok(loaded)
+}
+
+/// `writable_project` for commands that return a structured `TaskError`.
+fn writable_project_task(guard: &mut Option<LoadedProject>) -> Result<&mut LoadedProject, TaskError> {
+    let loaded = guard.as_mut().ok_or_else(no_project_error)?;
+    if loaded.read_only {
+        return Err(TaskError::new("read_only", READ_ONLY_MESSAGE));
+    }
+    Ok(loaded)
+}
+
+/// Apply `settings.limits.maxTasks` before `incoming` tasks are enqueued:
+/// prune old finished tasks or refuse with `limit_exceeded`. Either way the
+/// UI is warned through `project:limit`.
+pub(crate) fn admit_tasks(loaded: &mut LoadedProject, incoming: usize, app_handle: &tauri::AppHandle) -> Result<(), String> {
+    let limits = loaded.project.project.settings.effective_limits();
+    match loaded.project.make_room_for_tasks(incoming, &limits) {
+        Ok(pruned) if pruned.is_empty() => Ok(()),
+        Ok(pruned) => {
+            log::warn!("Task limit of {} reached; pruned {} finished task(s)", limits.max_tasks, pruned.len());
+            loaded.project.rebuild_indexes();
+            loaded.dirty = true;
+            let _ = app_handle.emit("project:limit", serde_json::json!({
+                "kind": "tasks",
+                "limit": limits.max_tasks,
+                "pruned": pruned,
+            }));
+            let _ = app_handle.emit("project:updated", ());
+            Ok(())
+        }
+        Err(e) => {
+            log::warn!("{}", e.message);
+            let _ = app_handle.emit("project:limit", serde_json::json!({
+                "kind": "tasks",
+                "limit": limits.max_tasks,
+                "error": e,
+            }));
+            Err(format!("{}: {}", e.code, e.message))
+        }
+    }
+}
+
+/// Refuse clips past `settings.limits.maxClips`, warning through `project:limit`.
+fn admit_clips(loaded: &LoadedProject, incoming: usize, app_handle: &tauri::AppHandle) -> Result<(), TaskError> {
+    let limits = loaded.project.project.settings.effective_limits();
+    if let Err(e) = loaded.project.check_clip_limit(incoming, &limits) {
+        log::warn!("{}", e.message);
+        let _ = app_handle.emit("project:limit", serde_json::json!({
+            "kind": "clips",
+            "limit": limits.max_clips,
+            "error": e,
+        }));
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Append an empty track of `track_type` (video, audio, text or subtitle).
+#[tauri::command]
+async fn timeline_add_track(
+    track_type: String,
+    name: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Track, String> {
+    if !project::model::TRACK_TYPES.contains(&track_type.as_str()) {
+        return Err(format!(
+            "Unknown track type {}; expected one of {}",
+            track_type,
+            project::model::TRACK_TYPES.join(", ")
+        ));
+    }
+
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let track = Track {
+        track_id: format!("trk_{}_{}", track_type, &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]),
+        track_type: track_type.clone(),
+        name: name.filter(|n| !n.trim().is_empty()).unwrap_or_else(|| {
+            let mut chars = track_type.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+        }),
+        clip_ids: vec![],
+    };
+    loaded.project.timeline.tracks.push(track.clone());
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(track)
+}
+
+/// Clone `track_id` and its clips onto a new track right after it, for
+/// trying an alternate cut without touching the original.
+#[tauri::command]
+async fn timeline_duplicate_track(
+    track_id: String,
+    new_name: String,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Track, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let incoming = loaded
+        .project
+        .timeline
+        .tracks
+        .iter()
+        .find(|t| t.track_id == track_id)
+        .map_or(0, |t| t.clip_ids.len());
+    admit_clips(loaded, incoming, &app_handle).map_err(|e| format!("{}: {}", e.code, e.message))?;
+
+    let track = loaded.project.timeline.duplicate_track(&track_id, &new_name)?;
+    loaded.project.timeline.recalc_duration();
+    loaded.project.rebuild_indexes();
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(track)
+}
+
+/// Place `asset_id` on `track_id` at `start_ms`, or at `position` (e.g. a
+/// percent of the timeline's current duration, evaluated before the clip is added).
 #[tauri::command]
 async fn timeline_add_clip(
     track_id: String,
     asset_id: String,
-    start_ms: i64,
+    start_ms: Option<i64>,
+    position: Option<project::model::TimelinePosition>,
+    allow_type_mismatch: Option<bool>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
-) -> Result<Clip, String> {
+) -> Result<Clip, TaskError> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project_task(&mut guard)?;
+    let start_ms = loaded
+        .project
+        .timeline
+        .resolve_position(start_ms, position)
+        .map_err(|e| TaskError::new("invalid_position", e))?;
 
     let asset = loaded
         .project
         .assets
         .iter()
         .find(|a| a.asset_id == asset_id)
-        .ok_or(format!("Asset not found: {}", asset_id))?;
+        .ok_or_else(|| TaskError::new("asset_not_found", format!("Asset not found: {}", asset_id)))?;
+    if asset.tags.iter().any(|t| t == media::probe::UNPROBEABLE_TAG) {
+        return Err(TaskError::new(
+            "asset_unprobeable",
+            format!("Asset {} could not be probed and can't be placed on the timeline", asset_id),
+        ));
+    }
+
+    let target = loaded
+        .project
+        .timeline
+        .tracks
+        .iter()
+        .find(|t| t.track_id == track_id)
+        .ok_or_else(|| TaskError::new("track_not_found", format!("Track not found: {}", track_id)))?;
+    if !allow_type_mismatch.unwrap_or(false) && !target.accepts_asset_type(&asset.asset_type) {
+        return Err(TaskError::new(
+            "type_mismatch",
+            format!(
+                "{} asset {} can't be placed on {} track {}",
+                asset.asset_type, asset_id, target.track_type, track_id
+            ),
+        ));
+    }
 
-    let duration_sec = asset
-        .meta
-        .get("durationSec")
-        .and_then(|v| v.as_f64())
-        .unwrap_or(5.0);
-    let duration_ms = (duration_sec * 1000.0) as i64;
+    let mut duration_ms = asset
+        .clip_duration_ms(&loaded.project.project.settings)
+        .map_err(|e| TaskError::new("unknown_duration", e))?;
+    admit_clips(loaded, 1, &app_handle)?;
+    let mut start_ms = start_ms.max(0);
+
+    if let Some(fps) = frame_snap_fps(&loaded.project.project.settings) {
+        start_ms = snap_to_frame(start_ms, fps);
+        duration_ms = snap_to_frame(duration_ms, fps);
+    }
 
     let track = loaded
         .project
@@ -592,7 +1636,7 @@ async fn timeline_add_clip(
         .tracks
         .iter_mut()
         .find(|t| t.track_id == track_id)
-        .ok_or(format!("Track not found: {}", track_id))?;
+        .ok_or_else(|| TaskError::new("track_not_found", format!("Track not found: {}", track_id)))?;
 
     let clip_id = format!(
         "clip_{}",
@@ -603,10 +1647,12 @@ async fn timeline_add_clip(
         clip_id: clip_id.clone(),
         asset_id,
         track_id: track_id.clone(),
-        start_ms: start_ms.max(0),
+        start_ms,
         duration_ms,
         in_ms: 0,
         out_ms: duration_ms,
+        label_color: None,
+        note: None,
     };
 
     track.clip_ids.push(clip_id.clone());
@@ -634,7 +1680,8 @@ async fn timeline_move_clip(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
+    let snap_fps = frame_snap_fps(&loaded.project.project.settings);
 
     let clip = loaded
         .project
@@ -643,7 +1690,11 @@ async fn timeline_move_clip(
         .get_mut(&clip_id)
         .ok_or(format!("Clip not found: {}", clip_id))?;
 
-    clip.start_ms = new_start_ms.max(0);
+    let mut start = new_start_ms.max(0);
+    if let Some(fps) = snap_fps {
+        start = snap_to_frame(start, fps);
+    }
+    clip.start_ms = start;
     loaded.project.timeline.recalc_duration();
     loaded.dirty = true;
 
@@ -657,13 +1708,140 @@ async fn timeline_move_clip(
 #[tauri::command]
 async fn timeline_trim_clip(
     clip_id: String,
-    in_ms: Option<i64>,
-    out_ms: Option<i64>,
+    in_ms: Option<i64>,
+    out_ms: Option<i64>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), TaskError> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project_task(&mut guard)?;
+    let snap_fps = frame_snap_fps(&loaded.project.project.settings);
+
+    let clip = loaded
+        .project
+        .timeline
+        .clips
+        .get_mut(&clip_id)
+        .ok_or_else(|| TaskError::new("clip_not_found", format!("Clip not found: {}", clip_id)))?;
+    let source_duration_ms = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == clip.asset_id)
+        .and_then(|a| a.duration_ms());
+
+    clip.trim(in_ms, out_ms, snap_fps, source_duration_ms)?;
+    loaded.project.timeline.recalc_duration();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
+
+/// Set the out-point so the clip ends at `target_ms` (the next clip's start
+/// or the timeline end), without doing the trim arithmetic by hand.
+#[tauri::command]
+async fn timeline_extend_clip_to(
+    clip_id: String,
+    target_ms: i64,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Clip, TaskError> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project_task(&mut guard)?;
+    let target_ms = match frame_snap_fps(&loaded.project.project.settings) {
+        Some(fps) => snap_to_frame(target_ms, fps),
+        None => target_ms,
+    };
+
+    let clip = loaded
+        .project
+        .timeline
+        .clips
+        .get_mut(&clip_id)
+        .ok_or_else(|| TaskError::new("clip_not_found", format!("Clip not found: {}", clip_id)))?;
+    let source_duration_ms = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == clip.asset_id)
+        .and_then(|a| a.duration_ms());
+
+    clip.extend_to(target_ms, source_duration_ms)?;
+    let updated = clip.clone();
+    loaded.project.timeline.recalc_duration();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn timeline_set_clip_asset(
+    clip_id: String,
+    asset_id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Clip, TaskError> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project_task(&mut guard)?;
+
+    let asset = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id)
+        .ok_or_else(|| TaskError::new("asset_not_found", format!("Asset not found: {}", asset_id)))?;
+    if asset.tags.iter().any(|t| t == media::probe::UNPROBEABLE_TAG) {
+        return Err(TaskError::new(
+            "asset_unprobeable",
+            format!("Asset {} could not be probed and can't be placed on the timeline", asset_id),
+        ));
+    }
+    let mut asset_duration_ms = asset
+        .clip_duration_ms(&loaded.project.project.settings)
+        .map_err(|e| TaskError::new("unknown_duration", e))?;
+    if let Some(fps) = frame_snap_fps(&loaded.project.project.settings) {
+        asset_duration_ms = snap_to_frame(asset_duration_ms, fps);
+    }
+
+    let clip = loaded
+        .project
+        .timeline
+        .clips
+        .get_mut(&clip_id)
+        .ok_or_else(|| TaskError::new("clip_not_found", format!("Clip not found: {}", clip_id)))?;
+    clip.repoint(&asset_id, asset_duration_ms);
+    let updated = clip.clone();
+
+    loaded.project.timeline.recalc_duration();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(updated)
+}
+
+/// Set a clip's label color and note (organizational only; export ignores
+/// them). Passing `None` clears a field.
+#[tauri::command]
+async fn timeline_set_clip_meta(
+    clip_id: String,
+    label_color: Option<String>,
+    note: Option<String>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<Clip, String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
     let clip = loaded
         .project
@@ -671,30 +1849,15 @@ async fn timeline_trim_clip(
         .clips
         .get_mut(&clip_id)
         .ok_or(format!("Clip not found: {}", clip_id))?;
-
-    if let Some(new_in) = in_ms {
-        if new_in < 0 {
-            return Err("inMs cannot be negative".to_string());
-        }
-        clip.in_ms = new_in;
-    }
-    if let Some(new_out) = out_ms {
-        clip.out_ms = new_out;
-    }
-
-    if clip.out_ms <= clip.in_ms {
-        return Err("outMs must be greater than inMs".to_string());
-    }
-
-    clip.duration_ms = clip.out_ms - clip.in_ms;
-    loaded.project.timeline.recalc_duration();
+    clip.set_meta(label_color, note)?;
+    let updated = clip.clone();
     loaded.dirty = true;
 
     drop(guard);
     let _ = app_handle.emit("project:updated", ());
     state.save_notify.notify_one();
 
-    Ok(())
+    Ok(updated)
 }
 
 #[tauri::command]
@@ -704,7 +1867,7 @@ async fn timeline_remove_clip(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
     loaded.project.timeline.clips.remove(&clip_id);
 
@@ -717,7 +1880,7 @@ async fn timeline_remove_clip(
     loaded.dirty = true;
 
     // Force save on deletion
-    project::io::write_project_atomic(&loaded.json_path, &loaded.project)?;
+    project::io::write_project_compact(&loaded.json_path, &loaded.project)?;
     loaded.dirty = false;
 
     drop(guard);
@@ -732,9 +1895,9 @@ async fn timeline_reorder_clips(
     clip_ids: Vec<String>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), TaskError> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project_task(&mut guard)?;
 
     let track = loaded
         .project
@@ -742,11 +1905,14 @@ async fn timeline_reorder_clips(
         .tracks
         .iter_mut()
         .find(|t| t.track_id == track_id)
-        .ok_or(format!("Track not found: {}", track_id))?;
+        .ok_or_else(|| TaskError::new("track_not_found", format!("Track not found: {}", track_id)))?;
 
     for cid in &clip_ids {
         if !track.clip_ids.contains(cid) {
-            return Err(format!("Clip {} not in track {}", cid, track_id));
+            return Err(TaskError::new(
+                "clip_not_in_track",
+                format!("Clip {} not in track {}", cid, track_id),
+            ));
         }
     }
 
@@ -760,20 +1926,86 @@ async fn timeline_reorder_clips(
     Ok(())
 }
 
+/// Set the compositing order of tracks; `track_ids` must list every track once.
+#[tauri::command]
+async fn timeline_reorder_tracks(
+    track_ids: Vec<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    loaded.project.timeline.reorder_tracks(&track_ids)?;
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
+
+/// Apply several timeline edits atomically: either every op succeeds and the
+/// project is saved and emitted once, or nothing changes.
+#[tauri::command]
+async fn timeline_batch(
+    ops: Vec<project::batch::TimelineOp>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<String>, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+    if ops.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let snap_fps = frame_snap_fps(&loaded.project.project.settings);
+    let (next, added) = project::batch::apply_timeline_ops(&loaded.project, &ops, snap_fps)?;
+    let growth = next.timeline.clips.len().saturating_sub(loaded.project.timeline.clips.len());
+    if growth > 0 {
+        admit_clips(loaded, growth, &app_handle).map_err(|e| format!("{}: {}", e.code, e.message))?;
+    }
+    loaded.project = next;
+    loaded.dirty = true;
+
+    // Force save when the batch deletes clips, like timeline_remove_clip
+    let removes = ops
+        .iter()
+        .any(|op| matches!(op, project::batch::TimelineOp::Remove { .. }));
+    if removes {
+        project::io::write_project_compact(&loaded.json_path, &loaded.project)?;
+        loaded.dirty = false;
+    }
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    if !removes {
+        state.save_notify.notify_one();
+    }
+
+    Ok(added)
+}
+
 // ============================================================
 // Marker Commands
 // ============================================================
 
+/// Add a marker at `t_ms`, or at `position` resolved against the timeline's
+/// current duration.
 #[tauri::command]
 async fn marker_add(
-    t_ms: i64,
+    t_ms: Option<i64>,
     label: Option<String>,
     prompt_text: Option<String>,
+    end_ms: Option<i64>,
+    position: Option<project::model::TimelinePosition>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<Marker, String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
+    let t_ms = loaded.project.timeline.resolve_position(t_ms, position)?;
 
     let marker = Marker {
         marker_id: format!(
@@ -783,8 +2015,10 @@ async fn marker_add(
         t_ms,
         label: label.unwrap_or_default(),
         prompt_text: prompt_text.unwrap_or_default(),
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: crate::clock::now_rfc3339(),
+        end_ms,
     };
+    marker.validate()?;
 
     loaded.project.timeline.markers.push(marker.clone());
     loaded
@@ -801,17 +2035,27 @@ async fn marker_add(
     Ok(marker)
 }
 
+/// Omitted fields are left unchanged; `clear_end` turns a range marker back
+/// into a point marker.
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn marker_update(
     marker_id: String,
     label: Option<String>,
     prompt_text: Option<String>,
     t_ms: Option<i64>,
+    end_ms: Option<i64>,
+    clear_end: Option<bool>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
+    let clear_end = clear_end.unwrap_or(false);
+    if clear_end && end_ms.is_some() {
+        return Err("endMs and clearEnd can't be used together".to_string());
+    }
+
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
     let marker = loaded
         .project
@@ -821,15 +2065,24 @@ async fn marker_update(
         .find(|m| m.marker_id == marker_id)
         .ok_or(format!("Marker not found: {}", marker_id))?;
 
+    let mut updated = marker.clone();
     if let Some(l) = label {
-        marker.label = l;
+        updated.label = l;
     }
     if let Some(p) = prompt_text {
-        marker.prompt_text = p;
+        updated.prompt_text = p;
     }
     if let Some(t) = t_ms {
-        marker.t_ms = t;
+        updated.t_ms = t;
+    }
+    if let Some(e) = end_ms {
+        updated.end_ms = Some(e);
+    }
+    if clear_end {
+        updated.end_ms = None;
     }
+    updated.validate()?;
+    *marker = updated;
 
     loaded
         .project
@@ -852,7 +2105,7 @@ async fn marker_remove(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
     let before_len = loaded.project.timeline.markers.len();
     loaded
@@ -874,27 +2127,49 @@ async fn marker_remove(
     Ok(())
 }
 
+#[tauri::command]
+async fn markers_shift(
+    after_ms: i64,
+    delta_ms: i64,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<usize, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let moved = loaded.project.timeline.shift_markers(after_ms, delta_ms);
+    if moved > 0 {
+        loaded.dirty = true;
+    }
+
+    drop(guard);
+    if moved > 0 {
+        let _ = app_handle.emit("project:updated", ());
+        state.save_notify.notify_one();
+    }
+
+    Ok(moved)
+}
+
 // ============================================================
 // Note / Prompt Asset Commands
 // ============================================================
 
-#[tauri::command]
-async fn create_note(
-    text: String,
+/// Write `text` to the prompts dir (workspace/assets/prompts) and register it as an authored
+/// prompt asset. The caller emits and schedules the save.
+fn write_prompt_asset(
+    loaded: &mut LoadedProject,
+    text: &str,
     label: Option<String>,
     language: Option<String>,
-    state: tauri::State<'_, Arc<AppState>>,
-    app_handle: tauri::AppHandle,
 ) -> Result<Asset, String> {
-    let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
-
     let asset_id = format!(
         "ast_prompt_{}",
         &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
     );
 
-    let prompts_dir = loaded.project_dir.join("workspace/assets/prompts");
+    let layout = loaded.layout();
+    let prompts_dir = layout.dir(WorkspaceSubdir::Prompts);
     std::fs::create_dir_all(&prompts_dir)
         .map_err(|e| format!("创建目录失败: {}", e))?;
 
@@ -904,12 +2179,12 @@ async fn create_note(
         .map_err(|e| format!("写入文件失败: {}", e))?;
 
     let fp = asset::fingerprint::compute_content_fingerprint(text.as_bytes());
-    let relative_path = format!("workspace/assets/prompts/{}", file_name);
+    let relative_path = layout.stored_path(WorkspaceSubdir::Prompts, &file_name);
     let lang = language.unwrap_or_else(|| "zh".to_string());
     let lbl = label.unwrap_or_default();
 
     let asset = Asset {
-        asset_id: asset_id.clone(),
+        asset_id,
         asset_type: "prompt".to_string(),
         source: "authored".to_string(),
         fingerprint: fp,
@@ -922,12 +2197,27 @@ async fn create_note(
         }),
         generation: None,
         tags: vec!["prompt".to_string()],
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: crate::clock::now_rfc3339(),
     };
 
     loaded.project.assets.push(asset.clone());
     loaded.project.rebuild_indexes();
     loaded.dirty = true;
+    Ok(asset)
+}
+
+#[tauri::command]
+async fn create_note(
+    text: String,
+    label: Option<String>,
+    language: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<Asset, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let asset = write_prompt_asset(loaded, &text, label, language)?;
 
     drop(guard);
     let _ = app_handle.emit("project:updated", ());
@@ -936,6 +2226,99 @@ async fn create_note(
     Ok(asset)
 }
 
+/// Default on-timeline length of a new prompt clip.
+const PROMPT_CLIP_DURATION_MS: i64 = 5000;
+
+#[derive(serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptClip {
+    asset: Asset,
+    clip: Clip,
+}
+
+/// Create an authored prompt asset from `text` and place it on text track
+/// `track_id` at `t_ms`, in one step.
+#[tauri::command]
+async fn timeline_add_prompt(
+    track_id: String,
+    t_ms: i64,
+    text: String,
+    label: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<PromptClip, TaskError> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project_task(&mut guard)?;
+
+    if text.trim().is_empty() {
+        return Err(TaskError::new("empty_prompt", "Prompt text is empty"));
+    }
+    let track = loaded
+        .project
+        .timeline
+        .tracks
+        .iter()
+        .find(|t| t.track_id == track_id)
+        .ok_or_else(|| TaskError::new("track_not_found", format!("Track not found: {}", track_id)))?;
+    if !track.accepts_asset_type("prompt") {
+        return Err(TaskError::new(
+            "type_mismatch",
+            format!("prompt can't be placed on {} track {}", track.track_type, track_id),
+        ));
+    }
+
+    admit_clips(loaded, 1, &app_handle)?;
+
+    let mut start_ms = t_ms.max(0);
+    let mut duration_ms = PROMPT_CLIP_DURATION_MS;
+    if let Some(fps) = frame_snap_fps(&loaded.project.project.settings) {
+        start_ms = snap_to_frame(start_ms, fps);
+        duration_ms = snap_to_frame(duration_ms, fps);
+    }
+
+    let asset = write_prompt_asset(loaded, &text, label, None).map_err(|e| TaskError::new("io_error", e))?;
+
+    let clip_id = format!(
+        "clip_{}",
+        &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
+    );
+    let clip = Clip {
+        clip_id: clip_id.clone(),
+        asset_id: asset.asset_id.clone(),
+        track_id: track_id.clone(),
+        start_ms,
+        duration_ms,
+        in_ms: 0,
+        out_ms: duration_ms,
+        label_color: None,
+        note: None,
+    };
+
+    if let Some(track) = loaded
+        .project
+        .timeline
+        .tracks
+        .iter_mut()
+        .find(|t| t.track_id == track_id)
+    {
+        track.clip_ids.push(clip_id.clone());
+    }
+    loaded
+        .project
+        .timeline
+        .clips
+        .insert(clip_id, clip.clone());
+    loaded.project.timeline.recalc_duration();
+    loaded.project.rebuild_indexes();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(PromptClip { asset, clip })
+}
+
 #[tauri::command]
 async fn update_note(
     asset_id: String,
@@ -945,8 +2328,9 @@ async fn update_note(
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+    let loaded = writable_project(&mut guard)?;
 
+    let workspace_dir = loaded.workspace_dir();
     let asset = loaded
         .project
         .assets
@@ -954,16 +2338,163 @@ async fn update_note(
         .find(|a| a.asset_id == asset_id && a.asset_type == "prompt")
         .ok_or(format!("Prompt asset not found: {}", asset_id))?;
 
-    let dest_path = loaded.project_dir.join(&asset.path);
+    let dest_path = project::io::resolve_project_path(&loaded.project_dir, &workspace_dir, &asset.path);
     std::fs::write(&dest_path, text.as_bytes())
         .map_err(|e| format!("写入文件失败: {}", e))?;
 
-    asset.fingerprint = asset::fingerprint::compute_content_fingerprint(text.as_bytes());
+    asset.fingerprint = asset::fingerprint::compute_content_fingerprint(text.as_bytes());
+
+    if let Some(lbl) = label {
+        asset.meta["label"] = serde_json::Value::String(lbl);
+    }
+
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn read_note(
+    asset_id: String,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<String, String> {
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+
+    let asset = loaded
+        .project
+        .assets
+        .iter()
+        .find(|a| a.asset_id == asset_id && a.asset_type == "prompt")
+        .ok_or(format!("Prompt asset not found: {}", asset_id))?;
+
+    let file_path = loaded.resolve_path(&asset.path);
+    std::fs::read_to_string(&file_path)
+        .map_err(|e| format!("读取文件失败: {}", e))
+}
+
+// ============================================================
+// Project Settings Commands
+// ============================================================
+
+#[tauri::command]
+async fn update_generation_settings(
+    video_provider: Option<String>,
+    video_profile: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    loaded.project.project.settings.generation = Some(
+        project::model::GenerationSettings {
+            video_provider,
+            video_profile,
+        },
+    );
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
+
+/// Replace the project settings (fps, resolution, aspect ratio, ...).
+/// When fps changes, clips are re-snapped to the new frame grid if `resnap`
+/// is set, defaulting to the project's `snapToFrame`.
+#[tauri::command]
+async fn update_project_settings(
+    settings: ProjectSettings,
+    resnap: Option<bool>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    settings.validate()?;
+
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let fps_changed = settings.fps != loaded.project.project.settings.fps;
+    if fps_changed {
+        loaded.project.timeline.timebase.fps = settings.fps;
+        if resnap.unwrap_or(settings.snap_to_frame) {
+            let moved = loaded.project.timeline.snap_clips_to_frames(settings.fps as f64);
+            log::info!("Re-snapped {} clip(s) to {} fps", moved, settings.fps);
+        }
+    }
+
+    state.apply_settings(&settings);
+    loaded.project.project.settings = settings;
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
+    loaded.dirty = true;
+
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
+
+/// Store editor view state (playhead, selected track, zoom) so reopening the
+/// project restores it. Not a content edit: `updated_at` is left alone and no
+/// `project:updated` is emitted. Read-only projects keep it in memory only.
+#[tauri::command]
+async fn update_view_state(
+    view_state: project::model::ViewState,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
+
+    if view_state.playhead_ms < 0 {
+        return Err("playheadMs cannot be negative".to_string());
+    }
+    if let Some(zoom) = view_state.zoom {
+        if !zoom.is_finite() || zoom <= 0.0 {
+            return Err(format!("zoom must be positive, got {}", zoom));
+        }
+    }
+    if let Some(track_id) = &view_state.selected_track_id {
+        if !loaded.project.timeline.tracks.iter().any(|t| &t.track_id == track_id) {
+            return Err(format!("Track not found: {}", track_id));
+        }
+    }
+
+    if loaded.project.project.view_state.as_ref() == Some(&view_state) {
+        return Ok(());
+    }
+    loaded.project.project.view_state = Some(view_state);
+    if loaded.read_only {
+        return Ok(());
+    }
+    loaded.dirty = true;
+
+    drop(guard);
+    state.save_notify.notify_one();
+
+    Ok(())
+}
 
-    if let Some(lbl) = label {
-        asset.meta["label"] = serde_json::Value::String(lbl);
-    }
+/// Set the tasks `import_assets` enqueues when a call passes no options.
+#[tauri::command]
+async fn update_import_defaults(
+    options: project::model::ImportOptions,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
 
+    loaded.project.project.settings.import_defaults = Some(options);
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
     loaded.dirty = true;
 
     drop(guard);
@@ -973,47 +2504,53 @@ async fn update_note(
     Ok(())
 }
 
+/// Set the task/clip caps; `None` goes back to the defaults. Lowering a cap
+/// below the current count prunes or refuses only on the next addition.
 #[tauri::command]
-async fn read_note(
-    asset_id: String,
+async fn update_project_limits(
+    limits: Option<project::model::ProjectLimits>,
     state: tauri::State<'_, Arc<AppState>>,
-) -> Result<String, String> {
-    let guard = state.inner.lock().await;
-    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
 
-    let asset = loaded
-        .project
-        .assets
-        .iter()
-        .find(|a| a.asset_id == asset_id && a.asset_type == "prompt")
-        .ok_or(format!("Prompt asset not found: {}", asset_id))?;
+    let mut settings = loaded.project.project.settings.clone();
+    settings.limits = limits;
+    settings.validate_persistence()?;
 
-    let file_path = loaded.project_dir.join(&asset.path);
-    std::fs::read_to_string(&file_path)
-        .map_err(|e| format!("读取文件失败: {}", e))
-}
+    loaded.project.project.settings = settings;
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
+    loaded.dirty = true;
 
-// ============================================================
-// Project Settings Commands
-// ============================================================
+    drop(guard);
+    let _ = app_handle.emit("project:updated", ());
+    state.save_notify.notify_one();
+
+    Ok(())
+}
 
 #[tauri::command]
-async fn update_generation_settings(
-    video_provider: Option<String>,
-    video_profile: Option<String>,
+async fn update_persistence_settings(
+    save_debounce_ms: Option<u64>,
+    max_task_events: Option<usize>,
+    compress_on_disk: Option<bool>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let mut guard = state.inner.lock().await;
-    let loaded = guard.as_mut().ok_or("没有打开的项目")?;
-
-    loaded.project.project.settings.generation = Some(
-        project::model::GenerationSettings {
-            video_provider,
-            video_profile,
-        },
-    );
-    loaded.project.project.updated_at = chrono::Utc::now().to_rfc3339();
+    let loaded = writable_project(&mut guard)?;
+
+    // Omitted fields keep their current value
+    let mut settings = loaded.project.project.settings.clone();
+    settings.save_debounce_ms = save_debounce_ms.or(settings.save_debounce_ms);
+    settings.max_task_events = max_task_events.or(settings.max_task_events);
+    settings.compress_on_disk = compress_on_disk.or(settings.compress_on_disk);
+    settings.validate_persistence()?;
+
+    state.apply_settings(&settings);
+    loaded.project.project.settings = settings;
+    loaded.project.project.updated_at = crate::clock::now_rfc3339();
     loaded.dirty = true;
 
     drop(guard);
@@ -1060,15 +2597,45 @@ async fn providers_get(
         .ok_or(format!("provider_not_found: {}", name))
 }
 
+/// Returns the credential refs this provider's profiles share with other
+/// profiles, so the UI can warn that their secret is shared.
 #[tauri::command]
 async fn providers_upsert(
     name: String,
     config: provider::model::ProviderConfig,
     app_handle: tauri::AppHandle,
+) -> Result<Vec<provider::model::SharedCredential>, String> {
+    if name.trim().is_empty() {
+        return Err("invalid_provider_config: name: must not be empty".to_string());
+    }
+    provider::validate::validate_provider_config(&config)
+        .map_err(|errs| provider::validate::format_errors(&errs))?;
+    let path = provider::io::providers_path(&app_handle)?;
+    let mut file = provider::io::load_providers(&path)?;
+    file.providers.insert(name.clone(), config);
+    provider::io::save_providers_atomic(&path, &file)?;
+
+    let shared = file.shared_credentials(&name);
+    for s in &shared {
+        log::warn!("Credential {} is shared by {} profiles", s.credential_ref, s.profiles.len());
+    }
+    Ok(shared)
+}
+
+/// Turn verbose (redacted) request/response logging on or off for a provider.
+#[tauri::command]
+async fn set_provider_debug_logging(
+    name: String,
+    enabled: bool,
+    app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
     let path = provider::io::providers_path(&app_handle)?;
     let mut file = provider::io::load_providers(&path)?;
-    file.providers.insert(name, config);
+    let provider = file
+        .providers
+        .get_mut(&name)
+        .ok_or(format!("provider_not_found: {}", name))?;
+    provider.debug_logging = enabled;
     provider::io::save_providers_atomic(&path, &file)
 }
 
@@ -1098,6 +2665,18 @@ async fn secrets_exists(
     secrets::exists(&credential_ref)
 }
 
+/// Profiles whose secret is stored under `credential_ref`; check before
+/// deleting the secret or a provider using it.
+#[tauri::command]
+async fn secrets_usage(
+    credential_ref: String,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<provider::model::ProfileRef>, String> {
+    let path = provider::io::providers_path(&app_handle)?;
+    let file = provider::io::load_providers(&path)?;
+    Ok(file.credential_users(&credential_ref))
+}
+
 #[tauri::command]
 async fn secrets_delete(
     credential_ref: String,
@@ -1114,54 +2693,57 @@ async fn providers_test(
     Ok(provider::test::run_provider_test(&app_handle, &provider_name, &profile_name).await)
 }
 
+#[tauri::command]
+async fn providers_test_all(
+    provider_name: String,
+    app_handle: tauri::AppHandle,
+) -> Result<HashMap<String, provider::model::TestResult>, String> {
+    provider::test::run_provider_test_all(&app_handle, &provider_name).await
+}
+
 // ============================================================
 // Jimeng Provider Commands
 // ============================================================
 
-async fn build_jimeng_client(
+/// Resolve omitted provider/profile/model for a generation command from the
+/// open project's generation settings and the provider profile.
+async fn generation_target(
+    state: &Arc<AppState>,
     app_handle: &tauri::AppHandle,
-    provider_name: &str,
-    profile_name: &str,
-) -> Result<providers::jimeng::client::JimengClient, String> {
+    provider_name: Option<String>,
+    profile_name: Option<String>,
+    model: Option<String>,
+) -> Result<provider::model::GenerationTarget, String> {
+    let defaults = {
+        let guard = state.inner.lock().await;
+        guard
+            .as_ref()
+            .and_then(|l| l.project.project.settings.generation.clone())
+    };
     let path = provider::io::providers_path(app_handle)?;
     let file = provider::io::load_providers(&path)?;
-    let prov = file
-        .providers
-        .get(provider_name)
-        .ok_or(format!("provider_not_found: {}", provider_name))?;
-    let profile = prov
-        .profiles
-        .get(profile_name)
-        .ok_or(format!("profile_not_found: {}", profile_name))?;
-
-    let secret = secrets::get_secret(&profile.credential_ref)?
-        .ok_or("missing_credentials: 请在设置中连接 Provider")?;
-
-    let timeout_secs = profile.timeout_ms / 1000;
-    providers::jimeng::client::JimengClient::new(
-        &secret,
-        Some(prov.base_url.as_str()),
-        timeout_secs.max(10),
-    )
+    file.resolve_generation(provider_name, profile_name, model, defaults.as_ref())
 }
 
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 async fn jimeng_generate_image(
-    provider_name: String,
-    profile_name: String,
+    provider_name: Option<String>,
+    profile_name: Option<String>,
     prompt: String,
     model: Option<String>,
     ratio: Option<String>,
     negative_prompt: Option<String>,
     image_count: Option<u32>,
+    state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<providers::jimeng::api::GenerateResult, String> {
-    let client = build_jimeng_client(&app_handle, &provider_name, &profile_name).await?;
+    let target = generation_target(&state, &app_handle, provider_name, profile_name, model).await?;
+    let client = providers::jimeng::build_client(&app_handle, &target.provider_name, &target.profile_name, None)?;
     providers::jimeng::api::generate_image(
         &client,
         &prompt,
-        model.as_deref().unwrap_or("jimeng-4.5"),
+        target.model.as_deref().unwrap_or("jimeng-4.5"),
         ratio.as_deref().unwrap_or("1:1"),
         negative_prompt.as_deref().unwrap_or(""),
         image_count.unwrap_or(4),
@@ -1176,7 +2758,7 @@ async fn jimeng_task_status(
     history_ids: Vec<String>,
     app_handle: tauri::AppHandle,
 ) -> Result<HashMap<String, providers::jimeng::api::TaskStatusResult>, String> {
-    let client = build_jimeng_client(&app_handle, &provider_name, &profile_name).await?;
+    let client = providers::jimeng::build_client(&app_handle, &provider_name, &profile_name, None)?;
     providers::jimeng::api::get_task_status(&client, &history_ids, None).await
 }
 
@@ -1186,7 +2768,7 @@ async fn jimeng_credit_balance(
     profile_name: String,
     app_handle: tauri::AppHandle,
 ) -> Result<providers::jimeng::api::CreditInfo, String> {
-    let client = build_jimeng_client(&app_handle, &provider_name, &profile_name).await?;
+    let client = providers::jimeng::build_client(&app_handle, &provider_name, &profile_name, None)?;
     providers::jimeng::api::get_credit(&client).await
 }
 
@@ -1197,8 +2779,8 @@ async fn jimeng_credit_balance(
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 async fn gen_video_enqueue(
-    provider_name: String,
-    profile_name: String,
+    provider_name: Option<String>,
+    profile_name: Option<String>,
     prompt: String,
     model: Option<String>,
     ratio: Option<String>,
@@ -1207,24 +2789,44 @@ async fn gen_video_enqueue(
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    let now = chrono::Utc::now().to_rfc3339();
+    let now = crate::clock::now_rfc3339();
     let task_id = format!(
         "task_gen_video_{}",
         &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
     );
 
+    let target = generation_target(&state, &app_handle, provider_name, profile_name, model).await?;
     let mut input = serde_json::json!({
-        "providerName": provider_name,
-        "profileName": profile_name,
+        "providerName": target.provider_name,
+        "profileName": target.profile_name,
         "prompt": prompt,
     });
-    if let Some(m) = &model {
+    if let Some(m) = &target.model {
         input["model"] = serde_json::json!(m);
     }
     if let Some(r) = &ratio {
         input["ratio"] = serde_json::json!(r);
     }
-    if let Some(d) = duration_ms {
+    let mut events = vec![TaskEvent {
+        t: now.clone(),
+        level: "info".to_string(),
+        msg: "gen_video task enqueued".to_string(),
+    }];
+    if let Some(requested) = duration_ms {
+        // The provider only accepts discrete durations per model; catch it here
+        // rather than as a rejection deep in the poll loop.
+        let model = target
+            .model
+            .as_deref()
+            .unwrap_or(providers::jimeng::constants::DEFAULT_VIDEO_MODEL);
+        let d = providers::jimeng::constants::normalize_video_duration(model, requested)?;
+        if d != requested {
+            events.push(TaskEvent {
+                t: now.clone(),
+                level: "warn".to_string(),
+                msg: format!("durationMs {} adjusted to {} (nearest supported by {})", requested, d, model),
+            });
+        }
         input["durationMs"] = serde_json::json!(d);
     }
     if let Some(s) = start_ms {
@@ -1243,22 +2845,22 @@ async fn gen_video_enqueue(
         error: None,
         retries: TaskRetries { count: 0, max: 2 },
         deps: vec![],
-        events: vec![TaskEvent {
-            t: now,
-            level: "info".to_string(),
-            msg: "gen_video task enqueued".to_string(),
-        }],
+        events,
         dedupe_key: None,
+        started_at: None,
+        finished_at: None,
     };
 
     {
         let mut guard = state.inner.lock().await;
-        let loaded = guard.as_mut().ok_or("No project loaded")?;
+        let loaded = writable_project(&mut guard)?;
+        admit_tasks(loaded, 1, &app_handle)?;
         loaded.project.tasks.push(task.clone());
         loaded.project.rebuild_indexes();
         loaded.dirty = true;
     }
 
+    state.save_notify.notify_one();
     state.task_notify.notify_one();
     let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
 
@@ -1266,20 +2868,77 @@ async fn gen_video_enqueue(
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn export_draft(
     track_id: Option<String>,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    format: Option<String>,
+    fps: Option<u32>,
+    width: Option<u32>,
+    subtitles: Option<String>,
+    subtitle_track_id: Option<String>,
+    loudnorm: Option<bool>,
+    target_lufs: Option<f64>,
+    extra_ffmpeg_args: Option<Vec<String>>,
     state: tauri::State<'_, Arc<AppState>>,
     app_handle: tauri::AppHandle,
 ) -> Result<serde_json::Value, String> {
-    let now = chrono::Utc::now().to_rfc3339();
+    if start_ms.is_some_and(|s| s < 0) {
+        return Err("startMs cannot be negative".to_string());
+    }
+    task::export_plan::parse_range(start_ms, end_ms)?;
+
+    // Resolve up front so a bad track fails here, not in the export task.
+    let track_id = {
+        let guard = state.inner.lock().await;
+        let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+        loaded.project.export_track_id(track_id.as_deref())?
+    };
+
+    let now = crate::clock::now_rfc3339();
     let task_id = format!(
         "task_export_{}",
         &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
     );
 
-    let input = serde_json::json!({
-        "trackId": track_id.unwrap_or_else(|| "trk_draft".to_string()),
+    let mut input = serde_json::json!({
+        "trackId": track_id,
     });
+    if let Some(start) = start_ms {
+        input["startMs"] = serde_json::json!(start);
+    }
+    if let Some(end) = end_ms {
+        input["endMs"] = serde_json::json!(end);
+    }
+    // gif/webp only; validated by the export handler
+    if let Some(format) = format {
+        input["format"] = serde_json::json!(format);
+    }
+    if let Some(fps) = fps {
+        input["fps"] = serde_json::json!(fps);
+    }
+    if let Some(width) = width {
+        input["width"] = serde_json::json!(width);
+    }
+    // "burn" or "mux"; validated by the export handler
+    if let Some(subtitles) = subtitles {
+        input["subtitles"] = serde_json::json!(subtitles);
+    }
+    if let Some(id) = subtitle_track_id {
+        input["subtitleTrackId"] = serde_json::json!(id);
+    }
+    // Target range is validated by the export handler
+    if let Some(loudnorm) = loudnorm {
+        input["loudnorm"] = serde_json::json!(loudnorm);
+    }
+    if let Some(target) = target_lufs {
+        input["targetLufs"] = serde_json::json!(target);
+    }
+    // Checked against the export paths by the handler
+    if let Some(args) = extra_ffmpeg_args.filter(|a| !a.is_empty()) {
+        input["extraFfmpegArgs"] = serde_json::json!(args);
+    }
 
     let task = Task {
         task_id: task_id.clone(),
@@ -1299,26 +2958,346 @@ async fn export_draft(
             msg: "export task enqueued".to_string(),
         }],
         dedupe_key: None,
+        started_at: None,
+        finished_at: None,
     };
 
     {
         let mut guard = state.inner.lock().await;
-        let loaded = guard.as_mut().ok_or("No project loaded")?;
+        let loaded = writable_project(&mut guard)?;
+        admit_tasks(loaded, 1, &app_handle)?;
         loaded.project.tasks.push(task.clone());
         loaded.project.rebuild_indexes();
         loaded.dirty = true;
     }
 
+    state.save_notify.notify_one();
     state.task_notify.notify_one();
     let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
 
     Ok(serde_json::json!({ "taskId": task_id }))
 }
 
+/// Cache artifacts `rebuild_caches` can regenerate: task kind and the meta
+/// key its output is recorded under.
+const CACHE_ARTIFACTS: [(&str, &str); 3] = [("thumb", "thumbUri"), ("proxy", "proxyUri"), ("waveform", "waveformUri")];
+
+/// Cache kinds accepted by `rebuild_caches` that have no generating task yet.
+/// Not supported yet: there is no filmstrip task, so these always report 0.
+const UNSUPPORTED_CACHE_KINDS: &[&str] = &["filmstrip"];
+
+/// Enqueue a thumb/proxy/waveform task for every asset whose artifact is
+/// absent from its meta or missing on disk (after clearing the cache or
+/// moving the project). `kinds` empty means all three. Assets with such a
+/// task already queued or running are skipped. Returns tasks queued per kind;
+/// `filmstrip` is accepted but not supported yet and always counts 0.
+#[tauri::command]
+async fn rebuild_caches(
+    kinds: Vec<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<HashMap<String, usize>, String> {
+    let mut wanted = Vec::new();
+    let mut unsupported = Vec::new();
+    for kind in &kinds {
+        if UNSUPPORTED_CACHE_KINDS.contains(&kind.as_str()) {
+            unsupported.push(kind.clone());
+            continue;
+        }
+        let artifact = CACHE_ARTIFACTS.iter().find(|(k, _)| *k == kind.as_str()).ok_or_else(|| {
+            format!("unknown cache kind {}; expected one of thumb, proxy, waveform, filmstrip", kind)
+        })?;
+        wanted.push(*artifact);
+    }
+    if wanted.is_empty() && unsupported.is_empty() {
+        wanted = CACHE_ARTIFACTS.to_vec();
+    }
+
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let mut counts: HashMap<String, usize> = wanted
+        .iter()
+        .map(|(k, _)| k.to_string())
+        .chain(unsupported)
+        .map(|k| (k, 0))
+        .collect();
+    let mut queued = Vec::new();
+    for asset in &loaded.project.assets {
+        if asset.tags.iter().any(|t| t == media::probe::UNPROBEABLE_TAG) {
+            continue;
+        }
+        for (kind, meta_key) in &wanted {
+            let applies = match *kind {
+                "thumb" => matches!(asset.asset_type.as_str(), "video" | "image"),
+                "proxy" => matches!(asset.asset_type.as_str(), "video" | "audio"),
+                _ => has_audio(asset),
+            };
+            let present = asset
+                .meta
+                .get(*meta_key)
+                .and_then(|v| v.as_str())
+                .is_some_and(|rel| loaded.resolve_path(rel).exists());
+            if !applies || present {
+                continue;
+            }
+            let pending = loaded.project.tasks.iter().any(|t| {
+                t.kind == *kind
+                    && (t.state == "queued" || t.state == "running")
+                    && t.input.get("assetId").and_then(|v| v.as_str()) == Some(asset.asset_id.as_str())
+            });
+            if pending {
+                continue;
+            }
+            queued.push(auto_task(kind, &asset.asset_id, vec![], "rebuild caches"));
+            *counts.entry(kind.to_string()).or_default() += 1;
+        }
+    }
+
+    if queued.is_empty() {
+        return Ok(counts);
+    }
+    admit_tasks(loaded, queued.len(), &app_handle)?;
+    log::info!("Rebuilding caches: {} task(s) queued", queued.len());
+    loaded.project.tasks.extend(queued.iter().cloned());
+    loaded.project.rebuild_indexes();
+    loaded.dirty = true;
+    drop(guard);
+
+    state.save_notify.notify_one();
+    state.task_notify.notify_one();
+    for task in queued {
+        let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
+    }
+
+    Ok(counts)
+}
+
+/// Queue a `contact_sheet` task tiling every video/image thumbnail into
+/// one PNG. Assets without a thumbnail get a thumb task first, as deps.
+#[tauri::command]
+async fn contact_sheet(
+    columns: Option<u32>,
+    cell_width: Option<u32>,
+    cell_height: Option<u32>,
+    state: tauri::State<'_, Arc<AppState>>,
+    app_handle: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
+    let mut guard = state.inner.lock().await;
+    let loaded = writable_project(&mut guard)?;
+
+    let mut deps = Vec::new();
+    let mut thumb_tasks = Vec::new();
+    for asset in &loaded.project.assets {
+        if !matches!(asset.asset_type.as_str(), "video" | "image")
+            || asset.tags.iter().any(|t| t == media::probe::UNPROBEABLE_TAG)
+            || asset.meta.get("thumbUri").is_some()
+        {
+            continue;
+        }
+        // Wait on a thumb already in flight rather than queueing a second one
+        let pending = loaded.project.tasks.iter().find(|t| {
+            t.kind == "thumb"
+                && (t.state == "queued" || t.state == "running")
+                && t.input.get("assetId").and_then(|v| v.as_str()) == Some(asset.asset_id.as_str())
+        });
+        match pending {
+            Some(t) => deps.push(t.task_id.clone()),
+            None => {
+                let task = auto_task("thumb", &asset.asset_id, vec![], "contact sheet");
+                deps.push(task.task_id.clone());
+                thumb_tasks.push(task);
+            }
+        }
+    }
+
+    let now = crate::clock::now_rfc3339();
+    let task_id = format!(
+        "task_contact_sheet_{}",
+        &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
+    );
+    // Validated by the contact_sheet handler
+    let mut input = serde_json::json!({});
+    if let Some(columns) = columns {
+        input["columns"] = serde_json::json!(columns);
+    }
+    if let Some(width) = cell_width {
+        input["cellWidth"] = serde_json::json!(width);
+    }
+    if let Some(height) = cell_height {
+        input["cellHeight"] = serde_json::json!(height);
+    }
+
+    let task = Task {
+        task_id: task_id.clone(),
+        kind: "contact_sheet".to_string(),
+        state: "queued".to_string(),
+        created_at: now.clone(),
+        updated_at: now.clone(),
+        input,
+        output: None,
+        progress: None,
+        error: None,
+        retries: TaskRetries { count: 0, max: 1 },
+        deps,
+        events: vec![TaskEvent {
+            t: now,
+            level: "info".to_string(),
+            msg: format!("contact_sheet task enqueued ({} thumbnail(s) to generate)", thumb_tasks.len()),
+        }],
+        dedupe_key: None,
+        started_at: None,
+        finished_at: None,
+    };
+
+    let path = loaded.layout().stored_path(WorkspaceSubdir::Exports, task::handlers::CONTACT_SHEET_FILENAME);
+    admit_tasks(loaded, thumb_tasks.len() + 1, &app_handle)?;
+    loaded.project.tasks.extend(thumb_tasks);
+    loaded.project.tasks.push(task.clone());
+    loaded.project.rebuild_indexes();
+    loaded.dirty = true;
+    drop(guard);
+
+    state.save_notify.notify_one();
+    state.task_notify.notify_one();
+    let _ = app_handle.emit("task:updated", serde_json::json!({ "task": task }));
+
+    Ok(serde_json::json!({ "taskId": task_id, "path": path }))
+}
+
+/// Dry run of `export_draft`: the segments, gaps and output length the
+/// export task would encode, without running ffmpeg.
+#[tauri::command]
+async fn export_plan(
+    track_id: Option<String>,
+    start_ms: Option<i64>,
+    end_ms: Option<i64>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<task::export_plan::ExportPlan, String> {
+    let range = task::export_plan::parse_range(start_ms, end_ms)?;
+
+    let guard = state.inner.lock().await;
+    let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+    let track_id = loaded.project.export_track_id(track_id.as_deref())?;
+    task::export_plan::build_export_plan(loaded, &track_id, range)
+        .map_err(|e| format!("{}: {}", e.code, e.message))
+}
+
+/// Export the frame visible at timeline position `t_ms` as a PNG.
+/// Defaults to `<exports dir>/frame_<t>_<timestamp>.png`; an explicit
+/// `output_path` must be a `.png` inside the exports dir. Returns the path written.
+#[tauri::command]
+async fn export_frame(
+    t_ms: i64,
+    output_path: Option<String>,
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<String, String> {
+    if t_ms < 0 {
+        return Err("tMs cannot be negative".to_string());
+    }
+
+    let (src_path, src_ms, is_image, exports_dir) = {
+        let guard = state.inner.lock().await;
+        let loaded = guard.as_ref().ok_or("没有打开的项目")?;
+        let (clip, src_ms) = loaded
+            .project
+            .timeline
+            .visible_clip_at(t_ms)
+            .ok_or(format!("No video clip at {}ms", t_ms))?;
+        let asset = loaded
+            .project
+            .assets
+            .iter()
+            .find(|a| a.asset_id == clip.asset_id)
+            .ok_or(format!("Asset not found: {}", clip.asset_id))?;
+        (
+            loaded.resolve_path(&asset.path),
+            src_ms,
+            asset.asset_type == "image",
+            loaded.layout().dir(WorkspaceSubdir::Exports),
+        )
+    };
+
+    std::fs::create_dir_all(&exports_dir)
+        .map_err(|e| format!("创建导出目录失败: {}", e))?;
+    let out_path = match output_path {
+        Some(p) => frame_output_path(Path::new(&p), &exports_dir)?,
+        None => {
+            let timestamp = crate::clock::now().format("%Y%m%d_%H%M%S");
+            exports_dir.join(format!("frame_{}_{}.png", t_ms, timestamp))
+        }
+    };
+
+    let mut args: Vec<String> = vec!["-y".to_string()];
+    if !is_image {
+        args.extend(["-ss".to_string(), format!("{:.3}", src_ms as f64 / 1000.0)]);
+    }
+    args.extend([
+        "-i".to_string(),
+        src_path.to_string_lossy().to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+        "-f".to_string(),
+        "image2".to_string(),
+        "-c:v".to_string(),
+        "png".to_string(),
+        out_path.to_string_lossy().to_string(),
+    ]);
+
+    // Not a queued task, but registered like one so shutdown can kill it
+    let child_id = format!("export_frame_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]);
+    task::handlers::run_ffmpeg(&args, &child_id, &state).await.map_err(|e| match e.detail {
+        Some(detail) => format!("{}: {}: {}", e.code, e.message, task::handlers::stderr_tail(&detail, 512)),
+        None => format!("{}: {}", e.code, e.message),
+    })?;
+
+    Ok(out_path.to_string_lossy().to_string())
+}
+
+/// Validate a caller-chosen `export_frame` target: a `.png` file whose
+/// directory already exists inside `exports_dir`.
+fn frame_output_path(requested: &Path, exports_dir: &Path) -> Result<PathBuf, String> {
+    let is_png = requested
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    let file_name = requested.file_name().filter(|_| is_png).ok_or("outputPath must be a .png file")?;
+    let parent = match requested.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => return Err("outputPath must be an absolute path in the exports folder".to_string()),
+    };
+    let dir = project::io::canonicalize_within(parent, exports_dir)
+        .map_err(|e| format!("outputPath must be in the exports folder: {}", e))?;
+    Ok(dir.join(file_name))
+}
+
+/// Environment diagnostics: ffmpeg/ffprobe, workspace write access, secret
+/// storage and a full render -> probe -> thumbnail pass. Never errors; each
+/// check reports pass/fail/skip on its own.
+#[tauri::command]
+async fn self_test(
+    state: tauri::State<'_, Arc<AppState>>,
+) -> Result<diagnostics::SelfTestReport, String> {
+    let scratch_dir = {
+        let guard = state.inner.lock().await;
+        guard.as_ref().map(|loaded| loaded.layout().dir(WorkspaceSubdir::Cache))
+    };
+    Ok(diagnostics::run_self_test(scratch_dir).await)
+}
+
 // ============================================================
 // Helpers
 // ============================================================
 
+/// Frame rate to snap timeline edits to, if the project has snapping enabled.
+fn frame_snap_fps(settings: &ProjectSettings) -> Option<f64> {
+    if settings.snap_to_frame && settings.fps > 0 {
+        Some(settings.fps as f64)
+    } else {
+        None
+    }
+}
+
 fn guess_asset_type(path: &Path) -> String {
     let ext = path
         .extension()
@@ -1329,6 +3308,7 @@ fn guess_asset_type(path: &Path) -> String {
         "mp4" | "mov" | "avi" | "mkv" | "webm" | "flv" | "wmv" => "video".to_string(),
         "mp3" | "wav" | "aac" | "flac" | "ogg" | "wma" => "audio".to_string(),
         "png" | "jpg" | "jpeg" | "webp" | "bmp" | "gif" | "tiff" => "image".to_string(),
+        "srt" | "vtt" => "subtitle".to_string(),
         _ => "video".to_string(),
     }
 }
@@ -1342,9 +3322,11 @@ pub fn run() {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
         .format_timestamp_millis()
         .init();
+    paths::init_from_env();
 
     let app_state = AppState::new();
     let state_for_protocol = app_state.clone();
+    let state_for_exit = app_state.clone();
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -1358,17 +3340,40 @@ pub fn run() {
                 .get("range")
                 .and_then(|v| v.to_str().ok())
                 .map(|s| s.to_string());
+            let if_none_match = request
+                .headers()
+                .get("if-none-match")
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string());
+            let allow_origin = {
+                let origin = request.headers().get("origin").and_then(|v| v.to_str().ok());
+                let allowed = state.media_origins.read().map(|o| o.clone()).unwrap_or_default();
+                media::protocol::allow_origin(origin, &allowed)
+            };
 
             let (asset_id, prefer_proxy) = parse_media_uri(&uri);
 
-            match serve_media_asset_sync(&state, &asset_id, prefer_proxy, range_header.as_deref()) {
+            let result = serve_media_asset_sync(
+                &state,
+                &asset_id,
+                prefer_proxy,
+                range_header.as_deref(),
+                if_none_match.as_deref(),
+            );
+            let mut resp = match result {
                 Ok(resp) => resp,
                 Err(e) => tauri::http::Response::builder()
                     .status(500)
-                    .header("Access-Control-Allow-Origin", "*")
+                    .header("Cache-Control", "no-store")
                     .body(e.into_bytes())
                     .unwrap(),
+            };
+            if let Some(origin) = allow_origin.and_then(|o| o.parse::<tauri::http::HeaderValue>().ok()) {
+                let headers = resp.headers_mut();
+                headers.insert("Access-Control-Allow-Origin", origin);
+                headers.insert("Vary", tauri::http::HeaderValue::from_static("Origin"));
             }
+            resp
         })
         .manage(app_state.clone())
         .setup(move |app| {
@@ -1376,9 +3381,7 @@ pub fn run() {
             let state_for_runner = app_state.clone();
             let state_for_saver = app_state.clone();
 
-            let config_dir = handle.path()
-                .app_config_dir()
-                .expect("Failed to resolve app config dir");
+            let config_dir = paths::config_dir(&handle).expect("Failed to resolve app config dir");
             secrets::init(config_dir);
 
             // Spawn debounce saver
@@ -1396,43 +3399,90 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             create_project,
             open_project,
+            open_project_dir,
+            close_project,
+            reload_project,
             save_project,
             get_project,
+            project_search,
+            asset_usages,
+            project_diff,
             import_assets,
             probe_media,
             read_file_base64,
+            artifact_cache_stats,
+            asset_abs_path,
+            set_media_allowed_origins,
             task_enqueue,
             task_retry,
             task_cancel,
+            task_cancel_cascade,
+            task_cancel_all,
+            task_clear_queue,
             task_list,
+            asset_list,
+            asset_set_rating,
+            task_stats,
+            task_output,
+            queue_pause,
+            queue_resume,
+            regenerate_thumb,
+            regenerate_proxy,
             timeline_add_clip,
             timeline_move_clip,
             timeline_trim_clip,
+            timeline_extend_clip_to,
+            timeline_set_clip_asset,
+            timeline_set_clip_meta,
             timeline_remove_clip,
             timeline_reorder_clips,
+            timeline_reorder_tracks,
+            timeline_add_track,
+            timeline_duplicate_track,
+            timeline_batch,
             marker_add,
             marker_update,
             marker_remove,
+            markers_shift,
             create_note,
+            timeline_add_prompt,
             update_note,
             read_note,
             update_generation_settings,
+            update_project_settings,
+            update_persistence_settings,
+            update_import_defaults,
+            update_project_limits,
+            update_view_state,
             providers_list,
             providers_get,
             providers_upsert,
             providers_delete,
+            set_provider_debug_logging,
             secrets_set,
             secrets_exists,
             secrets_delete,
+            secrets_usage,
             providers_test,
+            providers_test_all,
             jimeng_generate_image,
             jimeng_task_status,
             jimeng_credit_balance,
             gen_video_enqueue,
             export_draft,
+            contact_sheet,
+            rebuild_caches,
+            export_plan,
+            export_frame,
+            self_test,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(move |_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                tauri::async_runtime::block_on(task::runner::shutdown(&state_for_exit));
+            }
+        });
 }
 
 fn parse_media_uri(uri: &str) -> (String, bool) {
@@ -1480,6 +3530,7 @@ fn serve_media_asset_sync(
     asset_id: &str,
     prefer_proxy: bool,
     range_header: Option<&str>,
+    if_none_match: Option<&str>,
 ) -> Result<tauri::http::Response<Vec<u8>>, String> {
     let guard = state.inner.blocking_lock();
     let loaded = guard.as_ref().ok_or("No project loaded")?;
@@ -1491,20 +3542,29 @@ fn serve_media_asset_sync(
         .find(|a| a.asset_id == asset_id)
         .ok_or(format!("Asset not found: {}", asset_id))?;
 
-    let file_path = if prefer_proxy {
-        asset
-            .meta
-            .get("proxyUri")
-            .and_then(|v| v.as_str())
-            .map(|p| loaded.project_dir.join(p))
-            .unwrap_or_else(|| loaded.project_dir.join(&asset.path))
-    } else {
-        loaded.project_dir.join(&asset.path)
-    };
+    let derived = |key: &str| asset.meta.get(key).and_then(|v| v.as_str()).map(|p| loaded.resolve_path(p));
+    // A remuxed mp4 stands in for sources the webview can't play
+    let proxy_path = if prefer_proxy { derived("proxyUri") } else { None }.or_else(|| derived("playableUri"));
+    let file_path = proxy_path.unwrap_or_else(|| loaded.resolve_path(&asset.path));
+    let cache_control = media::protocol::CACHE_CONTROL;
 
     drop(guard);
 
-    let file_bytes = std::fs::read(&file_path)
+    let meta = std::fs::metadata(&file_path).map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
+    let (etag, last_modified) = media::protocol::validators(&meta);
+    if media::protocol::not_modified(if_none_match, &etag) {
+        return tauri::http::Response::builder()
+            .status(304)
+            .header("ETag", etag)
+            .header("Cache-Control", cache_control)
+            .body(Vec::new())
+            .map_err(|e| format!("Failed to build response: {}", e));
+    }
+
+    // Large sources and proxies bypass the cache; small image assets hit it
+    let file_bytes = state
+        .artifact_cache
+        .read(&file_path)
         .map_err(|e| format!("Failed to read {}: {}", file_path.display(), e))?;
 
     let total_len = file_bytes.len();
@@ -1524,6 +3584,7 @@ fn serve_media_asset_sync(
         "mp3" => "audio/mpeg",
         "wav" => "audio/wav",
         "aac" => "audio/aac",
+        "m4a" => "audio/mp4",
         "ogg" => "audio/ogg",
         "png" => "image/png",
         "jpg" | "jpeg" => "image/jpeg",
@@ -1532,27 +3593,30 @@ fn serve_media_asset_sync(
         _ => "application/octet-stream",
     };
 
+    let mut builder = tauri::http::Response::builder()
+        .header("Content-Type", content_type)
+        .header("Accept-Ranges", "bytes")
+        .header("Cache-Control", cache_control)
+        .header("ETag", etag);
+    if let Some(last_modified) = last_modified {
+        builder = builder.header("Last-Modified", last_modified);
+    }
+
     if let Some(range) = range_header {
         let (start, end) = parse_range_header(range, total_len);
         let chunk = file_bytes[start..=end].to_vec();
 
-        tauri::http::Response::builder()
+        builder
             .status(206)
-            .header("Content-Type", content_type)
             .header("Content-Length", chunk.len())
             .header("Content-Range", format!("bytes {}-{}/{}", start, end, total_len))
-            .header("Accept-Ranges", "bytes")
-            .header("Access-Control-Allow-Origin", "*")
             .body(chunk)
             .map_err(|e| format!("Failed to build response: {}", e))
     } else {
-        tauri::http::Response::builder()
+        builder
             .status(200)
-            .header("Content-Type", content_type)
             .header("Content-Length", total_len)
-            .header("Accept-Ranges", "bytes")
-            .header("Access-Control-Allow-Origin", "*")
-            .body(file_bytes)
+            .body(Arc::try_unwrap(file_bytes).unwrap_or_else(|shared| shared.to_vec()))
             .map_err(|e| format!("Failed to build response: {}", e))
     }
 }
@@ -1571,3 +3635,50 @@ fn parse_range_header(range: &str, total: usize) -> (usize, usize) {
         .min(total - 1);
     (start, end)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::make_empty_project;
+
+    fn loaded(read_only: bool) -> Option<LoadedProject> {
+        Some(LoadedProject {
+            project: make_empty_project(),
+            json_path: "/tmp/project.json".into(),
+            project_dir: "/tmp".into(),
+            dirty: false,
+            read_only,
+        })
+    }
+
+    #[test]
+    fn mutating_commands_refuse_read_only_projects() {
+        let Err(err) = writable_project(&mut loaded(true)) else { panic!("read-only project was writable") };
+        assert!(err.starts_with("read_only:"), "{}", err);
+        let Err(err) = writable_project_task(&mut loaded(true)) else { panic!("read-only project was writable") };
+        assert_eq!(err.code, "read_only");
+
+        assert!(writable_project(&mut loaded(false)).is_ok());
+        assert!(writable_project_task(&mut loaded(false)).is_ok());
+        assert!(writable_project(&mut None).is_err());
+    }
+
+    #[test]
+    fn frame_output_must_be_a_png_in_the_exports_dir() {
+        let root = std::env::temp_dir().join(format!("cutline_frame_{}", uuid::Uuid::new_v4()));
+        let exports = root.join("exports");
+        std::fs::create_dir_all(exports.join("stills")).unwrap();
+
+        let ok = frame_output_path(&exports.join("stills/f.png"), &exports).unwrap();
+        assert!(ok.ends_with("stills/f.png"));
+        assert!(frame_output_path(&exports.join("f.PNG"), &exports).is_ok());
+
+        assert!(frame_output_path(&exports.join("f.mp4"), &exports).is_err());
+        assert!(frame_output_path(&root.join("f.png"), &exports).is_err());
+        assert!(frame_output_path(&exports.join("../f.png"), &exports).is_err());
+        assert!(frame_output_path(&exports.join("missing/f.png"), &exports).is_err());
+        assert!(frame_output_path(Path::new("f.png"), &exports).is_err());
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+}
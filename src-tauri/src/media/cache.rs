@@ -0,0 +1,192 @@
+//! In-memory LRU for small files served over and over: thumbnails, waveform
+//! images, small image assets. Entries are keyed by path plus mtime and size,
+//! so a regenerated thumbnail is never served stale.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Total bytes held across all entries.
+pub const CACHE_BUDGET_BYTES: usize = 32 * 1024 * 1024;
+/// Files above this are read straight from disk (sources, proxies).
+pub const MAX_ENTRY_BYTES: u64 = 1024 * 1024;
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    /// Bytes and the tick they were last used at.
+    entries: HashMap<CacheKey, (Arc<Vec<u8>>, u64)>,
+    tick: u64,
+    bytes: usize,
+}
+
+impl Lru {
+    fn get(&mut self, key: &CacheKey) -> Option<Arc<Vec<u8>>> {
+        self.tick += 1;
+        let tick = self.tick;
+        self.entries.get_mut(key).map(|(bytes, used)| {
+            *used = tick;
+            bytes.clone()
+        })
+    }
+
+    /// Insert, dropping older versions of the same file and then the least
+    /// recently used entries until the budget fits.
+    fn insert(&mut self, key: CacheKey, bytes: Arc<Vec<u8>>, budget: usize) {
+        let stale: Vec<CacheKey> = self.entries.keys().filter(|k| k.path == key.path).cloned().collect();
+        for k in stale {
+            self.remove(&k);
+        }
+        while self.bytes + bytes.len() > budget {
+            let Some(oldest) = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| k.clone()) else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+        if bytes.len() > budget {
+            return;
+        }
+        self.tick += 1;
+        self.bytes += bytes.len();
+        self.entries.insert(key, (bytes, self.tick));
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some((bytes, _)) = self.entries.remove(key) {
+            self.bytes -= bytes.len();
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Reads that skipped the cache because the file was too large.
+    pub bypassed: u64,
+    pub entries: usize,
+    pub bytes: usize,
+    pub budget_bytes: usize,
+}
+
+pub struct ArtifactCache {
+    budget: usize,
+    max_entry: u64,
+    lru: Mutex<Lru>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    bypassed: AtomicU64,
+}
+
+impl ArtifactCache {
+    pub fn new(budget: usize, max_entry: u64) -> Self {
+        Self {
+            budget,
+            max_entry,
+            lru: Mutex::new(Lru::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            bypassed: AtomicU64::new(0),
+        }
+    }
+
+    /// Read `path`, from memory when this version of it was read before.
+    pub fn read(&self, path: &Path) -> std::io::Result<Arc<Vec<u8>>> {
+        let meta = fs::metadata(path)?;
+        if meta.len() > self.max_entry {
+            self.bypassed.fetch_add(1, Ordering::Relaxed);
+            return fs::read(path).map(Arc::new);
+        }
+        let key = CacheKey { path: path.to_path_buf(), modified: meta.modified().ok(), len: meta.len() };
+        if let Some(bytes) = self.lru.lock().ok().and_then(|mut lru| lru.get(&key)) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(bytes);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let bytes = Arc::new(fs::read(path)?);
+        if let Ok(mut lru) = self.lru.lock() {
+            lru.insert(key, bytes.clone(), self.budget);
+        }
+        Ok(bytes)
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let (entries, bytes) = self.lru.lock().map(|lru| (lru.entries.len(), lru.bytes)).unwrap_or_default();
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            bypassed: self.bypassed.load(Ordering::Relaxed),
+            entries,
+            bytes,
+            budget_bytes: self.budget,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("cutline_cache_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn serves_repeat_reads_from_memory_and_bypasses_large_files() {
+        let dir = temp_dir();
+        let thumb = dir.join("a.jpg");
+        let source = dir.join("a.mp4");
+        fs::write(&thumb, vec![1u8; 100]).unwrap();
+        fs::write(&source, vec![2u8; 300]).unwrap();
+        let cache = ArtifactCache::new(1000, 200);
+
+        assert_eq!(cache.read(&thumb).unwrap().len(), 100);
+        assert_eq!(cache.read(&thumb).unwrap().len(), 100);
+        assert_eq!(cache.read(&source).unwrap().len(), 300);
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.bypassed), (1, 1, 1));
+        assert_eq!((stats.entries, stats.bytes), (1, 100));
+
+        // A rewritten file is a new version: the old entry is replaced
+        fs::write(&thumb, vec![3u8; 150]).unwrap();
+        assert_eq!(cache.read(&thumb).unwrap()[0], 3);
+        assert_eq!((cache.stats().entries, cache.stats().bytes), (1, 150));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn evicts_least_recently_used_to_fit_the_budget() {
+        let dir = temp_dir();
+        let paths: Vec<PathBuf> = (0..3).map(|i| dir.join(format!("{}.png", i))).collect();
+        for p in &paths {
+            fs::write(p, vec![0u8; 100]).unwrap();
+        }
+        let cache = ArtifactCache::new(250, 200);
+
+        cache.read(&paths[0]).unwrap();
+        cache.read(&paths[1]).unwrap();
+        cache.read(&paths[0]).unwrap(); // 0 is now more recent than 1
+        cache.read(&paths[2]).unwrap(); // evicts 1
+        assert_eq!(cache.stats().bytes, 200);
+
+        cache.read(&paths[0]).unwrap();
+        cache.read(&paths[1]).unwrap();
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses), (2, 4));
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1 +1,4 @@
+pub mod cache;
 pub mod probe;
+pub mod protocol;
+pub mod subtitle;
@@ -2,8 +2,10 @@ use serde_json::Value;
 use std::path::Path;
 use std::process::Command;
 
+use crate::project::model::meta_duration_ms;
+
 pub fn ffprobe(file_path: &Path) -> Result<Value, String> {
-    let output = Command::new("ffprobe")
+    let output = Command::new(crate::paths::ffprobe())
         .args([
             "-v",
             "quiet",
@@ -32,6 +34,58 @@ pub fn ffprobe(file_path: &Path) -> Result<Value, String> {
     Ok(json)
 }
 
+/// Result of checking that a file is a playable video.
+pub enum MediaCheck {
+    /// ffprobe parsed the file; carries `extract_video_meta` output.
+    Valid(Value),
+    /// ffprobe ran but the file is truncated, corrupt or has no video.
+    Invalid(String),
+    /// ffprobe itself couldn't be run, so the file can't be judged.
+    Unavailable(String),
+}
+
+pub fn verify_video(file_path: &Path) -> MediaCheck {
+    let output = match Command::new(crate::paths::ffprobe())
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(file_path)
+        .output()
+    {
+        Ok(o) => o,
+        Err(e) => return MediaCheck::Unavailable(format!("执行 ffprobe 失败: {}", e)),
+    };
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return MediaCheck::Invalid(format!("ffprobe 返回错误: {}", stderr.trim()));
+    }
+
+    match serde_json::from_slice::<Value>(&output.stdout) {
+        Ok(data) => match check_video_probe(&data) {
+            Ok(meta) => MediaCheck::Valid(meta),
+            Err(reason) => MediaCheck::Invalid(reason),
+        },
+        Err(e) => MediaCheck::Invalid(format!("解析 ffprobe 输出失败: {}", e)),
+    }
+}
+
+fn check_video_probe(probe_data: &Value) -> Result<Value, String> {
+    let meta = extract_video_meta(probe_data);
+    if meta.get("kind").and_then(|k| k.as_str()) != Some("video") {
+        return Err("no video stream".to_string());
+    }
+    if meta_duration_ms(&meta).unwrap_or(0) <= 0 {
+        return Err("zero or unknown duration".to_string());
+    }
+    Ok(meta)
+}
+
 pub fn extract_video_meta(probe_data: &Value) -> Value {
     let streams = probe_data
         .get("streams")
@@ -54,11 +108,12 @@ pub fn extract_video_meta(probe_data: &Value) -> Value {
             .unwrap_or(false)
     });
 
-    let duration_sec = format
+    let duration_ms = format
         .get("duration")
         .and_then(|d| d.as_str())
         .and_then(|d| d.parse::<f64>().ok())
-        .unwrap_or(0.0);
+        .map(|s| (s * 1000.0).round() as i64)
+        .unwrap_or(0);
 
     let container = format
         .get("format_name")
@@ -85,6 +140,11 @@ pub fn extract_video_meta(probe_data: &Value) -> Value {
                 .and_then(|f| f.as_str())
                 .unwrap_or("0/1"),
         );
+        let avg_fps = parse_fps(
+            vs.get("avg_frame_rate")
+                .and_then(|f| f.as_str())
+                .unwrap_or("0/1"),
+        );
 
         let audio_meta = audio_stream.map(|a| {
             let sample_rate = a
@@ -107,10 +167,12 @@ pub fn extract_video_meta(probe_data: &Value) -> Value {
             "kind": "video",
             "container": container,
             "codec": codec,
-            "durationSec": duration_sec,
+            "durationMs": duration_ms,
             "width": width,
             "height": height,
             "fps": fps,
+            "avgFps": avg_fps,
+            "vfr": is_vfr(fps, avg_fps),
             "audio": audio_meta.unwrap_or(serde_json::json!(null))
         })
     } else if let Some(a) = audio_stream {
@@ -131,7 +193,7 @@ pub fn extract_video_meta(probe_data: &Value) -> Value {
         serde_json::json!({
             "kind": "audio",
             "codec": codec,
-            "durationSec": duration_sec,
+            "durationMs": duration_ms,
             "sampleRate": sample_rate,
             "channels": channels
         })
@@ -183,6 +245,53 @@ pub fn extract_image_meta(file_path: &Path) -> Value {
     })
 }
 
+/// Tag put on imported assets whose probe found nothing playable.
+pub const UNPROBEABLE_TAG: &str = "unprobeable";
+
+/// Check that meta from `extract_video_meta`/`extract_image_meta` describes
+/// something usable: timed media needs a positive duration, images need
+/// dimensions. A corrupt or empty file fails here rather than importing as
+/// a zero-length clip.
+pub fn check_usable_meta(meta: &Value) -> Result<(), String> {
+    match meta.get("kind").and_then(|k| k.as_str()) {
+        Some("video") | Some("audio") => {
+            if meta_duration_ms(meta).unwrap_or(0) > 0 {
+                Ok(())
+            } else {
+                Err("zero or unknown duration".to_string())
+            }
+        }
+        Some("image") => {
+            let width = meta.get("width").and_then(|w| w.as_u64()).unwrap_or(0);
+            let height = meta.get("height").and_then(|h| h.as_u64()).unwrap_or(0);
+            if width > 0 && height > 0 {
+                Ok(())
+            } else {
+                Err("no image dimensions".to_string())
+            }
+        }
+        Some("subtitle") => {
+            if meta.get("cueCount").and_then(|c| c.as_u64()).unwrap_or(0) > 0 {
+                Ok(())
+            } else {
+                Err("no subtitle cues".to_string())
+            }
+        }
+        _ => Err("no usable streams".to_string()),
+    }
+}
+
+/// Relative gap between `r_frame_rate` and `avg_frame_rate` above which a
+/// stream is treated as variable frame rate.
+const VFR_TOLERANCE: f64 = 0.01;
+
+/// Screen recordings and phone video report a nominal rate (often the
+/// timebase, e.g. 90000/1 or 60/1) well above what they actually average.
+/// Either rate missing (0) means unknown, not VFR.
+fn is_vfr(r_fps: f64, avg_fps: f64) -> bool {
+    r_fps > 0.0 && avg_fps > 0.0 && (r_fps - avg_fps).abs() / r_fps > VFR_TOLERANCE
+}
+
 fn parse_fps(rate: &str) -> f64 {
     let parts: Vec<&str> = rate.split('/').collect();
     if parts.len() == 2 {
@@ -194,3 +303,87 @@ fn parse_fps(rate: &str) -> f64 {
     }
     0.0
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe_with(streams: Value, duration: &str) -> Value {
+        serde_json::json!({
+            "streams": streams,
+            "format": { "duration": duration, "format_name": "mov,mp4" }
+        })
+    }
+
+    #[test]
+    fn check_video_probe_accepts_video_with_duration() {
+        let data = probe_with(
+            serde_json::json!([{ "codec_type": "video", "codec_name": "h264", "width": 640, "height": 360, "r_frame_rate": "24/1" }]),
+            "5.000",
+        );
+        let meta = check_video_probe(&data).unwrap();
+        assert_eq!(meta["kind"], "video");
+        assert_eq!(meta["durationMs"], 5000);
+    }
+
+    #[test]
+    fn vfr_flagged_when_rates_differ() {
+        let stream = |r: &str, avg: &str| probe_with(
+            serde_json::json!([{ "codec_type": "video", "codec_name": "h264", "r_frame_rate": r, "avg_frame_rate": avg }]),
+            "5.000",
+        );
+        let phone = extract_video_meta(&stream("60/1", "10800/361"));
+        assert_eq!(phone["vfr"], true);
+        assert_eq!(phone["avgFps"], 29.92);
+
+        assert_eq!(extract_video_meta(&stream("30000/1001", "30000/1001"))["vfr"], false);
+        // Rounding noise and a missing average are not VFR
+        assert_eq!(extract_video_meta(&stream("25/1", "2997/120"))["vfr"], false);
+        assert_eq!(extract_video_meta(&stream("24/1", "0/0"))["vfr"], false);
+    }
+
+    #[test]
+    fn check_video_probe_rejects_missing_video_stream() {
+        let data = probe_with(serde_json::json!([{ "codec_type": "audio" }]), "5.000");
+        assert!(check_video_probe(&data).is_err());
+    }
+
+    #[test]
+    fn check_video_probe_rejects_zero_duration() {
+        let data = probe_with(
+            serde_json::json!([{ "codec_type": "video", "codec_name": "h264" }]),
+            "0.0",
+        );
+        assert!(check_video_probe(&data).is_err());
+    }
+
+    #[test]
+    fn empty_probe_json_is_unusable() {
+        let meta = extract_video_meta(&serde_json::json!({}));
+        assert_eq!(meta["kind"], "unknown");
+        assert_eq!(check_usable_meta(&meta).unwrap_err(), "no usable streams");
+
+        let meta = extract_video_meta(&serde_json::json!({ "streams": [], "format": {} }));
+        assert!(check_usable_meta(&meta).is_err());
+    }
+
+    #[test]
+    fn check_usable_meta_by_kind() {
+        let audio = extract_video_meta(&probe_with(
+            serde_json::json!([{ "codec_type": "audio", "codec_name": "aac" }]),
+            "3.5",
+        ));
+        assert!(check_usable_meta(&audio).is_ok());
+
+        let silent = extract_video_meta(&probe_with(
+            serde_json::json!([{ "codec_type": "audio", "codec_name": "aac" }]),
+            "0",
+        ));
+        assert!(check_usable_meta(&silent).is_err());
+
+        // What import stores when ffprobe itself fails
+        assert!(check_usable_meta(&serde_json::json!({ "kind": "video" })).is_err());
+        assert!(check_usable_meta(&serde_json::json!({ "kind": "image", "width": 0, "height": 0 })).is_err());
+        assert!(check_usable_meta(&serde_json::json!({ "kind": "image", "width": 64, "height": 64 })).is_ok());
+    }
+}
@@ -0,0 +1,123 @@
+//! Response header policy for the `media://` protocol.
+
+/// Origins the webview loads from: tauri on macOS/Linux, tauri on Windows,
+/// and the Vite dev server.
+pub const DEFAULT_ALLOWED_ORIGINS: &[&str] = &[
+    "tauri://localhost",
+    "http://tauri.localhost",
+    "https://tauri.localhost",
+    "http://localhost:1420",
+];
+
+/// Sources can be replaced in place and proxies are regenerated under the
+/// same URI, so every response is revalidated; an unchanged file costs a 304.
+pub const CACHE_CONTROL: &str = "no-cache";
+
+/// `ETag` and `Last-Modified` for a file, both derived from its size and
+/// mtime like the artifact cache keys.
+pub fn validators(meta: &std::fs::Metadata) -> (String, Option<String>) {
+    let modified = meta.modified().ok();
+    let nanos = modified
+        .and_then(|m| m.duration_since(std::time::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos());
+    let etag = format!("\"{:x}-{:x}\"", meta.len(), nanos);
+    let last_modified = modified.map(|m| {
+        chrono::DateTime::<chrono::Utc>::from(m).format("%a, %d %b %Y %H:%M:%S GMT").to_string()
+    });
+    (etag, last_modified)
+}
+
+/// Whether an `If-None-Match` header matches `etag`, so a 304 can be sent.
+pub fn not_modified(if_none_match: Option<&str>, etag: &str) -> bool {
+    if_none_match.is_some_and(|header| {
+        header
+            .split(',')
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag)
+    })
+}
+
+/// Containers the webview can't stream; imports of these get a `remux` task
+/// producing an mp4 served in their place (`meta.playableUri`).
+pub const REMUX_EXTENSIONS: &[&str] = &["mkv", "avi", "flv", "wmv"];
+
+pub fn needs_remux(path: &str) -> bool {
+    std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| REMUX_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Value for `Access-Control-Allow-Origin`, or `None` to omit the header.
+/// Echoes the request origin when it's allowed; `"*"` in the list allows any.
+pub fn allow_origin(request_origin: Option<&str>, allowed: &[String]) -> Option<String> {
+    if allowed.iter().any(|o| o == "*") {
+        return Some("*".to_string());
+    }
+    let origin = request_origin?;
+    allowed
+        .iter()
+        .any(|o| o.eq_ignore_ascii_case(origin))
+        .then(|| origin.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> Vec<String> {
+        DEFAULT_ALLOWED_ORIGINS.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn remux_only_for_unstreamable_containers() {
+        assert!(needs_remux("workspace/assets/video/clip.MKV"));
+        assert!(needs_remux("a/b.wmv"));
+        assert!(!needs_remux("a/b.mp4"));
+        assert!(!needs_remux("a/b.webm"));
+        assert!(!needs_remux("a/mkv"));
+    }
+
+    #[test]
+    fn echoes_allowed_origin_only() {
+        assert_eq!(
+            allow_origin(Some("tauri://localhost"), &defaults()).as_deref(),
+            Some("tauri://localhost")
+        );
+        assert_eq!(allow_origin(Some("https://evil.example"), &defaults()), None);
+        assert_eq!(allow_origin(None, &defaults()), None);
+    }
+
+    #[test]
+    fn wildcard_allows_any() {
+        let allowed = vec!["*".to_string()];
+        assert_eq!(allow_origin(None, &allowed).as_deref(), Some("*"));
+        assert_eq!(allow_origin(Some("https://x.example"), &allowed).as_deref(), Some("*"));
+    }
+
+    #[test]
+    fn rewritten_file_gets_a_new_etag() {
+        let dir = std::env::temp_dir().join(format!("cutline_etag_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let proxy = dir.join("proxy.mp4");
+        std::fs::write(&proxy, vec![0u8; 10]).unwrap();
+        let (etag, last_modified) = validators(&std::fs::metadata(&proxy).unwrap());
+        assert!(last_modified.is_some_and(|m| m.ends_with(" GMT")));
+
+        std::fs::write(&proxy, vec![0u8; 20]).unwrap();
+        let (new_etag, _) = validators(&std::fs::metadata(&proxy).unwrap());
+        assert_ne!(etag, new_etag);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn if_none_match_compares_etags() {
+        let etag = "\"a-1f\"";
+        assert!(not_modified(Some("\"a-1f\""), etag));
+        assert!(not_modified(Some("\"b-2\", W/\"a-1f\""), etag));
+        assert!(not_modified(Some("*"), etag));
+        assert!(!not_modified(Some("\"b-2\""), etag));
+        assert!(!not_modified(None, etag));
+    }
+}
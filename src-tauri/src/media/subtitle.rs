@@ -0,0 +1,158 @@
+use serde_json::Value;
+
+/// One timed caption. Times are milliseconds from the start of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub text: String,
+}
+
+/// Subtitle format for a file extension, if it is one we import.
+pub fn format_for_extension(ext: &str) -> Option<&'static str> {
+    match ext {
+        "srt" => Some("srt"),
+        "vtt" => Some("vtt"),
+        _ => None,
+    }
+}
+
+/// Read a subtitle file. Bytes that aren't UTF-8 (e.g. a GBK-encoded SRT)
+/// become U+FFFD instead of failing the import or export.
+pub fn read_lossy(path: &std::path::Path) -> std::io::Result<String> {
+    std::fs::read(path).map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// `hh:mm:ss,mmm`, `hh:mm:ss.mmm` or (WebVTT) `mm:ss.mmm`.
+fn parse_timestamp(s: &str) -> Option<i64> {
+    let (clock, millis) = s.trim().rsplit_once([',', '.'])?;
+    let millis: i64 = millis.parse().ok()?;
+    let parts: Vec<i64> = clock
+        .split(':')
+        .map(|p| p.parse().ok())
+        .collect::<Option<Vec<_>>>()?;
+    let (h, m, sec) = match parts.as_slice() {
+        [h, m, s] => (*h, *m, *s),
+        [m, s] => (0, *m, *s),
+        _ => return None,
+    };
+    Some(((h * 60 + m) * 60 + sec) * 1000 + millis)
+}
+
+/// `start --> end [vtt cue settings]`
+fn parse_timing(line: &str) -> Option<(i64, i64)> {
+    let (start, rest) = line.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+    Some((parse_timestamp(start)?, parse_timestamp(end)?))
+}
+
+/// Parse SRT or WebVTT. Blocks without a timing line (the WEBVTT header,
+/// NOTE/STYLE blocks) are skipped, as are cues that end before they start.
+pub fn parse(content: &str) -> Vec<Cue> {
+    let normalized = content.trim_start_matches('\u{feff}').replace("\r\n", "\n");
+    let mut cues = Vec::new();
+    for block in normalized.split("\n\n") {
+        let mut lines = block.lines().skip_while(|l| !l.contains("-->"));
+        let Some((start_ms, end_ms)) = lines.next().and_then(parse_timing) else {
+            continue;
+        };
+        if end_ms <= start_ms {
+            continue;
+        }
+        let text = lines.collect::<Vec<_>>().join("\n");
+        cues.push(Cue { start_ms, end_ms, text });
+    }
+    cues
+}
+
+fn format_srt_timestamp(ms: i64) -> String {
+    let ms = ms.max(0);
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        ms % 1000
+    )
+}
+
+pub fn to_srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(i, c)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                format_srt_timestamp(c.start_ms),
+                format_srt_timestamp(c.end_ms),
+                c.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Asset meta for a subtitle file. `durationMs` is the end of the last cue,
+/// so a clip placed from it covers every caption.
+pub fn extract_subtitle_meta(content: &str, format: &str) -> Value {
+    let cues = parse(content);
+    let duration_ms = cues.iter().map(|c| c.end_ms).max().unwrap_or(0);
+    serde_json::json!({
+        "kind": "subtitle",
+        "format": format,
+        "cueCount": cues.len(),
+        "durationMs": duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_srt() {
+        let srt = "1\r\n00:00:01,000 --> 00:00:02,500\r\nHello\r\nworld\r\n\r\n2\r\n00:01:00,000 --> 00:01:01,000\r\nBye\r\n";
+        let cues = parse(srt);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0], Cue { start_ms: 1000, end_ms: 2500, text: "Hello\nworld".to_string() });
+        assert_eq!(cues[1].start_ms, 60_000);
+    }
+
+    #[test]
+    fn reads_non_utf8_files_lossily() {
+        let path = std::env::temp_dir().join(format!("cutline_subs_{}.srt", uuid::Uuid::new_v4()));
+        // "你好" in GBK
+        std::fs::write(&path, b"1\n00:00:01,000 --> 00:00:02,000\n\xc4\xe3\xba\xc3\n").unwrap();
+        let cues = parse(&read_lossy(&path).unwrap());
+        assert_eq!(cues.len(), 1);
+        assert!(cues[0].text.contains('\u{fffd}'));
+        std::fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn parses_vtt_with_header_notes_and_settings() {
+        let vtt = "WEBVTT\n\nNOTE a comment\n\nintro\n00:01.000 --> 00:02.000 align:start\nHi\n\n01:00:00.000 --> 01:00:01.500\nLate\n";
+        let cues = parse(vtt);
+        assert_eq!(cues.len(), 2);
+        assert_eq!(cues[0], Cue { start_ms: 1000, end_ms: 2000, text: "Hi".to_string() });
+        assert_eq!(cues[1].end_ms, 3_601_500);
+    }
+
+    #[test]
+    fn srt_round_trips() {
+        let cues = vec![
+            Cue { start_ms: 0, end_ms: 1500, text: "a".to_string() },
+            Cue { start_ms: 3_723_004, end_ms: 3_724_000, text: "b".to_string() },
+        ];
+        let srt = to_srt(&cues);
+        assert!(srt.contains("01:02:03,004 --> 01:02:04,000"), "{}", srt);
+        assert_eq!(parse(&srt), cues);
+    }
+
+    #[test]
+    fn meta_reports_cues_and_duration() {
+        let meta = extract_subtitle_meta("1\n00:00:01,000 --> 00:00:04,000\nx\n", "srt");
+        assert_eq!(meta["cueCount"], 1);
+        assert_eq!(meta["durationMs"], 4000);
+    }
+}
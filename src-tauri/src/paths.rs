@@ -0,0 +1,74 @@
+//! Locations that can be overridden from the environment, for CI and portable
+//! installs: `CUTLINE_FFMPEG` / `CUTLINE_FFPROBE` pick the binaries (default:
+//! looked up in PATH) and `CUTLINE_CONFIG_DIR` replaces the app config dir
+//! holding providers.json and the secrets fallback file.
+
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use tauri::Manager;
+
+pub const FFMPEG_ENV: &str = "CUTLINE_FFMPEG";
+pub const FFPROBE_ENV: &str = "CUTLINE_FFPROBE";
+pub const CONFIG_DIR_ENV: &str = "CUTLINE_CONFIG_DIR";
+
+static FFMPEG: OnceLock<PathBuf> = OnceLock::new();
+static FFPROBE: OnceLock<PathBuf> = OnceLock::new();
+static CONFIG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// An override is only taken when the variable is set and not blank.
+fn override_from(value: Option<OsString>) -> Option<PathBuf> {
+    value.filter(|v| !v.to_string_lossy().trim().is_empty()).map(PathBuf::from)
+}
+
+/// Read the overrides once at startup, logging where each path comes from.
+pub fn init_from_env() {
+    for (name, var, cell) in [("ffmpeg", FFMPEG_ENV, &FFMPEG), ("ffprobe", FFPROBE_ENV, &FFPROBE)] {
+        match override_from(std::env::var_os(var)) {
+            Some(path) => {
+                log::info!("{}: {} (from {})", name, path.display(), var);
+                let _ = cell.set(path);
+            }
+            None => log::info!("{}: looked up in PATH", name),
+        }
+    }
+    if let Some(dir) = override_from(std::env::var_os(CONFIG_DIR_ENV)) {
+        log::info!("Config dir: {} (from {})", dir.display(), CONFIG_DIR_ENV);
+        let _ = CONFIG_DIR.set(dir);
+    }
+}
+
+pub fn ffmpeg() -> &'static Path {
+    FFMPEG.get().map(PathBuf::as_path).unwrap_or(Path::new("ffmpeg"))
+}
+
+pub fn ffprobe() -> &'static Path {
+    FFPROBE.get().map(PathBuf::as_path).unwrap_or(Path::new("ffprobe"))
+}
+
+/// `CUTLINE_CONFIG_DIR` if set, else Tauri's app config dir.
+pub fn config_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
+    if let Some(dir) = CONFIG_DIR.get() {
+        return Ok(dir.clone());
+    }
+    app_handle
+        .path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blank_overrides_fall_back_to_defaults() {
+        assert_eq!(override_from(None), None);
+        assert_eq!(override_from(Some(OsString::from("  "))), None);
+        assert_eq!(
+            override_from(Some(OsString::from("/opt/ffmpeg/bin/ffmpeg"))),
+            Some(PathBuf::from("/opt/ffmpeg/bin/ffmpeg"))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+use serde::Deserialize;
+
+use super::model::{snap_to_frame, Clip, ProjectFile};
+use crate::media::probe::UNPROBEABLE_TAG;
+
+/// One edit in a `timeline_batch`. Mirrors the single-edit timeline commands.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum TimelineOp {
+    /// `clip_id` may be supplied so later ops in the same batch can refer to
+    /// the new clip; otherwise one is generated.
+    Add {
+        track_id: String,
+        asset_id: String,
+        start_ms: i64,
+        #[serde(default)]
+        clip_id: Option<String>,
+        #[serde(default)]
+        allow_type_mismatch: bool,
+    },
+    Move {
+        clip_id: String,
+        new_start_ms: i64,
+    },
+    Trim {
+        clip_id: String,
+        #[serde(default)]
+        in_ms: Option<i64>,
+        #[serde(default)]
+        out_ms: Option<i64>,
+    },
+    Remove {
+        clip_id: String,
+    },
+    Reorder {
+        track_id: String,
+        clip_ids: Vec<String>,
+    },
+}
+
+impl TimelineOp {
+    fn name(&self) -> &'static str {
+        match self {
+            TimelineOp::Add { .. } => "add",
+            TimelineOp::Move { .. } => "move",
+            TimelineOp::Trim { .. } => "trim",
+            TimelineOp::Remove { .. } => "remove",
+            TimelineOp::Reorder { .. } => "reorder",
+        }
+    }
+}
+
+/// Apply `ops` in order to a copy of `project`. On success the copy (with
+/// duration and indexes rebuilt) is returned together with the ids of added
+/// clips; on the first failing op the error names it and `project` is untouched.
+pub fn apply_timeline_ops(
+    project: &ProjectFile,
+    ops: &[TimelineOp],
+    snap_fps: Option<f64>,
+) -> Result<(ProjectFile, Vec<String>), String> {
+    let mut next = project.clone();
+    let mut added = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        apply_op(&mut next, op, snap_fps, &mut added)
+            .map_err(|e| format!("op {} ({}): {}", i, op.name(), e))?;
+    }
+    next.timeline.recalc_duration();
+    next.rebuild_indexes();
+    Ok((next, added))
+}
+
+fn snap(ms: i64, snap_fps: Option<f64>) -> i64 {
+    match snap_fps {
+        Some(fps) => snap_to_frame(ms, fps),
+        None => ms,
+    }
+}
+
+fn apply_op(
+    pf: &mut ProjectFile,
+    op: &TimelineOp,
+    snap_fps: Option<f64>,
+    added: &mut Vec<String>,
+) -> Result<(), String> {
+    match op {
+        TimelineOp::Add { track_id, asset_id, start_ms, clip_id, allow_type_mismatch } => {
+            let asset = pf
+                .assets
+                .iter()
+                .find(|a| &a.asset_id == asset_id)
+                .ok_or(format!("Asset not found: {}", asset_id))?;
+            if asset.tags.iter().any(|t| t == UNPROBEABLE_TAG) {
+                return Err(format!("Asset {} could not be probed and can't be placed on the timeline", asset_id));
+            }
+            let track = pf
+                .timeline
+                .tracks
+                .iter()
+                .find(|t| &t.track_id == track_id)
+                .ok_or(format!("Track not found: {}", track_id))?;
+            if !allow_type_mismatch && !track.accepts_asset_type(&asset.asset_type) {
+                return Err(format!(
+                    "type_mismatch: {} asset {} can't be placed on {} track {}",
+                    asset.asset_type, asset_id, track.track_type, track_id
+                ));
+            }
+
+            let duration_ms = asset
+                .clip_duration_ms(&pf.project.settings)
+                .map_err(|e| format!("unknown_duration: {}", e))?;
+            let duration_ms = snap(duration_ms, snap_fps);
+            let clip_id = clip_id.clone().unwrap_or_else(|| {
+                format!("clip_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8])
+            });
+            if pf.timeline.clips.contains_key(&clip_id) {
+                return Err(format!("Clip {} already exists", clip_id));
+            }
+
+            let clip = Clip {
+                clip_id: clip_id.clone(),
+                asset_id: asset_id.clone(),
+                track_id: track_id.clone(),
+                start_ms: snap((*start_ms).max(0), snap_fps),
+                duration_ms,
+                in_ms: 0,
+                out_ms: duration_ms,
+                label_color: None,
+                note: None,
+            };
+            if let Some(track) = pf.timeline.tracks.iter_mut().find(|t| &t.track_id == track_id) {
+                track.clip_ids.push(clip_id.clone());
+            }
+            pf.timeline.clips.insert(clip_id.clone(), clip);
+            added.push(clip_id);
+        }
+        TimelineOp::Move { clip_id, new_start_ms } => {
+            let clip = pf
+                .timeline
+                .clips
+                .get_mut(clip_id)
+                .ok_or(format!("Clip not found: {}", clip_id))?;
+            clip.start_ms = snap((*new_start_ms).max(0), snap_fps);
+        }
+        TimelineOp::Trim { clip_id, in_ms, out_ms } => {
+            let clip = pf
+                .timeline
+                .clips
+                .get_mut(clip_id)
+                .ok_or(format!("Clip not found: {}", clip_id))?;
+            let source_duration_ms = pf
+                .assets
+                .iter()
+                .find(|a| a.asset_id == clip.asset_id)
+                .and_then(|a| a.duration_ms());
+            clip.trim(*in_ms, *out_ms, snap_fps, source_duration_ms)
+                .map_err(|e| format!("{}: {}", e.code, e.message))?;
+        }
+        TimelineOp::Remove { clip_id } => {
+            pf.timeline
+                .clips
+                .remove(clip_id)
+                .ok_or(format!("Clip not found: {}", clip_id))?;
+            for track in &mut pf.timeline.tracks {
+                track.clip_ids.retain(|id| id != clip_id);
+            }
+        }
+        TimelineOp::Reorder { track_id, clip_ids } => {
+            let track = pf
+                .timeline
+                .tracks
+                .iter_mut()
+                .find(|t| &t.track_id == track_id)
+                .ok_or(format!("Track not found: {}", track_id))?;
+            for cid in clip_ids {
+                if !track.clip_ids.contains(cid) {
+                    return Err(format!("Clip {} not in track {}", cid, track_id));
+                }
+            }
+            track.clip_ids = clip_ids.clone();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::{make_empty_project, make_prompt_asset};
+    use crate::project::model::{Asset, DEFAULT_STILL_DURATION_MS};
+
+    fn add(clip_id: &str, start_ms: i64) -> TimelineOp {
+        TimelineOp::Add {
+            track_id: "trk_t".into(),
+            asset_id: "p1".into(),
+            start_ms,
+            clip_id: Some(clip_id.into()),
+            allow_type_mismatch: false,
+        }
+    }
+
+    fn project() -> ProjectFile {
+        let mut pf = make_empty_project();
+        pf.assets.push(make_prompt_asset("p1", "a"));
+        pf
+    }
+
+    #[test]
+    fn ops_apply_in_order_and_may_refer_to_added_clips() {
+        let pf = project();
+        let ops = vec![
+            add("c1", 0),
+            add("c2", 5000),
+            TimelineOp::Trim { clip_id: "c1".into(), in_ms: Some(1000), out_ms: None },
+            TimelineOp::Move { clip_id: "c2".into(), new_start_ms: 4000 },
+            TimelineOp::Reorder { track_id: "trk_t".into(), clip_ids: vec!["c2".into(), "c1".into()] },
+        ];
+        let (next, added) = apply_timeline_ops(&pf, &ops, None).unwrap();
+        assert_eq!(added, vec!["c1", "c2"]);
+        assert_eq!(next.timeline.clips["c1"].duration_ms, 4000);
+        assert_eq!(next.timeline.clips["c2"].start_ms, 4000);
+        assert_eq!(next.timeline.tracks[2].clip_ids, vec!["c2", "c1"]);
+        assert_eq!(next.timeline.duration_ms, 9000);
+        assert_eq!(next.indexes.clip_by_id.len(), 2);
+    }
+
+    #[test]
+    fn failing_op_rejects_the_whole_batch() {
+        let pf = project();
+        let ops = vec![
+            add("c1", 0),
+            TimelineOp::Trim { clip_id: "c1".into(), in_ms: Some(6000), out_ms: None },
+        ];
+        let err = apply_timeline_ops(&pf, &ops, None).unwrap_err();
+        assert!(err.starts_with("op 1 (trim)"), "{}", err);
+        assert!(err.contains("invalid_trim_out_le_in"), "{}", err);
+        assert!(pf.timeline.clips.is_empty());
+    }
+
+    #[test]
+    fn add_checks_track_type_and_duplicate_ids() {
+        let pf = project();
+        let wrong_track = TimelineOp::Add {
+            track_id: "trk_v".into(),
+            asset_id: "p1".into(),
+            start_ms: 0,
+            clip_id: None,
+            allow_type_mismatch: false,
+        };
+        let err = apply_timeline_ops(&pf, &[wrong_track], None).unwrap_err();
+        assert!(err.contains("type_mismatch"), "{}", err);
+
+        let err = apply_timeline_ops(&pf, &[add("c1", 0), add("c1", 100)], None).unwrap_err();
+        assert!(err.contains("already exists"), "{}", err);
+    }
+
+    #[test]
+    fn add_uses_still_duration_for_images_and_refuses_zero_length_media() {
+        let mut pf = project();
+        let image = Asset { asset_type: "image".into(), ..make_prompt_asset("img", "a") };
+        let silent = Asset {
+            asset_type: "video".into(),
+            meta: serde_json::json!({ "durationMs": 0 }),
+            ..make_prompt_asset("vid", "b")
+        };
+        pf.assets.extend([image, silent]);
+        let add_to_video = |asset_id: &str| TimelineOp::Add {
+            track_id: "trk_v".into(),
+            asset_id: asset_id.into(),
+            start_ms: 0,
+            clip_id: Some(format!("c_{}", asset_id)),
+            allow_type_mismatch: false,
+        };
+
+        let (next, _) = apply_timeline_ops(&pf, &[add_to_video("img")], None).unwrap();
+        assert_eq!(next.timeline.clips["c_img"].duration_ms, DEFAULT_STILL_DURATION_MS);
+
+        let err = apply_timeline_ops(&pf, &[add_to_video("vid")], None).unwrap_err();
+        assert!(err.contains("unknown_duration"), "{}", err);
+    }
+
+    #[test]
+    fn ops_deserialize_from_tagged_json() {
+        let ops: Vec<TimelineOp> = serde_json::from_value(serde_json::json!([
+            { "op": "move", "clipId": "c1", "newStartMs": 100 },
+            { "op": "remove", "clipId": "c2" },
+        ]))
+        .unwrap();
+        assert!(matches!(&ops[0], TimelineOp::Move { new_start_ms: 100, .. }));
+        assert!(matches!(&ops[1], TimelineOp::Remove { .. }));
+    }
+}
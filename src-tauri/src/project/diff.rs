@@ -0,0 +1,131 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::model::ProjectFile;
+
+/// Ids added, removed or changed in `other` relative to the current project.
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntityDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+}
+
+#[cfg(test)]
+impl EntityDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectDiff {
+    pub assets: EntityDiff,
+    pub clips: EntityDiff,
+    pub tracks: EntityDiff,
+    pub markers: EntityDiff,
+}
+
+fn to_map<'a, T: Serialize + 'a>(
+    items: impl Iterator<Item = (&'a String, &'a T)>,
+) -> BTreeMap<&'a String, serde_json::Value> {
+    items
+        .map(|(id, item)| (id, serde_json::to_value(item).unwrap_or(serde_json::Value::Null)))
+        .collect()
+}
+
+fn diff_maps(
+    ours: BTreeMap<&String, serde_json::Value>,
+    theirs: BTreeMap<&String, serde_json::Value>,
+) -> EntityDiff {
+    let mut diff = EntityDiff::default();
+    for (id, value) in &theirs {
+        match ours.get(id) {
+            None => diff.added.push((*id).clone()),
+            Some(v) if v != value => diff.changed.push((*id).clone()),
+            Some(_) => {}
+        }
+    }
+    for id in ours.keys() {
+        if !theirs.contains_key(id) {
+            diff.removed.push((*id).clone());
+        }
+    }
+    diff
+}
+
+/// Structural diff of `other` against `ours`, keyed by entity id.
+pub fn diff_projects(ours: &ProjectFile, other: &ProjectFile) -> ProjectDiff {
+    ProjectDiff {
+        assets: diff_maps(
+            to_map(ours.assets.iter().map(|a| (&a.asset_id, a))),
+            to_map(other.assets.iter().map(|a| (&a.asset_id, a))),
+        ),
+        clips: diff_maps(
+            to_map(ours.timeline.clips.iter()),
+            to_map(other.timeline.clips.iter()),
+        ),
+        tracks: diff_maps(
+            to_map(ours.timeline.tracks.iter().map(|t| (&t.track_id, t))),
+            to_map(other.timeline.tracks.iter().map(|t| (&t.track_id, t))),
+        ),
+        markers: diff_maps(
+            to_map(ours.timeline.markers.iter().map(|m| (&m.marker_id, m))),
+            to_map(other.timeline.markers.iter().map(|m| (&m.marker_id, m))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::{make_empty_project, make_prompt_asset};
+    use crate::project::model::Clip;
+
+    fn clip(id: &str, start_ms: i64) -> Clip {
+        Clip {
+            clip_id: id.to_string(),
+            asset_id: "p1".to_string(),
+            track_id: "trk_t".to_string(),
+            start_ms,
+            duration_ms: 1000,
+            in_ms: 0,
+            out_ms: 1000,
+            label_color: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn identical_projects_have_empty_diff() {
+        let pf = make_empty_project();
+        let diff = diff_projects(&pf, &pf.clone());
+        assert!(diff.assets.is_empty());
+        assert!(diff.clips.is_empty());
+        assert!(diff.tracks.is_empty());
+        assert!(diff.markers.is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed() {
+        let mut ours = make_empty_project();
+        ours.assets.push(make_prompt_asset("p1", "a"));
+        ours.assets.push(make_prompt_asset("p2", "b"));
+        ours.timeline.clips.insert("c1".to_string(), clip("c1", 0));
+
+        let mut theirs = ours.clone();
+        theirs.assets.retain(|a| a.asset_id != "p2");
+        theirs.assets.push(make_prompt_asset("p3", "c"));
+        theirs.timeline.clips.get_mut("c1").unwrap().start_ms = 500;
+        theirs.timeline.tracks.pop();
+
+        let diff = diff_projects(&ours, &theirs);
+        assert_eq!(diff.assets.added, vec!["p3"]);
+        assert_eq!(diff.assets.removed, vec!["p2"]);
+        assert!(diff.assets.changed.is_empty());
+        assert_eq!(diff.clips.changed, vec!["c1"]);
+        assert_eq!(diff.tracks.removed, vec!["trk_t"]);
+    }
+}
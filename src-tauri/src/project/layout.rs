@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+
+use super::io::resolve_project_path;
+use super::model::ProjectPaths;
+
+/// Every directory Cutline writes into, grouped under the three roots that
+/// `ProjectPaths` configures (`assetsDir`, `cacheDir`, `exportsDir`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceSubdir {
+    VideoAssets,
+    AudioAssets,
+    ImageAssets,
+    Prompts,
+    SubtitleAssets,
+    Cache,
+    Thumbs,
+    Proxies,
+    Captures,
+    Generated,
+    Exports,
+}
+
+impl WorkspaceSubdir {
+    pub const ALL: [WorkspaceSubdir; 11] = [
+        WorkspaceSubdir::VideoAssets,
+        WorkspaceSubdir::AudioAssets,
+        WorkspaceSubdir::ImageAssets,
+        WorkspaceSubdir::Prompts,
+        WorkspaceSubdir::SubtitleAssets,
+        WorkspaceSubdir::Cache,
+        WorkspaceSubdir::Thumbs,
+        WorkspaceSubdir::Proxies,
+        WorkspaceSubdir::Captures,
+        WorkspaceSubdir::Generated,
+        WorkspaceSubdir::Exports,
+    ];
+
+    /// Where imported media of `asset_type` is copied.
+    pub fn for_asset_type(asset_type: &str) -> Self {
+        match asset_type {
+            "audio" => WorkspaceSubdir::AudioAssets,
+            "image" => WorkspaceSubdir::ImageAssets,
+            "prompt" => WorkspaceSubdir::Prompts,
+            "subtitle" => WorkspaceSubdir::SubtitleAssets,
+            _ => WorkspaceSubdir::VideoAssets,
+        }
+    }
+}
+
+const DEFAULT_ASSETS_DIR: &str = "workspace/assets";
+const DEFAULT_CACHE_DIR: &str = "workspace/cache";
+const DEFAULT_EXPORTS_DIR: &str = "workspace/exports";
+
+/// Single source of truth for on-disk locations. Built from `ProjectPaths`;
+/// the defaults reproduce the original fixed `workspace/...` layout.
+///
+/// Roots are stored the way project.json references files: relative to the
+/// project dir (with `workspace/...` following a relocated workspace root),
+/// or absolute, e.g. `cacheDir: "/mnt/fast/cutline-cache"` for proxies on
+/// another drive.
+#[derive(Debug, Clone)]
+pub struct WorkspaceLayout {
+    project_dir: PathBuf,
+    workspace_dir: PathBuf,
+    assets: String,
+    cache: String,
+    exports: String,
+}
+
+/// "./workspace/cache/" -> "workspace/cache"; blank -> `default`.
+fn normalize_root(configured: &str, default: &str) -> String {
+    let trimmed = configured.trim();
+    let trimmed = trimmed.strip_prefix("./").unwrap_or(trimmed);
+    let trimmed = trimmed.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        default.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+impl WorkspaceLayout {
+    pub fn new(project_dir: &Path, workspace_dir: &Path, paths: &ProjectPaths) -> Self {
+        Self {
+            project_dir: project_dir.to_path_buf(),
+            workspace_dir: workspace_dir.to_path_buf(),
+            assets: normalize_root(&paths.assets_dir, DEFAULT_ASSETS_DIR),
+            cache: normalize_root(&paths.cache_dir, DEFAULT_CACHE_DIR),
+            exports: normalize_root(&paths.exports_dir, DEFAULT_EXPORTS_DIR),
+        }
+    }
+
+    /// The directory as referenced from project.json.
+    pub fn stored_dir(&self, sub: WorkspaceSubdir) -> String {
+        let (root, leaf) = match sub {
+            WorkspaceSubdir::VideoAssets => (&self.assets, Some("video")),
+            WorkspaceSubdir::AudioAssets => (&self.assets, Some("audio")),
+            WorkspaceSubdir::ImageAssets => (&self.assets, Some("images")),
+            WorkspaceSubdir::Prompts => (&self.assets, Some("prompts")),
+            WorkspaceSubdir::SubtitleAssets => (&self.assets, Some("subtitles")),
+            WorkspaceSubdir::Cache => (&self.cache, None),
+            WorkspaceSubdir::Thumbs => (&self.cache, Some("thumbs")),
+            WorkspaceSubdir::Proxies => (&self.cache, Some("proxy")),
+            WorkspaceSubdir::Captures => (&self.cache, Some("captures")),
+            WorkspaceSubdir::Generated => (&self.cache, Some("gen")),
+            WorkspaceSubdir::Exports => (&self.exports, None),
+        };
+        match leaf {
+            Some(leaf) => format!("{}/{}", root, leaf),
+            None => root.clone(),
+        }
+    }
+
+    /// Path to store in project.json for `file_name` inside `sub`.
+    pub fn stored_path(&self, sub: WorkspaceSubdir, file_name: &str) -> String {
+        format!("{}/{}", self.stored_dir(sub), file_name)
+    }
+
+    /// Absolute directory on disk.
+    pub fn dir(&self, sub: WorkspaceSubdir) -> PathBuf {
+        resolve_project_path(&self.project_dir, &self.workspace_dir, &self.stored_dir(sub))
+    }
+
+    /// Absolute assets/cache/exports roots; files Cutline hands out must live
+    /// under one of them.
+    pub fn roots(&self) -> [PathBuf; 3] {
+        [&self.assets, &self.cache, &self.exports]
+            .map(|root| resolve_project_path(&self.project_dir, &self.workspace_dir, root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(assets: &str, cache: &str, exports: &str) -> ProjectPaths {
+        ProjectPaths {
+            workspace_root: "./workspace".to_string(),
+            assets_dir: assets.to_string(),
+            cache_dir: cache.to_string(),
+            exports_dir: exports.to_string(),
+        }
+    }
+
+    #[test]
+    fn default_paths_match_the_original_layout() {
+        let layout = WorkspaceLayout::new(
+            Path::new("/proj"),
+            Path::new("/proj/workspace"),
+            &paths("./workspace/assets", "./workspace/cache", "./workspace/exports"),
+        );
+        assert_eq!(layout.stored_path(WorkspaceSubdir::Thumbs, "a.jpg"), "workspace/cache/thumbs/a.jpg");
+        assert_eq!(layout.stored_dir(WorkspaceSubdir::Proxies), "workspace/cache/proxy");
+        assert_eq!(layout.stored_dir(WorkspaceSubdir::ImageAssets), "workspace/assets/images");
+        assert_eq!(layout.stored_dir(WorkspaceSubdir::SubtitleAssets), "workspace/assets/subtitles");
+        assert_eq!(layout.stored_dir(WorkspaceSubdir::Exports), "workspace/exports");
+        assert_eq!(layout.dir(WorkspaceSubdir::Generated), PathBuf::from("/proj/workspace/cache/gen"));
+    }
+
+    #[test]
+    fn relocated_workspace_root_is_followed() {
+        let layout = WorkspaceLayout::new(
+            Path::new("/proj"),
+            Path::new("/ext/ws"),
+            &paths("./workspace/assets", "./workspace/cache/", ""),
+        );
+        assert_eq!(layout.dir(WorkspaceSubdir::Proxies), PathBuf::from("/ext/ws/cache/proxy"));
+        assert_eq!(layout.dir(WorkspaceSubdir::Exports), PathBuf::from("/ext/ws/exports"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn absolute_cache_dir_moves_cache_only() {
+        let layout = WorkspaceLayout::new(
+            Path::new("/proj"),
+            Path::new("/proj/workspace"),
+            &paths("./workspace/assets", "/mnt/fast/cache", "./workspace/exports"),
+        );
+        assert_eq!(layout.stored_path(WorkspaceSubdir::Proxies, "a.mp4"), "/mnt/fast/cache/proxy/a.mp4");
+        assert_eq!(layout.dir(WorkspaceSubdir::Proxies).join("a.mp4"), PathBuf::from("/mnt/fast/cache/proxy/a.mp4"));
+        assert_eq!(layout.dir(WorkspaceSubdir::VideoAssets), PathBuf::from("/proj/workspace/assets/video"));
+        assert_eq!(layout.roots()[1], PathBuf::from("/mnt/fast/cache"));
+    }
+}
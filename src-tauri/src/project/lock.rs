@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Advisory lock written next to project.json while a project is open,
+/// so a second app instance doesn't clobber our saves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LockInfo {
+    pub pid: u32,
+    pub acquired_at: String,
+}
+
+pub fn lock_path(json_path: &Path) -> PathBuf {
+    let mut name = json_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "project.json".into());
+    name.push(".lock");
+    json_path.with_file_name(name)
+}
+
+pub fn read_lock(json_path: &Path) -> Option<LockInfo> {
+    let content = fs::read_to_string(lock_path(json_path)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Take the lock for `json_path`.
+/// A lock held by a live process other than us is refused unless `force` is set;
+/// locks left by dead processes (or unreadable lock files) are reclaimed.
+pub fn acquire(json_path: &Path, force: bool) -> Result<(), String> {
+    let own_pid = std::process::id();
+    if let Some(existing) = read_lock(json_path) {
+        if !force && existing.pid != own_pid && is_pid_alive(existing.pid) {
+            return Err(format!(
+                "project_locked: 项目已在其他窗口打开 (pid {}, since {})",
+                existing.pid, existing.acquired_at
+            ));
+        }
+        if existing.pid != own_pid {
+            log::warn!(
+                "Reclaiming project lock from pid {} (force={})",
+                existing.pid, force
+            );
+        }
+    }
+
+    let info = LockInfo {
+        pid: own_pid,
+        acquired_at: crate::clock::now_rfc3339(),
+    };
+    let content = serde_json::to_string(&info).map_err(|e| format!("序列化锁文件失败: {}", e))?;
+    fs::write(lock_path(json_path), content).map_err(|e| format!("写入锁文件失败: {}", e))
+}
+
+/// A lock taken by `acquire_guarded`, released on drop unless `keep` is
+/// called, so an open that fails halfway doesn't leave the project locked.
+pub struct LockGuard {
+    /// None when the lock was already ours before (the loaded project still
+    /// relies on it) or once kept.
+    json_path: Option<PathBuf>,
+}
+
+impl LockGuard {
+    pub fn keep(mut self) {
+        self.json_path = None;
+    }
+}
+
+impl Drop for LockGuard {
+    fn drop(&mut self) {
+        if let Some(path) = self.json_path.take() {
+            release(&path);
+        }
+    }
+}
+
+/// `acquire`, returning a guard that gives the lock back on error paths.
+pub fn acquire_guarded(json_path: &Path, force: bool) -> Result<LockGuard, String> {
+    let already_ours = read_lock(json_path).is_some_and(|info| info.pid == std::process::id());
+    acquire(json_path, force)?;
+    Ok(LockGuard { json_path: (!already_ours).then(|| json_path.to_path_buf()) })
+}
+
+/// Remove the lock if we still own it. Errors are logged, not returned,
+/// since this runs on close/shutdown paths.
+pub fn release(json_path: &Path) {
+    match read_lock(json_path) {
+        Some(info) if info.pid == std::process::id() => {
+            if let Err(e) = fs::remove_file(lock_path(json_path)) {
+                log::warn!("Failed to remove project lock: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn is_pid_alive(pid: u32) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+#[cfg(windows)]
+fn is_pid_alive(pid: u32) -> bool {
+    std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+        .unwrap_or(false)
+}
+
+#[cfg(all(unix, not(target_os = "linux")))]
+fn is_pid_alive(pid: u32) -> bool {
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_json() -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("cutline_lock_{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let json = dir.join("project.json");
+        (dir, json)
+    }
+
+    fn write_lock(json: &Path, pid: u32) {
+        let info = LockInfo { pid, acquired_at: "2026-01-01T00:00:00Z".to_string() };
+        fs::write(lock_path(json), serde_json::to_string(&info).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn lock_path_appends_suffix() {
+        assert_eq!(
+            lock_path(Path::new("/p/project.json")),
+            PathBuf::from("/p/project.json.lock")
+        );
+    }
+
+    #[test]
+    fn acquire_and_release_round_trip() {
+        let (dir, json) = temp_json();
+        acquire(&json, false).unwrap();
+        assert_eq!(read_lock(&json).unwrap().pid, std::process::id());
+        // Re-opening from the same instance is fine
+        acquire(&json, false).unwrap();
+        release(&json);
+        assert!(!lock_path(&json).exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn guard_releases_a_new_lock_unless_kept() {
+        let (dir, json) = temp_json();
+        drop(acquire_guarded(&json, false).unwrap());
+        assert!(!lock_path(&json).exists());
+
+        acquire_guarded(&json, false).unwrap().keep();
+        assert!(lock_path(&json).exists());
+        // A lock we already held survives a failed reopen
+        drop(acquire_guarded(&json, false).unwrap());
+        assert!(lock_path(&json).exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn live_foreign_lock_is_refused_unless_forced() {
+        let (dir, json) = temp_json();
+        // pid 1 is always alive on Linux
+        write_lock(&json, 1);
+        let err = acquire(&json, false).unwrap_err();
+        assert!(err.starts_with("project_locked"));
+        acquire(&json, true).unwrap();
+        assert_eq!(read_lock(&json).unwrap().pid, std::process::id());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn stale_lock_is_reclaimed() {
+        let (dir, json) = temp_json();
+        write_lock(&json, u32::MAX - 1);
+        acquire(&json, false).unwrap();
+        assert_eq!(read_lock(&json).unwrap().pid, std::process::id());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn release_leaves_foreign_lock_alone() {
+        let (dir, json) = temp_json();
+        write_lock(&json, u32::MAX - 1);
+        release(&json);
+        assert!(lock_path(&json).exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,2 +1,7 @@
+pub mod batch;
+pub mod diff;
 pub mod io;
+pub mod layout;
+pub mod lock;
 pub mod model;
+pub mod query;
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 // ============================================================
 // Cutline Project JSON v0 — Rust 数据结构
@@ -29,6 +29,20 @@ pub struct ProjectMeta {
     pub paths: ProjectPaths,
     pub timeline_id: String,
     pub default_draft_track_ids: DraftTrackIds,
+    /// Editor UI state restored on reopen. Absent in older projects.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view_state: Option<ViewState>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ViewState {
+    #[serde(default)]
+    pub playhead_ms: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub selected_track_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub zoom: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +54,249 @@ pub struct ProjectSettings {
     pub sample_rate: u32,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub generation: Option<GenerationSettings>,
+    #[serde(default)]
+    pub snap_to_frame: bool,
+    /// Debounce before writing project.json after an edit (default 800ms).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub save_debounce_ms: Option<u64>,
+    /// Max events kept per task (default 200).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_task_events: Option<usize>,
+    /// Store project.json gzipped as project.json.gz (default off).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compress_on_disk: Option<bool>,
+    /// Tasks `import_assets` enqueues when the call doesn't say otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_defaults: Option<ImportOptions>,
+    /// How clips of another aspect are fitted into the project frame on
+    /// export and in proxies (default `fit`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale_mode: Option<ScaleMode>,
+    /// Length of a new clip made from an image (default 3000ms).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub still_duration_ms: Option<i64>,
+    /// Keyframe spacing of video proxies, in ms (default 500): scrubbing
+    /// lands on a keyframe at most this far from the playhead.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_keyframe_interval_ms: Option<u32>,
+    /// Caps on tasks and clips (default: on, see `ProjectLimits`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<ProjectLimits>,
+}
+
+/// Soft caps that keep a runaway script from growing project.json without
+/// bound. On by default with 5000 tasks and 10000 clips; `enabled: false`
+/// opts out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectLimits {
+    pub enabled: bool,
+    pub max_tasks: usize,
+    pub max_clips: usize,
+    /// What an enqueue past `max_tasks` does (default `prune`).
+    pub on_task_limit: TaskLimitAction,
+}
+
+impl Default for ProjectLimits {
+    fn default() -> Self {
+        ProjectLimits {
+            enabled: true,
+            max_tasks: DEFAULT_MAX_TASKS,
+            max_clips: DEFAULT_MAX_CLIPS,
+            on_task_limit: TaskLimitAction::default(),
+        }
+    }
+}
+
+/// `prune` drops the oldest finished tasks to make room; `reject` refuses
+/// the enqueue with `limit_exceeded`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskLimitAction {
+    #[default]
+    Prune,
+    Reject,
+}
+
+/// `fit` letterboxes/pillarboxes the whole picture into the frame; `fill`
+/// covers the frame and crops the overflow.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScaleMode {
+    #[default]
+    Fit,
+    Fill,
+}
+
+/// Which background tasks an import enqueues per asset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ImportOptions {
+    /// Thumbnail for video/image
+    pub generate_thumb: bool,
+    /// Preview proxy for video/audio
+    pub generate_proxy: bool,
+    /// Waveform image for audio and video with sound
+    pub generate_waveform: bool,
+    /// Register the probed asset and enqueue nothing
+    pub probe_only: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            generate_thumb: true,
+            generate_proxy: true,
+            generate_waveform: false,
+            probe_only: false,
+        }
+    }
+}
+
+/// Per-call `import_assets` options; unset fields fall back to the project's
+/// `importDefaults`.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportOverrides {
+    pub generate_thumb: Option<bool>,
+    pub generate_proxy: Option<bool>,
+    pub generate_waveform: Option<bool>,
+    pub probe_only: Option<bool>,
+}
+
+impl ImportOverrides {
+    pub fn apply(self, base: ImportOptions) -> ImportOptions {
+        ImportOptions {
+            generate_thumb: self.generate_thumb.unwrap_or(base.generate_thumb),
+            generate_proxy: self.generate_proxy.unwrap_or(base.generate_proxy),
+            generate_waveform: self.generate_waveform.unwrap_or(base.generate_waveform),
+            probe_only: self.probe_only.unwrap_or(base.probe_only),
+        }
+    }
+}
+
+pub const TRACK_TYPES: [&str; 4] = ["video", "audio", "text", "subtitle"];
+pub const ASPECT_RATIOS: [&str; 8] = ["16:9", "9:16", "1:1", "4:3", "3:4", "21:9", "3:2", "2:3"];
+pub const FPS_LIMITS: (u32, u32) = (1, 240);
+pub const RESOLUTION_LIMITS: (u32, u32) = (16, 8192);
+pub const SAMPLE_RATES: [u32; 5] = [22050, 32000, 44100, 48000, 96000];
+
+pub const DEFAULT_SAVE_DEBOUNCE_MS: u64 = 800;
+pub const DEFAULT_STILL_DURATION_MS: i64 = 3000;
+pub const DEFAULT_PROXY_KEYFRAME_INTERVAL_MS: u32 = 500;
+pub const PROXY_KEYFRAME_INTERVAL_LIMITS_MS: (u32, u32) = (100, 10_000);
+pub const SAVE_DEBOUNCE_LIMITS_MS: (u64, u64) = (50, 60_000);
+pub const DEFAULT_MAX_TASKS: usize = 5000;
+pub const DEFAULT_MAX_CLIPS: usize = 10_000;
+pub const PROJECT_LIMITS_RANGE: (usize, usize) = (100, 1_000_000);
+
+impl ProjectSettings {
+    pub fn compress_on_disk(&self) -> bool {
+        self.compress_on_disk.unwrap_or(false)
+    }
+
+    pub fn effective_save_debounce_ms(&self) -> u64 {
+        self.save_debounce_ms.unwrap_or(DEFAULT_SAVE_DEBOUNCE_MS)
+    }
+
+    pub fn effective_max_task_events(&self) -> usize {
+        self.max_task_events.unwrap_or(DEFAULT_MAX_TASK_EVENTS)
+    }
+
+    /// Cap passed to [`Task::append_event`]; a hand-edited project.json is
+    /// clamped to the valid range.
+    pub fn task_event_cap(&self) -> usize {
+        let (min_ev, max_ev) = MAX_TASK_EVENTS_LIMITS;
+        self.effective_max_task_events().clamp(min_ev, max_ev)
+    }
+
+    pub fn effective_import_options(&self) -> ImportOptions {
+        self.import_defaults.unwrap_or_default()
+    }
+
+    pub fn effective_scale_mode(&self) -> ScaleMode {
+        self.scale_mode.unwrap_or_default()
+    }
+
+    pub fn effective_still_duration_ms(&self) -> i64 {
+        self.still_duration_ms.unwrap_or(DEFAULT_STILL_DURATION_MS)
+    }
+
+    pub fn effective_proxy_keyframe_interval_ms(&self) -> u32 {
+        self.proxy_keyframe_interval_ms.unwrap_or(DEFAULT_PROXY_KEYFRAME_INTERVAL_MS)
+    }
+
+    pub fn effective_limits(&self) -> ProjectLimits {
+        self.limits.unwrap_or_default()
+    }
+
+    /// Check format fields, then the persistence fields.
+    pub fn validate(&self) -> Result<(), String> {
+        let (min_fps, max_fps) = FPS_LIMITS;
+        if !(min_fps..=max_fps).contains(&self.fps) {
+            return Err(format!("fps must be between {} and {}, got {}", min_fps, max_fps, self.fps));
+        }
+        let (min_px, max_px) = RESOLUTION_LIMITS;
+        let Resolution { width, height } = self.resolution;
+        for (name, px) in [("width", width), ("height", height)] {
+            if !(min_px..=max_px).contains(&px) {
+                return Err(format!("resolution {} must be between {} and {}, got {}", name, min_px, max_px, px));
+            }
+            // yuv420p exports need even dimensions.
+            if px % 2 != 0 {
+                return Err(format!("resolution {} must be even, got {}", name, px));
+            }
+        }
+        if !ASPECT_RATIOS.contains(&self.aspect_ratio.as_str()) {
+            return Err(format!(
+                "unknown aspectRatio {}; expected one of {}",
+                self.aspect_ratio,
+                ASPECT_RATIOS.join(", ")
+            ));
+        }
+        if !SAMPLE_RATES.contains(&self.sample_rate) {
+            return Err(format!("unsupported sampleRate {}", self.sample_rate));
+        }
+        if self.effective_still_duration_ms() <= 0 {
+            return Err(format!("stillDurationMs must be positive, got {}", self.effective_still_duration_ms()));
+        }
+        let (min_kf, max_kf) = PROXY_KEYFRAME_INTERVAL_LIMITS_MS;
+        let keyframe_ms = self.effective_proxy_keyframe_interval_ms();
+        if !(min_kf..=max_kf).contains(&keyframe_ms) {
+            return Err(format!(
+                "proxyKeyframeIntervalMs must be between {} and {}, got {}",
+                min_kf, max_kf, keyframe_ms
+            ));
+        }
+        self.validate_persistence()
+    }
+
+    pub fn validate_persistence(&self) -> Result<(), String> {
+        let (min_ms, max_ms) = SAVE_DEBOUNCE_LIMITS_MS;
+        let debounce = self.effective_save_debounce_ms();
+        if !(min_ms..=max_ms).contains(&debounce) {
+            return Err(format!(
+                "saveDebounceMs must be between {} and {}, got {}",
+                min_ms, max_ms, debounce
+            ));
+        }
+        let (min_ev, max_ev) = MAX_TASK_EVENTS_LIMITS;
+        let events = self.effective_max_task_events();
+        if !(min_ev..=max_ev).contains(&events) {
+            return Err(format!(
+                "maxTaskEvents must be between {} and {}, got {}",
+                min_ev, max_ev, events
+            ));
+        }
+        let (min_cap, max_cap) = PROJECT_LIMITS_RANGE;
+        let limits = self.effective_limits();
+        for (name, cap) in [("maxTasks", limits.max_tasks), ("maxClips", limits.max_clips)] {
+            if !(min_cap..=max_cap).contains(&cap) {
+                return Err(format!("limits.{} must be between {} and {}, got {}", name, min_cap, max_cap, cap));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -128,19 +385,39 @@ pub struct Task {
     pub events: Vec<TaskEvent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dedupe_key: Option<String>,
+    /// When the task last entered `running`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<String>,
+    /// When the task last reached a terminal state.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<String>,
 }
 
-pub const MAX_TASK_EVENTS: usize = 200;
+pub const DEFAULT_MAX_TASK_EVENTS: usize = 200;
+pub const MAX_TASK_EVENTS_LIMITS: (usize, usize) = (10, 10_000);
 
 impl Task {
-    pub fn append_event(&mut self, level: &str, msg: &str) {
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state.as_str(), "succeeded" | "failed" | "canceled")
+    }
+
+    /// Wall time of the last run, from `started_at` to `finished_at`.
+    pub fn duration_ms(&self) -> Option<i64> {
+        let start = chrono::DateTime::parse_from_rfc3339(self.started_at.as_deref()?).ok()?;
+        let end = chrono::DateTime::parse_from_rfc3339(self.finished_at.as_deref()?).ok()?;
+        Some((end - start).num_milliseconds().max(0))
+    }
+
+    /// Log an event, keeping only the newest `cap` (see
+    /// [`ProjectSettings::task_event_cap`]).
+    pub fn append_event(&mut self, level: &str, msg: &str, cap: usize) {
         self.events.push(TaskEvent {
-            t: chrono::Utc::now().to_rfc3339(),
+            t: crate::clock::now_rfc3339(),
             level: level.to_string(),
             msg: msg.to_string(),
         });
-        if self.events.len() > MAX_TASK_EVENTS {
-            let drain_count = self.events.len() - MAX_TASK_EVENTS;
+        if self.events.len() > cap {
+            let drain_count = self.events.len() - cap;
             self.events.drain(0..drain_count);
         }
     }
@@ -165,6 +442,16 @@ pub struct TaskError {
     pub detail: Option<String>,
 }
 
+impl TaskError {
+    pub fn new(code: &str, message: impl Into<String>) -> Self {
+        Self {
+            code: code.to_string(),
+            message: message.into(),
+            detail: None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct TaskRetries {
@@ -194,8 +481,39 @@ pub struct Timeline {
     pub duration_ms: i64,
 }
 
+/// Where to put something on the timeline, as an alternative to plain ms.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum TimelinePosition {
+    Ms { value: i64 },
+    /// Fraction (0.0-1.0) of the timeline's `duration_ms` as it is when the
+    /// command runs, before the edit itself; 0.5 is halfway.
+    Percent { value: f64 },
+}
+
 impl Timeline {
+    /// Resolve a command's position from exactly one of `ms` (the default
+    /// form) and `position`. Percent positions round to the nearest ms.
+    pub fn resolve_position(&self, ms: Option<i64>, position: Option<TimelinePosition>) -> Result<i64, String> {
+        match (ms, position) {
+            (Some(ms), None) | (None, Some(TimelinePosition::Ms { value: ms })) => Ok(ms),
+            (None, Some(TimelinePosition::Percent { value })) => {
+                if !(0.0..=1.0).contains(&value) {
+                    return Err(format!("percent position must be between 0 and 1, got {}", value));
+                }
+                Ok((self.duration_ms as f64 * value).round() as i64)
+            }
+            (None, None) => Err("a position (ms or percent) is required".to_string()),
+            (Some(_), Some(_)) => Err("give either a ms position or a percent position, not both".to_string()),
+        }
+    }
+
+    /// Recompute the timeline length, first repairing any clip whose
+    /// `duration_ms` has drifted from its in/out range.
     pub fn recalc_duration(&mut self) {
+        for clip_id in self.repair_clip_durations() {
+            log::warn!("Repaired duration of clip {}", clip_id);
+        }
         self.duration_ms = self
             .clips
             .values()
@@ -203,6 +521,182 @@ impl Timeline {
             .max()
             .unwrap_or(0);
     }
+
+    /// Snap every clip's start and in/out points to frame boundaries at `fps`
+    /// (e.g. after the project frame rate changes). Returns how many clips moved.
+    pub fn snap_clips_to_frames(&mut self, fps: f64) -> usize {
+        let mut changed = 0;
+        for clip in self.clips.values_mut() {
+            let before = (clip.start_ms, clip.in_ms, clip.out_ms);
+            clip.start_ms = snap_to_frame(clip.start_ms, fps);
+            clip.in_ms = snap_to_frame(clip.in_ms, fps);
+            clip.out_ms = snap_to_frame(clip.out_ms, fps);
+            if clip.out_ms <= clip.in_ms {
+                // Never collapse a clip; keep at least one frame.
+                clip.out_ms = clip.in_ms + (1000.0 / fps).round().max(1.0) as i64;
+            }
+            clip.duration_ms = clip.out_ms - clip.in_ms;
+            if (clip.start_ms, clip.in_ms, clip.out_ms) != before {
+                changed += 1;
+            }
+        }
+        self.recalc_duration();
+        changed
+    }
+
+    /// Enforce `duration_ms == out_ms - in_ms` on every clip (clips play at
+    /// 1x; there is no speed field). An empty or inverted in/out range is
+    /// rebuilt from `duration_ms` instead. Returns the ids of repaired clips.
+    pub fn repair_clip_durations(&mut self) -> Vec<String> {
+        let mut repaired = Vec::new();
+        for clip in self.clips.values_mut() {
+            if !clip.has_consistent_duration() {
+                if clip.out_ms > clip.in_ms {
+                    clip.duration_ms = clip.out_ms - clip.in_ms;
+                } else {
+                    clip.duration_ms = clip.duration_ms.max(1);
+                    clip.out_ms = clip.in_ms + clip.duration_ms;
+                }
+                repaired.push(clip.clip_id.clone());
+            }
+        }
+        repaired.sort();
+        repaired
+    }
+
+    /// Reorder `tracks` to match `track_ids`, which must be a permutation of
+    /// the current track ids. Later tracks composite over earlier ones.
+    pub fn reorder_tracks(&mut self, track_ids: &[String]) -> Result<(), String> {
+        let mut requested: Vec<&str> = track_ids.iter().map(String::as_str).collect();
+        let mut current: Vec<&str> = self.tracks.iter().map(|t| t.track_id.as_str()).collect();
+        requested.sort_unstable();
+        current.sort_unstable();
+        if requested != current {
+            return Err(format!(
+                "trackIds must list every track exactly once (current: {})",
+                self.tracks.iter().map(|t| t.track_id.as_str()).collect::<Vec<_>>().join(", ")
+            ));
+        }
+        let mut tracks = std::mem::take(&mut self.tracks);
+        for id in track_ids {
+            if let Some(pos) = tracks.iter().position(|t| &t.track_id == id) {
+                self.tracks.push(tracks.remove(pos));
+            }
+        }
+        Ok(())
+    }
+
+    /// Copy `track_id` and all of its clips (new ids, same asset, in/out and
+    /// start) onto a new track placed right after it. A blank `new_name`
+    /// becomes "<name> copy". Callers recalc duration and rebuild indexes.
+    pub fn duplicate_track(&mut self, track_id: &str, new_name: &str) -> Result<Track, String> {
+        let pos = self
+            .tracks
+            .iter()
+            .position(|t| t.track_id == track_id)
+            .ok_or_else(|| format!("Track not found: {}", track_id))?;
+        let source = &self.tracks[pos];
+        let short_id = || uuid::Uuid::new_v4().to_string().replace("-", "")[..8].to_string();
+
+        let mut track = Track {
+            track_id: format!("trk_{}_{}", source.track_type, short_id()),
+            track_type: source.track_type.clone(),
+            name: match new_name.trim() {
+                "" => format!("{} copy", source.name),
+                name => name.to_string(),
+            },
+            clip_ids: Vec::with_capacity(source.clip_ids.len()),
+        };
+        let copies: Vec<Clip> = source
+            .clip_ids
+            .iter()
+            .filter_map(|id| self.clips.get(id))
+            .map(|clip| Clip {
+                clip_id: format!("clip_{}", short_id()),
+                track_id: track.track_id.clone(),
+                ..clip.clone()
+            })
+            .collect();
+        for clip in copies {
+            track.clip_ids.push(clip.clip_id.clone());
+            self.clips.insert(clip.clip_id.clone(), clip);
+        }
+        self.tracks.insert(pos + 1, track.clone());
+        Ok(track)
+    }
+
+    /// A track's clips in playback order: by `start_ms`, with ties broken by
+    /// position in `track.clip_ids` (never by HashMap iteration order).
+    pub fn ordered_clips(&self, track: &Track) -> Vec<&Clip> {
+        let mut clips: Vec<(usize, &Clip)> = track
+            .clip_ids
+            .iter()
+            .filter_map(|id| self.clips.get(id))
+            .enumerate()
+            .collect();
+        clips.sort_by_key(|(pos, c)| (c.start_ms, *pos));
+        clips.into_iter().map(|(_, c)| c).collect()
+    }
+
+    /// The clip showing at `t_ms` on the video tracks. Later tracks draw on top,
+    /// so they win when clips overlap. Returns the clip and its source time.
+    pub fn visible_clip_at(&self, t_ms: i64) -> Option<(&Clip, i64)> {
+        self.tracks
+            .iter()
+            .rev()
+            .filter(|t| t.track_type == "video")
+            .find_map(|track| {
+                track
+                    .clip_ids
+                    .iter()
+                    .filter_map(|id| self.clips.get(id))
+                    .find(|c| c.start_ms <= t_ms && t_ms < c.start_ms + c.duration_ms)
+            })
+            .map(|c| (c, c.in_ms + (t_ms - c.start_ms)))
+    }
+
+    /// Shift every marker at or after `after_ms` by `delta_ms`, clamping at 0.
+    /// Returns how many markers moved.
+    pub fn shift_markers(&mut self, after_ms: i64, delta_ms: i64) -> usize {
+        let mut moved = 0;
+        for marker in &mut self.markers {
+            if marker.t_ms >= after_ms {
+                marker.t_ms = (marker.t_ms + delta_ms).max(0);
+                if let Some(end) = marker.end_ms {
+                    // Keep at least 1ms of span when clamping at 0.
+                    marker.end_ms = Some((end + delta_ms).max(marker.t_ms + 1));
+                }
+                moved += 1;
+            }
+        }
+        self.markers.sort_by_key(|m| m.t_ms);
+        moved
+    }
+}
+
+/// Clip length used when an asset has no recorded duration (e.g. prompts).
+pub const DEFAULT_CLIP_DURATION_MS: i64 = 5000;
+
+/// Duration recorded in asset meta, in ms. `durationMs` is canonical; the
+/// legacy float `durationSec` is still read for projects not yet migrated.
+pub fn meta_duration_ms(meta: &serde_json::Value) -> Option<i64> {
+    meta.get("durationMs")
+        .and_then(|v| v.as_i64())
+        .or_else(|| {
+            meta.get("durationSec")
+                .and_then(|v| v.as_f64())
+                .map(|s| (s * 1000.0).round() as i64)
+        })
+}
+
+/// Round a millisecond position to the nearest frame boundary at `fps`.
+/// Non-positive fps leaves the value untouched.
+pub fn snap_to_frame(ms: i64, fps: f64) -> i64 {
+    if fps <= 0.0 {
+        return ms;
+    }
+    let frame = (ms as f64 * fps / 1000.0).round();
+    (frame * 1000.0 / fps).round() as i64
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -218,9 +712,26 @@ pub struct Track {
     #[serde(rename = "type")]
     pub track_type: String,
     pub name: String,
+    /// Authoritative clip order: clips are played by `start_ms`, and clips
+    /// sharing a start play in the order they appear here.
     pub clip_ids: Vec<String>,
 }
 
+impl Track {
+    /// Whether an asset of `asset_type` belongs on this track:
+    /// video/image on video tracks, audio on audio tracks, prompts on text tracks.
+    pub fn accepts_asset_type(&self, asset_type: &str) -> bool {
+        matches!(
+            (self.track_type.as_str(), asset_type),
+            ("video", "video")
+                | ("video", "image")
+                | ("audio", "audio")
+                | ("text", "prompt")
+                | ("subtitle", "subtitle")
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Clip {
@@ -231,6 +742,131 @@ pub struct Clip {
     pub duration_ms: i64,
     pub in_ms: i64,
     pub out_ms: i64,
+    /// Organizational only (timeline color tag, free-form note); export
+    /// ignores both.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+/// Longest clip note accepted, in chars.
+pub const MAX_CLIP_NOTE_CHARS: usize = 2000;
+
+impl Clip {
+    pub fn has_consistent_duration(&self) -> bool {
+        self.out_ms > self.in_ms && self.duration_ms == self.out_ms - self.in_ms
+    }
+
+    /// Set in/out (snapped to frames when `snap_fps` is set) and recompute the
+    /// duration. The prospective pair is validated together, so moving both
+    /// points past the old ones in one call works in either direction, and
+    /// nothing changes unless the result is valid. `out_of_bounds` is only
+    /// checked when `out_ms` is given and the source length is known.
+    pub fn trim(
+        &mut self,
+        in_ms: Option<i64>,
+        out_ms: Option<i64>,
+        snap_fps: Option<f64>,
+        source_duration_ms: Option<i64>,
+    ) -> Result<(), TaskError> {
+        let snap = |ms: i64| match snap_fps {
+            Some(fps) => snap_to_frame(ms, fps),
+            None => ms,
+        };
+        if let Some(new_in) = in_ms.filter(|ms| *ms < 0) {
+            return Err(TaskError::new(
+                "invalid_trim_negative_in",
+                format!("inMs cannot be negative (got {})", new_in),
+            ));
+        }
+        let new_in = in_ms.map(snap).unwrap_or(self.in_ms);
+        let new_out = out_ms.map(snap).unwrap_or(self.out_ms);
+        if new_out <= new_in {
+            return Err(TaskError::new(
+                "invalid_trim_out_le_in",
+                format!("outMs ({}) must be greater than inMs ({})", new_out, new_in),
+            ));
+        }
+        // Checked before snapping: rounding the source's last frame up is fine
+        if let (Some(out), Some(source)) = (out_ms, source_duration_ms) {
+            if out > source {
+                return Err(TaskError::new(
+                    "out_of_bounds",
+                    format!("outMs ({}) is past the end of the source ({}ms)", out, source),
+                ));
+            }
+        }
+        self.in_ms = new_in;
+        self.out_ms = new_out;
+        self.duration_ms = new_out - new_in;
+        Ok(())
+    }
+
+    /// Move the out-point so the clip ends exactly at timeline `target_ms`,
+    /// e.g. the next clip's start. `asset_too_short` when the source (if its
+    /// length is known) runs out first.
+    pub fn extend_to(&mut self, target_ms: i64, source_duration_ms: Option<i64>) -> Result<(), TaskError> {
+        if target_ms <= self.start_ms {
+            return Err(TaskError::new(
+                "invalid_target",
+                format!("targetMs ({}) must be after the clip start ({})", target_ms, self.start_ms),
+            ));
+        }
+        let new_out = self.in_ms + (target_ms - self.start_ms);
+        if let Some(source) = source_duration_ms.filter(|d| new_out > *d) {
+            return Err(TaskError::new(
+                "asset_too_short",
+                format!(
+                    "Source is {}ms; the clip can end at {} at the latest",
+                    source,
+                    self.start_ms + source - self.in_ms
+                ),
+            ));
+        }
+        self.out_ms = new_out;
+        self.duration_ms = new_out - self.in_ms;
+        Ok(())
+    }
+
+    /// Replace the label color and note; `None` or blank clears either. A
+    /// color is a CSS hex (`#rgb`/`#rrggbb`) or a lowercase name like `red`.
+    pub fn set_meta(&mut self, label_color: Option<String>, note: Option<String>) -> Result<(), String> {
+        let label_color = label_color.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+        if let Some(color) = &label_color {
+            let hex = color
+                .strip_prefix('#')
+                .is_some_and(|h| matches!(h.len(), 3 | 6) && h.chars().all(|c| c.is_ascii_hexdigit()));
+            let name = color.len() <= 20 && color.chars().all(|c| c.is_ascii_lowercase());
+            if !hex && !name {
+                return Err(format!("invalid labelColor {:?}; expected #rgb, #rrggbb or a color name", color));
+            }
+        }
+        let note = note.filter(|n| !n.trim().is_empty());
+        if let Some(n) = &note {
+            if n.chars().count() > MAX_CLIP_NOTE_CHARS {
+                return Err(format!("note is longer than {} characters", MAX_CLIP_NOTE_CHARS));
+            }
+        }
+        self.label_color = label_color;
+        self.note = note;
+        Ok(())
+    }
+
+    /// Point this clip at a different asset of `asset_duration_ms`, keeping
+    /// its start and track. In/out are clamped to the new asset; if the old
+    /// in-point is past the end, the clip restarts from 0 with its old length.
+    pub fn repoint(&mut self, asset_id: &str, asset_duration_ms: i64) {
+        let asset_duration_ms = asset_duration_ms.max(1);
+        let length = self.out_ms - self.in_ms;
+        self.asset_id = asset_id.to_string();
+        self.out_ms = self.out_ms.min(asset_duration_ms);
+        if self.in_ms >= self.out_ms {
+            self.in_ms = 0;
+            self.out_ms = length.clamp(1, asset_duration_ms);
+        }
+        self.duration_ms = self.out_ms - self.in_ms;
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -243,6 +879,26 @@ pub struct Marker {
     #[serde(default)]
     pub prompt_text: String,
     pub created_at: String,
+    /// End of the annotated span; `None` for a point marker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_ms: Option<i64>,
+}
+
+impl Marker {
+    #[cfg(test)]
+    pub fn is_range(&self) -> bool {
+        self.end_ms.is_some()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self.end_ms {
+            Some(end) if end <= self.t_ms => Err(format!(
+                "endMs ({}) must be greater than tMs ({})",
+                end, self.t_ms
+            )),
+            _ => Ok(()),
+        }
+    }
 }
 
 // --- Export ---
@@ -257,6 +913,24 @@ pub struct ExportRecord {
     pub end_ms: i64,
     pub output_uri: String,
     pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<String>,
+    #[serde(default)]
+    pub clip_count: u32,
+    /// Integrated loudness target the mix was normalized to, when it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loudness_target_lufs: Option<f64>,
+    /// What ffprobe found in the finished file, as opposed to the preset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub width: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub codec: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_size_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -280,7 +954,239 @@ pub struct Indexes {
 
 // --- Helper: rebuild indexes ---
 
+/// Highest star rating `meta.rating` can hold.
+pub const MAX_RATING: u8 = 5;
+
+/// Asset `meta` is freeform, but two keys are shared conventions for picking
+/// between variants: `rating` (1-5 stars; absent means unrated) and
+/// `favorite` (bool; absent means false).
+impl Asset {
+    pub fn duration_ms(&self) -> Option<i64> {
+        meta_duration_ms(&self.meta)
+    }
+
+    /// Length of a new clip of this asset: the project's still duration for
+    /// images, the probed duration for video and audio (which must be known
+    /// and positive), `DEFAULT_CLIP_DURATION_MS` for anything else.
+    pub fn clip_duration_ms(&self, settings: &ProjectSettings) -> Result<i64, String> {
+        match self.asset_type.as_str() {
+            "image" => Ok(settings.effective_still_duration_ms()),
+            "video" | "audio" => self.duration_ms().filter(|d| *d > 0).ok_or_else(|| {
+                format!("Asset {} has no known duration; probe it before adding it to the timeline", self.asset_id)
+            }),
+            _ => Ok(self.duration_ms().filter(|d| *d > 0).unwrap_or(DEFAULT_CLIP_DURATION_MS)),
+        }
+    }
+
+    pub fn rating(&self) -> Option<u8> {
+        self.meta
+            .get("rating")
+            .and_then(|v| v.as_u64())
+            .filter(|r| (1..=MAX_RATING as u64).contains(r))
+            .map(|r| r as u8)
+    }
+
+    pub fn is_favorite(&self) -> bool {
+        self.meta.get("favorite").and_then(|v| v.as_bool()).unwrap_or(false)
+    }
+
+    /// Set the star rating; `0` clears it.
+    pub fn set_rating(&mut self, rating: u8) -> Result<(), String> {
+        if rating > MAX_RATING {
+            return Err(format!("rating must be between 0 and {}, got {}", MAX_RATING, rating));
+        }
+        let meta = self.meta_object_mut();
+        if rating > 0 {
+            meta.insert("rating".to_string(), serde_json::json!(rating));
+        } else {
+            meta.remove("rating");
+        }
+        Ok(())
+    }
+
+    pub fn set_favorite(&mut self, favorite: bool) {
+        let meta = self.meta_object_mut();
+        if favorite {
+            meta.insert("favorite".to_string(), serde_json::json!(true));
+        } else {
+            meta.remove("favorite");
+        }
+    }
+
+    fn meta_object_mut(&mut self) -> &mut serde_json::Map<String, serde_json::Value> {
+        if !self.meta.is_object() {
+            self.meta = serde_json::json!({});
+        }
+        self.meta.as_object_mut().expect("meta is an object")
+    }
+}
+
 impl ProjectFile {
+    /// Rewrite legacy `meta.durationSec` as `meta.durationMs` on every asset.
+    /// Returns how many assets changed.
+    pub fn migrate_asset_durations(&mut self) -> usize {
+        let mut migrated = 0;
+        for asset in &mut self.assets {
+            let Some(obj) = asset.meta.as_object_mut() else { continue };
+            let Some(secs) = obj.remove("durationSec") else { continue };
+            if !obj.contains_key("durationMs") {
+                if let Some(secs) = secs.as_f64() {
+                    obj.insert("durationMs".to_string(), serde_json::json!((secs * 1000.0).round() as i64));
+                }
+            }
+            migrated += 1;
+        }
+        migrated
+    }
+
+    /// Track an export should read: `requested`, or the project's default
+    /// draft video track. Errors list the available tracks.
+    pub fn export_track_id(&self, requested: Option<&str>) -> Result<String, String> {
+        let track_id = requested.unwrap_or(&self.project.default_draft_track_ids.video);
+        if self.timeline.tracks.iter().any(|t| t.track_id == track_id) {
+            return Ok(track_id.to_string());
+        }
+        let available: Vec<String> = self
+            .timeline
+            .tracks
+            .iter()
+            .map(|t| format!("{} ({}, {})", t.track_id, t.track_type, t.name))
+            .collect();
+        Err(format!(
+            "track_not_found: Track {} not found; available tracks: {}",
+            track_id,
+            if available.is_empty() { "none".to_string() } else { available.join(", ") }
+        ))
+    }
+
+    /// Ids of every task that depends on `task_id`, directly or transitively,
+    /// in breadth-first order. Cycles in `deps` are tolerated.
+    pub fn downstream_tasks(&self, task_id: &str) -> Vec<String> {
+        let mut found: Vec<String> = Vec::new();
+        let mut frontier = vec![task_id.to_string()];
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for task in &self.tasks {
+                if task.task_id == task_id || found.contains(&task.task_id) {
+                    continue;
+                }
+                if task.deps.iter().any(|d| frontier.contains(d)) {
+                    found.push(task.task_id.clone());
+                    next.push(task.task_id.clone());
+                }
+            }
+            frontier = next;
+        }
+        found
+    }
+
+    /// Flip every queued task to canceled; returns their post-cancel snapshots.
+    /// Running tasks are left for the caller to flag.
+    pub fn cancel_queued_tasks(&mut self, reason: &str) -> Vec<Task> {
+        let now = crate::clock::now_rfc3339();
+        let cap = self.project.settings.task_event_cap();
+        self.tasks
+            .iter_mut()
+            .filter(|t| t.state == "queued")
+            .map(|task| {
+                task.state = "canceled".to_string();
+                task.updated_at = now.clone();
+                task.finished_at = Some(now.clone());
+                task.append_event("warn", reason, cap);
+                task.clone()
+            })
+            .collect()
+    }
+
+    /// Fail tasks left `running` by a previous session that exited mid-task;
+    /// returns their ids.
+    pub fn recover_crashed_tasks(&mut self) -> Vec<String> {
+        let now = crate::clock::now_rfc3339();
+        let cap = self.project.settings.task_event_cap();
+        self.tasks
+            .iter_mut()
+            .filter(|t| t.state == "running")
+            .map(|task| {
+                task.state = "failed".to_string();
+                task.updated_at = now.clone();
+                task.finished_at = Some(now.clone());
+                task.error = Some(TaskError::new("crash_recovered", "Task was running when app exited."));
+                task.append_event("warn", "crash_recovered: task was running when app exited", cap);
+                task.task_id.clone()
+            })
+            .collect()
+    }
+
+    /// Drop the given tasks from the project. Callers rebuild indexes.
+    pub fn remove_tasks(&mut self, task_ids: &[String]) -> usize {
+        let before = self.tasks.len();
+        self.tasks.retain(|t| !task_ids.contains(&t.task_id));
+        before - self.tasks.len()
+    }
+
+    /// Make room under `limits.max_tasks` for `incoming` new tasks. With
+    /// `prune`, the oldest finished tasks no pending task depends on are
+    /// dropped down to 90% of the cap, so pruning doesn't run on every
+    /// enqueue; returns their ids. Fails with `limit_exceeded` under `reject`
+    /// or when too few tasks can go. Callers rebuild indexes.
+    pub fn make_room_for_tasks(&mut self, incoming: usize, limits: &ProjectLimits) -> Result<Vec<String>, TaskError> {
+        let total = self.tasks.len() + incoming;
+        if !limits.enabled || total <= limits.max_tasks {
+            return Ok(vec![]);
+        }
+        let exceeded = TaskError::new(
+            "limit_exceeded",
+            format!(
+                "Project would hold {} tasks, over the limit of {} (settings.limits.maxTasks)",
+                total, limits.max_tasks
+            ),
+        );
+        if limits.on_task_limit == TaskLimitAction::Reject {
+            return Err(exceeded);
+        }
+
+        let needed: HashSet<&str> = self
+            .tasks
+            .iter()
+            .filter(|t| !t.is_finished())
+            .flat_map(|t| t.deps.iter().map(String::as_str))
+            .collect();
+        let mut prunable: Vec<(&str, &str)> = self
+            .tasks
+            .iter()
+            .filter(|t| t.is_finished() && !needed.contains(t.task_id.as_str()))
+            .map(|t| (t.finished_at.as_deref().unwrap_or(&t.updated_at), t.task_id.as_str()))
+            .collect();
+        prunable.sort_unstable();
+        let target = limits.max_tasks - limits.max_tasks / 10;
+        let ids: Vec<String> = prunable
+            .into_iter()
+            .take(total.saturating_sub(target))
+            .map(|(_, id)| id.to_string())
+            .collect();
+        if total - ids.len() > limits.max_tasks {
+            return Err(exceeded);
+        }
+        self.remove_tasks(&ids);
+        Ok(ids)
+    }
+
+    /// Refuse with `limit_exceeded` when `incoming` more clips would go
+    /// past `limits.max_clips`.
+    pub fn check_clip_limit(&self, incoming: usize, limits: &ProjectLimits) -> Result<(), TaskError> {
+        let total = self.timeline.clips.len() + incoming;
+        if limits.enabled && total > limits.max_clips {
+            return Err(TaskError::new(
+                "limit_exceeded",
+                format!(
+                    "Timeline would hold {} clips, over the limit of {} (settings.limits.maxClips)",
+                    total, limits.max_clips
+                ),
+            ));
+        }
+        Ok(())
+    }
+
     pub fn rebuild_indexes(&mut self) {
         self.indexes.asset_by_id.clear();
         self.indexes.task_by_id.clear();
@@ -302,10 +1208,10 @@ impl ProjectFile {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
-    fn make_empty_project() -> ProjectFile {
+    pub(crate) fn make_empty_project() -> ProjectFile {
         ProjectFile {
             schema_version: "0.2".to_string(),
             project: ProjectMeta {
@@ -319,6 +1225,15 @@ mod tests {
                     aspect_ratio: "16:9".to_string(),
                     sample_rate: 48000,
                     generation: None,
+                    snap_to_frame: false,
+                    save_debounce_ms: None,
+                    max_task_events: None,
+                    compress_on_disk: None,
+                    import_defaults: None,
+                    scale_mode: None,
+                    still_duration_ms: None,
+                    proxy_keyframe_interval_ms: None,
+                    limits: None,
                 },
                 paths: ProjectPaths {
                     workspace_root: "./workspace".to_string(),
@@ -332,6 +1247,7 @@ mod tests {
                     audio: "trk_a".to_string(),
                     text: "trk_t".to_string(),
                 },
+                view_state: None,
             },
             assets: vec![],
             tasks: vec![],
@@ -356,7 +1272,27 @@ mod tests {
         }
     }
 
-    fn make_prompt_asset(id: &str, label: &str) -> Asset {
+    pub(crate) fn make_task(id: &str, kind: &str, state: &str, deps: &[&str]) -> Task {
+        Task {
+            task_id: id.to_string(),
+            kind: kind.to_string(),
+            state: state.to_string(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            updated_at: "2026-01-01T00:00:00Z".to_string(),
+            input: serde_json::json!({}),
+            output: None,
+            progress: None,
+            error: None,
+            retries: TaskRetries { count: 0, max: 3 },
+            deps: deps.iter().map(|d| d.to_string()).collect(),
+            events: vec![],
+            dedupe_key: None,
+            started_at: None,
+            finished_at: None,
+        }
+    }
+
+    pub(crate) fn make_prompt_asset(id: &str, label: &str) -> Asset {
         Asset {
             asset_id: id.to_string(),
             asset_type: "prompt".to_string(),
@@ -379,6 +1315,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn new_clip_length_depends_on_asset_type() {
+        let mut settings = make_empty_project().project.settings;
+        let media = |asset_type: &str, meta: serde_json::Value| Asset {
+            asset_type: asset_type.to_string(),
+            meta,
+            ..make_prompt_asset("a1", "x")
+        };
+
+        let image = media("image", serde_json::json!({ "width": 800, "height": 600 }));
+        assert_eq!(image.clip_duration_ms(&settings), Ok(DEFAULT_STILL_DURATION_MS));
+        settings.still_duration_ms = Some(1500);
+        assert_eq!(image.clip_duration_ms(&settings), Ok(1500));
+
+        assert_eq!(media("video", serde_json::json!({ "durationMs": 4200 })).clip_duration_ms(&settings), Ok(4200));
+        for meta in [serde_json::json!({ "durationMs": 0 }), serde_json::json!({})] {
+            let err = media("audio", meta).clip_duration_ms(&settings).unwrap_err();
+            assert!(err.contains("probe it"), "{}", err);
+        }
+
+        assert_eq!(make_prompt_asset("p1", "x").clip_duration_ms(&settings), Ok(DEFAULT_CLIP_DURATION_MS));
+    }
+
+    #[test]
+    fn rating_and_favorite_round_trip_through_meta() {
+        let mut asset = make_prompt_asset("p1", "Take 1");
+        assert_eq!((asset.rating(), asset.is_favorite()), (None, false));
+
+        asset.set_rating(4).unwrap();
+        asset.set_favorite(true);
+        let json = serde_json::to_string(&asset).unwrap();
+        let back: Asset = serde_json::from_str(&json).unwrap();
+        assert_eq!((back.rating(), back.is_favorite()), (Some(4), true));
+        assert_eq!(back.meta["label"], "Take 1");
+
+        asset.set_rating(0).unwrap();
+        asset.set_favorite(false);
+        assert!(asset.meta.get("rating").is_none() && asset.meta.get("favorite").is_none());
+        assert!(asset.set_rating(6).is_err());
+
+        asset.meta = serde_json::Value::Null;
+        asset.set_rating(2).unwrap();
+        assert_eq!(asset.rating(), Some(2));
+    }
+
     #[test]
     fn prompt_asset_added_and_indexed() {
         let mut pf = make_empty_project();
@@ -414,6 +1395,8 @@ mod tests {
             duration_ms: 5000,
             in_ms: 0,
             out_ms: 5000,
+            label_color: None,
+            note: None,
         };
 
         let text_track = pf.timeline.tracks.iter_mut()
@@ -445,6 +1428,8 @@ mod tests {
             duration_ms: 5000,
             in_ms: 0,
             out_ms: 5000,
+            label_color: None,
+            note: None,
         };
 
         pf.timeline.clips.insert("clip_ph".to_string(), clip.clone());
@@ -483,6 +1468,44 @@ mod tests {
         assert_eq!(json["tags"], serde_json::json!(["prompt"]));
     }
 
+    #[test]
+    fn view_state_is_optional_in_project_json() {
+        let mut pf = make_empty_project();
+        let json = serde_json::to_value(&pf).unwrap();
+        assert!(json["project"].get("viewState").is_none());
+        let loaded: ProjectFile = serde_json::from_value(json).unwrap();
+        assert!(loaded.project.view_state.is_none());
+
+        pf.project.view_state = Some(ViewState {
+            playhead_ms: 1500,
+            selected_track_id: Some("trk_v".to_string()),
+            zoom: None,
+        });
+        let json = serde_json::to_value(&pf).unwrap();
+        assert_eq!(json["project"]["viewState"], serde_json::json!({
+            "playheadMs": 1500,
+            "selectedTrackId": "trk_v",
+        }));
+        let loaded: ProjectFile = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.project.view_state, pf.project.view_state);
+    }
+
+    #[test]
+    fn asset_durations_migrate_from_seconds_to_ms() {
+        let mut pf = make_empty_project();
+        let mut probed = make_prompt_asset("v1", "a");
+        probed.meta = serde_json::json!({ "kind": "video", "durationSec": 5.0426 });
+        let mut generated = make_prompt_asset("v2", "b");
+        generated.meta = serde_json::json!({ "durationMs": 4000 });
+        pf.assets = vec![probed, generated];
+
+        assert_eq!(pf.assets[0].duration_ms(), Some(5043));
+        assert_eq!(pf.migrate_asset_durations(), 1);
+        assert_eq!(pf.assets[0].meta, serde_json::json!({ "kind": "video", "durationMs": 5043 }));
+        assert_eq!(pf.assets[1].duration_ms(), Some(4000));
+        assert_eq!(pf.migrate_asset_durations(), 0);
+    }
+
     #[test]
     fn recalc_duration_with_no_clips() {
         let mut pf = make_empty_project();
@@ -501,4 +1524,620 @@ mod tests {
         pf.rebuild_indexes();
         assert_eq!(pf.indexes.asset_by_id.len(), 0);
     }
+
+    #[test]
+    fn snap_to_frame_30fps_boundaries() {
+        // 1 frame = 33.333ms
+        assert_eq!(snap_to_frame(0, 30.0), 0);
+        assert_eq!(snap_to_frame(16, 30.0), 0);
+        assert_eq!(snap_to_frame(17, 30.0), 33);
+        assert_eq!(snap_to_frame(34, 30.0), 33);
+        assert_eq!(snap_to_frame(1000, 30.0), 1000);
+        assert_eq!(snap_to_frame(1010, 30.0), 1000);
+        assert_eq!(snap_to_frame(1020, 30.0), 1033);
+    }
+
+    #[test]
+    fn snap_to_frame_23_976fps_boundaries() {
+        let fps = 24000.0 / 1001.0;
+        // 1 frame = 41.708ms
+        assert_eq!(snap_to_frame(20, fps), 0);
+        assert_eq!(snap_to_frame(21, fps), 42);
+        assert_eq!(snap_to_frame(42, fps), 42);
+        assert_eq!(snap_to_frame(1000, fps), 1001);
+        // 24 frames = 1001ms exactly
+        assert_eq!(snap_to_frame(1001, fps), 1001);
+    }
+
+    #[test]
+    fn snap_to_frame_ignores_invalid_fps() {
+        assert_eq!(snap_to_frame(1234, 0.0), 1234);
+        assert_eq!(snap_to_frame(1234, -24.0), 1234);
+    }
+
+    fn make_marker(id: &str, t_ms: i64) -> Marker {
+        Marker {
+            marker_id: id.to_string(),
+            t_ms,
+            label: String::new(),
+            prompt_text: String::new(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            end_ms: None,
+        }
+    }
+
+    fn make_clip(in_ms: i64, out_ms: i64) -> Clip {
+        Clip {
+            clip_id: "clip_1".to_string(),
+            asset_id: "ast_a".to_string(),
+            track_id: "trk_v".to_string(),
+            start_ms: 2000,
+            duration_ms: out_ms - in_ms,
+            in_ms,
+            out_ms,
+            label_color: None,
+            note: None,
+        }
+    }
+
+    #[test]
+    fn trim_validates_before_changing_the_clip() {
+        let mut clip = make_clip(500, 3000);
+        assert_eq!(clip.trim(Some(-1), None, None, None).unwrap_err().code, "invalid_trim_negative_in");
+        assert_eq!(clip.trim(Some(3000), None, None, None).unwrap_err().code, "invalid_trim_out_le_in");
+        assert_eq!(clip.trim(Some(0), Some(4000), None, Some(3500)).unwrap_err().code, "out_of_bounds");
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (500, 3000, 2500));
+
+        clip.trim(None, Some(4010), Some(25.0), None).unwrap();
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (500, 4000, 3500));
+    }
+
+    #[test]
+    fn trim_sets_both_points_at_once_in_either_direction() {
+        // Later window: the new in is past the old out
+        let mut clip = make_clip(500, 3000);
+        clip.trim(Some(3500), Some(6000), None, Some(10_000)).unwrap();
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (3500, 6000, 2500));
+
+        // Earlier window: the new out is before the old in
+        clip.trim(Some(100), Some(1000), None, Some(10_000)).unwrap();
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (100, 1000, 900));
+
+        // An inverted pair fails as a pair and leaves the clip alone
+        let err = clip.trim(Some(5000), Some(4000), None, Some(10_000)).unwrap_err();
+        assert_eq!(err.code, "invalid_trim_out_le_in");
+        let err = clip.trim(Some(9000), Some(11_000), None, Some(10_000)).unwrap_err();
+        assert_eq!(err.code, "out_of_bounds");
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (100, 1000, 900));
+    }
+
+    #[test]
+    fn extend_to_meets_target_within_the_source() {
+        let mut clip = make_clip(500, 3000);
+        clip.extend_to(6000, Some(10_000)).unwrap();
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (500, 4500, 4000));
+        assert_eq!(clip.start_ms + clip.duration_ms, 6000);
+
+        let err = clip.extend_to(12_000, Some(10_000)).unwrap_err();
+        assert_eq!(err.code, "asset_too_short");
+        assert!(err.message.contains("11500"), "{}", err.message);
+        assert_eq!(clip.extend_to(2000, None).unwrap_err().code, "invalid_target");
+        assert_eq!(clip.out_ms, 4500);
+
+        // Stills have no source length and stretch freely
+        clip.extend_to(60_000, None).unwrap();
+        assert_eq!(clip.duration_ms, 58_000);
+    }
+
+    #[test]
+    fn clip_meta_is_validated_and_optional_on_disk() {
+        let mut clip = make_clip(0, 1000);
+        clip.set_meta(Some("#E53935".to_string()), Some("regenerate: hands".to_string())).unwrap();
+        assert_eq!(clip.label_color.as_deref(), Some("#E53935"));
+        clip.set_meta(Some(" red ".to_string()), None).unwrap();
+        assert_eq!((clip.label_color.as_deref(), clip.note.as_deref()), (Some("red"), None));
+        assert!(clip.set_meta(Some("#12345".to_string()), None).is_err());
+        assert!(clip.set_meta(None, Some("x".repeat(MAX_CLIP_NOTE_CHARS + 1))).is_err());
+        assert_eq!(clip.label_color.as_deref(), Some("red"));
+
+        clip.set_meta(Some(String::new()), Some("  ".to_string())).unwrap();
+        let json = serde_json::to_value(&clip).unwrap();
+        assert!(json.get("labelColor").is_none() && json.get("note").is_none());
+        let old: Clip = serde_json::from_value(json).unwrap();
+        assert_eq!((old.label_color, old.note), (None, None));
+    }
+
+    #[test]
+    fn repoint_keeps_trim_when_it_fits() {
+        let mut clip = make_clip(500, 3000);
+        clip.repoint("ast_b", 10_000);
+        assert_eq!(clip.asset_id, "ast_b");
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (500, 3000, 2500));
+        assert_eq!(clip.start_ms, 2000);
+        assert_eq!(clip.track_id, "trk_v");
+    }
+
+    #[test]
+    fn repoint_clamps_to_shorter_asset() {
+        let mut clip = make_clip(500, 3000);
+        clip.repoint("ast_b", 2000);
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (500, 2000, 1500));
+
+        let mut clip = make_clip(4000, 6000);
+        clip.repoint("ast_b", 3000);
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (0, 2000, 2000));
+
+        let mut clip = make_clip(4000, 9000);
+        clip.repoint("ast_b", 3000);
+        assert_eq!((clip.in_ms, clip.out_ms, clip.duration_ms), (0, 3000, 3000));
+    }
+
+    #[test]
+    fn ordered_clips_breaks_start_ties_by_clip_ids_order() {
+        let mut pf = make_empty_project();
+        let mut a = make_clip(0, 1000);
+        a.clip_id = "clip_a".into();
+        a.start_ms = 1000;
+        let mut b = make_clip(0, 1000);
+        b.clip_id = "clip_b".into();
+        b.start_ms = 1000;
+        let mut c = make_clip(0, 1000);
+        c.clip_id = "clip_c".into();
+        c.start_ms = 0;
+        for clip in [a, b, c] {
+            pf.timeline.clips.insert(clip.clip_id.clone(), clip);
+        }
+
+        let mut track = Track {
+            track_id: "trk_v".into(),
+            track_type: "video".into(),
+            name: "V".into(),
+            clip_ids: vec!["clip_b".into(), "clip_a".into(), "clip_c".into()],
+        };
+        let ids: Vec<&str> = pf.timeline.ordered_clips(&track).iter().map(|c| c.clip_id.as_str()).collect();
+        assert_eq!(ids, vec!["clip_c", "clip_b", "clip_a"]);
+
+        track.clip_ids = vec!["clip_a".into(), "clip_b".into(), "clip_c".into()];
+        let ids: Vec<&str> = pf.timeline.ordered_clips(&track).iter().map(|c| c.clip_id.as_str()).collect();
+        assert_eq!(ids, vec!["clip_c", "clip_a", "clip_b"]);
+    }
+
+    #[test]
+    fn visible_clip_at_maps_to_source_time_and_prefers_upper_track() {
+        let mut pf = make_empty_project();
+        pf.timeline.tracks = vec![
+            Track { track_id: "trk_v1".into(), track_type: "video".into(), name: "V1".into(), clip_ids: vec!["c1".into()] },
+            Track { track_id: "trk_t".into(), track_type: "text".into(), name: "T".into(), clip_ids: vec!["c3".into()] },
+            Track { track_id: "trk_v2".into(), track_type: "video".into(), name: "V2".into(), clip_ids: vec!["c2".into()] },
+        ];
+        let mut c1 = make_clip(1000, 6000);
+        c1.clip_id = "c1".into();
+        c1.start_ms = 0;
+        let mut c2 = make_clip(0, 1000);
+        c2.clip_id = "c2".into();
+        c2.asset_id = "ast_b".into();
+        c2.start_ms = 3000;
+        let mut c3 = make_clip(0, 10_000);
+        c3.clip_id = "c3".into();
+        c3.start_ms = 0;
+        for c in [c1, c2, c3] {
+            pf.timeline.clips.insert(c.clip_id.clone(), c);
+        }
+
+        let (clip, src) = pf.timeline.visible_clip_at(1500).unwrap();
+        assert_eq!((clip.clip_id.as_str(), src), ("c1", 2500));
+        let (clip, src) = pf.timeline.visible_clip_at(3500).unwrap();
+        assert_eq!((clip.clip_id.as_str(), src), ("c2", 500));
+        // End is exclusive
+        let (clip, _) = pf.timeline.visible_clip_at(4000).unwrap();
+        assert_eq!(clip.clip_id, "c1");
+        assert!(pf.timeline.visible_clip_at(5000).is_none());
+    }
+
+    #[test]
+    fn track_accepts_matching_asset_types() {
+        let track = |t: &str| Track {
+            track_id: format!("trk_{}", t),
+            track_type: t.to_string(),
+            name: t.to_string(),
+            clip_ids: vec![],
+        };
+        assert!(track("video").accepts_asset_type("video"));
+        assert!(track("video").accepts_asset_type("image"));
+        assert!(track("audio").accepts_asset_type("audio"));
+        assert!(track("text").accepts_asset_type("prompt"));
+        assert!(!track("text").accepts_asset_type("video"));
+        assert!(!track("audio").accepts_asset_type("video"));
+        assert!(!track("video").accepts_asset_type("prompt"));
+        assert!(track("subtitle").accepts_asset_type("subtitle"));
+        assert!(!track("video").accepts_asset_type("subtitle"));
+    }
+
+    #[test]
+    fn settings_validation_rejects_bad_format_fields() {
+        let base = make_empty_project().project.settings;
+        assert!(base.validate().is_ok());
+
+        let vertical = ProjectSettings {
+            fps: 60,
+            resolution: Resolution { width: 1080, height: 1920 },
+            aspect_ratio: "9:16".to_string(),
+            ..base.clone()
+        };
+        assert!(vertical.validate().is_ok());
+
+        let bad = [
+            ProjectSettings { fps: 0, ..base.clone() },
+            ProjectSettings { resolution: Resolution { width: 1921, height: 1080 }, ..base.clone() },
+            ProjectSettings { resolution: Resolution { width: 0, height: 1080 }, ..base.clone() },
+            ProjectSettings { aspect_ratio: "17:9".to_string(), ..base.clone() },
+            ProjectSettings { sample_rate: 12345, ..base.clone() },
+            ProjectSettings { save_debounce_ms: Some(1), ..base.clone() },
+        ];
+        for settings in bad {
+            assert!(settings.validate().is_err(), "{:?}", settings);
+        }
+    }
+
+    #[test]
+    fn snap_clips_to_frames_moves_points_onto_new_frame_grid() {
+        let mut pf = make_empty_project();
+        let mut clip = make_clip(0, 1000);
+        clip.start_ms = 1010;
+        pf.timeline.clips.insert(clip.clip_id.clone(), clip);
+
+        assert_eq!(pf.timeline.snap_clips_to_frames(60.0), 1);
+        let clip = &pf.timeline.clips["clip_1"];
+        assert_eq!(clip.start_ms, 1017);
+        assert!(clip.has_consistent_duration());
+        assert_eq!(pf.timeline.duration_ms, 2017);
+        assert_eq!(pf.timeline.snap_clips_to_frames(60.0), 0);
+    }
+
+    #[test]
+    fn recalc_duration_repairs_drifted_clips() {
+        let mut pf = make_empty_project();
+        let mut clip = |id: &str, start_ms, duration_ms, in_ms, out_ms| {
+            pf.timeline.clips.insert(id.to_string(), Clip {
+                clip_id: id.to_string(),
+                asset_id: "a".to_string(),
+                track_id: "trk_v".to_string(),
+                start_ms,
+                duration_ms,
+                in_ms,
+                out_ms,
+                label_color: None,
+                note: None,
+            });
+        };
+        clip("ok", 0, 2000, 500, 2500);
+        clip("drifted", 1000, 9000, 0, 3000);
+        clip("inverted", 0, 1500, 4000, 1000);
+        pf.timeline.recalc_duration();
+
+        let clips = &pf.timeline.clips;
+        assert!(clips.values().all(|c| c.has_consistent_duration()));
+        assert_eq!(clips["ok"].duration_ms, 2000);
+        assert_eq!(clips["drifted"].duration_ms, 3000);
+        assert_eq!((clips["inverted"].in_ms, clips["inverted"].out_ms), (4000, 5500));
+        assert_eq!(pf.timeline.duration_ms, 4000);
+        assert!(pf.timeline.repair_clip_durations().is_empty());
+    }
+
+    #[test]
+    fn reorder_tracks_requires_a_permutation() {
+        let mut pf = make_empty_project();
+        let ids = |pf: &ProjectFile| pf.timeline.tracks.iter().map(|t| t.track_id.clone()).collect::<Vec<_>>();
+
+        pf.timeline.reorder_tracks(&["trk_t".into(), "trk_v".into(), "trk_a".into()]).unwrap();
+        assert_eq!(ids(&pf), vec!["trk_t", "trk_v", "trk_a"]);
+
+        assert!(pf.timeline.reorder_tracks(&["trk_t".into(), "trk_v".into()]).is_err());
+        assert!(pf.timeline.reorder_tracks(&["trk_t".into(), "trk_t".into(), "trk_a".into()]).is_err());
+        assert!(pf.timeline.reorder_tracks(&["trk_t".into(), "trk_v".into(), "trk_x".into()]).is_err());
+        assert_eq!(ids(&pf), vec!["trk_t", "trk_v", "trk_a"]);
+    }
+
+    #[test]
+    fn duplicate_track_copies_clips_with_new_ids() {
+        let mut pf = make_empty_project();
+        for (id, start) in [("clip_b", 3000), ("clip_a", 0)] {
+            let clip = Clip { clip_id: id.into(), start_ms: start, ..make_clip(500, 2500) };
+            pf.timeline.tracks[0].clip_ids.push(id.into());
+            pf.timeline.clips.insert(id.into(), clip);
+        }
+
+        let copy = pf.timeline.duplicate_track("trk_v", "  ").unwrap();
+        assert_eq!(copy.track_type, "video");
+        assert_eq!(copy.name, format!("{} copy", pf.timeline.tracks[0].name));
+        assert_eq!(pf.timeline.tracks[1].track_id, copy.track_id);
+        assert_eq!(copy.clip_ids.len(), 2);
+        assert_eq!(pf.timeline.tracks[0].clip_ids, vec!["clip_b", "clip_a"]);
+
+        let starts: Vec<i64> = copy.clip_ids.iter().map(|id| pf.timeline.clips[id].start_ms).collect();
+        assert_eq!(starts, vec![3000, 0]);
+        for id in &copy.clip_ids {
+            let clip = &pf.timeline.clips[id];
+            assert!(id != "clip_a" && id != "clip_b");
+            assert_eq!(clip.track_id, copy.track_id);
+            assert_eq!((clip.asset_id.as_str(), clip.in_ms, clip.out_ms), ("ast_a", 500, 2500));
+        }
+
+        assert!(pf.timeline.duplicate_track("trk_missing", "B").is_err());
+    }
+
+    #[test]
+    fn export_track_defaults_to_draft_video_track() {
+        let pf = make_empty_project();
+        assert_eq!(pf.export_track_id(None).unwrap(), "trk_v");
+        assert_eq!(pf.export_track_id(Some("trk_a")).unwrap(), "trk_a");
+        let err = pf.export_track_id(Some("trk_draft")).unwrap_err();
+        assert!(err.starts_with("track_not_found"), "{}", err);
+        assert!(err.contains("trk_v (video, Video)"), "{}", err);
+    }
+
+    #[test]
+    fn downstream_tasks_follows_transitive_deps() {
+        let mut pf = make_empty_project();
+        pf.tasks = vec![
+            make_task("import", "thumb", "queued", &[]),
+            make_task("thumb", "thumb", "queued", &["import"]),
+            make_task("proxy", "thumb", "queued", &["thumb"]),
+            make_task("export", "thumb", "queued", &["proxy", "other"]),
+            make_task("other", "thumb", "queued", &[]),
+            make_task("loop_a", "thumb", "queued", &["loop_b", "import"]),
+            make_task("loop_b", "thumb", "queued", &["loop_a"]),
+        ];
+        assert_eq!(
+            pf.downstream_tasks("import"),
+            vec!["thumb", "loop_a", "proxy", "loop_b", "export"]
+        );
+        assert_eq!(pf.downstream_tasks("other"), vec!["export"]);
+        assert!(pf.downstream_tasks("export").is_empty());
+    }
+
+    #[test]
+    fn cancel_queued_tasks_leaves_running_and_finished_alone() {
+        let mut pf = make_empty_project();
+        pf.tasks = vec![
+            make_task("q1", "thumb", "queued", &[]),
+            make_task("run", "thumb", "running", &[]),
+            make_task("done", "thumb", "succeeded", &[]),
+            make_task("q2", "thumb", "queued", &["run"]),
+        ];
+
+        let canceled = pf.cancel_queued_tasks("Task canceled (cancel all)");
+        let ids: Vec<&str> = canceled.iter().map(|t| t.task_id.as_str()).collect();
+        assert_eq!(ids, vec!["q1", "q2"]);
+        assert!(canceled.iter().all(|t| t.state == "canceled" && t.finished_at.is_some()));
+        assert_eq!(pf.tasks[1].state, "running");
+        assert_eq!(pf.tasks[2].state, "succeeded");
+
+        let ids: Vec<String> = canceled.into_iter().map(|t| t.task_id).collect();
+        assert_eq!(pf.remove_tasks(&ids), 2);
+        let left: Vec<&str> = pf.tasks.iter().map(|t| t.task_id.as_str()).collect();
+        assert_eq!(left, vec!["run", "done"]);
+    }
+
+    #[test]
+    fn crash_recovery_fails_running_tasks_and_reports_them() {
+        let mut pf = make_empty_project();
+        pf.tasks = vec![
+            make_task("run1", "thumb", "running", &[]),
+            make_task("q", "thumb", "queued", &[]),
+            make_task("run2", "thumb", "running", &[]),
+        ];
+
+        assert_eq!(pf.recover_crashed_tasks(), vec!["run1", "run2"]);
+        assert_eq!(pf.tasks[0].state, "failed");
+        assert_eq!(pf.tasks[0].error.as_ref().unwrap().code, "crash_recovered");
+        assert_eq!(pf.tasks[1].state, "queued");
+        assert!(pf.recover_crashed_tasks().is_empty());
+    }
+
+    #[test]
+    fn task_timestamps_follow_the_injected_clock() {
+        let start = chrono::DateTime::parse_from_rfc3339("2026-03-01T10:00:00Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let (clock, _guard) = crate::clock::freeze(start);
+
+        let mut task = make_task("t1", "thumb", "queued", &[]);
+        task.started_at = Some(crate::clock::now_rfc3339());
+        task.append_event("info", "started", DEFAULT_MAX_TASK_EVENTS);
+        clock.advance_ms(2500);
+        task.append_event("info", "finished", DEFAULT_MAX_TASK_EVENTS);
+        task.finished_at = Some(crate::clock::now_rfc3339());
+
+        assert_eq!(task.events[0].t, "2026-03-01T10:00:00+00:00");
+        assert_eq!(task.events[1].t, "2026-03-01T10:00:02.500+00:00");
+        assert_eq!(task.duration_ms(), Some(2500));
+    }
+
+    #[test]
+    fn task_timing_defaults_and_duration() {
+        let mut task: Task = serde_json::from_value(serde_json::json!({
+            "taskId": "task_1",
+            "kind": "thumb",
+            "state": "queued",
+            "createdAt": "2026-01-01T00:00:00Z",
+            "updatedAt": "2026-01-01T00:00:00Z",
+            "input": {},
+            "retries": { "count": 0, "max": 3 },
+            "deps": [],
+            "events": []
+        }))
+        .unwrap();
+        assert!(task.started_at.is_none());
+        assert_eq!(task.duration_ms(), None);
+
+        task.started_at = Some("2026-01-01T00:00:01Z".to_string());
+        assert_eq!(task.duration_ms(), None);
+        task.finished_at = Some("2026-01-01T00:00:03.500Z".to_string());
+        assert_eq!(task.duration_ms(), Some(2500));
+
+        let json = serde_json::to_value(&task).unwrap();
+        assert_eq!(json["startedAt"], "2026-01-01T00:00:01Z");
+    }
+
+    #[test]
+    fn shift_markers_moves_only_at_or_after() {
+        let mut pf = make_empty_project();
+        pf.timeline.markers = vec![make_marker("a", 500), make_marker("b", 1000), make_marker("c", 2000)];
+
+        let moved = pf.timeline.shift_markers(1000, 250);
+
+        assert_eq!(moved, 2);
+        let ts: Vec<i64> = pf.timeline.markers.iter().map(|m| m.t_ms).collect();
+        assert_eq!(ts, vec![500, 1250, 2250]);
+    }
+
+    #[test]
+    fn shift_markers_clamps_and_resorts() {
+        let mut pf = make_empty_project();
+        pf.timeline.markers = vec![make_marker("a", 300), make_marker("b", 800)];
+
+        pf.timeline.shift_markers(500, -1000);
+
+        let ids: Vec<&str> = pf.timeline.markers.iter().map(|m| m.marker_id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "a"]);
+        assert_eq!(pf.timeline.markers[0].t_ms, 0);
+    }
+
+    #[test]
+    fn range_marker_validation() {
+        let mut m = make_marker("r", 1000);
+        assert!(!m.is_range());
+        assert!(m.validate().is_ok());
+
+        m.end_ms = Some(5000);
+        assert!(m.is_range());
+        assert!(m.validate().is_ok());
+
+        m.end_ms = Some(1000);
+        assert!(m.validate().is_err());
+    }
+
+    #[test]
+    fn positions_resolve_against_current_duration() {
+        let mut timeline = make_empty_project().timeline;
+        timeline.duration_ms = 9000;
+        let percent = |value: f64| Some(TimelinePosition::Percent { value });
+
+        assert_eq!(timeline.resolve_position(Some(1234), None), Ok(1234));
+        assert_eq!(timeline.resolve_position(None, Some(TimelinePosition::Ms { value: 50 })), Ok(50));
+        assert_eq!(timeline.resolve_position(None, percent(0.5)), Ok(4500));
+        assert_eq!(timeline.resolve_position(None, percent(1.0 / 3.0)), Ok(3000));
+        assert_eq!(timeline.resolve_position(None, percent(1.0)), Ok(9000));
+        assert!(timeline.resolve_position(None, percent(1.5)).is_err());
+        assert!(timeline.resolve_position(None, percent(f64::NAN)).is_err());
+        assert!(timeline.resolve_position(None, None).is_err());
+        assert!(timeline.resolve_position(Some(0), percent(0.5)).is_err());
+
+        let parsed: TimelinePosition = serde_json::from_value(serde_json::json!({ "kind": "percent", "value": 0.25 })).unwrap();
+        assert_eq!(parsed, TimelinePosition::Percent { value: 0.25 });
+    }
+
+    #[test]
+    fn point_marker_omits_end_ms_in_json() {
+        let json = serde_json::to_value(make_marker("p", 10)).unwrap();
+        assert!(json.get("endMs").is_none());
+
+        let mut range = make_marker("r", 10);
+        range.end_ms = Some(20);
+        assert_eq!(serde_json::to_value(range).unwrap()["endMs"], 20);
+    }
+
+    #[test]
+    fn import_overrides_fall_back_to_project_defaults() {
+        let mut settings = make_empty_project().project.settings;
+        assert_eq!(settings.effective_import_options(), ImportOptions::default());
+
+        settings.import_defaults = Some(serde_json::from_value(serde_json::json!({ "generateProxy": false })).unwrap());
+        let base = settings.effective_import_options();
+        assert!(base.generate_thumb && !base.generate_proxy && !base.generate_waveform);
+
+        let call: ImportOverrides = serde_json::from_value(serde_json::json!({ "generateWaveform": true })).unwrap();
+        let options = call.apply(base);
+        assert!(options.generate_thumb && !options.generate_proxy && options.generate_waveform && !options.probe_only);
+    }
+
+    #[test]
+    fn persistence_settings_default_and_validate() {
+        let mut settings = make_empty_project().project.settings;
+        assert_eq!(settings.effective_save_debounce_ms(), DEFAULT_SAVE_DEBOUNCE_MS);
+        assert_eq!(settings.effective_max_task_events(), DEFAULT_MAX_TASK_EVENTS);
+        assert!(settings.validate_persistence().is_ok());
+
+        settings.save_debounce_ms = Some(0);
+        assert!(settings.validate_persistence().is_err());
+
+        settings.save_debounce_ms = Some(200);
+        settings.max_task_events = Some(1_000_000);
+        assert!(settings.validate_persistence().is_err());
+
+        settings.max_task_events = Some(1000);
+        assert!(settings.validate_persistence().is_ok());
+        settings.max_task_events = Some(3);
+        assert_eq!(settings.task_event_cap(), MAX_TASK_EVENTS_LIMITS.0);
+
+        let mut task = make_task("t1", "thumb", "queued", &[]);
+        for i in 0..15 {
+            task.append_event("info", &i.to_string(), 10);
+        }
+        assert_eq!(task.events.len(), 10);
+        assert_eq!(task.events[0].msg, "5");
+    }
+
+    #[test]
+    fn limits_default_and_validate() {
+        let mut settings = make_empty_project().project.settings;
+        assert_eq!(settings.effective_limits(), ProjectLimits::default());
+
+        let limits: ProjectLimits = serde_json::from_value(serde_json::json!({ "maxTasks": 10 })).unwrap();
+        assert!(limits.enabled && limits.max_clips == DEFAULT_MAX_CLIPS);
+        settings.limits = Some(limits);
+        assert!(settings.validate_persistence().is_err());
+    }
+
+    #[test]
+    fn task_limit_prunes_oldest_finished_tasks_unless_rejecting() {
+        let mut pf = make_empty_project();
+        pf.tasks = (0..100).map(|i| make_task(&format!("t{:03}", i), "thumb", "queued", &[])).collect();
+        pf.tasks[0].deps = vec!["t001".to_string()];
+        for (i, task) in pf.tasks.iter_mut().enumerate().skip(1) {
+            task.state = "succeeded".to_string();
+            task.finished_at = Some(format!("2026-01-01T00:{:02}:{:02}Z", i / 60, i % 60));
+        }
+        let mut limits = ProjectLimits { max_tasks: 100, ..ProjectLimits::default() };
+
+        assert!(pf.make_room_for_tasks(0, &limits).unwrap().is_empty());
+
+        limits.on_task_limit = TaskLimitAction::Reject;
+        assert_eq!(pf.make_room_for_tasks(1, &limits).unwrap_err().code, "limit_exceeded");
+        assert_eq!(pf.tasks.len(), 100);
+
+        // Down to 90% of the cap; t000 is pending and t001 is its dependency
+        limits.on_task_limit = TaskLimitAction::Prune;
+        let pruned = pf.make_room_for_tasks(1, &limits).unwrap();
+        let expected: Vec<String> = (2..=12).map(|i| format!("t{:03}", i)).collect();
+        assert_eq!(pruned, expected);
+        assert_eq!(pf.tasks.len(), 89);
+
+        for task in &mut pf.tasks {
+            task.state = "running".to_string();
+        }
+        assert!(pf.make_room_for_tasks(12, &limits).is_err());
+        limits.enabled = false;
+        assert!(pf.make_room_for_tasks(12, &limits).unwrap().is_empty());
+    }
+
+    #[test]
+    fn clip_limit_counts_incoming_clips() {
+        let mut pf = make_empty_project();
+        pf.timeline.clips.insert("clip_1".to_string(), make_clip(0, 1000));
+        let limits = ProjectLimits { max_clips: 2, ..ProjectLimits::default() };
+        assert!(pf.check_clip_limit(1, &limits).is_ok());
+        assert_eq!(pf.check_clip_limit(2, &limits).unwrap_err().code, "limit_exceeded");
+    }
 }
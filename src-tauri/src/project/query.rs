@@ -0,0 +1,484 @@
+use serde::{Deserialize, Serialize};
+
+use super::model::{Asset, Marker, ProjectFile, Task, MAX_RATING};
+
+/// Filters for `project_search`. Each result group is only searched when
+/// `text` or one of its own filters is set; set filters are ANDed.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectQuery {
+    /// Case-insensitive substring matched against names, labels and tags.
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub asset_type: Option<String>,
+    #[serde(default)]
+    pub asset_tag: Option<String>,
+    #[serde(default)]
+    pub clip_asset_id: Option<String>,
+    #[serde(default)]
+    pub clip_track_id: Option<String>,
+    #[serde(default)]
+    pub task_state: Option<String>,
+    #[serde(default)]
+    pub task_kind: Option<String>,
+    #[serde(default)]
+    pub marker_label: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchResults {
+    pub asset_ids: Vec<String>,
+    pub clip_ids: Vec<String>,
+    pub task_ids: Vec<String>,
+    pub marker_ids: Vec<String>,
+}
+
+fn contains_ci(haystack: &str, needle_lower: &str) -> bool {
+    haystack.to_lowercase().contains(needle_lower)
+}
+
+fn asset_name(asset: &Asset) -> &str {
+    asset
+        .meta
+        .get("label")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or(&asset.path)
+}
+
+fn asset_matches_text(asset: &Asset, text: &str) -> bool {
+    contains_ci(&asset.asset_id, text)
+        || contains_ci(asset_name(asset), text)
+        || contains_ci(&asset.path, text)
+        || asset.tags.iter().any(|t| contains_ci(t, text))
+}
+
+fn task_matches_text(task: &Task, text: &str) -> bool {
+    contains_ci(&task.task_id, text) || contains_ci(&task.kind, text)
+}
+
+fn marker_matches_text(marker: &Marker, text: &str) -> bool {
+    contains_ci(&marker.label, text) || contains_ci(&marker.prompt_text, text)
+}
+
+pub fn search(project: &ProjectFile, query: &ProjectQuery) -> SearchResults {
+    let text = query.text.as_ref().map(|t| t.to_lowercase()).filter(|t| !t.is_empty());
+    let mut results = SearchResults::default();
+
+    if text.is_some() || query.asset_type.is_some() || query.asset_tag.is_some() {
+        results.asset_ids = project
+            .assets
+            .iter()
+            .filter(|a| query.asset_type.as_ref().is_none_or(|t| &a.asset_type == t))
+            .filter(|a| query.asset_tag.as_ref().is_none_or(|t| a.tags.contains(t)))
+            .filter(|a| text.as_ref().is_none_or(|t| asset_matches_text(a, t)))
+            .map(|a| a.asset_id.clone())
+            .collect();
+    }
+
+    if text.is_some() || query.clip_asset_id.is_some() || query.clip_track_id.is_some() {
+        let mut clips: Vec<_> = project
+            .timeline
+            .clips
+            .values()
+            .filter(|c| query.clip_asset_id.as_ref().is_none_or(|id| &c.asset_id == id))
+            .filter(|c| query.clip_track_id.as_ref().is_none_or(|id| &c.track_id == id))
+            .filter(|c| {
+                text.as_ref().is_none_or(|t| {
+                    contains_ci(&c.clip_id, t)
+                        || project
+                            .assets
+                            .iter()
+                            .find(|a| a.asset_id == c.asset_id)
+                            .is_some_and(|a| asset_matches_text(a, t))
+                })
+            })
+            .collect();
+        clips.sort_by(|a, b| a.start_ms.cmp(&b.start_ms).then_with(|| a.clip_id.cmp(&b.clip_id)));
+        results.clip_ids = clips.into_iter().map(|c| c.clip_id.clone()).collect();
+    }
+
+    if text.is_some() || query.task_state.is_some() || query.task_kind.is_some() {
+        results.task_ids = project
+            .tasks
+            .iter()
+            .filter(|t| query.task_state.as_ref().is_none_or(|s| &t.state == s))
+            .filter(|t| query.task_kind.as_ref().is_none_or(|k| &t.kind == k))
+            .filter(|t| text.as_ref().is_none_or(|q| task_matches_text(t, q)))
+            .map(|t| t.task_id.clone())
+            .collect();
+    }
+
+    if text.is_some() || query.marker_label.is_some() {
+        let label = query.marker_label.as_ref().map(|l| l.to_lowercase());
+        results.marker_ids = project
+            .timeline
+            .markers
+            .iter()
+            .filter(|m| label.as_ref().is_none_or(|l| contains_ci(&m.label, l)))
+            .filter(|m| text.as_ref().is_none_or(|t| marker_matches_text(m, t)))
+            .map(|m| m.marker_id.clone())
+            .collect();
+    }
+
+    results
+}
+
+/// Where an asset is referenced. Used before deleting or relinking it.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetUsages {
+    pub asset_id: String,
+    /// Clips using the asset, in timeline order.
+    pub clips: Vec<ClipUsage>,
+    /// Tasks whose input mentions the asset id anywhere.
+    pub task_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipUsage {
+    pub clip_id: String,
+    pub track_id: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+impl AssetUsages {
+    #[cfg(test)]
+    pub fn is_used(&self) -> bool {
+        !self.clips.is_empty() || !self.task_ids.is_empty()
+    }
+}
+
+fn json_mentions(value: &serde_json::Value, needle: &str) -> bool {
+    match value {
+        serde_json::Value::String(s) => s == needle,
+        serde_json::Value::Array(items) => items.iter().any(|v| json_mentions(v, needle)),
+        serde_json::Value::Object(map) => map.values().any(|v| json_mentions(v, needle)),
+        _ => false,
+    }
+}
+
+pub fn asset_usages(project: &ProjectFile, asset_id: &str) -> Result<AssetUsages, String> {
+    if !project.indexes.asset_by_id.contains_key(asset_id) {
+        return Err(format!("Asset not found: {}", asset_id));
+    }
+
+    let mut clips: Vec<ClipUsage> = project
+        .timeline
+        .clips
+        .values()
+        .filter(|c| c.asset_id == asset_id)
+        .map(|c| ClipUsage {
+            clip_id: c.clip_id.clone(),
+            // clip_by_id is the clip -> track index; fall back to the clip itself
+            track_id: project
+                .indexes
+                .clip_by_id
+                .get(&c.clip_id)
+                .cloned()
+                .unwrap_or_else(|| c.track_id.clone()),
+            start_ms: c.start_ms,
+            end_ms: c.start_ms + c.duration_ms,
+        })
+        .collect();
+    clips.sort_by(|a, b| a.start_ms.cmp(&b.start_ms).then_with(|| a.clip_id.cmp(&b.clip_id)));
+
+    let task_ids = project
+        .tasks
+        .iter()
+        .filter(|t| json_mentions(&t.input, asset_id))
+        .map(|t| t.task_id.clone())
+        .collect();
+
+    Ok(AssetUsages { asset_id: asset_id.to_string(), clips, task_ids })
+}
+
+/// Filters, ordering and paging for `task_list`. Without `sort_by` tasks
+/// keep insertion order, which `order: "desc"` reverses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskListQuery {
+    #[serde(default)]
+    pub state: Option<String>,
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// `created` or `updated`
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    /// `asc` (default) or `desc`
+    #[serde(default)]
+    pub order: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+pub fn list_tasks<'a>(project: &'a ProjectFile, query: &TaskListQuery) -> Result<Vec<&'a Task>, String> {
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => return Err(format!("order must be asc or desc, got {}", other)),
+    };
+    let mut tasks: Vec<&Task> = project
+        .tasks
+        .iter()
+        .filter(|t| query.state.as_ref().is_none_or(|s| &t.state == s))
+        .filter(|t| query.kind.as_ref().is_none_or(|k| &t.kind == k))
+        .collect();
+    // RFC 3339 timestamps sort lexically; the stable sort keeps ties in insertion order
+    match query.sort_by.as_deref() {
+        None => {}
+        Some("created") => tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+        Some("updated") => tasks.sort_by(|a, b| a.updated_at.cmp(&b.updated_at)),
+        Some(other) => return Err(format!("sortBy must be created or updated, got {}", other)),
+    }
+    if descending {
+        tasks.reverse();
+    }
+    Ok(tasks
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+/// Filters, ordering and paging for `asset_list`. Without `sort_by` assets
+/// keep project order, which `order: "desc"` reverses.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetListQuery {
+    #[serde(default)]
+    pub asset_type: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    /// Only assets rated at least this many stars.
+    #[serde(default)]
+    pub min_rating: Option<u8>,
+    #[serde(default)]
+    pub favorite: Option<bool>,
+    /// `rating` or `created`. Unrated assets sort below one star.
+    #[serde(default)]
+    pub sort_by: Option<String>,
+    /// `asc` (default) or `desc`
+    #[serde(default)]
+    pub order: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+pub fn list_assets<'a>(project: &'a ProjectFile, query: &AssetListQuery) -> Result<Vec<&'a Asset>, String> {
+    let descending = match query.order.as_deref() {
+        None | Some("asc") => false,
+        Some("desc") => true,
+        Some(other) => return Err(format!("order must be asc or desc, got {}", other)),
+    };
+    if let Some(min) = query.min_rating.filter(|min| *min > MAX_RATING) {
+        return Err(format!("minRating must be between 0 and {}, got {}", MAX_RATING, min));
+    }
+    let mut assets: Vec<&Asset> = project
+        .assets
+        .iter()
+        .filter(|a| query.asset_type.as_ref().is_none_or(|t| &a.asset_type == t))
+        .filter(|a| query.tag.as_ref().is_none_or(|t| a.tags.contains(t)))
+        .filter(|a| query.min_rating.is_none_or(|min| a.rating().unwrap_or(0) >= min))
+        .filter(|a| query.favorite.is_none_or(|f| a.is_favorite() == f))
+        .collect();
+    match query.sort_by.as_deref() {
+        None => {}
+        Some("rating") => assets.sort_by_key(|a| a.rating().unwrap_or(0)),
+        Some("created") => assets.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+        Some(other) => return Err(format!("sortBy must be rating or created, got {}", other)),
+    }
+    if descending {
+        assets.reverse();
+    }
+    Ok(assets
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::{make_empty_project, make_prompt_asset, make_task};
+    use crate::project::model::Clip;
+
+    fn make_project() -> ProjectFile {
+        let mut pf = make_empty_project();
+        pf.assets.push(make_prompt_asset("p1", "Opening shot"));
+        pf.assets.push(make_prompt_asset("p2", "Closing"));
+        pf.tasks.push(make_task("t1", "thumb", "failed", &[]));
+        pf.tasks.push(make_task("t2", "proxy", "succeeded", &[]));
+        pf.timeline.clips.insert(
+            "c1".to_string(),
+            Clip {
+                clip_id: "c1".to_string(),
+                asset_id: "p1".to_string(),
+                track_id: "trk_t".to_string(),
+                start_ms: 0,
+                duration_ms: 1000,
+                in_ms: 0,
+                out_ms: 1000,
+                label_color: None,
+                note: None,
+            },
+        );
+        pf.timeline.markers.push(Marker {
+            marker_id: "m1".to_string(),
+            t_ms: 500,
+            label: "Regenerate".to_string(),
+            prompt_text: "sunset".to_string(),
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            end_ms: None,
+        });
+        pf
+    }
+
+    #[test]
+    fn empty_query_returns_nothing() {
+        let results = search(&make_project(), &ProjectQuery::default());
+        assert!(results.asset_ids.is_empty());
+        assert!(results.clip_ids.is_empty());
+        assert!(results.task_ids.is_empty());
+        assert!(results.marker_ids.is_empty());
+    }
+
+    #[test]
+    fn filters_tasks_by_state_and_kind() {
+        let pf = make_project();
+        let q = ProjectQuery { task_state: Some("failed".to_string()), ..Default::default() };
+        assert_eq!(search(&pf, &q).task_ids, vec!["t1"]);
+
+        let q = ProjectQuery {
+            task_state: Some("failed".to_string()),
+            task_kind: Some("proxy".to_string()),
+            ..Default::default()
+        };
+        assert!(search(&pf, &q).task_ids.is_empty());
+    }
+
+    #[test]
+    fn finds_clips_by_asset() {
+        let pf = make_project();
+        let q = ProjectQuery { clip_asset_id: Some("p1".to_string()), ..Default::default() };
+        let results = search(&pf, &q);
+        assert_eq!(results.clip_ids, vec!["c1"]);
+        assert!(results.asset_ids.is_empty());
+    }
+
+    #[test]
+    fn text_matches_across_groups_case_insensitively() {
+        let pf = make_project();
+        let q = ProjectQuery { text: Some("OPENING".to_string()), ..Default::default() };
+        let results = search(&pf, &q);
+        assert_eq!(results.asset_ids, vec!["p1"]);
+        assert_eq!(results.clip_ids, vec!["c1"]);
+        assert!(results.marker_ids.is_empty());
+
+        let q = ProjectQuery { text: Some("sunset".to_string()), ..Default::default() };
+        assert_eq!(search(&pf, &q).marker_ids, vec!["m1"]);
+    }
+
+    #[test]
+    fn filters_assets_by_type_and_tag() {
+        let pf = make_project();
+        let q = ProjectQuery {
+            asset_type: Some("prompt".to_string()),
+            asset_tag: Some("prompt".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search(&pf, &q).asset_ids, vec!["p1", "p2"]);
+
+        let q = ProjectQuery { asset_type: Some("video".to_string()), ..Default::default() };
+        assert!(search(&pf, &q).asset_ids.is_empty());
+    }
+
+    #[test]
+    fn asset_usages_lists_clips_and_tasks() {
+        let mut pf = make_project();
+        pf.tasks[0].input = serde_json::json!({ "assetId": "p1" });
+        pf.tasks[1].input = serde_json::json!({ "sources": [{ "assetId": "p1" }] });
+        pf.rebuild_indexes();
+
+        let usages = asset_usages(&pf, "p1").unwrap();
+        assert_eq!(usages.clips, vec![ClipUsage {
+            clip_id: "c1".to_string(),
+            track_id: "trk_t".to_string(),
+            start_ms: 0,
+            end_ms: 1000,
+        }]);
+        assert_eq!(usages.task_ids, vec!["t1", "t2"]);
+        assert!(usages.is_used());
+
+        let unused = asset_usages(&pf, "p2").unwrap();
+        assert!(!unused.is_used());
+        assert!(asset_usages(&pf, "nope").is_err());
+    }
+
+    #[test]
+    fn list_tasks_filters_sorts_and_pages() {
+        let mut pf = make_project();
+        pf.tasks.push(make_task("t3", "thumb", "queued", &[]));
+        pf.tasks[0].created_at = "2026-01-01T00:00:02Z".to_string();
+        pf.tasks[1].created_at = "2026-01-01T00:00:03Z".to_string();
+        pf.tasks[2].created_at = "2026-01-01T00:00:01Z".to_string();
+        pf.tasks[1].updated_at = "2026-01-01T00:00:09Z".to_string();
+        let ids = |q: TaskListQuery| -> Vec<String> {
+            list_tasks(&pf, &q).unwrap().into_iter().map(|t| t.task_id.clone()).collect()
+        };
+
+        assert_eq!(ids(TaskListQuery::default()), vec!["t1", "t2", "t3"]);
+        assert_eq!(ids(TaskListQuery { kind: Some("thumb".to_string()), ..Default::default() }), vec!["t1", "t3"]);
+        assert_eq!(ids(TaskListQuery { state: Some("failed".to_string()), ..Default::default() }), vec!["t1"]);
+        assert_eq!(ids(TaskListQuery { sort_by: Some("created".to_string()), ..Default::default() }), vec!["t3", "t1", "t2"]);
+        assert_eq!(
+            ids(TaskListQuery {
+                sort_by: Some("updated".to_string()),
+                order: Some("desc".to_string()),
+                limit: Some(1),
+                ..Default::default()
+            }),
+            vec!["t2"]
+        );
+        assert_eq!(ids(TaskListQuery { offset: Some(1), limit: Some(1), ..Default::default() }), vec!["t2"]);
+
+        let bad_sort = TaskListQuery { sort_by: Some("name".to_string()), ..Default::default() };
+        assert!(list_tasks(&pf, &bad_sort).is_err());
+        let bad_order = TaskListQuery { order: Some("up".to_string()), ..Default::default() };
+        assert!(list_tasks(&pf, &bad_order).is_err());
+    }
+
+    #[test]
+    fn list_assets_filters_and_sorts_by_rating() {
+        let mut pf = make_project();
+        pf.assets.push(make_prompt_asset("p3", "Variant"));
+        pf.assets[0].set_rating(3).unwrap();
+        pf.assets[2].set_rating(5).unwrap();
+        pf.assets[2].set_favorite(true);
+        let ids = |q: AssetListQuery| -> Vec<String> {
+            list_assets(&pf, &q).unwrap().into_iter().map(|a| a.asset_id.clone()).collect()
+        };
+
+        assert_eq!(ids(AssetListQuery::default()), vec!["p1", "p2", "p3"]);
+        assert_eq!(ids(AssetListQuery { min_rating: Some(3), ..Default::default() }), vec!["p1", "p3"]);
+        assert_eq!(ids(AssetListQuery { favorite: Some(true), ..Default::default() }), vec!["p3"]);
+        assert_eq!(
+            ids(AssetListQuery { sort_by: Some("rating".to_string()), order: Some("desc".to_string()), ..Default::default() }),
+            vec!["p3", "p1", "p2"]
+        );
+        assert_eq!(ids(AssetListQuery { asset_type: Some("video".to_string()), ..Default::default() }), Vec::<String>::new());
+        let bad_sort = AssetListQuery { sort_by: Some("stars".to_string()), ..Default::default() };
+        assert!(list_assets(&pf, &bad_sort).is_err());
+        let bad_min = AssetListQuery { min_rating: Some(6), ..Default::default() };
+        assert!(list_assets(&pf, &bad_min).is_err());
+    }
+}
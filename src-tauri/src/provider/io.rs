@@ -1,13 +1,10 @@
+use serde_json::Value;
 use std::path::{Path, PathBuf};
-use tauri::Manager;
 
-use super::model::ProvidersFile;
+use super::model::{ProviderConfig, ProvidersFile, PROVIDERS_FILE_VERSION};
 
 pub fn providers_path(app_handle: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let config_dir = app_handle
-        .path()
-        .app_config_dir()
-        .map_err(|e| format!("Failed to resolve app config dir: {}", e))?;
+    let config_dir = crate::paths::config_dir(app_handle)?;
     std::fs::create_dir_all(&config_dir)
         .map_err(|e| format!("Failed to create config dir: {}", e))?;
     Ok(config_dir.join("providers.json"))
@@ -19,14 +16,146 @@ pub fn load_providers(path: &Path) -> Result<ProvidersFile, String> {
     }
     let data =
         std::fs::read_to_string(path).map_err(|e| format!("Failed to read providers.json: {}", e))?;
-    serde_json::from_str(&data).map_err(|e| format!("Failed to parse providers.json: {}", e))
+    let raw: Value =
+        serde_json::from_str(&data).map_err(|e| format!("Failed to parse providers.json: {}", e))?;
+    Ok(parse_providers(migrate_providers(raw)))
 }
 
 pub fn save_providers_atomic(path: &Path, file: &ProvidersFile) -> Result<(), String> {
-    let json = serde_json::to_string_pretty(file)
+    let mut value = serde_json::to_value(file)
+        .map_err(|e| format!("Failed to serialize providers: {}", e))?;
+    value["version"] = Value::from(PROVIDERS_FILE_VERSION);
+    let json = serde_json::to_string_pretty(&value)
         .map_err(|e| format!("Failed to serialize providers: {}", e))?;
     let tmp = path.with_extension("json.tmp");
     std::fs::write(&tmp, &json).map_err(|e| format!("Failed to write tmp: {}", e))?;
     std::fs::rename(&tmp, path).map_err(|e| format!("Failed to rename tmp: {}", e))?;
     Ok(())
 }
+
+/// Upgrade a raw providers.json document to `PROVIDERS_FILE_VERSION`.
+/// Files written before versioning (no `version` key) are treated as v1.
+fn migrate_providers(mut raw: Value) -> Value {
+    let mut version = raw.get("version").and_then(|v| v.as_u64()).unwrap_or(1) as u32;
+
+    if version < 2 {
+        migrate_v1_to_v2(&mut raw);
+        version = 2;
+    }
+
+    if let Some(obj) = raw.as_object_mut() {
+        obj.insert("version".to_string(), Value::from(version));
+    }
+    raw
+}
+
+/// v2 adds `providerType`; infer it from the base URL for existing entries.
+fn migrate_v1_to_v2(raw: &mut Value) {
+    let providers = match raw.get_mut("providers").and_then(|p| p.as_object_mut()) {
+        Some(p) => p,
+        None => return,
+    };
+    for cfg in providers.values_mut() {
+        let Some(obj) = cfg.as_object_mut() else { continue };
+        if obj.contains_key("providerType") {
+            continue;
+        }
+        let base_url = obj.get("baseUrl").and_then(|v| v.as_str()).unwrap_or("");
+        let provider_type = if base_url.contains("jianying.com") || base_url.contains("jimeng") {
+            "jimeng"
+        } else {
+            "generic"
+        };
+        obj.insert("providerType".to_string(), Value::from(provider_type));
+    }
+}
+
+/// Deserialize providers one by one so a single malformed entry doesn't
+/// discard every other provider in the file.
+fn parse_providers(raw: Value) -> ProvidersFile {
+    let mut file = ProvidersFile::default();
+    let providers = match raw.get("providers").and_then(|p| p.as_object()) {
+        Some(p) => p,
+        None => return file,
+    };
+    for (name, cfg) in providers {
+        match serde_json::from_value::<ProviderConfig>(cfg.clone()) {
+            Ok(c) => {
+                file.providers.insert(name.clone(), c);
+            }
+            Err(e) => {
+                log::warn!("Skipping unparseable provider '{}' in providers.json: {}", name, e);
+            }
+        }
+    }
+    file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v1_provider(base_url: &str) -> Value {
+        serde_json::json!({
+            "displayName": "Test",
+            "baseUrl": base_url,
+            "auth": { "kind": "session_cookie" },
+            "profiles": {
+                "default": {
+                    "model": "m",
+                    "timeoutMs": 30000,
+                    "retry": { "max": 1, "backoffMs": 500 },
+                    "credentialRef": "cred_default"
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn migrate_v1_infers_provider_type() {
+        let raw = serde_json::json!({
+            "version": 1,
+            "providers": {
+                "jimeng": v1_provider("https://jimeng.jianying.com"),
+                "other": v1_provider("https://api.example.com"),
+            }
+        });
+        let file = parse_providers(migrate_providers(raw));
+        assert_eq!(file.version, PROVIDERS_FILE_VERSION);
+        assert_eq!(file.providers["jimeng"].provider_type, "jimeng");
+        assert_eq!(file.providers["other"].provider_type, "generic");
+    }
+
+    #[test]
+    fn migrate_without_version_is_treated_as_v1() {
+        let raw = serde_json::json!({
+            "providers": { "jimeng": v1_provider("https://jimeng.jianying.com") }
+        });
+        let migrated = migrate_providers(raw);
+        assert_eq!(migrated["version"], PROVIDERS_FILE_VERSION);
+        assert_eq!(migrated["providers"]["jimeng"]["providerType"], "jimeng");
+    }
+
+    #[test]
+    fn migrate_keeps_explicit_provider_type() {
+        let mut p = v1_provider("https://jimeng.jianying.com");
+        p["providerType"] = Value::from("custom");
+        let raw = serde_json::json!({ "version": 1, "providers": { "x": p } });
+        let file = parse_providers(migrate_providers(raw));
+        assert_eq!(file.providers["x"].provider_type, "custom");
+    }
+
+    #[test]
+    fn parse_preserves_valid_providers_when_one_is_broken() {
+        let raw = serde_json::json!({
+            "version": 2,
+            "providers": {
+                "good": v1_provider("https://api.example.com"),
+                "bad": { "displayName": 42 },
+            }
+        });
+        let file = parse_providers(migrate_providers(raw));
+        assert_eq!(file.providers.len(), 1);
+        assert!(file.providers.contains_key("good"));
+    }
+}
@@ -3,3 +3,4 @@ pub mod io;
 pub mod auth;
 pub mod redact;
 pub mod test;
+pub mod validate;
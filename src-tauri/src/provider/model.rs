@@ -7,24 +7,156 @@ pub struct ProvidersFile {
     pub providers: HashMap<String, ProviderConfig>,
 }
 
+/// Current on-disk schema version of providers.json.
+pub const PROVIDERS_FILE_VERSION: u32 = 2;
+
 impl Default for ProvidersFile {
     fn default() -> Self {
         Self {
-            version: 1,
+            version: PROVIDERS_FILE_VERSION,
             providers: HashMap::new(),
         }
     }
 }
 
+/// Provider, profile and model a generation call resolved to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GenerationTarget {
+    pub provider_name: String,
+    pub profile_name: String,
+    /// Explicit model, else the profile's configured one. `None` when the
+    /// profile leaves it blank, in which case the handler's default applies.
+    pub model: Option<String>,
+}
+
+impl ProvidersFile {
+    /// Fill in what the caller omitted: provider/profile from the project's
+    /// generation settings, model from the profile. Explicit values win.
+    pub fn resolve_generation(
+        &self,
+        provider_name: Option<String>,
+        profile_name: Option<String>,
+        model: Option<String>,
+        defaults: Option<&crate::project::model::GenerationSettings>,
+    ) -> Result<GenerationTarget, String> {
+        let provider_name = provider_name
+            .or_else(|| defaults.and_then(|d| d.video_provider.clone()))
+            .ok_or("no_provider: 未指定 Provider，且项目未配置默认生成 Provider")?;
+        let profile_name = profile_name
+            .or_else(|| defaults.and_then(|d| d.video_profile.clone()))
+            .ok_or("no_profile: 未指定 Profile，且项目未配置默认生成 Profile")?;
+
+        let provider = self
+            .providers
+            .get(&provider_name)
+            .ok_or(format!("provider_not_found: {}", provider_name))?;
+        let profile = provider
+            .profiles
+            .get(&profile_name)
+            .ok_or(format!("profile_not_found: {}", profile_name))?;
+
+        let model = model
+            .filter(|m| !m.trim().is_empty())
+            .or_else(|| Some(profile.model.clone()).filter(|m| !m.trim().is_empty()));
+        Ok(GenerationTarget { provider_name, profile_name, model })
+    }
+
+    /// Every profile whose secret lives under `credential_ref`, sorted.
+    pub fn credential_users(&self, credential_ref: &str) -> Vec<ProfileRef> {
+        let mut users: Vec<ProfileRef> = self
+            .providers
+            .iter()
+            .flat_map(|(provider, cfg)| {
+                cfg.profiles
+                    .iter()
+                    .filter(|(_, p)| p.credential_ref == credential_ref)
+                    .map(|(profile, _)| ProfileRef { provider: provider.clone(), profile: profile.clone() })
+            })
+            .collect();
+        users.sort();
+        users
+    }
+
+    /// Credential refs of `provider_name`'s profiles that more than one
+    /// profile (of any provider) uses. Setting or deleting one of those
+    /// secrets affects all of them.
+    pub fn shared_credentials(&self, provider_name: &str) -> Vec<SharedCredential> {
+        let Some(provider) = self.providers.get(provider_name) else { return Vec::new() };
+        let mut refs: Vec<&str> = provider.profiles.values().map(|p| p.credential_ref.as_str()).collect();
+        refs.sort();
+        refs.dedup();
+        refs.into_iter()
+            .map(|r| SharedCredential { credential_ref: r.to_string(), profiles: self.credential_users(r) })
+            .filter(|shared| shared.profiles.len() > 1)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileRef {
+    pub provider: String,
+    pub profile: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SharedCredential {
+    pub credential_ref: String,
+    pub profiles: Vec<ProfileRef>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProviderConfig {
     pub display_name: String,
+    #[serde(default = "default_provider_type")]
+    pub provider_type: String,
     pub base_url: String,
     pub auth: AuthConfig,
     #[serde(default)]
     pub test: Option<TestEndpoint>,
     pub profiles: HashMap<String, ProfileConfig>,
+    /// Log (redacted) request and response bodies. Off by default: drafts
+    /// carry the user's prompts.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub debug_logging: bool,
+    /// Request-signing parameters, for when the provider's web app version
+    /// moves on before a release does. Unset fields use the built-in values.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sign: Option<SignConfig>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SignConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub app_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform_code: Option<String>,
+}
+
+impl ProviderConfig {
+    /// URL requests for `profile` go to: its `baseUrlOverride` (staging, a
+    /// regional mirror) if set, else the provider's, without trailing slashes.
+    pub fn effective_base_url(&self, profile: &ProfileConfig) -> String {
+        profile
+            .base_url_override
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .unwrap_or(&self.base_url)
+            .trim_end_matches('/')
+            .to_string()
+    }
+}
+
+fn default_provider_type() -> String {
+    "generic".to_string()
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +182,23 @@ pub struct AuthConfig {
 pub struct TestEndpoint {
     pub method: String,
     pub path: String,
+    /// What a working provider answers; any 2xx when omitted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub success: Option<TestSuccess>,
+}
+
+/// Success predicate for the provider test. Both parts must hold.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSuccess {
+    /// Accepted statuses instead of 2xx, e.g. `[200, 401]` for a health path
+    /// that only checks auth on real endpoints.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub statuses: Vec<u16>,
+    /// Text the body must contain, for providers that answer 200 with an
+    /// error payload.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body_contains: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,8 +208,19 @@ pub struct ProfileConfig {
     pub timeout_ms: u64,
     pub retry: RetryConfig,
     pub credential_ref: String,
+    /// Max generations in flight against this provider (default 1).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent: Option<u32>,
+    /// Minimum gap between generation submits, in ms (default none).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_submit_interval_ms: Option<u64>,
+    /// Replaces the provider's `baseUrl` for this profile.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url_override: Option<String>,
 }
 
+pub const DEFAULT_MAX_CONCURRENT_GENERATIONS: u32 = 1;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RetryConfig {
@@ -87,3 +247,117 @@ pub struct TestResult {
     #[serde(default)]
     pub error: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::GenerationSettings;
+
+    fn providers(model: &str) -> ProvidersFile {
+        let profile = ProfileConfig {
+            model: model.to_string(),
+            timeout_ms: 30000,
+            retry: RetryConfig { max: 1, backoff_ms: 500 },
+            credential_ref: "cred".to_string(),
+            max_concurrent: None,
+            min_submit_interval_ms: None,
+            base_url_override: None,
+        };
+        let provider = ProviderConfig {
+            display_name: "Jimeng".to_string(),
+            provider_type: "jimeng".to_string(),
+            base_url: "https://api.example.com".to_string(),
+            auth: AuthConfig {
+                kind: AuthKind::ApiKey,
+                header: None,
+                prefix: None,
+                cookie_name: None,
+            },
+            test: None,
+            profiles: HashMap::from([("default".to_string(), profile)]),
+            debug_logging: false,
+            sign: None,
+        };
+        ProvidersFile {
+            version: PROVIDERS_FILE_VERSION,
+            providers: HashMap::from([("jimeng".to_string(), provider)]),
+        }
+    }
+
+    fn settings() -> GenerationSettings {
+        GenerationSettings {
+            video_provider: Some("jimeng".to_string()),
+            video_profile: Some("default".to_string()),
+        }
+    }
+
+    #[test]
+    fn profile_base_url_override_wins_and_drops_trailing_slashes() {
+        let mut file = providers("m");
+        let provider = file.providers.get_mut("jimeng").unwrap();
+        let mut profile = provider.profiles["default"].clone();
+        provider.base_url = "https://api.example.com/".to_string();
+        assert_eq!(provider.effective_base_url(&profile), "https://api.example.com");
+
+        profile.base_url_override = Some("https://mirror.example.cn//".to_string());
+        assert_eq!(provider.effective_base_url(&profile), "https://mirror.example.cn");
+        profile.base_url_override = Some("  ".to_string());
+        assert_eq!(provider.effective_base_url(&profile), "https://api.example.com");
+    }
+
+    #[test]
+    fn reports_profiles_sharing_a_credential_ref() {
+        let mut file = providers("m");
+        let provider = file.providers["jimeng"].clone();
+        let mut staging = provider.profiles["default"].clone();
+        staging.credential_ref = "cred_staging".to_string();
+        file.providers.get_mut("jimeng").unwrap().profiles.insert("staging".to_string(), staging);
+        assert!(file.shared_credentials("jimeng").is_empty());
+
+        file.providers.insert("mirror".to_string(), provider);
+        let pr = |provider: &str, profile: &str| ProfileRef { provider: provider.to_string(), profile: profile.to_string() };
+        assert_eq!(file.credential_users("cred"), vec![pr("jimeng", "default"), pr("mirror", "default")]);
+        assert_eq!(file.shared_credentials("jimeng"), vec![SharedCredential {
+            credential_ref: "cred".to_string(),
+            profiles: vec![pr("jimeng", "default"), pr("mirror", "default")],
+        }]);
+        assert!(file.credential_users("unused").is_empty());
+        assert!(file.shared_credentials("missing").is_empty());
+    }
+
+    #[test]
+    fn resolve_generation_falls_back_to_project_and_profile() {
+        let target = providers("jimeng-video-3.0")
+            .resolve_generation(None, None, None, Some(&settings()))
+            .unwrap();
+        assert_eq!(target, GenerationTarget {
+            provider_name: "jimeng".to_string(),
+            profile_name: "default".to_string(),
+            model: Some("jimeng-video-3.0".to_string()),
+        });
+    }
+
+    #[test]
+    fn resolve_generation_prefers_explicit_arguments() {
+        let target = providers("jimeng-video-3.0")
+            .resolve_generation(None, None, Some("custom".to_string()), Some(&settings()))
+            .unwrap();
+        assert_eq!(target.model.as_deref(), Some("custom"));
+
+        let err = providers("m")
+            .resolve_generation(Some("other".to_string()), None, None, Some(&settings()))
+            .unwrap_err();
+        assert!(err.starts_with("provider_not_found"));
+    }
+
+    #[test]
+    fn resolve_generation_needs_a_provider_and_keeps_blank_model_unset() {
+        let err = providers("m").resolve_generation(None, None, None, None).unwrap_err();
+        assert!(err.starts_with("no_provider"));
+
+        let target = providers(" ")
+            .resolve_generation(Some("jimeng".to_string()), Some("default".to_string()), None, None)
+            .unwrap();
+        assert_eq!(target.model, None);
+    }
+}
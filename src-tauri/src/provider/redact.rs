@@ -26,7 +26,9 @@ pub fn redact(text: &str) -> String {
     out = redact_url_params(&out);
 
     if out.len() > MAX_LEN {
-        out.truncate(MAX_LEN);
+        // Response bodies may be non-ASCII; cut on a char boundary
+        let cut = (0..=MAX_LEN).rev().find(|&i| out.is_char_boundary(i)).unwrap_or(0);
+        out.truncate(cut);
         out.push_str("...<truncated>");
     }
     out
@@ -80,5 +82,8 @@ mod tests {
         let result = redact(&long);
         assert!(result.len() <= MAX_LEN + 20);
         assert!(result.ends_with("...<truncated>"));
+
+        let wide = "页".repeat(1000);
+        assert!(redact(&wide).ends_with("...<truncated>"));
     }
 }
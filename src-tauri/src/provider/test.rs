@@ -1,11 +1,54 @@
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
 
 use super::auth::apply_auth;
 use super::io::{load_providers, providers_path};
-use super::model::TestResult;
+use super::model::{TestResult, TestSuccess};
 use super::redact::redact;
 use crate::secrets;
 
+/// Max profiles tested in parallel by `run_provider_test_all`.
+const TEST_ALL_CONCURRENCY: usize = 4;
+
+/// Test every profile of a provider concurrently, keyed by profile name.
+pub async fn run_provider_test_all(
+    app_handle: &tauri::AppHandle,
+    provider_name: &str,
+) -> Result<HashMap<String, TestResult>, String> {
+    let path = providers_path(app_handle)?;
+    let file = load_providers(&path)?;
+    let provider = file
+        .providers
+        .get(provider_name)
+        .ok_or(format!("provider_not_found: {}", provider_name))?;
+
+    let semaphore = Arc::new(tokio::sync::Semaphore::new(TEST_ALL_CONCURRENCY));
+    let mut set = tokio::task::JoinSet::new();
+    for profile_name in provider.profiles.keys() {
+        let app_handle = app_handle.clone();
+        let provider_name = provider_name.to_string();
+        let profile_name = profile_name.clone();
+        let semaphore = semaphore.clone();
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = run_provider_test(&app_handle, &provider_name, &profile_name).await;
+            (profile_name, result)
+        });
+    }
+
+    let mut results = HashMap::new();
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok((profile_name, result)) => {
+                results.insert(profile_name, result);
+            }
+            Err(e) => log::error!("provider test task panicked: {}", e),
+        }
+    }
+    Ok(results)
+}
+
 pub async fn run_provider_test(
     app_handle: &tauri::AppHandle,
     provider_name: &str,
@@ -85,21 +128,21 @@ pub async fn run_provider_test(
     let start = Instant::now();
     match builder.send().await {
         Ok(resp) => {
+            let status = resp.status().as_u16();
+            // The body is part of the predicate, so latency includes reading it
+            let body = resp.text().await.unwrap_or_default();
             let latency = start.elapsed().as_millis() as u64;
-            let status = resp.status();
-            if status.is_success() || status.as_u16() == 204 {
-                TestResult {
+            match check_response(test_ep.and_then(|t| t.success.as_ref()), status, &body) {
+                Ok(()) => TestResult {
                     ok: true,
                     latency_ms: Some(latency),
                     error: None,
-                }
-            } else {
-                let body = resp.text().await.unwrap_or_default();
-                TestResult {
+                },
+                Err(e) => TestResult {
                     ok: false,
                     latency_ms: Some(latency),
-                    error: Some(redact(&format!("http_{}: {}", status.as_u16(), body))),
-                }
+                    error: Some(redact(&e)),
+                },
             }
         }
         Err(e) => {
@@ -119,3 +162,48 @@ pub async fn run_provider_test(
         }
     }
 }
+
+/// Whether a test response means the provider is usable: a status from
+/// `success.statuses` (any 2xx if unset) and, if set, `bodyContains` in the body.
+fn check_response(success: Option<&TestSuccess>, status: u16, body: &str) -> Result<(), String> {
+    let default = TestSuccess::default();
+    let success = success.unwrap_or(&default);
+    let status_ok = if success.statuses.is_empty() {
+        (200..300).contains(&status)
+    } else {
+        success.statuses.contains(&status)
+    };
+    if !status_ok {
+        return Err(format!("http_{}: {}", status, body));
+    }
+    if let Some(needle) = success.body_contains.as_deref() {
+        if !body.contains(needle) {
+            return Err(format!("unexpected_body: http_{} without \"{}\": {}", status, needle, body));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_predicate_accepts_2xx_only() {
+        assert!(check_response(None, 200, "").is_ok());
+        assert!(check_response(None, 204, "").is_ok());
+        assert_eq!(check_response(None, 401, "denied").unwrap_err(), "http_401: denied");
+    }
+
+    #[test]
+    fn custom_statuses_and_body_text() {
+        let auth_probe = TestSuccess { statuses: vec![200, 401], body_contains: None };
+        assert!(check_response(Some(&auth_probe), 401, "").is_ok());
+        assert!(check_response(Some(&auth_probe), 204, "").is_err());
+
+        let body_check = TestSuccess { statuses: vec![], body_contains: Some("\"ret\":\"0\"".to_string()) };
+        assert!(check_response(Some(&body_check), 200, r#"{"ret":"0","data":{}}"#).is_ok());
+        let err = check_response(Some(&body_check), 200, r#"{"ret":"1015","errmsg":"login error"}"#).unwrap_err();
+        assert!(err.starts_with("unexpected_body: http_200"), "{}", err);
+    }
+}
@@ -0,0 +1,266 @@
+use super::model::{AuthKind, ProviderConfig};
+
+/// A single validation problem, addressed by its camelCase JSON path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    fn new(field: impl Into<String>, message: &str) -> Self {
+        Self {
+            field: field.into(),
+            message: message.to_string(),
+        }
+    }
+}
+
+pub fn validate_provider_config(config: &ProviderConfig) -> Result<(), Vec<FieldError>> {
+    let mut errors = Vec::new();
+
+    if config.base_url.trim().is_empty() {
+        errors.push(FieldError::new("baseUrl", "must not be empty"));
+    } else {
+        check_http_url("baseUrl".to_string(), &config.base_url, &mut errors);
+    }
+
+    match config.auth.kind {
+        AuthKind::ApiKey => {
+            if config.auth.header.as_deref().map(str::trim).unwrap_or("").is_empty() {
+                errors.push(FieldError::new("auth.header", "required for api_key auth"));
+            }
+        }
+        AuthKind::SessionCookie => {
+            if let Some(name) = &config.auth.cookie_name {
+                if name.trim().is_empty() {
+                    errors.push(FieldError::new(
+                        "auth.cookieName",
+                        "must not be empty (omit to use the default)",
+                    ));
+                }
+            }
+        }
+    }
+
+    if let Some(success) = config.test.as_ref().and_then(|t| t.success.as_ref()) {
+        if success.statuses.iter().any(|s| !(100..=599).contains(s)) {
+            errors.push(FieldError::new("test.success.statuses", "must be HTTP status codes (100-599)"));
+        }
+        if success.body_contains.as_deref().is_some_and(|b| b.is_empty()) {
+            errors.push(FieldError::new(
+                "test.success.bodyContains",
+                "must not be empty (omit to skip the body check)",
+            ));
+        }
+    }
+
+    let mut names: Vec<&String> = config.profiles.keys().collect();
+    names.sort();
+    for name in names {
+        let profile = &config.profiles[name];
+        if profile.credential_ref.trim().is_empty() {
+            errors.push(FieldError::new(
+                format!("profiles.{}.credentialRef", name),
+                "must not be empty",
+            ));
+        }
+        if profile.timeout_ms == 0 {
+            errors.push(FieldError::new(
+                format!("profiles.{}.timeoutMs", name),
+                "must be positive",
+            ));
+        }
+        if profile.max_concurrent == Some(0) {
+            errors.push(FieldError::new(
+                format!("profiles.{}.maxConcurrent", name),
+                "must be at least 1",
+            ));
+        }
+        // Blank means "use the provider's baseUrl"
+        if let Some(url) = profile.base_url_override.as_deref().filter(|u| !u.trim().is_empty()) {
+            check_http_url(format!("profiles.{}.baseUrlOverride", name), url, &mut errors);
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+fn check_http_url(field: String, value: &str, errors: &mut Vec<FieldError>) {
+    match reqwest::Url::parse(value.trim()) {
+        Ok(url) if url.scheme() == "http" || url.scheme() == "https" => {}
+        Ok(url) => errors.push(FieldError::new(
+            field,
+            &format!("unsupported scheme '{}'", url.scheme()),
+        )),
+        Err(e) => errors.push(FieldError::new(field, &format!("invalid URL: {}", e))),
+    }
+}
+
+/// Render validation errors as a single command error string.
+pub fn format_errors(errors: &[FieldError]) -> String {
+    let parts: Vec<String> = errors
+        .iter()
+        .map(|e| format!("{}: {}", e.field, e.message))
+        .collect();
+    format!("invalid_provider_config: {}", parts.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::model::{AuthConfig, ProfileConfig, RetryConfig, TestEndpoint, TestSuccess};
+    use std::collections::HashMap;
+
+    fn make_config() -> ProviderConfig {
+        let mut profiles = HashMap::new();
+        profiles.insert(
+            "default".to_string(),
+            ProfileConfig {
+                model: "m".to_string(),
+                timeout_ms: 30000,
+                retry: RetryConfig { max: 1, backoff_ms: 500 },
+                credential_ref: "cred_default".to_string(),
+                max_concurrent: None,
+                min_submit_interval_ms: None,
+                base_url_override: None,
+            },
+        );
+        ProviderConfig {
+            display_name: "Test".to_string(),
+            provider_type: "generic".to_string(),
+            base_url: "https://api.example.com".to_string(),
+            auth: AuthConfig {
+                kind: AuthKind::ApiKey,
+                header: Some("Authorization".to_string()),
+                prefix: Some("Bearer ".to_string()),
+                cookie_name: None,
+            },
+            test: None,
+            profiles,
+            debug_logging: false,
+            sign: None,
+        }
+    }
+
+    fn fields(errors: Vec<FieldError>) -> Vec<String> {
+        errors.into_iter().map(|e| e.field).collect()
+    }
+
+    #[test]
+    fn valid_config_passes() {
+        assert!(validate_provider_config(&make_config()).is_ok());
+
+        // Profiles can be added after the provider is saved
+        let mut cfg = make_config();
+        cfg.profiles.clear();
+        assert!(validate_provider_config(&cfg).is_ok());
+    }
+
+    #[test]
+    fn rejects_empty_and_malformed_base_url() {
+        let mut cfg = make_config();
+        cfg.base_url = "".to_string();
+        assert_eq!(fields(validate_provider_config(&cfg).unwrap_err()), vec!["baseUrl"]);
+
+        cfg.base_url = "not a url".to_string();
+        assert_eq!(fields(validate_provider_config(&cfg).unwrap_err()), vec!["baseUrl"]);
+
+        cfg.base_url = "ftp://example.com".to_string();
+        assert_eq!(fields(validate_provider_config(&cfg).unwrap_err()), vec!["baseUrl"]);
+    }
+
+    #[test]
+    fn rejects_blank_credential_ref_and_zero_timeout() {
+        let mut cfg = make_config();
+        let p = cfg.profiles.get_mut("default").unwrap();
+        p.credential_ref = "  ".to_string();
+        p.timeout_ms = 0;
+        assert_eq!(
+            fields(validate_provider_config(&cfg).unwrap_err()),
+            vec!["profiles.default.credentialRef", "profiles.default.timeoutMs"]
+        );
+    }
+
+    #[test]
+    fn rejects_zero_max_concurrent() {
+        let mut cfg = make_config();
+        cfg.profiles.get_mut("default").unwrap().max_concurrent = Some(0);
+        assert_eq!(
+            fields(validate_provider_config(&cfg).unwrap_err()),
+            vec!["profiles.default.maxConcurrent"]
+        );
+    }
+
+    #[test]
+    fn validates_profile_base_url_override() {
+        let mut cfg = make_config();
+        let p = cfg.profiles.get_mut("default").unwrap();
+        p.base_url_override = Some("https://staging.example.com/".to_string());
+        assert!(validate_provider_config(&cfg).is_ok());
+
+        cfg.profiles.get_mut("default").unwrap().base_url_override = Some("mirror".to_string());
+        assert_eq!(
+            fields(validate_provider_config(&cfg).unwrap_err()),
+            vec!["profiles.default.baseUrlOverride"]
+        );
+    }
+
+    #[test]
+    fn validates_test_success_predicate() {
+        let mut cfg = make_config();
+        cfg.test = Some(TestEndpoint {
+            method: "GET".to_string(),
+            path: "/health".to_string(),
+            success: Some(TestSuccess { statuses: vec![200, 401], body_contains: None }),
+        });
+        assert!(validate_provider_config(&cfg).is_ok());
+
+        cfg.test.as_mut().unwrap().success = Some(TestSuccess {
+            statuses: vec![99],
+            body_contains: Some(String::new()),
+        });
+        assert_eq!(
+            fields(validate_provider_config(&cfg).unwrap_err()),
+            vec!["test.success.statuses", "test.success.bodyContains"]
+        );
+    }
+
+    #[test]
+    fn api_key_requires_header() {
+        let mut cfg = make_config();
+        cfg.auth.header = None;
+        assert_eq!(fields(validate_provider_config(&cfg).unwrap_err()), vec!["auth.header"]);
+    }
+
+    #[test]
+    fn session_cookie_allows_default_cookie_name() {
+        let mut cfg = make_config();
+        cfg.auth = AuthConfig {
+            kind: AuthKind::SessionCookie,
+            header: None,
+            prefix: None,
+            cookie_name: None,
+        };
+        assert!(validate_provider_config(&cfg).is_ok());
+
+        cfg.auth.cookie_name = Some("".to_string());
+        assert_eq!(fields(validate_provider_config(&cfg).unwrap_err()), vec!["auth.cookieName"]);
+    }
+
+    #[test]
+    fn format_errors_lists_every_field() {
+        let errors = vec![
+            FieldError::new("baseUrl", "must not be empty"),
+            FieldError::new("profiles.a.timeoutMs", "must be positive"),
+        ];
+        assert_eq!(
+            format_errors(&errors),
+            "invalid_provider_config: baseUrl: must not be empty; profiles.a.timeoutMs: must be positive"
+        );
+    }
+}
@@ -97,7 +97,7 @@ pub(crate) fn build_txt2img_draft(
     negative_prompt: &str,
     seed: Option<u64>,
     sample_strength: f64,
-) -> String {
+) -> Value {
     let size = aspect.size_2k;
     let seed = seed.unwrap_or_else(random_seed);
 
@@ -151,7 +151,19 @@ pub(crate) fn build_txt2img_draft(
         }]
     });
 
-    draft.to_string()
+    draft
+}
+
+/// `build_txt2img_draft` serialized for the request's `draft_content`.
+pub(crate) fn build_txt2img_draft_string(
+    prompt: &str,
+    model: &str,
+    aspect: &AspectRatio,
+    negative_prompt: &str,
+    seed: Option<u64>,
+    sample_strength: f64,
+) -> String {
+    build_txt2img_draft(prompt, model, aspect, negative_prompt, seed, sample_strength).to_string()
 }
 
 pub(crate) fn build_metrics_extra(
@@ -189,13 +201,51 @@ fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// One beat of a multi-segment generation; each becomes a `video_gen_inputs` entry.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VideoSegmentSpec<'a> {
+    pub prompt: &'a str,
+    /// Defaults to `SEEDANCE_DEFAULT_DURATION_MS`
+    pub duration_ms: Option<u32>,
+}
+
 pub(crate) fn build_text2video_draft(
     prompt: &str,
     model: &str,
     ratio: &str,
     duration_ms: Option<u32>,
+) -> Value {
+    build_text2video_draft_segments(&[VideoSegmentSpec { prompt, duration_ms }], model, ratio)
+}
+
+/// `build_text2video_draft` serialized for the request's `draft_content`.
+pub(crate) fn build_text2video_draft_string(
+    prompt: &str,
+    model: &str,
+    ratio: &str,
+    duration_ms: Option<u32>,
 ) -> String {
-    let duration_ms = duration_ms.unwrap_or(SEEDANCE_DEFAULT_DURATION_MS);
+    build_text2video_draft(prompt, model, ratio, duration_ms).to_string()
+}
+
+pub(crate) fn build_text2video_draft_segments(
+    segments: &[VideoSegmentSpec],
+    model: &str,
+    ratio: &str,
+) -> Value {
+    let video_gen_inputs: Vec<Value> = segments
+        .iter()
+        .map(|seg| json!({
+            "duration_ms": seg.duration_ms.unwrap_or(SEEDANCE_DEFAULT_DURATION_MS),
+            "fps": SEEDANCE_DEFAULT_FPS,
+            "id": new_uuid(),
+            "min_version": VIDEO_MIN_VERSION,
+            "prompt": seg.prompt,
+            "resolution": "720p",
+            "type": "",
+            "video_mode": 2
+        }))
+        .collect();
     let component_id = new_uuid();
 
     let metrics_extra = json!({
@@ -240,16 +290,7 @@ pub(crate) fn build_text2video_draft(
                         "priority": 0,
                         "seed": random_seed(),
                         "video_aspect_ratio": ratio,
-                        "video_gen_inputs": [{
-                            "duration_ms": duration_ms,
-                            "fps": SEEDANCE_DEFAULT_FPS,
-                            "id": new_uuid(),
-                            "min_version": VIDEO_MIN_VERSION,
-                            "prompt": prompt,
-                            "resolution": "720p",
-                            "type": "",
-                            "video_mode": 2
-                        }]
+                        "video_gen_inputs": video_gen_inputs
                     },
                     "video_task_extra": metrics_extra.to_string(),
                 }
@@ -257,7 +298,7 @@ pub(crate) fn build_text2video_draft(
         }]
     });
 
-    draft.to_string()
+    draft
 }
 
 pub(crate) fn build_video_metrics_extra() -> String {
@@ -277,8 +318,40 @@ pub(crate) fn build_seedance_draft(
     ratio: &str,
     duration_ms: Option<u32>,
     video_task_extra: &str,
+) -> Value {
+    build_seedance_draft_segments(&[VideoSegmentSpec { prompt, duration_ms }], internal_model, ratio, video_task_extra)
+}
+
+/// `build_seedance_draft` serialized for the request's `draft_content`.
+pub(crate) fn build_seedance_draft_string(
+    prompt: &str,
+    internal_model: &str,
+    ratio: &str,
+    duration_ms: Option<u32>,
+    video_task_extra: &str,
 ) -> String {
-    let dur = duration_ms.unwrap_or(SEEDANCE_DEFAULT_DURATION_MS);
+    build_seedance_draft(prompt, internal_model, ratio, duration_ms, video_task_extra).to_string()
+}
+
+pub(crate) fn build_seedance_draft_segments(
+    segments: &[VideoSegmentSpec],
+    internal_model: &str,
+    ratio: &str,
+    video_task_extra: &str,
+) -> Value {
+    let video_gen_inputs: Vec<Value> = segments
+        .iter()
+        .map(|seg| json!({
+            "type": "",
+            "id": new_uuid(),
+            "min_version": VIDEO_MIN_VERSION,
+            "prompt": seg.prompt,
+            "video_mode": SEEDANCE_VIDEO_MODE,
+            "fps": SEEDANCE_DEFAULT_FPS,
+            "duration_ms": seg.duration_ms.unwrap_or(SEEDANCE_DEFAULT_DURATION_MS),
+            "idip_meta_list": []
+        }))
+        .collect();
     let seed: u64 = rand::thread_rng().gen_range(1_000_000_000..2_600_000_000);
 
     let component_id = new_uuid();
@@ -314,16 +387,7 @@ pub(crate) fn build_seedance_draft(
                     "text_to_video_params": {
                         "type": "",
                         "id": new_uuid(),
-                        "video_gen_inputs": [{
-                            "type": "",
-                            "id": new_uuid(),
-                            "min_version": VIDEO_MIN_VERSION,
-                            "prompt": prompt,
-                            "video_mode": SEEDANCE_VIDEO_MODE,
-                            "fps": SEEDANCE_DEFAULT_FPS,
-                            "duration_ms": dur,
-                            "idip_meta_list": []
-                        }],
+                        "video_gen_inputs": video_gen_inputs,
                         "video_aspect_ratio": ratio,
                         "seed": seed,
                         "model_req_key": internal_model,
@@ -336,7 +400,7 @@ pub(crate) fn build_seedance_draft(
         }]
     });
 
-    draft.to_string()
+    draft
 }
 
 pub(crate) fn build_seedance_metrics_extra(internal_model: &str, duration_ms: u32, submit_id: &str) -> String {
@@ -446,7 +510,7 @@ pub async fn generate_image(
     let internal_model = resolve_model(model);
     let aspect = get_aspect_ratio(ratio);
 
-    let draft = build_txt2img_draft(
+    let draft = build_txt2img_draft_string(
         prompt,
         &internal_model,
         &aspect,
@@ -496,16 +560,16 @@ pub async fn generate_video(
     let (draft, metrics_extra, benefit_type) = if is_seedance {
         let dur = duration_ms.unwrap_or(SEEDANCE_DEFAULT_DURATION_MS);
         let metrics = build_seedance_metrics_extra(&internal_model, dur, &submit_id);
-        let draft = build_seedance_draft(prompt, &internal_model, ratio, duration_ms, &metrics);
+        let draft = build_seedance_draft_string(prompt, &internal_model, ratio, duration_ms, &metrics);
         (draft, metrics, SEEDANCE_BENEFIT_TYPE)
     } else {
-        let draft = build_text2video_draft(prompt, &internal_model, ratio, duration_ms);
+        let draft = build_text2video_draft_string(prompt, &internal_model, ratio, duration_ms);
         let metrics = build_video_metrics_extra();
         (draft, metrics, VIDEO_BENEFIT_TYPE)
     };
 
     log::info!("[generate_video] internal_model={}, benefit_type={}, seedance={}", internal_model, benefit_type, is_seedance);
-    log::info!("[generate_video] draft_content={}", draft);
+    client.debug_log("generate_video draft_content", &draft);
 
     let body = json!({
         "extend": {
@@ -531,7 +595,7 @@ pub async fn generate_video(
 
     let resp = client.post(GENERATE_PATH, &body, &internal_model, false, None).await?;
 
-    log::info!("[generate_video] full response: {}", serde_json::to_string_pretty(&resp).unwrap_or_default());
+    client.debug_log("generate_video response", &resp.to_string());
 
     let history_id = parse_history_id(&resp);
     let server_submit_id = parse_submit_id(&resp);
@@ -613,15 +677,14 @@ mod tests {
 
     #[test]
     fn draft_is_valid_json() {
-        let draft = build_txt2img_draft("test prompt", "high_aes_general_v40l", &get_aspect_ratio("1:1"), "", None, 0.5);
+        let draft = build_txt2img_draft_string("test prompt", "high_aes_general_v40l", &get_aspect_ratio("1:1"), "", None, 0.5);
         let parsed: Value = serde_json::from_str(&draft).expect("draft should be valid JSON");
         assert_eq!(parsed["type"], "draft");
     }
 
     #[test]
     fn draft_has_required_top_level_fields() {
-        let draft = build_txt2img_draft("hello", "model_v1", &get_aspect_ratio("16:9"), "", None, 0.5);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("hello", "model_v1", &get_aspect_ratio("16:9"), "", None, 0.5);
 
         assert_eq!(v["type"], "draft");
         assert!(v["id"].is_string());
@@ -635,8 +698,7 @@ mod tests {
 
     #[test]
     fn draft_component_structure() {
-        let draft = build_txt2img_draft("cat", "model_v1", &get_aspect_ratio("1:1"), "ugly", Some(12345), 0.7);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("cat", "model_v1", &get_aspect_ratio("1:1"), "ugly", Some(12345), 0.7);
         let comp = &v["component_list"][0];
 
         assert_eq!(comp["type"], "image_base_component");
@@ -655,8 +717,7 @@ mod tests {
 
     #[test]
     fn draft_16_9_aspect_ratio() {
-        let draft = build_txt2img_draft("test", "m", &get_aspect_ratio("16:9"), "", None, 0.5);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("test", "m", &get_aspect_ratio("16:9"), "", None, 0.5);
         let core = &v["component_list"][0]["abilities"]["generate"]["core_param"];
 
         assert_eq!(core["image_ratio"], 3);
@@ -668,8 +729,7 @@ mod tests {
 
     #[test]
     fn draft_main_component_id_matches() {
-        let draft = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
 
         let main_id = v["main_component_id"].as_str().unwrap();
         let comp_id = v["component_list"][0]["id"].as_str().unwrap();
@@ -678,8 +738,7 @@ mod tests {
 
     #[test]
     fn draft_uuids_are_unique() {
-        let draft = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
 
         let draft_id = v["id"].as_str().unwrap();
         let main_id = v["main_component_id"].as_str().unwrap();
@@ -920,8 +979,7 @@ mod tests {
     #[test]
     fn draft_auto_seed_in_expected_range() {
         for _ in 0..20 {
-            let draft = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
-            let v: Value = serde_json::from_str(&draft).unwrap();
+            let v = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", None, 0.5);
             let seed = v["component_list"][0]["abilities"]["generate"]["core_param"]["seed"]
                 .as_u64()
                 .unwrap();
@@ -935,8 +993,7 @@ mod tests {
 
     #[test]
     fn draft_explicit_seed_used() {
-        let draft = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", Some(999), 0.5);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_txt2img_draft("test", "m", &get_aspect_ratio("1:1"), "", Some(999), 0.5);
         let seed = v["component_list"][0]["abilities"]["generate"]["core_param"]["seed"]
             .as_u64()
             .unwrap();
@@ -949,7 +1006,7 @@ mod tests {
 
     #[test]
     fn video_draft_is_valid_json() {
-        let draft = build_text2video_draft("test video", "model_v1", "16:9", None);
+        let draft = build_text2video_draft_string("test video", "model_v1", "16:9", None);
         let v: Value = serde_json::from_str(&draft).expect("video draft should be valid JSON");
         assert_eq!(v["type"], "draft");
         assert_eq!(v["version"], VIDEO_DRAFT_VERSION);
@@ -957,8 +1014,7 @@ mod tests {
 
     #[test]
     fn video_draft_structure() {
-        let draft = build_text2video_draft("a cat running", "model_v1", "16:9", Some(8000));
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_text2video_draft("a cat running", "model_v1", "16:9", Some(8000));
 
         assert_eq!(v["type"], "draft");
         assert!(v["id"].is_string());
@@ -989,8 +1045,7 @@ mod tests {
 
     #[test]
     fn video_draft_default_duration() {
-        let draft = build_text2video_draft("test", "m", "1:1", None);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_text2video_draft("test", "m", "1:1", None);
         let dur = v["component_list"][0]["abilities"]["gen_video"]["text_to_video_params"]["video_gen_inputs"][0]["duration_ms"]
             .as_u64().unwrap();
         assert_eq!(dur, SEEDANCE_DEFAULT_DURATION_MS as u64);
@@ -999,8 +1054,7 @@ mod tests {
     #[test]
     fn video_draft_ratio_passed_through() {
         for ratio in &["16:9", "9:16", "1:1"] {
-            let draft = build_text2video_draft("test", "m", ratio, None);
-            let v: Value = serde_json::from_str(&draft).unwrap();
+            let v = build_text2video_draft("test", "m", ratio, None);
             assert_eq!(
                 v["component_list"][0]["abilities"]["gen_video"]["text_to_video_params"]["video_aspect_ratio"].as_str().unwrap(),
                 *ratio,
@@ -1008,10 +1062,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn video_draft_segments_become_separate_inputs() {
+        let segments = [
+            VideoSegmentSpec { prompt: "a door opens", duration_ms: Some(3000) },
+            VideoSegmentSpec { prompt: "a cat walks in", duration_ms: None },
+        ];
+        let drafts = [
+            build_text2video_draft_segments(&segments, "m", "16:9"),
+            build_seedance_draft_segments(&segments, "m", "16:9", "{}"),
+        ];
+        for v in drafts {
+            let inputs = v["component_list"][0]["abilities"]["gen_video"]["text_to_video_params"]["video_gen_inputs"]
+                .as_array()
+                .unwrap();
+            assert_eq!(inputs.len(), 2);
+            assert_eq!(inputs[0]["prompt"], "a door opens");
+            assert_eq!(inputs[0]["duration_ms"], 3000);
+            assert_eq!(inputs[1]["prompt"], "a cat walks in");
+            assert_eq!(inputs[1]["duration_ms"], SEEDANCE_DEFAULT_DURATION_MS);
+            assert_ne!(inputs[0]["id"], inputs[1]["id"]);
+        }
+    }
+
     #[test]
     fn video_draft_main_component_id_matches() {
-        let draft = build_text2video_draft("test", "m", "16:9", None);
-        let v: Value = serde_json::from_str(&draft).unwrap();
+        let v = build_text2video_draft("test", "m", "16:9", None);
         let main_id = v["main_component_id"].as_str().unwrap();
         let comp_id = v["component_list"][0]["id"].as_str().unwrap();
         assert_eq!(main_id, comp_id);
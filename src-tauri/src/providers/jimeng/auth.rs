@@ -3,6 +3,48 @@ use rand::Rng;
 
 use super::constants::{APP_VERSION, PLATFORM_CODE, SIGN_PREFIX, SIGN_SUFFIX};
 use super::now_secs;
+use crate::provider::model::SignConfig;
+
+/// sign 计算与 Appvr/Pf 请求头所用的参数。默认取内置常量，
+/// 可由 Provider 配置中的 `sign` 覆盖（应对网页版升级）。
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignParams {
+    pub prefix: String,
+    pub suffix: String,
+    pub app_version: String,
+    pub platform_code: String,
+}
+
+impl Default for SignParams {
+    fn default() -> Self {
+        Self {
+            prefix: SIGN_PREFIX.to_string(),
+            suffix: SIGN_SUFFIX.to_string(),
+            app_version: APP_VERSION.to_string(),
+            platform_code: PLATFORM_CODE.to_string(),
+        }
+    }
+}
+
+impl SignParams {
+    /// 配置中非空的字段覆盖默认值。
+    pub fn from_config(config: Option<&SignConfig>) -> Self {
+        let mut params = Self::default();
+        let Some(config) = config else { return params };
+        let overrides = [
+            (&config.prefix, &mut params.prefix),
+            (&config.suffix, &mut params.suffix),
+            (&config.app_version, &mut params.app_version),
+            (&config.platform_code, &mut params.platform_code),
+        ];
+        for (value, field) in overrides {
+            if let Some(v) = value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+                *field = v.to_string();
+            }
+        }
+        params
+    }
+}
 
 fn random_digits(len: usize) -> String {
     let mut rng = rand::thread_rng();
@@ -84,8 +126,9 @@ pub fn generate_cookie(token: &str) -> String {
 
 /// 生成内部 API 的 sign 值。
 ///
-/// sign = MD5("9e2c|{uri_last_7}|7|8.4.0|{device_time}||11ac")
-pub fn generate_sign(uri: &str, device_time: u64) -> String {
+/// sign = MD5("{prefix}|{uri_last_7}|{platform_code}|{app_version}|{device_time}||{suffix}")，
+/// 默认参数下即 MD5("9e2c|{uri_last_7}|7|8.4.0|{device_time}||11ac")
+pub fn generate_sign(uri: &str, device_time: u64, params: &SignParams) -> String {
     let uri_bytes = uri.as_bytes();
     let start = if uri_bytes.len() > 7 {
         uri_bytes.len() - 7
@@ -96,7 +139,7 @@ pub fn generate_sign(uri: &str, device_time: u64) -> String {
 
     let raw = format!(
         "{}|{}|{}|{}|{}||{}",
-        SIGN_PREFIX, uri_suffix, PLATFORM_CODE, APP_VERSION, device_time, SIGN_SUFFIX
+        params.prefix, uri_suffix, params.platform_code, params.app_version, device_time, params.suffix
     );
 
     let mut hasher = Md5::new();
@@ -110,36 +153,36 @@ mod tests {
 
     #[test]
     fn sign_is_32_char_hex() {
-        let sign = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
+        let sign = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
         assert_eq!(sign.len(), 32);
         assert!(sign.chars().all(|c| c.is_ascii_hexdigit()));
     }
 
     #[test]
     fn sign_is_deterministic() {
-        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
-        let b = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
+        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
+        let b = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
         assert_eq!(a, b);
     }
 
     #[test]
     fn sign_changes_with_uri() {
-        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
-        let b = generate_sign("/mweb/v1/get_history_by_ids", 1700000000);
+        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
+        let b = generate_sign("/mweb/v1/get_history_by_ids", 1700000000, &SignParams::default());
         assert_ne!(a, b);
     }
 
     #[test]
     fn sign_changes_with_time() {
-        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
-        let b = generate_sign("/mweb/v1/aigc_draft/generate", 1700000001);
+        let a = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
+        let b = generate_sign("/mweb/v1/aigc_draft/generate", 1700000001, &SignParams::default());
         assert_ne!(a, b);
     }
 
     #[test]
     fn sign_matches_python_formula() {
         // Python: MD5("9e2c|enerate|7|8.4.0|1700000000||11ac")
-        let sign = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000);
+        let sign = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &SignParams::default());
         let raw = "9e2c|enerate|7|8.4.0|1700000000||11ac";
         let mut hasher = Md5::new();
         hasher.update(raw.as_bytes());
@@ -147,10 +190,27 @@ mod tests {
         assert_eq!(sign, expected);
     }
 
+    #[test]
+    fn sign_uses_configured_parameters() {
+        let config = SignConfig {
+            app_version: Some("8.5.0".to_string()),
+            prefix: Some("  ".to_string()),
+            ..SignConfig::default()
+        };
+        let params = SignParams::from_config(Some(&config));
+        assert_eq!(params.app_version, "8.5.0");
+        assert_eq!(params.prefix, SIGN_PREFIX);
+
+        let sign = generate_sign("/mweb/v1/aigc_draft/generate", 1700000000, &params);
+        let mut hasher = Md5::new();
+        hasher.update("9e2c|enerate|7|8.5.0|1700000000||11ac".as_bytes());
+        assert_eq!(sign, format!("{:x}", hasher.finalize()));
+    }
+
     #[test]
     fn sign_short_uri() {
         // URI shorter than 7 chars: use the whole URI
-        let sign = generate_sign("/ab", 1000);
+        let sign = generate_sign("/ab", 1000, &SignParams::default());
         let raw = "9e2c|/ab|7|8.4.0|1000||11ac";
         let mut hasher = Md5::new();
         hasher.update(raw.as_bytes());
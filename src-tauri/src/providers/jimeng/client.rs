@@ -5,15 +5,17 @@ use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use serde_json::Value;
 
 use super::a_bogus::{generate_a_bogus, generate_ms_token};
-use super::auth::{generate_cookie, generate_sign};
+use super::auth::{generate_cookie, generate_sign, SignParams};
 use super::constants::*;
 use super::now_secs;
 
 pub struct JimengClient {
-    base_url: String,
+    pub(crate) base_url: String,
     cookie: String,
     web_id: String,
     http: reqwest::Client,
+    pub(crate) debug_logging: bool,
+    sign: SignParams,
 }
 
 impl JimengClient {
@@ -39,14 +41,36 @@ impl JimengClient {
             cookie,
             web_id,
             http,
+            debug_logging: false,
+            sign: SignParams::default(),
         })
     }
 
+    /// Sign with `params` (and send their app version and platform code).
+    pub fn with_sign_params(mut self, params: SignParams) -> Self {
+        self.sign = params;
+        self
+    }
+
+    /// Log request and response bodies (redacted) for this client.
+    pub fn with_debug_logging(mut self, enabled: bool) -> Self {
+        self.debug_logging = enabled;
+        self
+    }
+
+    /// Verbose request/response logging, only when the provider's
+    /// `debugLogging` is on. Always redacted.
+    pub(crate) fn debug_log(&self, label: &str, text: &str) {
+        if self.debug_logging {
+            log::info!("[Jimeng] {}: {}", label, crate::provider::redact::redact(text));
+        }
+    }
+
     const USER_AGENT: &'static str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36";
 
     pub(crate) fn common_headers(&self, uri: &str) -> HeaderMap {
         let device_time = now_secs();
-        let sign = generate_sign(uri, device_time);
+        let sign = generate_sign(uri, device_time, &self.sign);
 
         let pairs: Vec<(&str, String)> = vec![
             ("Accept", "application/json, text/plain, */*".into()),
@@ -54,7 +78,7 @@ impl JimengClient {
             ("Cache-Control", "no-cache".into()),
             ("Content-Type", "application/json".into()),
             ("Appid", APP_ID.into()),
-            ("Appvr", APP_VERSION.into()),
+            ("Appvr", self.sign.app_version.clone()),
             ("device-time", device_time.to_string()),
             ("sign-ver", "1".into()),
             ("sign", sign),
@@ -66,7 +90,7 @@ impl JimengClient {
             ("Pragma", "no-cache".into()),
             ("Priority", "u=1, i".into()),
             ("Referer", BASE_URL.into()),
-            ("Pf", PLATFORM_CODE.into()),
+            ("Pf", self.sign.platform_code.clone()),
             (
                 "Sec-Ch-Ua",
                 r#""Google Chrome";v="131", "Chromium";v="131", "Not_A Brand";v="24""#.into(),
@@ -165,17 +189,9 @@ impl JimengClient {
         let a_bogus = generate_a_bogus(&query_string, Self::USER_AGENT);
         params.push(("a_bogus".into(), a_bogus));
 
-        let body_str = body.to_string();
-        let body_preview = if body_str.len() > 2000 {
-            format!("{}...(truncated, total {} bytes)", &body_str[..2000], body_str.len())
-        } else {
-            body_str.clone()
-        };
-
         log::info!("[Jimeng] POST {}", path);
-        log::debug!("[Jimeng] URL: {}", url);
-        log::debug!("[Jimeng] Query params: {:?}", params);
-        log::info!("[Jimeng] Body: {}", body_preview);
+        self.debug_log("URL", &url);
+        self.debug_log("Body", &body.to_string());
 
         let resp = self
             .http
@@ -196,14 +212,8 @@ impl JimengClient {
             .await
             .map_err(|e| format!("Failed to read response body: {}", e))?;
 
-        let resp_preview = if text.len() > 3000 {
-            format!("{}...(truncated, total {} bytes)", &text[..3000], text.len())
-        } else {
-            text.clone()
-        };
-
         log::info!("[Jimeng] Response status: {}", status);
-        log::info!("[Jimeng] Response body: {}", resp_preview);
+        self.debug_log("Response body", &text);
 
         if !status.is_success() {
             return Err(format!("HTTP {}: {}", status, text));
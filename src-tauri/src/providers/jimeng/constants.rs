@@ -71,6 +71,48 @@ pub static VIDEO_MODELS: LazyLock<HashMap<&str, &str>> = LazyLock::new(|| {
     ])
 });
 
+/// 未指定模型时 gen_video 使用的视频模型。
+pub const DEFAULT_VIDEO_MODEL: &str = "jimeng-video-3.0";
+
+// ---------------------------------------------------------------------------
+// 视频时长: 各模型只接受离散的时长 (毫秒)
+// ---------------------------------------------------------------------------
+const SEEDANCE_DURATIONS_MS: [u32; 12] = [
+    4000, 5000, 6000, 7000, 8000, 9000, 10000, 11000, 12000, 13000, 14000, 15000,
+];
+
+pub static VIDEO_DURATIONS_MS: LazyLock<HashMap<&str, &[u32]>> = LazyLock::new(|| {
+    HashMap::from([
+        ("jimeng-video-3.0", &[5000, 10000][..]),
+        ("jimeng-video-3.0-pro", &[5000, 10000][..]),
+        ("jimeng-video-2.0-pro", &[5000][..]),
+        ("jimeng-video-2.0", &[5000][..]),
+        ("seedance-2.0", &SEEDANCE_DURATIONS_MS[..]),
+    ])
+});
+
+/// 将请求时长吸附到模型支持的最近值。未知模型原样返回；
+/// 为 0 或超出支持范围一倍以上的请求视为无效。
+pub fn normalize_video_duration(model: &str, requested_ms: u32) -> Result<u32, String> {
+    let Some(allowed) = VIDEO_DURATIONS_MS.get(model) else {
+        return Ok(requested_ms);
+    };
+    let min = allowed[0];
+    let max = allowed[allowed.len() - 1];
+    if requested_ms == 0 || requested_ms < min / 2 || requested_ms > max * 2 {
+        return Err(format!(
+            "invalid_duration: {}ms is not supported by {} (allowed: {})",
+            requested_ms,
+            model,
+            allowed.iter().map(|d| format!("{}ms", d)).collect::<Vec<_>>().join(", ")
+        ));
+    }
+    Ok(*allowed
+        .iter()
+        .min_by_key(|d| d.abs_diff(requested_ms))
+        .unwrap_or(&requested_ms))
+}
+
 /// 将用户模型名解析为内部名称，找不到则原样返回。
 pub fn resolve_model(name: &str) -> String {
     if let Some(v) = IMAGE_MODELS.get(name) {
@@ -256,6 +298,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn video_duration_snaps_to_supported_values() {
+        assert_eq!(normalize_video_duration("jimeng-video-3.0", 5000), Ok(5000));
+        assert_eq!(normalize_video_duration("jimeng-video-3.0", 6000), Ok(5000));
+        assert_eq!(normalize_video_duration("jimeng-video-3.0", 9000), Ok(10000));
+        assert_eq!(normalize_video_duration("jimeng-video-2.0", 10000), Ok(5000));
+        assert_eq!(normalize_video_duration("seedance-2.0", 8400), Ok(8000));
+        assert_eq!(normalize_video_duration("custom_v1", 7777), Ok(7777));
+    }
+
+    #[test]
+    fn video_duration_rejects_wild_values() {
+        assert!(normalize_video_duration("jimeng-video-3.0", 0).is_err());
+        assert!(normalize_video_duration("jimeng-video-3.0", 500).is_err());
+        let err = normalize_video_duration("jimeng-video-3.0", 60_000).unwrap_err();
+        assert!(err.contains("5000ms, 10000ms"), "{}", err);
+    }
+
+    #[test]
+    fn every_video_model_has_durations() {
+        for name in VIDEO_MODELS.keys() {
+            assert!(VIDEO_DURATIONS_MS.contains_key(name), "{} has no durations", name);
+        }
+    }
+
     #[test]
     fn aspect_ratio_known_ratios() {
         let r = get_aspect_ratio("1:1");
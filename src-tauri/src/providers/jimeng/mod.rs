@@ -6,9 +6,114 @@ pub mod api;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::provider::model::ProvidersFile;
+use client::JimengClient;
+
+/// Floor for the per-request timeout, whatever the profile says.
+const MIN_TIMEOUT_SECS: u64 = 10;
+
 pub(crate) fn now_secs() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs()
 }
+
+/// Client for `provider_name`/`profile_name` from providers.json. Commands
+/// and task handlers both build clients here.
+pub fn build_client(
+    app_handle: &tauri::AppHandle,
+    provider_name: &str,
+    profile_name: &str,
+    token_override: Option<&str>,
+) -> Result<JimengClient, String> {
+    let path = crate::provider::io::providers_path(app_handle)?;
+    let file = crate::provider::io::load_providers(&path)?;
+    client_for_profile(&file, provider_name, profile_name, token_override)
+}
+
+/// `token_override` skips the keychain (e.g. testing a token before saving it).
+pub fn client_for_profile(
+    file: &ProvidersFile,
+    provider_name: &str,
+    profile_name: &str,
+    token_override: Option<&str>,
+) -> Result<JimengClient, String> {
+    let prov = file
+        .providers
+        .get(provider_name)
+        .ok_or(format!("provider_not_found: {}", provider_name))?;
+    let profile = prov
+        .profiles
+        .get(profile_name)
+        .ok_or(format!("profile_not_found: {}", profile_name))?;
+
+    let secret = match token_override {
+        Some(token) => token.to_string(),
+        None => crate::secrets::get_secret(&profile.credential_ref)?
+            .ok_or("missing_credentials: 请在设置中连接 Provider")?,
+    };
+
+    let timeout_secs = profile.timeout_ms / 1000;
+    JimengClient::new(
+        &secret,
+        Some(prov.effective_base_url(profile).as_str()),
+        timeout_secs.max(MIN_TIMEOUT_SECS),
+    )
+    .map(|client| {
+        client
+            .with_debug_logging(prov.debug_logging)
+            .with_sign_params(auth::SignParams::from_config(prov.sign.as_ref()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::model::{AuthConfig, AuthKind, ProfileConfig, ProviderConfig, RetryConfig};
+    use std::collections::HashMap;
+
+    fn providers_file() -> ProvidersFile {
+        let profile = ProfileConfig {
+            model: String::new(),
+            timeout_ms: 2000,
+            retry: RetryConfig { max: 1, backoff_ms: 500 },
+            credential_ref: "cred_jimeng_test".to_string(),
+            max_concurrent: None,
+            min_submit_interval_ms: None,
+            base_url_override: Some("https://mirror.example.cn/".to_string()),
+        };
+        let provider = ProviderConfig {
+            display_name: "Jimeng".to_string(),
+            provider_type: "jimeng".to_string(),
+            base_url: constants::BASE_URL.to_string(),
+            auth: AuthConfig {
+                kind: AuthKind::SessionCookie,
+                header: None,
+                prefix: None,
+                cookie_name: None,
+            },
+            test: None,
+            profiles: HashMap::from([("default".to_string(), profile)]),
+            debug_logging: false,
+            sign: None,
+        };
+        ProvidersFile {
+            providers: HashMap::from([("jimeng".to_string(), provider)]),
+            ..ProvidersFile::default()
+        }
+    }
+
+    #[test]
+    fn client_for_profile_uses_override_token_and_base_url() {
+        let file = providers_file();
+        let client = client_for_profile(&file, "jimeng", "default", Some("tok")).unwrap();
+        assert_eq!(client.base_url, "https://mirror.example.cn");
+        assert!(!client.debug_logging);
+
+        let Err(err) = client_for_profile(&file, "jimeng", "other", Some("tok")) else { panic!("unknown profile resolved") };
+        assert!(err.starts_with("profile_not_found"), "{}", err);
+        let Err(err) = client_for_profile(&file, "nope", "default", Some("tok")) else { panic!("unknown provider resolved") };
+        assert!(err.starts_with("provider_not_found"), "{}", err);
+    }
+}
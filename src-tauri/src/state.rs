@@ -1,14 +1,73 @@
 use std::path::PathBuf;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::sync::{Mutex, Notify};
 
-use crate::project::model::ProjectFile;
+use crate::media::cache::{ArtifactCache, CACHE_BUDGET_BYTES, MAX_ENTRY_BYTES};
+use crate::project::layout::WorkspaceLayout;
+use crate::project::model::{
+    ProjectFile, ProjectSettings, DEFAULT_SAVE_DEBOUNCE_MS, SAVE_DEBOUNCE_LIMITS_MS,
+};
 
 pub struct LoadedProject {
     pub project: ProjectFile,
     pub json_path: PathBuf,
     pub project_dir: PathBuf,
     pub dirty: bool,
+    /// Opened for inspection: edits are refused, nothing is saved and the
+    /// runner leaves its tasks alone.
+    pub read_only: bool,
+}
+
+impl LoadedProject {
+    /// Absolute workspace directory (honors an absolute `paths.workspaceRoot`).
+    pub fn workspace_dir(&self) -> PathBuf {
+        crate::project::io::resolve_workspace_root(
+            &self.project_dir,
+            &self.project.project.paths.workspace_root,
+        )
+    }
+
+    /// Resolve a path stored in project.json to an absolute path on disk.
+    pub fn resolve_path(&self, relative: &str) -> PathBuf {
+        crate::project::io::resolve_project_path(&self.project_dir, &self.workspace_dir(), relative)
+    }
+
+    /// Where each kind of workspace file lives, per `project.paths`.
+    pub fn layout(&self) -> WorkspaceLayout {
+        WorkspaceLayout::new(&self.project_dir, &self.workspace_dir(), &self.project.project.paths)
+    }
+}
+
+/// Throttle for one generation provider: caps in-flight generations and
+/// spaces out submits. Local (ffmpeg) tasks never touch this.
+pub struct ProviderGate {
+    pub max_in_flight: u32,
+    pub min_interval: std::time::Duration,
+    pub slots: Arc<tokio::sync::Semaphore>,
+    pub last_submit: Mutex<Option<tokio::time::Instant>>,
+}
+
+impl ProviderGate {
+    fn new(max_in_flight: u32, min_interval: std::time::Duration) -> Self {
+        Self {
+            max_in_flight,
+            min_interval,
+            slots: Arc::new(tokio::sync::Semaphore::new(max_in_flight as usize)),
+            last_submit: Mutex::new(None),
+        }
+    }
+
+    /// Wait until `min_interval` has passed since the previous submit, then
+    /// claim the submit slot. Concurrent callers are serialized.
+    pub async fn wait_for_submit(&self) {
+        let mut last = self.last_submit.lock().await;
+        if let Some(prev) = *last {
+            tokio::time::sleep_until(prev + self.min_interval).await;
+        }
+        *last = Some(tokio::time::Instant::now());
+    }
 }
 
 pub struct AppState {
@@ -16,6 +75,25 @@ pub struct AppState {
     pub save_notify: Notify,
     pub task_notify: Notify,
     pub cancel_flags: Mutex<std::collections::HashSet<String>>,
+    pub save_debounce_ms: AtomicU64,
+    /// Set once the app starts shutting down; the runner stops picking tasks
+    /// and running ffmpeg children are killed.
+    pub shutting_down: AtomicBool,
+    /// While set the runner starts no new tasks; in-flight ones finish.
+    pub queue_paused: AtomicBool,
+    /// Wakes throttled generations held back by a pause.
+    pub queue_resumed: Notify,
+    /// Running ffmpeg children keyed by task id (value is the OS pid).
+    pub children: std::sync::Mutex<HashMap<String, u32>>,
+    /// Origins allowed to read `media://` responses (CORS).
+    pub media_origins: std::sync::RwLock<Vec<String>>,
+    /// Generation throttles keyed by `"{provider}/{profile}"`.
+    pub provider_gates: std::sync::Mutex<HashMap<String, Arc<ProviderGate>>>,
+    /// Generations handed to a gate but still waiting for a slot. They stay
+    /// `queued` on disk; the runner must not pick them again.
+    pub throttled: std::sync::Mutex<std::collections::HashSet<String>>,
+    /// Thumbnails and other small artifacts the UI re-requests on repaint.
+    pub artifact_cache: ArtifactCache,
 }
 
 impl AppState {
@@ -25,6 +103,144 @@ impl AppState {
             save_notify: Notify::new(),
             task_notify: Notify::new(),
             cancel_flags: Mutex::new(std::collections::HashSet::new()),
+            save_debounce_ms: AtomicU64::new(DEFAULT_SAVE_DEBOUNCE_MS),
+            shutting_down: AtomicBool::new(false),
+            queue_paused: AtomicBool::new(false),
+            queue_resumed: Notify::new(),
+            children: std::sync::Mutex::new(HashMap::new()),
+            media_origins: std::sync::RwLock::new(
+                crate::media::protocol::DEFAULT_ALLOWED_ORIGINS
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+            ),
+            provider_gates: std::sync::Mutex::new(HashMap::new()),
+            throttled: std::sync::Mutex::new(std::collections::HashSet::new()),
+            artifact_cache: ArtifactCache::new(CACHE_BUDGET_BYTES, MAX_ENTRY_BYTES),
         })
     }
+
+    /// Apply a project's persistence tuning to the running app.
+    /// Values from a hand-edited project.json are clamped to the valid range.
+    pub fn apply_settings(&self, settings: &ProjectSettings) {
+        let (min_ms, max_ms) = SAVE_DEBOUNCE_LIMITS_MS;
+        self.save_debounce_ms.store(
+            settings.effective_save_debounce_ms().clamp(min_ms, max_ms),
+            Ordering::Relaxed,
+        );
+    }
+
+    pub fn save_debounce(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.save_debounce_ms.load(Ordering::Relaxed))
+    }
+
+    /// The gate for one profile of `provider`, rebuilt if its configured
+    /// limits changed. Limits are per profile, so each profile gets its own
+    /// gate. Holders of permits from a replaced gate finish undisturbed.
+    pub fn provider_gate(
+        &self,
+        provider: &str,
+        profile: &str,
+        max_in_flight: u32,
+        min_interval: std::time::Duration,
+    ) -> Arc<ProviderGate> {
+        let max_in_flight = max_in_flight.max(1);
+        let key = format!("{}/{}", provider, profile);
+        let mut gates = self.provider_gates.lock().unwrap_or_else(|e| e.into_inner());
+        match gates.get(&key) {
+            Some(gate) if gate.max_in_flight == max_in_flight && gate.min_interval == min_interval => {
+                gate.clone()
+            }
+            _ => {
+                let gate = Arc::new(ProviderGate::new(max_in_flight, min_interval));
+                gates.insert(key, gate.clone());
+                gate
+            }
+        }
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    pub fn is_queue_paused(&self) -> bool {
+        self.queue_paused.load(Ordering::SeqCst)
+    }
+
+    /// Pause or resume the task queue. Returns whether the state changed.
+    /// Resuming wakes the runner and any held generations so queued work
+    /// starts right away.
+    pub fn set_queue_paused(&self, paused: bool) -> bool {
+        let changed = self.queue_paused.swap(paused, Ordering::SeqCst) != paused;
+        if changed && !paused {
+            self.task_notify.notify_one();
+            self.queue_resumed.notify_waiters();
+        }
+        changed
+    }
+
+    pub fn register_child(&self, task_id: &str, pid: u32) {
+        if let Ok(mut children) = self.children.lock() {
+            children.insert(task_id.to_string(), pid);
+        }
+    }
+
+    pub fn unregister_child(&self, task_id: &str) {
+        if let Ok(mut children) = self.children.lock() {
+            children.remove(task_id);
+        }
+    }
+
+    pub fn active_children(&self) -> usize {
+        self.children.lock().map(|c| c.len()).unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn provider_gate_is_reused_until_limits_change() {
+        let state = AppState::new();
+        let a = state.provider_gate("jimeng", "default", 2, Duration::ZERO);
+        let b = state.provider_gate("jimeng", "default", 2, Duration::ZERO);
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(a.slots.available_permits(), 2);
+
+        let c = state.provider_gate("jimeng", "default", 3, Duration::ZERO);
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(c.slots.available_permits(), 3);
+
+        // Zero would deadlock every generation; treat it as 1
+        assert_eq!(state.provider_gate("other", "default", 0, Duration::ZERO).max_in_flight, 1);
+    }
+
+    #[test]
+    fn profiles_with_different_limits_keep_separate_gates() {
+        let state = AppState::new();
+        let fast = state.provider_gate("jimeng", "fast", 2, Duration::ZERO);
+        let _held = fast.slots.clone().try_acquire_owned().unwrap();
+
+        // Alternating profiles must not swap out the gate holding the permit
+        let slow = state.provider_gate("jimeng", "slow", 1, Duration::from_millis(500));
+        let fast_again = state.provider_gate("jimeng", "fast", 2, Duration::ZERO);
+        assert!(Arc::ptr_eq(&fast, &fast_again));
+        assert_eq!(fast_again.slots.available_permits(), 1);
+
+        let slow_again = state.provider_gate("jimeng", "slow", 1, Duration::from_millis(500));
+        assert!(Arc::ptr_eq(&slow, &slow_again));
+        assert_eq!(slow_again.min_interval, Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn provider_gate_spaces_out_submits() {
+        let state = AppState::new();
+        let gate = state.provider_gate("jimeng", "default", 1, Duration::from_millis(60));
+        let start = tokio::time::Instant::now();
+        gate.wait_for_submit().await;
+        gate.wait_for_submit().await;
+        assert!(start.elapsed() >= Duration::from_millis(60));
+    }
 }
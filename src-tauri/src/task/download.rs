@@ -0,0 +1,181 @@
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::AsyncWriteExt;
+
+use crate::project::model::TaskError;
+use crate::state::AppState;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Max time from sending the request to the response headers; a server that
+/// accepts the connection but never answers is retried like a dropped one.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Max time to wait for the next chunk before treating the connection as dead.
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_ATTEMPTS: u32 = 3;
+const BACKOFF_BASE_MS: u64 = 1000;
+/// How much of an error response body is kept in `dest` for diagnosis.
+pub const ERROR_BODY_BYTES: usize = 4096;
+
+/// Outcome of a single HTTP attempt.
+enum Attempt {
+    Done,
+    /// Transient failure; retry (resuming from the bytes already on disk).
+    Retry(String),
+}
+
+/// Download `url` to `dest`, resuming with a Range request if the connection
+/// drops. Checks the task's cancel flag between chunks and verifies the final
+/// size against the server-reported length. Returns the number of bytes written.
+pub async fn download_to_file(
+    url: &str,
+    dest: &Path,
+    task_id: &str,
+    state: &Arc<AppState>,
+) -> Result<u64, TaskError> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .build()
+        .map_err(|e| TaskError::new("download_error", format!("Failed to create HTTP client: {}", e)))?;
+
+    // Start from scratch; leftovers from a previous task run aren't trusted.
+    let _ = tokio::fs::remove_file(dest).await;
+
+    let mut expected: Option<u64> = None;
+    let mut last_err = String::new();
+
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            let backoff = BACKOFF_BASE_MS * 2u64.pow(attempt - 1);
+            log::warn!(
+                "[download] retrying {} in {}ms (attempt {}/{}): {}",
+                task_id, backoff, attempt + 1, MAX_ATTEMPTS, last_err
+            );
+            tokio::time::sleep(Duration::from_millis(backoff)).await;
+        }
+
+        match download_attempt(&client, url, dest, task_id, state, &mut expected).await? {
+            Attempt::Done => {
+                let written = file_len(dest).await;
+                match expected {
+                    Some(total) if written != total => {
+                        last_err = format!("received {} of {} bytes", written, total);
+                    }
+                    _ => return Ok(written),
+                }
+            }
+            Attempt::Retry(e) => last_err = e,
+        }
+    }
+
+    let written = file_len(dest).await;
+    if let Some(total) = expected {
+        if written != total {
+            return Err(TaskError::new(
+                "download_incomplete",
+                format!("Downloaded {} of {} bytes: {}", written, total, last_err),
+            ));
+        }
+    }
+    Err(TaskError::new("download_error", format!("Download failed: {}", last_err)))
+}
+
+async fn download_attempt(
+    client: &reqwest::Client,
+    url: &str,
+    dest: &Path,
+    task_id: &str,
+    state: &Arc<AppState>,
+    expected: &mut Option<u64>,
+) -> Result<Attempt, TaskError> {
+    let offset = file_len(dest).await;
+
+    let mut req = client.get(url);
+    if offset > 0 {
+        req = req.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+    }
+
+    let mut resp = match tokio::time::timeout(RESPONSE_TIMEOUT, req.send()).await {
+        Ok(Ok(r)) => r,
+        Ok(Err(e)) => return Ok(Attempt::Retry(format!("request failed: {}", e))),
+        Err(_) => return Ok(Attempt::Retry("timed out waiting for a response".to_string())),
+    };
+
+    let status = resp.status();
+    let resuming = offset > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT;
+    if !status.is_success() {
+        if status.is_server_error() {
+            return Ok(Attempt::Retry(format!("HTTP {}", status)));
+        }
+        // Not retried, so `dest` is free to hold the body (often an HTML error page)
+        keep_error_body(resp, dest).await;
+        return Err(TaskError::new("download_error", format!("Download HTTP {}", status)));
+    }
+
+    if let Some(len) = resp.content_length() {
+        *expected = Some(if resuming { offset + len } else { len });
+        let dir = dest.parent().unwrap_or(dest);
+        // A restart truncates what is already on disk
+        crate::disk::ensure_space(dir, if resuming { len } else { len.saturating_sub(offset) })?;
+    }
+
+    // Server ignored the Range header: restart from byte 0.
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resuming)
+        .truncate(!resuming)
+        .open(dest)
+        .await
+        .map_err(|e| TaskError::new("io_error", format!("Failed to open {}: {}", dest.display(), e)))?;
+
+    loop {
+        if is_canceled(state, task_id).await {
+            let _ = file.flush().await;
+            return Err(TaskError::new("canceled", "Download canceled".to_string()));
+        }
+
+        let chunk = match tokio::time::timeout(CHUNK_TIMEOUT, resp.chunk()).await {
+            Ok(Ok(Some(c))) => c,
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => {
+                let _ = file.flush().await;
+                return Ok(Attempt::Retry(format!("connection error: {}", e)));
+            }
+            Err(_) => {
+                let _ = file.flush().await;
+                return Ok(Attempt::Retry("timed out waiting for data".to_string()));
+            }
+        };
+
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| TaskError::new("io_error", format!("Failed to write video file: {}", e)))?;
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| TaskError::new("io_error", format!("Failed to write video file: {}", e)))?;
+    Ok(Attempt::Done)
+}
+
+async fn keep_error_body(mut resp: reqwest::Response, dest: &Path) {
+    let mut body = Vec::new();
+    while body.len() < ERROR_BODY_BYTES {
+        match tokio::time::timeout(CHUNK_TIMEOUT, resp.chunk()).await {
+            Ok(Ok(Some(chunk))) => body.extend_from_slice(&chunk),
+            _ => break,
+        }
+    }
+    body.truncate(ERROR_BODY_BYTES);
+    let _ = tokio::fs::write(dest, &body).await;
+}
+
+async fn is_canceled(state: &Arc<AppState>, task_id: &str) -> bool {
+    state.cancel_flags.lock().await.contains(task_id)
+}
+
+async fn file_len(path: &Path) -> u64 {
+    tokio::fs::metadata(path).await.map(|m| m.len()).unwrap_or(0)
+}
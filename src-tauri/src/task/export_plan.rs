@@ -0,0 +1,489 @@
+use serde::Serialize;
+use std::path::PathBuf;
+
+use crate::media::subtitle::Cue;
+use crate::project::model::{Clip, TaskError};
+use crate::state::LoadedProject;
+
+/// What an export of one track will encode, in order. `handle_export` feeds
+/// `segments` straight to ffmpeg; `export_plan` returns it without encoding.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPlan {
+    pub track_id: String,
+    /// `type` of the exported track; picks the export pipeline.
+    pub track_type: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub segments: Vec<PlanSegment>,
+    /// Empty stretches of the timeline inside the export. They are not
+    /// rendered: segments are concatenated back to back.
+    pub gaps: Vec<PlanGap>,
+    /// Expected length of the encoded output (sum of segment lengths).
+    pub output_duration_ms: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanSegment {
+    pub clip_id: String,
+    pub asset_id: String,
+    pub asset_type: String,
+    /// Probed video codec and container of the source, when known.
+    pub codec: Option<String>,
+    pub container: Option<String>,
+    /// Probed picture size and frame rate, when known.
+    pub dimensions: Option<(u32, u32)>,
+    pub fps: Option<f64>,
+    pub path: PathBuf,
+    /// Audio file, or video whose probe found an audio stream.
+    pub has_audio: bool,
+    /// Probed audio sample rate and channel count, when known.
+    pub audio_layout: Option<(u32, u32)>,
+    /// The probe flagged a variable frame rate.
+    pub vfr: bool,
+    pub timeline_start_ms: i64,
+    pub timeline_end_ms: i64,
+    /// Source range read from the file; `None` means the whole file.
+    pub source_in_ms: Option<i64>,
+    pub source_out_ms: Option<i64>,
+    /// Probed length of the whole source file, when known.
+    pub source_duration_ms: Option<i64>,
+}
+
+impl PlanSegment {
+    pub fn cut(&self) -> Option<(i64, i64)> {
+        Some((self.source_in_ms?, self.source_out_ms?))
+    }
+
+    /// Still images are shown for the clip's timeline length rather than cut.
+    pub fn is_still(&self) -> bool {
+        self.asset_type == "image"
+    }
+
+    pub fn timeline_duration_ms(&self) -> i64 {
+        self.timeline_end_ms - self.timeline_start_ms
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanGap {
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+/// A clip's portion of the export, in timeline time, after cutting to the range.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ExportWindow {
+    pub clip_id: String,
+    pub asset_id: String,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Source in/out to read.
+    pub cut: Option<(i64, i64)>,
+}
+
+/// Validate optional range bounds. Either bound alone is allowed; a missing
+/// start means 0 and a missing end means "to the end of the track".
+pub fn parse_range(start_ms: Option<i64>, end_ms: Option<i64>) -> Result<Option<(i64, i64)>, String> {
+    match (start_ms, end_ms) {
+        (None, None) => Ok(None),
+        (start, end) => {
+            let start = start.unwrap_or(0).max(0);
+            let end = end.unwrap_or(i64::MAX);
+            if end <= start {
+                return Err("endMs must be greater than startMs".to_string());
+            }
+            Ok(Some((start, end)))
+        }
+    }
+}
+
+/// Clips (sorted by start) that intersect `range`, with clips straddling
+/// either edge cut at the boundary. Without a range every clip keeps its own
+/// in/out.
+pub(crate) fn export_windows(clips: &[&Clip], range: Option<(i64, i64)>) -> Vec<ExportWindow> {
+    clips.iter()
+        .filter_map(|clip| {
+            let clip_end = clip.start_ms + clip.duration_ms;
+            match range {
+                None => Some(ExportWindow {
+                    clip_id: clip.clip_id.clone(),
+                    asset_id: clip.asset_id.clone(),
+                    start_ms: clip.start_ms,
+                    end_ms: clip_end,
+                    cut: Some((clip.in_ms, clip.out_ms)),
+                }),
+                Some((start, end)) => {
+                    if clip_end <= start || clip.start_ms >= end {
+                        return None;
+                    }
+                    let head = (start - clip.start_ms).max(0);
+                    let tail = (clip_end - end).max(0);
+                    Some(ExportWindow {
+                        clip_id: clip.clip_id.clone(),
+                        asset_id: clip.asset_id.clone(),
+                        start_ms: clip.start_ms + head,
+                        end_ms: clip_end - tail,
+                        cut: Some((clip.in_ms + head, clip.out_ms - tail)),
+                    })
+                }
+            }
+        })
+        .collect()
+}
+
+/// Uncovered stretches between `start_ms` and `end_ms` given windows sorted by start.
+fn plan_gaps(windows: &[ExportWindow], start_ms: i64, end_ms: i64) -> Vec<PlanGap> {
+    let mut gaps = Vec::new();
+    let mut covered_to = start_ms;
+    for w in windows {
+        if w.start_ms > covered_to {
+            gaps.push(PlanGap { start_ms: covered_to, end_ms: w.start_ms });
+        }
+        covered_to = covered_to.max(w.end_ms);
+    }
+    if end_ms > covered_to {
+        gaps.push(PlanGap { start_ms: covered_to, end_ms });
+    }
+    gaps
+}
+
+fn meta_str(meta: &serde_json::Value, key: &str) -> Option<String> {
+    meta.get(key).and_then(|v| v.as_str()).map(str::to_string)
+}
+
+/// Sample rate and channels: top level for audio files, under `audio` for video.
+fn meta_audio_layout(meta: &serde_json::Value) -> Option<(u32, u32)> {
+    let audio = meta.get("audio").filter(|a| a.is_object()).unwrap_or(meta);
+    let field = |key: &str| audio.get(key).and_then(|v| v.as_u64()).filter(|&v| v > 0).map(|v| v as u32);
+    Some((field("sampleRate")?, field("channels")?))
+}
+
+fn meta_dimensions(meta: &serde_json::Value) -> Option<(u32, u32)> {
+    let px = |key: &str| meta.get(key).and_then(|v| v.as_u64()).filter(|&v| v > 0).map(|v| v as u32);
+    Some((px("width")?, px("height")?))
+}
+
+pub fn build_export_plan(
+    loaded: &LoadedProject,
+    track_id: &str,
+    range: Option<(i64, i64)>,
+) -> Result<ExportPlan, TaskError> {
+    let timeline = &loaded.project.timeline;
+    let track = timeline
+        .tracks
+        .iter()
+        .find(|t| t.track_id == track_id)
+        .ok_or_else(|| TaskError::new("track_not_found", format!("Track {} not found", track_id)))?;
+
+    if track.clip_ids.is_empty() {
+        return Err(TaskError::new("no_clips", "Track has no clips to export"));
+    }
+
+    // Playback order: start_ms, ties broken by the track's clip_ids order
+    let clips = timeline.ordered_clips(track);
+    let windows = export_windows(&clips, range);
+    if windows.is_empty() {
+        return Err(TaskError::new("no_clips", "No clips intersect the export range"));
+    }
+
+    let clips_start = windows.first().map(|w| w.start_ms).unwrap_or(0);
+    let clips_end = windows.iter().map(|w| w.end_ms).max().unwrap_or(0);
+    let (start_ms, end_ms) = match range {
+        Some((start, end)) => (start, end.min(clips_end)),
+        None => (clips_start, clips_end),
+    };
+    let gaps = plan_gaps(&windows, start_ms, end_ms);
+
+    let segments: Vec<PlanSegment> = windows
+        .into_iter()
+        .filter_map(|w| {
+            let asset = loaded.project.assets.iter().find(|a| a.asset_id == w.asset_id)?;
+            let source_duration_ms = asset.meta.get("durationMs").and_then(|v| v.as_i64()).filter(|d| *d > 0);
+            // Reading the whole file needs no cut, which keeps stream copy open
+            let cut = w.cut.filter(|&(in_ms, out_ms)| {
+                in_ms > 0 || source_duration_ms.is_none_or(|d| out_ms < d)
+            });
+            Some(PlanSegment {
+                clip_id: w.clip_id,
+                asset_id: w.asset_id,
+                asset_type: asset.asset_type.clone(),
+                codec: meta_str(&asset.meta, "codec"),
+                container: meta_str(&asset.meta, "container"),
+                dimensions: meta_dimensions(&asset.meta),
+                fps: asset.meta.get("fps").and_then(|v| v.as_f64()).filter(|f| *f > 0.0),
+                path: loaded.resolve_path(&asset.path),
+                has_audio: asset.asset_type == "audio"
+                    || asset.meta.pointer("/audio/present").and_then(|v| v.as_bool()).unwrap_or(false),
+                audio_layout: meta_audio_layout(&asset.meta),
+                vfr: asset.meta.get("vfr").and_then(|v| v.as_bool()).unwrap_or(false),
+                timeline_start_ms: w.start_ms,
+                timeline_end_ms: w.end_ms,
+                source_in_ms: cut.map(|c| c.0),
+                source_out_ms: cut.map(|c| c.1),
+                source_duration_ms,
+            })
+        })
+        .collect();
+
+    if segments.is_empty() {
+        return Err(TaskError::new("no_assets", "No assets found for clips"));
+    }
+
+    let output_duration_ms = segments.iter().map(|s| s.timeline_duration_ms()).sum();
+
+    Ok(ExportPlan {
+        track_id: track_id.to_string(),
+        track_type: track.track_type.clone(),
+        start_ms,
+        end_ms,
+        segments,
+        gaps,
+        output_duration_ms,
+    })
+}
+
+/// A clip on a subtitle track: its file and where its in/out window sits on
+/// the timeline.
+#[derive(Debug, Clone)]
+pub struct SubtitleSource {
+    pub path: PathBuf,
+    pub start_ms: i64,
+    pub in_ms: i64,
+    pub out_ms: i64,
+}
+
+impl SubtitleSource {
+    /// Cues (file time) that show inside the clip, moved to timeline time.
+    pub fn place(&self, cues: &[Cue]) -> Vec<Cue> {
+        cues.iter()
+            .filter_map(|c| {
+                let start = c.start_ms.max(self.in_ms);
+                let end = c.end_ms.min(self.out_ms);
+                (end > start).then(|| Cue {
+                    start_ms: self.start_ms + start - self.in_ms,
+                    end_ms: self.start_ms + end - self.in_ms,
+                    text: c.text.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Clips of the subtitle track to render with an export: `subtitle_track_id`
+/// if given, otherwise the first subtitle track.
+pub fn subtitle_sources(
+    loaded: &LoadedProject,
+    subtitle_track_id: Option<&str>,
+) -> Result<Vec<SubtitleSource>, TaskError> {
+    let timeline = &loaded.project.timeline;
+    let track = match subtitle_track_id {
+        Some(id) => timeline
+            .tracks
+            .iter()
+            .find(|t| t.track_id == id)
+            .ok_or_else(|| TaskError::new("track_not_found", format!("Track {} not found", id)))?,
+        None => timeline
+            .tracks
+            .iter()
+            .find(|t| t.track_type == "subtitle")
+            .ok_or_else(|| TaskError::new("no_subtitle_track", "Project has no subtitle track"))?,
+    };
+    if track.track_type != "subtitle" {
+        return Err(TaskError::new(
+            "invalid_subtitle_track",
+            format!("Track {} is a {} track, not subtitle", track.track_id, track.track_type),
+        ));
+    }
+
+    Ok(timeline
+        .ordered_clips(track)
+        .into_iter()
+        .filter_map(|clip| {
+            let asset = loaded.project.assets.iter().find(|a| a.asset_id == clip.asset_id)?;
+            Some(SubtitleSource {
+                path: loaded.resolve_path(&asset.path),
+                start_ms: clip.start_ms,
+                in_ms: clip.in_ms,
+                out_ms: clip.out_ms,
+            })
+        })
+        .collect())
+}
+
+/// Move timeline-time cues onto the encoded output. Segments play back to
+/// back, so a cue keeps only the parts that fall inside a segment, shifted
+/// by the length of the segments before it; gaps and trimmed ranges drop out.
+pub fn output_cues(cues: &[Cue], segments: &[PlanSegment]) -> Vec<Cue> {
+    let mut out = Vec::new();
+    let mut offset = 0;
+    for seg in segments {
+        for c in cues {
+            let start = c.start_ms.max(seg.timeline_start_ms);
+            let end = c.end_ms.min(seg.timeline_end_ms);
+            if end > start {
+                out.push(Cue {
+                    start_ms: offset + start - seg.timeline_start_ms,
+                    end_ms: offset + end - seg.timeline_start_ms,
+                    text: c.text.clone(),
+                });
+            }
+        }
+        offset += seg.timeline_duration_ms();
+    }
+    out.sort_by_key(|c| c.start_ms);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::make_empty_project;
+    use crate::project::model::Track;
+
+    fn clip(asset_id: &str, start_ms: i64, in_ms: i64, out_ms: i64) -> Clip {
+        Clip {
+            clip_id: format!("clip_{}", asset_id),
+            asset_id: asset_id.to_string(),
+            track_id: "trk_v".to_string(),
+            start_ms,
+            duration_ms: out_ms - in_ms,
+            in_ms,
+            out_ms,
+            label_color: None,
+            note: None,
+        }
+    }
+
+    fn window(asset_id: &str, start_ms: i64, end_ms: i64, cut: Option<(i64, i64)>) -> ExportWindow {
+        ExportWindow {
+            clip_id: format!("clip_{}", asset_id),
+            asset_id: asset_id.into(),
+            start_ms,
+            end_ms,
+            cut,
+        }
+    }
+
+    #[test]
+    fn parse_range_validates_bounds() {
+        assert_eq!(parse_range(None, None).unwrap(), None);
+        assert_eq!(parse_range(Some(100), Some(200)).unwrap(), Some((100, 200)));
+        assert_eq!(parse_range(None, Some(200)).unwrap(), Some((0, 200)));
+        assert!(parse_range(Some(200), Some(200)).is_err());
+    }
+
+    #[test]
+    fn export_windows_without_range_keep_clip_in_out() {
+        let a = clip("a", 0, 0, 2000);
+        let b = clip("b", 2000, 500, 3500);
+        let windows = export_windows(&[&a, &b], None);
+        assert_eq!(windows, vec![
+            window("a", 0, 2000, Some((0, 2000))),
+            window("b", 2000, 5000, Some((500, 3500))),
+        ]);
+    }
+
+    #[test]
+    fn export_windows_cuts_straddling_clips() {
+        let a = clip("a", 0, 0, 2000);
+        let b = clip("b", 2000, 500, 3500);
+        let c = clip("c", 5000, 0, 1000);
+        let windows = export_windows(&[&a, &b, &c], Some((1500, 3000)));
+        assert_eq!(windows, vec![
+            window("a", 1500, 2000, Some((1500, 2000))),
+            window("b", 2000, 3000, Some((500, 1500))),
+        ]);
+    }
+
+    #[test]
+    fn plan_gaps_reports_leading_inner_and_trailing() {
+        let windows = vec![window("a", 1000, 2000, None), window("b", 3000, 4000, None)];
+        assert_eq!(plan_gaps(&windows, 0, 5000), vec![
+            PlanGap { start_ms: 0, end_ms: 1000 },
+            PlanGap { start_ms: 2000, end_ms: 3000 },
+            PlanGap { start_ms: 4000, end_ms: 5000 },
+        ]);
+        assert_eq!(plan_gaps(&windows, 1000, 4000), vec![PlanGap { start_ms: 2000, end_ms: 3000 }]);
+    }
+
+    #[test]
+    fn build_export_plan_for_range() {
+        let mut project = make_empty_project();
+        project.assets.push(crate::project::model::tests::make_prompt_asset("a", "A"));
+        project.assets.push(crate::project::model::tests::make_prompt_asset("b", "B"));
+        project.timeline.tracks = vec![Track {
+            track_id: "trk_v".into(),
+            track_type: "video".into(),
+            name: "V".into(),
+            clip_ids: vec!["clip_a".into(), "clip_b".into()],
+        }];
+        for c in [clip("a", 0, 0, 2000), clip("b", 3000, 0, 2000)] {
+            project.timeline.clips.insert(c.clip_id.clone(), c);
+        }
+        let loaded = LoadedProject {
+            project,
+            json_path: PathBuf::from("/proj/project.json"),
+            project_dir: PathBuf::from("/proj"),
+            dirty: false,
+            read_only: false,
+        };
+
+        let plan = build_export_plan(&loaded, "trk_v", Some((1000, 4000))).unwrap();
+        assert_eq!((plan.start_ms, plan.end_ms), (1000, 4000));
+        assert_eq!(plan.segments.len(), 2);
+        assert_eq!(plan.segments[0].cut(), Some((1000, 2000)));
+        assert_eq!(plan.segments[1].cut(), Some((0, 1000)));
+        assert_eq!(plan.gaps, vec![PlanGap { start_ms: 2000, end_ms: 3000 }]);
+        assert_eq!(plan.output_duration_ms, 2000);
+
+        // Without a range, clips of unknown length keep their in/out; only a
+        // clip spanning its whole probed source is read uncut.
+        let whole = build_export_plan(&loaded, "trk_v", None).unwrap();
+        assert_eq!((whole.start_ms, whole.end_ms), (0, 5000));
+        assert_eq!(whole.segments[0].cut(), Some((0, 2000)));
+
+        let mut loaded = loaded;
+        loaded.project.assets[0].meta["durationMs"] = serde_json::json!(2000);
+        loaded.project.assets[1].meta["durationMs"] = serde_json::json!(8000);
+        let whole = build_export_plan(&loaded, "trk_v", None).unwrap();
+        assert_eq!(whole.segments[0].cut(), None);
+        assert_eq!(whole.segments[1].cut(), Some((0, 2000)));
+
+        assert_eq!(build_export_plan(&loaded, "trk_x", None).unwrap_err().code, "track_not_found");
+        assert_eq!(build_export_plan(&loaded, "trk_v", Some((6000, 7000))).unwrap_err().code, "no_clips");
+    }
+
+    #[test]
+    fn subtitle_cues_follow_clip_window_and_segments() {
+        let cue = |start_ms, end_ms, text: &str| Cue { start_ms, end_ms, text: text.to_string() };
+        // File cues at 0-1s, 2-3s, 5-6s; the clip shows file time 1.5-5.5s from timeline 10s
+        let source = SubtitleSource { path: PathBuf::from("/s.srt"), start_ms: 10_000, in_ms: 1500, out_ms: 5500 };
+        let placed = source.place(&[cue(0, 1000, "a"), cue(2000, 3000, "b"), cue(5000, 6000, "c")]);
+        assert_eq!(placed, vec![cue(10_500, 11_500, "b"), cue(13_500, 14_000, "c")]);
+
+        // Two segments (9-12s, 13-15s) played back to back: the gap 12-13s drops out
+        let seg = |start, end| PlanSegment {
+            clip_id: "clip_v".into(),
+            asset_id: "ast_v".into(),
+            asset_type: "video".into(),
+            codec: None,
+            container: None,
+            dimensions: None,
+            fps: None,
+            path: PathBuf::from("/v.mp4"),
+            has_audio: false,
+            audio_layout: None,
+            vfr: false,
+            timeline_start_ms: start,
+            timeline_end_ms: end,
+            source_in_ms: None,
+            source_out_ms: None,
+            source_duration_ms: None,
+        };
+        let out = output_cues(&[cue(11_000, 13_500, "x")], &[seg(9000, 12_000), seg(13_000, 15_000)]);
+        assert_eq!(out, vec![cue(2000, 3000, "x"), cue(3000, 3500, "x")]);
+    }
+}
@@ -4,8 +4,11 @@ use tauri::Emitter;
 use tokio::process::Command;
 
 use crate::project::model::{
-    Asset, Clip, Fingerprint, GenerationInfo, TaskError, TaskProgress, Track,
+    Asset, Clip, Fingerprint, GenerationInfo, ProjectSettings, ScaleMode, TaskError, TaskProgress, Track,
 };
+use crate::media::probe::MediaCheck;
+use crate::project::layout::WorkspaceSubdir;
+use crate::task::export_plan::{build_export_plan, output_cues, parse_range, subtitle_sources, PlanSegment};
 use crate::state::AppState;
 
 pub struct HandlerResult {
@@ -27,17 +30,18 @@ pub async fn dispatch(
         "capture_frame" => handle_capture_frame(task_id, input, state, app_handle).await,
         "gen_video" => handle_gen_video(task_id, input, state, app_handle).await,
         "export" => handle_export(task_id, input, state, app_handle).await,
+        "contact_sheet" => handle_contact_sheet(task_id, input, state, app_handle).await,
+        "waveform" => handle_waveform(task_id, input, state, app_handle).await,
+        "remux" => handle_remux(task_id, input, state, app_handle).await,
         _ => HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "unknown_kind".to_string(),
-                message: format!("Unknown task kind: {}", kind),
-                detail: None,
-            }),
+            error: Some(TaskError::new("unknown_kind", format!("Unknown task kind: {}", kind))),
         },
     }
 }
 
+// Progress and event updates only mark the project dirty; they're persisted
+// by the runner's force_save on completion or the saver's autosave tick.
 async fn update_progress(
     state: &Arc<AppState>,
     task_id: &str,
@@ -48,7 +52,7 @@ async fn update_progress(
     if let Some(loaded) = guard.as_mut() {
         if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
             task.progress = Some(progress);
-            task.updated_at = chrono::Utc::now().to_rfc3339();
+            task.updated_at = crate::clock::now_rfc3339();
             loaded.dirty = true;
             let snapshot = task.clone();
             drop(guard);
@@ -57,17 +61,44 @@ async fn update_progress(
     }
 }
 
-async fn append_task_event(
+/// Targeted event so the media bin can refresh one asset without a full reload.
+fn emit_asset_updated(
+    app_handle: &tauri::AppHandle,
+    asset_id: &str,
+    meta: Option<serde_json::Value>,
+) {
+    if let Some(meta) = meta {
+        let _ = app_handle.emit(
+            "asset:updated",
+            serde_json::json!({ "assetId": asset_id, "meta": meta }),
+        );
+    }
+}
+
+pub(crate) async fn append_task_event(
     state: &Arc<AppState>,
     task_id: &str,
     level: &str,
     msg: &str,
+    app_handle: &tauri::AppHandle,
 ) {
     let mut guard = state.inner.lock().await;
     if let Some(loaded) = guard.as_mut() {
+        let cap = loaded.project.project.settings.task_event_cap();
         if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
-            task.append_event(level, msg);
+            task.append_event(level, msg, cap);
             loaded.dirty = true;
+            let event = task.events.last().cloned();
+            drop(guard);
+            // Lightweight live-log update; full snapshots still go out as task:updated.
+            if let Some(event) = event {
+                let _ = app_handle.emit("task:event", serde_json::json!({
+                    "taskId": task_id,
+                    "t": event.t,
+                    "level": event.level,
+                    "msg": event.msg,
+                }));
+            }
         }
     }
 }
@@ -82,11 +113,7 @@ async fn handle_probe(
         Some(id) => id.to_string(),
         None => return HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "missing_input".to_string(),
-                message: "Missing assetId in input".to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("missing_input", "Missing assetId in input")),
         },
     };
 
@@ -96,23 +123,15 @@ async fn handle_probe(
             Some(l) => l,
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "no_project".to_string(),
-                    message: "No project loaded".to_string(),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("no_project", "No project loaded")),
             },
         };
         let asset = loaded.project.assets.iter().find(|a| a.asset_id == asset_id);
         match asset {
-            Some(a) => loaded.project_dir.join(&a.path),
+            Some(a) => loaded.resolve_path(&a.path),
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "asset_not_found".to_string(),
-                    message: format!("Asset {} not found", asset_id),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("asset_not_found", format!("Asset {} not found", asset_id))),
             },
         }
     };
@@ -126,15 +145,19 @@ async fn handle_probe(
     match crate::media::probe::ffprobe(&abs_path) {
         Ok(probe_data) => {
             let meta = crate::media::probe::extract_video_meta(&probe_data);
-            {
+            let updated_meta = {
                 let mut guard = state.inner.lock().await;
+                let mut updated = None;
                 if let Some(loaded) = guard.as_mut() {
                     if let Some(asset) = loaded.project.assets.iter_mut().find(|a| a.asset_id == asset_id) {
                         asset.meta = meta.clone();
+                        updated = Some(asset.meta.clone());
                     }
                     loaded.dirty = true;
                 }
-            }
+                updated
+            };
+            emit_asset_updated(app_handle, &asset_id, updated_meta);
             HandlerResult {
                 output: Some(serde_json::json!({ "assetId": asset_id, "meta": meta })),
                 error: None,
@@ -142,11 +165,7 @@ async fn handle_probe(
         }
         Err(e) => HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "probe_failed".to_string(),
-                message: e.to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("probe_failed", e.to_string())),
         },
     }
 }
@@ -161,41 +180,46 @@ async fn handle_thumb(
         Some(id) => id.to_string(),
         None => return HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "missing_input".to_string(),
-                message: "Missing assetId in input".to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("missing_input", "Missing assetId in input")),
         },
     };
 
-    let (abs_path, project_dir, asset_type) = {
+    let requested_t_ms = input.get("tMs").and_then(|v| v.as_i64()).map(|t| t.max(0));
+    let max_edge = input
+        .get("maxEdge")
+        .and_then(|v| v.as_u64())
+        .map(|v| v.clamp(16, 4096) as u32)
+        .unwrap_or(DEFAULT_THUMB_MAX_EDGE);
+    let format = match input.get("format").and_then(|v| v.as_str()) {
+        Some("webp") => "webp",
+        _ => "jpg",
+    };
+    let quality = input
+        .get("quality")
+        .and_then(|v| v.as_u64())
+        .map(|v| v.clamp(1, 100) as u32)
+        .unwrap_or(DEFAULT_THUMB_QUALITY);
+
+    let (abs_path, layout, asset_type, duration_sec) = {
         let guard = state.inner.lock().await;
         let loaded = match guard.as_ref() {
             Some(l) => l,
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "no_project".to_string(),
-                    message: "No project loaded".to_string(),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("no_project", "No project loaded")),
             },
         };
         let asset = loaded.project.assets.iter().find(|a| a.asset_id == asset_id);
         match asset {
             Some(a) => (
-                loaded.project_dir.join(&a.path),
-                loaded.project_dir.clone(),
+                loaded.resolve_path(&a.path),
+                loaded.layout(),
                 a.asset_type.clone(),
+                a.duration_ms().unwrap_or(0) as f64 / 1000.0,
             ),
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "asset_not_found".to_string(),
-                    message: format!("Asset {} not found", asset_id),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("asset_not_found", format!("Asset {} not found", asset_id))),
             },
         }
     };
@@ -213,86 +237,116 @@ async fn handle_thumb(
         message: None,
     }, app_handle).await;
 
-    let thumb_dir = project_dir.join("workspace/cache/thumbs");
-    let _ = std::fs::create_dir_all(&thumb_dir);
-    let thumb_filename = format!("{}.jpg", asset_id);
-    let thumb_path = thumb_dir.join(&thumb_filename);
-    let thumb_relative = format!("workspace/cache/thumbs/{}", thumb_filename);
-
-    let result = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-i", &abs_path.to_string_lossy(),
-            "-vframes", "1",
-            "-q:v", "2",
-            &thumb_path.to_string_lossy(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn();
+    // Images have a single frame; for video pick an explicit frame or ~10%
+    // into the clip, since frame 0 is often black.
+    let t_ms = if asset_type == "video" {
+        requested_t_ms.unwrap_or_else(|| default_thumb_t_ms(duration_sec))
+    } else {
+        0
+    };
 
-    let child = match result {
-        Ok(c) => c,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_spawn_failed".to_string(),
-                message: format!("Failed to start ffmpeg: {}", e),
-                detail: Some("Ensure ffmpeg is installed and in PATH".to_string()),
-            }),
-        },
+    let thumb_dir = layout.dir(WorkspaceSubdir::Thumbs);
+    let _ = std::fs::create_dir_all(&thumb_dir);
+    // Explicit picks get their own file name so the UI doesn't show a cached old frame.
+    let thumb_filename = match requested_t_ms {
+        Some(t) if asset_type == "video" => format!("{}_{}.{}", asset_id, t, format),
+        _ => format!("{}.{}", asset_id, format),
     };
+    let thumb_path = thumb_dir.join(&thumb_filename);
+    let thumb_relative = layout.stored_path(WorkspaceSubdir::Thumbs, &thumb_filename);
 
-    let output = match child.wait_with_output().await {
-        Ok(o) => o,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_wait_failed".to_string(),
-                message: format!("ffmpeg process error: {}", e),
-                detail: None,
-            }),
-        },
+    let ss = format!("{:.3}", t_ms as f64 / 1000.0);
+    // Cap the longest edge without upscaling small sources.
+    let scale_filter = format!(
+        "scale='min({0},iw)':'min({0},ih)':force_original_aspect_ratio=decrease",
+        max_edge
+    );
+    let codec_args: Vec<String> = if format == "webp" {
+        vec!["-c:v".into(), "libwebp".into(), "-quality".into(), quality.to_string()]
+    } else {
+        vec!["-q:v".into(), jpeg_qscale(quality).to_string()]
     };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let detail = if stderr.len() > 2048 {
-            Some(stderr[..2048].to_string())
-        } else {
-            Some(stderr.to_string())
-        };
-        return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_failed".to_string(),
-                message: format!("ffmpeg exited with code {:?}", output.status.code()),
-                detail,
-            }),
-        };
+    let mut args: Vec<String> = vec![
+        "-y".into(),
+        "-ss".into(), ss,
+        "-i".into(), abs_path.to_string_lossy().into_owned(),
+        "-vframes".into(), "1".into(),
+        "-vf".into(), scale_filter,
+    ];
+    args.extend(codec_args);
+    args.push(thumb_path.to_string_lossy().into_owned());
+
+    // Tracked like every other ffmpeg child so cancel/shutdown can kill it.
+    if let Err(e) = run_ffmpeg(&args, task_id, state).await {
+        let _ = std::fs::remove_file(&thumb_path);
+        return HandlerResult { output: None, error: Some(e) };
     }
 
-    {
+    let thumb_meta = crate::media::probe::extract_image_meta(&thumb_path);
+    let thumb_width = thumb_meta.get("width").and_then(|v| v.as_u64()).unwrap_or(0);
+    let thumb_height = thumb_meta.get("height").and_then(|v| v.as_u64()).unwrap_or(0);
+
+    let (updated_meta, replaced_thumb) = {
         let mut guard = state.inner.lock().await;
+        let mut updated = None;
+        let mut replaced = None;
         if let Some(loaded) = guard.as_mut() {
+            let mut previous = None;
             if let Some(asset) = loaded.project.assets.iter_mut().find(|a| a.asset_id == asset_id) {
                 if let Some(meta) = asset.meta.as_object_mut() {
-                    meta.insert("thumbUri".to_string(), serde_json::Value::String(thumb_relative.clone()));
+                    previous = meta.insert("thumbUri".to_string(), serde_json::Value::String(thumb_relative.clone()));
+                    meta.insert("thumbWidth".to_string(), serde_json::json!(thumb_width));
+                    meta.insert("thumbHeight".to_string(), serde_json::json!(thumb_height));
                 }
+                updated = Some(asset.meta.clone());
             }
+            replaced = previous
+                .as_ref()
+                .and_then(|v| v.as_str())
+                .filter(|old| *old != thumb_relative)
+                .map(|old| loaded.resolve_path(old));
             loaded.dirty = true;
         }
+        (updated, replaced)
+    };
+    emit_asset_updated(app_handle, &asset_id, updated_meta);
+    // A re-pick at another frame or format writes a new file; drop the old one
+    if let Some(old) = replaced_thumb.filter(|old| *old != thumb_path) {
+        let _ = std::fs::remove_file(old);
     }
 
+    let _ = app_handle.emit("project:updated", serde_json::json!({}));
+
     HandlerResult {
         output: Some(serde_json::json!({
             "assetId": asset_id,
             "thumbUri": thumb_relative,
+            "thumbWidth": thumb_width,
+            "thumbHeight": thumb_height,
+            "tMs": t_ms,
         })),
         error: None,
     }
 }
 
+const DEFAULT_THUMB_MAX_EDGE: u32 = 320;
+const DEFAULT_THUMB_QUALITY: u32 = 85;
+
+/// Map a 1-100 quality to ffmpeg's JPEG qscale (2 = best, 31 = worst).
+fn jpeg_qscale(quality: u32) -> u32 {
+    2 + (100 - quality.min(100)) * 29 / 100
+}
+
+/// Default poster frame: 10% into the clip, or 0 when the duration is unknown.
+fn default_thumb_t_ms(duration_sec: f64) -> i64 {
+    if duration_sec > 0.0 {
+        (duration_sec * 1000.0 * 0.1) as i64
+    } else {
+        0
+    }
+}
+
 async fn handle_proxy(
     task_id: &str,
     input: &serde_json::Value,
@@ -303,54 +357,47 @@ async fn handle_proxy(
         Some(id) => id.to_string(),
         None => return HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "missing_input".to_string(),
-                message: "Missing assetId in input".to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("missing_input", "Missing assetId in input")),
         },
     };
 
     let width = input.get("width").and_then(|v| v.as_u64()).unwrap_or(960) as u32;
     let crf = input.get("crf").and_then(|v| v.as_u64()).unwrap_or(28) as u32;
 
-    let (abs_path, project_dir, asset_type) = {
+    let (abs_path, layout, asset_type, meta, frame, settings) = {
         let guard = state.inner.lock().await;
         let loaded = match guard.as_ref() {
             Some(l) => l,
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "no_project".to_string(),
-                    message: "No project loaded".to_string(),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("no_project", "No project loaded")),
             },
         };
         let asset = loaded.project.assets.iter().find(|a| a.asset_id == asset_id);
         match asset {
             Some(a) => (
-                loaded.project_dir.join(&a.path),
-                loaded.project_dir.clone(),
+                loaded.resolve_path(&a.path),
+                loaded.layout(),
                 a.asset_type.clone(),
+                a.meta.clone(),
+                OutputFrame::from_settings(&loaded.project.project.settings),
+                loaded.project.project.settings.clone(),
             ),
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "asset_not_found".to_string(),
-                    message: format!("Asset {} not found", asset_id),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("asset_not_found", format!("Asset {} not found", asset_id))),
             },
         }
     };
 
-    if asset_type != "video" {
+    if asset_type != "video" && asset_type != "audio" {
         return HandlerResult {
-            output: Some(serde_json::json!({ "skipped": true, "reason": "not a video asset" })),
+            output: Some(serde_json::json!({ "skipped": true, "reason": "not a video or audio asset" })),
             error: None,
         };
     }
+    let is_audio = asset_type == "audio";
+    let normalize = input.get("normalize").and_then(|v| v.as_bool()).unwrap_or(true);
 
     update_progress(state, task_id, TaskProgress {
         phase: "generating_proxy".to_string(),
@@ -358,69 +405,30 @@ async fn handle_proxy(
         message: Some("Starting ffmpeg transcode".to_string()),
     }, app_handle).await;
 
-    let proxy_dir = project_dir.join("workspace/cache/proxy");
+    let proxy_dir = layout.dir(WorkspaceSubdir::Proxies);
     let _ = std::fs::create_dir_all(&proxy_dir);
-    let proxy_filename = format!("{}.mp4", asset_id);
+    let proxy_filename = format!("{}.{}", asset_id, if is_audio { "m4a" } else { "mp4" });
     let proxy_path = proxy_dir.join(&proxy_filename);
-    let proxy_relative = format!("workspace/cache/proxy/{}", proxy_filename);
-
-    let scale_filter = format!("scale={}:-2", width);
-
-    let result = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-i", &abs_path.to_string_lossy(),
-            "-vf", &scale_filter,
-            "-crf", &crf.to_string(),
-            "-c:v", "libx264",
-            "-preset", "fast",
-            "-c:a", "aac",
-            "-b:a", "128k",
-            &proxy_path.to_string_lossy(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn();
-
-    let child = match result {
-        Ok(c) => c,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_spawn_failed".to_string(),
-                message: format!("Failed to start ffmpeg: {}", e),
-                detail: Some("Ensure ffmpeg is installed and in PATH".to_string()),
-            }),
-        },
-    };
+    let proxy_relative = layout.stored_path(WorkspaceSubdir::Proxies, &proxy_filename);
 
-    let output = match child.wait_with_output().await {
-        Ok(o) => o,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_wait_failed".to_string(),
-                message: format!("ffmpeg process error: {}", e),
-                detail: None,
-            }),
-        },
+    let cfr_fps = if is_audio { None } else { vfr_target_fps(&meta) };
+    if let Some(fps) = cfr_fps {
+        append_task_event(state, task_id, "warn", &format!("Variable frame rate source; proxy converted to {} fps CFR", fps), app_handle).await;
+    }
+    let args = if is_audio {
+        audio_proxy_args(&abs_path, &proxy_path, normalize)
+    } else {
+        let proxy_fps = cfr_fps
+            .or_else(|| meta.get("fps").and_then(|v| v.as_f64()).filter(|f| *f > 0.0))
+            .unwrap_or(settings.fps as f64);
+        let gop = proxy_gop_frames(proxy_fps, settings.effective_proxy_keyframe_interval_ms());
+        video_proxy_args(&abs_path, &proxy_path, &frame.scaled_to(width), crf, cfr_fps, gop)
     };
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let detail = if stderr.len() > 2048 {
-            Some(stderr[..2048].to_string())
-        } else {
-            Some(stderr.to_string())
-        };
-        return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_failed".to_string(),
-                message: format!("ffmpeg exited with code {:?}", output.status.code()),
-                detail,
-            }),
-        };
+    // Proxies are long-running; go through run_ffmpeg so cancel/shutdown can kill it.
+    if let Err(e) = run_ffmpeg(&args, task_id, state).await {
+        let _ = std::fs::remove_file(&proxy_path);
+        return HandlerResult { output: None, error: Some(e) };
     }
 
     update_progress(state, task_id, TaskProgress {
@@ -429,29 +437,245 @@ async fn handle_proxy(
         message: None,
     }, app_handle).await;
 
-    {
+    let updated_meta = {
         let mut guard = state.inner.lock().await;
+        let mut updated = None;
         if let Some(loaded) = guard.as_mut() {
             if let Some(asset) = loaded.project.assets.iter_mut().find(|a| a.asset_id == asset_id) {
                 if let Some(meta) = asset.meta.as_object_mut() {
                     meta.insert("proxyUri".to_string(), serde_json::Value::String(proxy_relative.clone()));
                 }
+                updated = Some(asset.meta.clone());
             }
             loaded.dirty = true;
         }
-    }
+        updated
+    };
+    emit_asset_updated(app_handle, &asset_id, updated_meta);
 
-    HandlerResult {
-        output: Some(serde_json::json!({
+    let output = if is_audio {
+        serde_json::json!({
+            "assetId": asset_id,
+            "proxyUri": proxy_relative,
+            "normalized": normalize,
+        })
+    } else {
+        serde_json::json!({
             "assetId": asset_id,
             "proxyUri": proxy_relative,
             "width": width,
             "crf": crf,
+            "cfrFps": cfr_fps,
+        })
+    };
+
+    HandlerResult {
+        output: Some(output),
+        error: None,
+    }
+}
+
+/// Rewrap a video the webview can't stream (mkv/avi/...) into a faststart mp4
+/// recorded as `meta.playableUri`. Streams are copied when the mp4 muxer takes
+/// them; otherwise the file is transcoded at full resolution.
+async fn handle_remux(
+    task_id: &str,
+    input: &serde_json::Value,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> HandlerResult {
+    let asset_id = match input.get("assetId").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_result("missing_input", "Missing assetId in input"),
+    };
+
+    let (abs_path, layout) = {
+        let guard = state.inner.lock().await;
+        let loaded = match guard.as_ref() {
+            Some(l) => l,
+            None => return err_result("no_project", "No project loaded"),
+        };
+        match loaded.project.assets.iter().find(|a| a.asset_id == asset_id) {
+            Some(a) if a.asset_type == "video" => (loaded.resolve_path(&a.path), loaded.layout()),
+            Some(_) => return HandlerResult {
+                output: Some(serde_json::json!({ "skipped": true, "reason": "not a video asset" })),
+                error: None,
+            },
+            None => return err_result("asset_not_found", &format!("Asset {} not found", asset_id)),
+        }
+    };
+
+    update_progress(state, task_id, TaskProgress {
+        phase: "remuxing".to_string(),
+        percent: Some(5.0),
+        message: Some("Rewrapping into mp4".to_string()),
+    }, app_handle).await;
+
+    let proxy_dir = layout.dir(WorkspaceSubdir::Proxies);
+    let _ = std::fs::create_dir_all(&proxy_dir);
+    let file_name = format!("{}.playable.mp4", asset_id);
+    let output_path = proxy_dir.join(&file_name);
+    let tmp_path = output_path.with_extension("mp4.tmp");
+    let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
+
+    let mut stream_copy = true;
+    match run_ffmpeg(&remux_args(&abs_path, &tmp_path, true), task_id, state).await {
+        Ok(()) => {}
+        Err(e) if e.code == "ffmpeg_failed" => {
+            stream_copy = false;
+            append_task_event(state, task_id, "warn", "Streams can't be copied into mp4; transcoding instead", app_handle).await;
+            update_progress(state, task_id, TaskProgress {
+                phase: "transcoding".to_string(),
+                percent: Some(20.0),
+                message: Some("Transcoding into mp4".to_string()),
+            }, app_handle).await;
+            if let Err(e) = run_ffmpeg(&remux_args(&abs_path, &tmp_path, false), task_id, state).await {
+                return HandlerResult { output: None, error: Some(e) };
+            }
+        }
+        Err(e) => return HandlerResult { output: None, error: Some(e) },
+    }
+    if let Err(e) = std::fs::rename(&tmp_path, &output_path) {
+        return err_result("io_error", &format!("Failed to finalize remux: {}", e));
+    }
+    tmp_guard.disarm();
+
+    let relative = layout.stored_path(WorkspaceSubdir::Proxies, &file_name);
+    let updated_meta = {
+        let mut guard = state.inner.lock().await;
+        let mut updated = None;
+        if let Some(loaded) = guard.as_mut() {
+            if let Some(asset) = loaded.project.assets.iter_mut().find(|a| a.asset_id == asset_id) {
+                if let Some(meta) = asset.meta.as_object_mut() {
+                    meta.insert("playableUri".to_string(), serde_json::json!(relative));
+                }
+                updated = Some(asset.meta.clone());
+            }
+            loaded.dirty = true;
+        }
+        updated
+    };
+    emit_asset_updated(app_handle, &asset_id, updated_meta);
+
+    HandlerResult {
+        output: Some(serde_json::json!({
+            "assetId": asset_id,
+            "playableUri": relative,
+            "streamCopy": stream_copy,
         })),
         error: None,
     }
 }
 
+/// mp4 with the moov atom up front so playback can start before the whole
+/// file is read. Only the first video and the audio streams are kept;
+/// subtitle and attachment streams would make the mp4 muxer fail.
+fn remux_args(input: &std::path::Path, tmp_path: &std::path::Path, stream_copy: bool) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-y".into(),
+        "-i".into(), input.to_string_lossy().into_owned(),
+        "-map".into(), "0:v:0".into(),
+        "-map".into(), "0:a?".into(),
+    ];
+    if stream_copy {
+        args.extend(["-c".into(), "copy".into()]);
+    } else {
+        args.extend([
+            "-c:v".into(), "libx264".into(),
+            "-crf".into(), "20".into(),
+            "-preset".into(), "fast".into(),
+            "-pix_fmt".into(), "yuv420p".into(),
+            "-c:a".into(), "aac".into(),
+            "-b:a".into(), "192k".into(),
+        ]);
+    }
+    args.extend([
+        "-movflags".into(), "+faststart".into(),
+        // The .tmp extension hides the container from ffmpeg
+        "-f".into(), EXPORT_CONTAINER.into(),
+        tmp_path.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
+/// Frame rate to force on the proxy of a VFR video (`meta.vfr`): the average
+/// rate, since the nominal one can be far above what was actually captured.
+fn vfr_target_fps(meta: &serde_json::Value) -> Option<f64> {
+    if !meta.get("vfr").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return None;
+    }
+    ["avgFps", "fps"]
+        .iter()
+        .filter_map(|key| meta.get(*key).and_then(|v| v.as_f64()))
+        .find(|fps| *fps > 0.0)
+}
+
+/// Frames between proxy keyframes for `interval_ms` at `fps`, at least 1.
+fn proxy_gop_frames(fps: f64, interval_ms: u32) -> u32 {
+    ((fps * interval_ms as f64 / 1000.0).round() as u32).max(1)
+}
+
+/// ffmpeg args for a video preview proxy fitted into `frame`, so previews
+/// match the export framing. `cfr_fps` resamples a VFR source to a constant
+/// rate so trims and the concat export don't drift against the audio. A
+/// keyframe every `gop` frames (scene cuts don't reset it) keeps scrubbing
+/// frame-accurate, and faststart lets `media://` start playback at once.
+fn video_proxy_args(
+    input: &std::path::Path,
+    output: &std::path::Path,
+    frame: &OutputFrame,
+    crf: u32,
+    cfr_fps: Option<f64>,
+    gop: u32,
+) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-y".into(),
+        "-i".into(), input.to_string_lossy().into_owned(),
+        "-vf".into(), frame.filter(),
+    ];
+    if let Some(fps) = cfr_fps {
+        args.extend(["-vsync".into(), "cfr".into(), "-r".into(), fps.to_string()]);
+    }
+    args.extend([
+        "-crf".into(), crf.to_string(),
+        "-c:v".into(), "libx264".into(),
+        "-preset".into(), "fast".into(),
+        "-g".into(), gop.to_string(),
+        "-keyint_min".into(), gop.to_string(),
+        "-sc_threshold".into(), "0".into(),
+        "-c:a".into(), "aac".into(),
+        "-b:a".into(), "128k".into(),
+        "-movflags".into(), "+faststart".into(),
+        output.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
+/// Target integrated loudness for audio previews (EBU R128-ish streaming level).
+const PROXY_LOUDNORM_FILTER: &str = "loudnorm=I=-16:TP=-1.5:LRA=11";
+
+/// ffmpeg args for an audio preview proxy: 128k AAC, 48kHz stereo,
+/// optionally loudness-normalized so previews play at consistent levels.
+fn audio_proxy_args(input: &std::path::Path, output: &std::path::Path, normalize: bool) -> Vec<String> {
+    let mut args: Vec<String> = vec![
+        "-y".into(),
+        "-i".into(), input.to_string_lossy().into_owned(),
+        "-vn".into(),
+    ];
+    if normalize {
+        args.push("-af".into());
+        args.push(PROXY_LOUDNORM_FILTER.into());
+    }
+    args.extend([
+        "-c:a".into(), "aac".into(),
+        "-b:a".into(), "128k".into(),
+        "-ar".into(), "48000".into(),
+        "-ac".into(), "2".into(),
+        output.to_string_lossy().into_owned(),
+    ]);
+    args
+}
+
 async fn handle_capture_frame(
     task_id: &str,
     input: &serde_json::Value,
@@ -462,11 +686,7 @@ async fn handle_capture_frame(
         Some(id) => id.to_string(),
         None => return HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "missing_input".to_string(),
-                message: "Missing assetId in input".to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("missing_input", "Missing assetId in input")),
         },
     };
 
@@ -474,38 +694,26 @@ async fn handle_capture_frame(
         Some(t) => t,
         None => return HandlerResult {
             output: None,
-            error: Some(TaskError {
-                code: "missing_input".to_string(),
-                message: "Missing tMs in input".to_string(),
-                detail: None,
-            }),
+            error: Some(TaskError::new("missing_input", "Missing tMs in input")),
         },
     };
 
     let use_proxy = input.get("useProxy").and_then(|v| v.as_bool()).unwrap_or(true);
 
-    let (src_path, project_dir) = {
+    let (src_path, layout) = {
         let guard = state.inner.lock().await;
         let loaded = match guard.as_ref() {
             Some(l) => l,
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "no_project".to_string(),
-                    message: "No project loaded".to_string(),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("no_project", "No project loaded")),
             },
         };
         let asset = match loaded.project.assets.iter().find(|a| a.asset_id == asset_id) {
             Some(a) => a,
             None => return HandlerResult {
                 output: None,
-                error: Some(TaskError {
-                    code: "asset_not_found".to_string(),
-                    message: format!("Asset {} not found", asset_id),
-                    detail: None,
-                }),
+                error: Some(TaskError::new("asset_not_found", format!("Asset {} not found", asset_id))),
             },
         };
 
@@ -514,13 +722,13 @@ async fn handle_capture_frame(
                 .meta
                 .get("proxyUri")
                 .and_then(|v| v.as_str())
-                .map(|p| loaded.project_dir.join(p))
-                .unwrap_or_else(|| loaded.project_dir.join(&asset.path))
+                .map(|p| loaded.resolve_path(p))
+                .unwrap_or_else(|| loaded.resolve_path(&asset.path))
         } else {
-            loaded.project_dir.join(&asset.path)
+            loaded.resolve_path(&asset.path)
         };
 
-        (src, loaded.project_dir.clone())
+        (src, loaded.layout())
     };
 
     update_progress(state, task_id, TaskProgress {
@@ -529,66 +737,25 @@ async fn handle_capture_frame(
         message: Some(format!("Capturing frame at {}ms", t_ms)),
     }, app_handle).await;
 
-    let captures_dir = project_dir.join("workspace/cache/captures");
+    let captures_dir = layout.dir(WorkspaceSubdir::Captures);
     let _ = std::fs::create_dir_all(&captures_dir);
     let out_filename = format!("{}_{}.png", asset_id, t_ms);
     let out_path = captures_dir.join(&out_filename);
-    let out_relative = format!("workspace/cache/captures/{}", out_filename);
+    let out_relative = layout.stored_path(WorkspaceSubdir::Captures, &out_filename);
 
     let ss = format!("{:.3}", t_ms as f64 / 1000.0);
 
-    let result = Command::new("ffmpeg")
-        .args([
-            "-y",
-            "-ss", &ss,
-            "-i", &src_path.to_string_lossy(),
-            "-vframes", "1",
-            "-q:v", "2",
-            &out_path.to_string_lossy(),
-        ])
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn();
-
-    let child = match result {
-        Ok(c) => c,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_spawn_failed".to_string(),
-                message: format!("Failed to start ffmpeg: {}", e),
-                detail: Some("Ensure ffmpeg is installed and in PATH".to_string()),
-            }),
-        },
-    };
-
-    let output = match child.wait_with_output().await {
-        Ok(o) => o,
-        Err(e) => return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_wait_failed".to_string(),
-                message: format!("ffmpeg process error: {}", e),
-                detail: None,
-            }),
-        },
-    };
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        let detail = if stderr.len() > 2048 {
-            Some(stderr[..2048].to_string())
-        } else {
-            Some(stderr.to_string())
-        };
-        return HandlerResult {
-            output: None,
-            error: Some(TaskError {
-                code: "ffmpeg_failed".to_string(),
-                message: format!("ffmpeg exited with code {:?}", output.status.code()),
-                detail,
-            }),
-        };
+    let args: Vec<String> = vec![
+        "-y".into(),
+        "-ss".into(), ss,
+        "-i".into(), src_path.to_string_lossy().into_owned(),
+        "-vframes".into(), "1".into(),
+        "-q:v".into(), "2".into(),
+        out_path.to_string_lossy().into_owned(),
+    ];
+    if let Err(e) = run_ffmpeg(&args, task_id, state).await {
+        let _ = std::fs::remove_file(&out_path);
+        return HandlerResult { output: None, error: Some(e) };
     }
 
     update_progress(state, task_id, TaskProgress {
@@ -621,7 +788,7 @@ async fn handle_capture_frame(
             params: serde_json::json!({ "assetId": asset_id, "tMs": t_ms }),
         }),
         tags: vec!["capture".to_string()],
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: crate::clock::now_rfc3339(),
     };
 
     // Add asset and auto-enqueue thumb task
@@ -630,7 +797,7 @@ async fn handle_capture_frame(
         if let Some(loaded) = guard.as_mut() {
             loaded.project.assets.push(new_asset);
 
-            let now = chrono::Utc::now().to_rfc3339();
+            let now = crate::clock::now_rfc3339();
             let tid = format!(
                 "task_thumb_{}",
                 &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]
@@ -653,11 +820,23 @@ async fn handle_capture_frame(
                     msg: "Auto-enqueued thumb for captured frame".to_string(),
                 }],
                 dedupe_key: Some(format!("thumb:{}", new_asset_id)),
+                started_at: None,
+                finished_at: None,
+            };
+            // Over the task limit the frame is still captured without a thumb
+            let admitted = match crate::admit_tasks(loaded, 1, app_handle) {
+                Ok(()) => {
+                    loaded.project.tasks.push(thumb_task);
+                    true
+                }
+                Err(e) => {
+                    log::warn!("Not enqueueing thumb for captured frame {}: {}", new_asset_id, e);
+                    false
+                }
             };
-            loaded.project.tasks.push(thumb_task);
             loaded.project.rebuild_indexes();
             loaded.dirty = true;
-            tid
+            if admitted { tid } else { String::new() }
         } else {
             String::new()
         }
@@ -681,33 +860,6 @@ async fn handle_capture_frame(
 // gen_video handler
 // ---------------------------------------------------------------------------
 
-fn build_jimeng_client(
-    app_handle: &tauri::AppHandle,
-    provider_name: &str,
-    profile_name: &str,
-) -> Result<crate::providers::jimeng::client::JimengClient, String> {
-    let path = crate::provider::io::providers_path(app_handle)?;
-    let file = crate::provider::io::load_providers(&path)?;
-    let prov = file
-        .providers
-        .get(provider_name)
-        .ok_or(format!("provider_not_found: {}", provider_name))?;
-    let profile = prov
-        .profiles
-        .get(profile_name)
-        .ok_or(format!("profile_not_found: {}", profile_name))?;
-
-    let secret = crate::secrets::get_secret(&profile.credential_ref)?
-        .ok_or("missing_credentials: 请在设置中连接 Provider".to_string())?;
-
-    let timeout_secs = profile.timeout_ms / 1000;
-    crate::providers::jimeng::client::JimengClient::new(
-        &secret,
-        Some(prov.base_url.as_str()),
-        timeout_secs.max(10),
-    )
-}
-
 const DRAFT_TRACK_ID: &str = "trk_draft";
 const MAX_POLL_ATTEMPTS: u32 = 120;
 const POLL_INTERVAL_SECS: u64 = 5;
@@ -730,7 +882,7 @@ async fn handle_gen_video(
         Some(s) => s.to_string(),
         None => return err_result("missing_input", "Missing prompt"),
     };
-    let model = input.get("model").and_then(|v| v.as_str()).unwrap_or("jimeng-video-3.0");
+    let model = input.get("model").and_then(|v| v.as_str()).unwrap_or(crate::providers::jimeng::constants::DEFAULT_VIDEO_MODEL);
     let ratio = input.get("ratio").and_then(|v| v.as_str()).unwrap_or("16:9");
     let duration_ms = input.get("durationMs").and_then(|v| v.as_u64()).map(|v| v as u32);
     let start_ms = input.get("startMs").and_then(|v| v.as_i64()).unwrap_or(0);
@@ -738,12 +890,12 @@ async fn handle_gen_video(
     // Step 1: Build client
     append_task_event(state, task_id, "info", &format!(
         "Building client for {}/{}", provider_name, profile_name
-    )).await;
+    ), app_handle).await;
 
-    let client = match build_jimeng_client(app_handle, &provider_name, &profile_name) {
+    let client = match crate::providers::jimeng::build_client(app_handle, &provider_name, &profile_name, None) {
         Ok(c) => c,
         Err(e) => {
-            append_task_event(state, task_id, "error", &format!("Client build failed: {}", e)).await;
+            append_task_event(state, task_id, "error", &format!("Client build failed: {}", e), app_handle).await;
             return err_result("provider_error", &format!("Failed to build client: {}", e));
         }
     };
@@ -754,24 +906,29 @@ async fn handle_gen_video(
         message: Some("Submitting video generation request".to_string()),
     }, app_handle).await;
 
+    // Canceled while waiting for a provider slot: don't submit at all
+    if let Err(err) = check_generation_canceled(state, task_id).await {
+        return HandlerResult { output: None, error: Some(err) };
+    }
+
     // Step 2: Submit
     append_task_event(state, task_id, "info", &format!(
         "Submitting: model={}, ratio={}, prompt={}", model, ratio, &prompt[..prompt.len().min(50)]
-    )).await;
+    ), app_handle).await;
 
     let gen_result = match crate::providers::jimeng::api::generate_video(
         &client, &prompt, model, ratio, duration_ms,
     ).await {
         Ok(r) => r,
         Err(e) => {
-            append_task_event(state, task_id, "error", &format!("Submit failed: {}", e)).await;
+            append_task_event(state, task_id, "error", &format!("Submit failed: {}", e), app_handle).await;
             return err_result("provider_error", &format!("Video generation submit failed: {}", e));
         }
     };
 
     append_task_event(state, task_id, "info", &format!(
         "Submitted: submit_id={}, history_id={}", gen_result.submit_id, gen_result.history_id
-    )).await;
+    ), app_handle).await;
 
     update_progress(state, task_id, TaskProgress {
         phase: "submitted".to_string(),
@@ -790,7 +947,18 @@ async fn handle_gen_video(
     let mut final_result = None;
 
     for attempt in 0..MAX_POLL_ATTEMPTS {
-        tokio::time::sleep(std::time::Duration::from_secs(POLL_INTERVAL_SECS)).await;
+        // Jimeng has no cancel endpoint, so a canceled generation is simply
+        // abandoned; the cancel flag is checked while sleeping between polls.
+        if let Err(err) = sleep_unless_canceled(
+            std::time::Duration::from_secs(POLL_INTERVAL_SECS),
+            task_id,
+            state,
+        ).await {
+            append_task_event(state, task_id, "warn", &format!(
+                "Abandoning submitted generation {}: {}", gen_result.submit_id, err.message
+            ), app_handle).await;
+            return HandlerResult { output: None, error: Some(err) };
+        }
 
         let percent = 10.0 + (attempt as f32 / MAX_POLL_ATTEMPTS as f32) * 70.0;
         update_progress(state, task_id, TaskProgress {
@@ -838,20 +1006,20 @@ async fn handle_gen_video(
     let task_status = match final_result {
         Some(r) => r,
         None => {
-            append_task_event(state, task_id, "error", "Generation timed out after polling").await;
+            append_task_event(state, task_id, "error", "Generation timed out after polling", app_handle).await;
             return err_result("timeout", "Video generation timed out after polling");
         }
     };
 
     append_task_event(state, task_id, "info", &format!(
         "Generation completed with status={}", task_status.status
-    )).await;
+    ), app_handle).await;
 
     // Step 4: Extract video URL
     let video_url = match crate::providers::jimeng::api::extract_video_url(&task_status) {
         Some(url) => url,
         None => {
-            append_task_event(state, task_id, "error", "No video URL in completed task").await;
+            append_task_event(state, task_id, "error", "No video URL in completed task", app_handle).await;
             return err_result("provider_error", "No video URL found in completed task");
         }
     };
@@ -862,61 +1030,66 @@ async fn handle_gen_video(
         message: Some("Downloading generated video".to_string()),
     }, app_handle).await;
 
-    // Step 5: Download to .cache/gen/
-    let project_dir = {
+    // Step 5: Download to the generated-media cache dir
+    let layout = {
         let guard = state.inner.lock().await;
         match guard.as_ref() {
-            Some(loaded) => loaded.project_dir.clone(),
+            Some(loaded) => loaded.layout(),
             None => return err_result("no_project", "No project loaded"),
         }
     };
 
-    let gen_dir = project_dir.join("workspace").join("cache").join("gen");
+    let gen_dir = layout.dir(WorkspaceSubdir::Generated);
     let _ = std::fs::create_dir_all(&gen_dir);
     let file_name = format!("{}.mp4", task_id);
     let file_path = gen_dir.join(&file_name);
-    let relative_path = format!("workspace/cache/gen/{}", file_name);
-
-    let download_client = reqwest::Client::new();
-    let resp = match download_client.get(&video_url).send().await {
-        Ok(r) => r,
-        Err(e) => return err_result("download_error", &format!("Failed to download video: {}", e)),
+    let relative_path = layout.stored_path(WorkspaceSubdir::Generated, &file_name);
+
+    let downloaded = match crate::task::download::download_to_file(&video_url, &file_path, task_id, state).await {
+        Ok(n) => n,
+        Err(mut err) => {
+            // Only a bad response leaves something worth keeping in the file
+            if err.code != "canceled" && err.code != "insufficient_disk_space" {
+                err.detail = save_error_artifact(&layout, &file_path, task_id);
+            }
+            let _ = std::fs::remove_file(&file_path);
+            append_task_event(state, task_id, "error", &format!("Download failed: {}", err.message), app_handle).await;
+            return HandlerResult { output: None, error: Some(err) };
+        }
     };
 
-    if !resp.status().is_success() {
-        return err_result("download_error", &format!("Download HTTP {}", resp.status()));
-    }
-
-    let bytes = match resp.bytes().await {
-        Ok(b) => b,
-        Err(e) => return err_result("download_error", &format!("Failed to read video bytes: {}", e)),
-    };
+    append_task_event(state, task_id, "info", &format!(
+        "Downloaded {} bytes to {}", downloaded, relative_path
+    ), app_handle).await;
 
-    if let Err(e) = std::fs::write(&file_path, &bytes) {
-        append_task_event(state, task_id, "error", &format!("File write failed: {}", e)).await;
-        return err_result("io_error", &format!("Failed to write video file: {}", e));
+    // Last chance to cancel: nothing is registered for a canceled generation
+    if let Err(err) = check_generation_canceled(state, task_id).await {
+        let _ = std::fs::remove_file(&file_path);
+        return HandlerResult { output: None, error: Some(err) };
     }
 
-    append_task_event(state, task_id, "info", &format!(
-        "Downloaded {} bytes to {}", bytes.len(), relative_path
-    )).await;
-
     update_progress(state, task_id, TaskProgress {
         phase: "registering".to_string(),
         percent: Some(92.0),
         message: Some("Registering asset and inserting clip".to_string()),
     }, app_handle).await;
 
-    // Step 6: Probe the downloaded video for duration
-    let probe_duration_ms = match crate::media::probe::ffprobe(&file_path) {
-        Ok(probe_data) => {
-            let meta = crate::media::probe::extract_video_meta(&probe_data);
-            meta.get("durationSec")
-                .and_then(|v| v.as_f64())
-                .map(|s| (s * 1000.0) as i64)
-                .unwrap_or(5000)
+    // Step 6: Verify the download is a playable video and read its duration
+    let probe_duration_ms = match crate::media::probe::verify_video(&file_path) {
+        MediaCheck::Valid(meta) => crate::project::model::meta_duration_ms(&meta)
+            .unwrap_or(crate::project::model::DEFAULT_CLIP_DURATION_MS),
+        MediaCheck::Invalid(reason) => {
+            let detail = save_error_artifact(&layout, &file_path, task_id);
+            let _ = std::fs::remove_file(&file_path);
+            append_task_event(state, task_id, "error", &format!("Downloaded file is not valid media: {}", reason), app_handle).await;
+            let mut err = TaskError::new("invalid_media", format!("Downloaded video failed verification: {}", reason));
+            err.detail = detail;
+            return HandlerResult { output: None, error: Some(err) };
+        }
+        MediaCheck::Unavailable(reason) => {
+            append_task_event(state, task_id, "warn", &format!("Skipping media verification: {}", reason), app_handle).await;
+            duration_ms.map(|d| d as i64).unwrap_or(5000)
         }
-        Err(_) => duration_ms.map(|d| d as i64).unwrap_or(5000),
     };
 
     // Step 7: Register asset + insert clip on trk_draft
@@ -955,7 +1128,7 @@ async fn handle_gen_video(
             }),
         }),
         tags: vec!["generated".to_string(), "video".to_string()],
-        created_at: chrono::Utc::now().to_rfc3339(),
+        created_at: crate::clock::now_rfc3339(),
     };
 
     let new_clip = Clip {
@@ -966,6 +1139,8 @@ async fn handle_gen_video(
         duration_ms: probe_duration_ms,
         in_ms: 0,
         out_ms: probe_duration_ms,
+        label_color: None,
+        note: None,
     };
 
     {
@@ -1008,14 +1183,40 @@ async fn handle_gen_video(
     }
 }
 
+/// `canceled`/`interrupted` error if the task was canceled or the app is
+/// shutting down.
+async fn check_generation_canceled(state: &Arc<AppState>, task_id: &str) -> Result<(), TaskError> {
+    if state.cancel_flags.lock().await.contains(task_id) {
+        return Err(TaskError::new("canceled", "Generation canceled"));
+    }
+    if state.is_shutting_down() {
+        return Err(TaskError::new("interrupted", "App shut down during generation"));
+    }
+    Ok(())
+}
+
+/// Sleep for `duration`, waking every `CANCEL_POLL_INTERVAL` to return early
+/// if the task is canceled.
+async fn sleep_unless_canceled(
+    duration: std::time::Duration,
+    task_id: &str,
+    state: &Arc<AppState>,
+) -> Result<(), TaskError> {
+    let deadline = tokio::time::Instant::now() + duration;
+    loop {
+        check_generation_canceled(state, task_id).await?;
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Ok(());
+        }
+        tokio::time::sleep(CANCEL_POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
 fn err_result(code: &str, message: &str) -> HandlerResult {
     HandlerResult {
         output: None,
-        error: Some(TaskError {
-            code: code.to_string(),
-            message: message.to_string(),
-            detail: None,
-        }),
+        error: Some(TaskError::new(code, message)),
     }
 }
 
@@ -1030,6 +1231,13 @@ async fn handle_export(
     app_handle: &tauri::AppHandle,
 ) -> HandlerResult {
     let track_id = input.get("trackId").and_then(|v| v.as_str()).unwrap_or(DRAFT_TRACK_ID);
+    let range = match parse_range(
+        input.get("startMs").and_then(|v| v.as_i64()),
+        input.get("endMs").and_then(|v| v.as_i64()),
+    ) {
+        Ok(r) => r,
+        Err(msg) => return err_result("invalid_range", &msg),
+    };
 
     update_progress(state, task_id, TaskProgress {
         phase: "collecting".to_string(),
@@ -1037,177 +1245,1717 @@ async fn handle_export(
         message: Some("Collecting clips from track".to_string()),
     }, app_handle).await;
 
-    // Collect clip info from the target track
-    let (clip_paths, project_dir) = {
+    let subtitle_mode = match SubtitleMode::from_input(input) {
+        Ok(m) => m,
+        Err(msg) => return err_result("invalid_subtitles", &msg),
+    };
+    let loudness_target = match parse_loudness_target(input) {
+        Ok(t) => t,
+        Err(msg) => return err_result("invalid_loudness", &msg),
+    };
+
+    // Collect clip info from the target track (and the subtitle track, if any)
+    let (plan, layout, subtitle_sources, frame) = {
         let guard = state.inner.lock().await;
         let loaded = match guard.as_ref() {
             Some(l) => l,
             None => return err_result("no_project", "No project loaded"),
         };
-
-        let track = match loaded.project.timeline.tracks.iter().find(|t| t.track_id == track_id) {
-            Some(t) => t,
-            None => return err_result("track_not_found", &format!("Track {} not found", track_id)),
+        let plan = match build_export_plan(loaded, track_id, range) {
+            Ok(plan) => plan,
+            Err(e) => return HandlerResult { output: None, error: Some(e) },
         };
+        let sources = match subtitle_mode {
+            Some(_) => match subtitle_sources(loaded, input.get("subtitleTrackId").and_then(|v| v.as_str())) {
+                Ok(sources) => sources,
+                Err(e) => return HandlerResult { output: None, error: Some(e) },
+            },
+            None => vec![],
+        };
+        (plan, loaded.layout(), sources, OutputFrame::from_settings(&loaded.project.project.settings))
+    };
 
-        if track.clip_ids.is_empty() {
-            return err_result("no_clips", "Track has no clips to export");
-        }
-
-        // Collect clips sorted by start_ms
-        let mut clips: Vec<&Clip> = track.clip_ids.iter()
-            .filter_map(|cid| loaded.project.timeline.clips.get(cid))
-            .collect();
-        clips.sort_by_key(|c| c.start_ms);
-
-        let paths: Vec<std::path::PathBuf> = clips.iter()
-            .filter_map(|clip| {
-                loaded.project.assets.iter()
-                    .find(|a| a.asset_id == clip.asset_id)
-                    .map(|a| loaded.project_dir.join(&a.path))
-            })
-            .collect();
+    // From here on every outcome, failures included, lands in the export history
+    let mut record = crate::project::model::ExportRecord {
+        export_id: format!("exp_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]),
+        status: "failed".to_string(),
+        preset: crate::project::model::ExportPreset {
+            container: String::new(),
+            codec: String::new(),
+            bitrate_kbps: 0,
+        },
+        start_ms: plan.start_ms,
+        end_ms: plan.end_ms,
+        output_uri: String::new(),
+        created_at: crate::clock::now_rfc3339(),
+        track_id: Some(track_id.to_string()),
+        clip_count: plan.segments.len() as u32,
+        loudness_target_lufs: None,
+        width: None,
+        height: None,
+        codec: None,
+        duration_ms: None,
+        file_size_bytes: None,
+    };
+    let exported = export_planned(
+        &plan, &layout, &subtitle_sources, &frame, subtitle_mode, loudness_target,
+        &mut record, task_id, input, state, app_handle,
+    );
+    let result = exported.await;
+    record.status = match &result {
+        Ok(_) => "completed",
+        Err(err) if err.code == "canceled" => "canceled",
+        Err(_) => "failed",
+    }
+    .to_string();
+    push_export_record(state, record).await;
+    let _ = app_handle.emit("project:updated", serde_json::json!({}));
 
-        if paths.is_empty() {
-            return err_result("no_assets", "No assets found for clips");
-        }
+    match result {
+        Ok(output) => HandlerResult { output: Some(output), error: None },
+        Err(err) => HandlerResult { output: None, error: Some(err) },
+    }
+}
 
-        (paths, loaded.project_dir.clone())
-    };
+/// Everything `handle_export` does once the plan is built: validate the
+/// format and extra args, check disk space, burn subtitles, encode and probe
+/// the result. Fills in `record` as it goes; the caller stores it either way.
+#[allow(clippy::too_many_arguments)]
+async fn export_planned(
+    plan: &crate::task::export_plan::ExportPlan,
+    layout: &crate::project::layout::WorkspaceLayout,
+    subtitle_sources: &[crate::task::export_plan::SubtitleSource],
+    frame: &OutputFrame,
+    subtitle_mode: Option<SubtitleMode>,
+    loudness_target: Option<f64>,
+    record: &mut crate::project::model::ExportRecord,
+    task_id: &str,
+    input: &serde_json::Value,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> Result<serde_json::Value, TaskError> {
+    let (segments, start_ms, end_ms) = (&plan.segments, plan.start_ms, plan.end_ms);
+
+    let format = ExportFormat::from_input(input, &plan.track_type).map_err(|msg| TaskError::new("invalid_format", msg))?;
+    record.preset.container = format.container().to_string();
+    record.preset.codec = format.codec().to_string();
+    if let Some(mode) = subtitle_mode {
+        mode.check_format(&format).map_err(|msg| TaskError::new("invalid_subtitles", msg))?;
+    }
+    // Stills are held for their length in video exports but carry no sound
+    if format.is_audio() && segments.iter().any(|s| s.is_still()) {
+        return Err(TaskError::new("mixed_media", "Audio exports can't include image clips; export them as video"));
+    }
 
-    let exports_dir = project_dir.join("workspace").join("exports");
+    let exports_dir = layout.dir(WorkspaceSubdir::Exports);
     let _ = std::fs::create_dir_all(&exports_dir);
 
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let output_filename = format!("export_{}.mp4", timestamp);
+    let timestamp = crate::clock::now().format("%Y%m%d_%H%M%S").to_string();
+    let output_filename = format!("export_{}.{}", timestamp, format.container());
     let output_path = exports_dir.join(&output_filename);
-    let output_relative = format!("workspace/exports/{}", output_filename);
+    let output_relative = layout.stored_path(WorkspaceSubdir::Exports, &output_filename);
+    record.output_uri = output_relative.clone();
+
+    let extra_args = parse_extra_ffmpeg_args(input, &[&exports_dir, &output_path])
+        .map_err(|msg| TaskError::new("invalid_ffmpeg_args", msg))?;
+
+    if let Err(e) = crate::disk::ensure_space(&exports_dir, export_size_estimate(segments)) {
+        append_task_event(state, task_id, "error", &e.message, app_handle).await;
+        return Err(e);
+    }
+
+    // Subtitle clips retimed onto the output, as one SRT next to the export
+    let subs_path = exports_dir.join(format!("subs_{}.srt", timestamp));
+    let _subs_guard = TempFileGuard::new(subs_path.clone());
+    let mut subtitles = None;
+    if let Some(mode) = subtitle_mode {
+        let mut timeline_cues = Vec::new();
+        for source in subtitle_sources {
+            let content = crate::media::subtitle::read_lossy(&source.path).map_err(|e| {
+                TaskError::new("io_error", format!("Failed to read {}: {}", source.path.display(), e))
+            })?;
+            timeline_cues.extend(source.place(&crate::media::subtitle::parse(&content)));
+        }
+        let cues = output_cues(&timeline_cues, segments);
+        if cues.is_empty() {
+            append_task_event(state, task_id, "warn", "No subtitle cues fall inside the export; exporting without subtitles", app_handle).await;
+        } else {
+            std::fs::write(&subs_path, crate::media::subtitle::to_srt(&cues))
+                .map_err(|e| TaskError::new("io_error", format!("Failed to write subtitles: {}", e)))?;
+            subtitles = Some((mode, subs_path.as_path()));
+        }
+    }
+
+    // Nothing to normalize in silent loops or sources without audio
+    let loudness_target = loudness_target.filter(|_| !format.is_loop() && segments.iter().any(|s| s.has_audio));
+    if input.get("loudnorm").and_then(|v| v.as_bool()).unwrap_or(false) && loudness_target.is_none() {
+        append_task_event(state, task_id, "info", "Export has no audio; skipping loudness normalization", app_handle).await;
+    }
 
     update_progress(state, task_id, TaskProgress {
         phase: "encoding".to_string(),
         percent: Some(20.0),
-        message: Some(format!("Exporting {} clip(s)", clip_paths.len())),
+        message: Some(format!("Exporting {} clip(s)", segments.len())),
     }, app_handle).await;
 
-    if clip_paths.len() == 1 {
-        // Single clip: transcode
-        let child = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-i", &clip_paths[0].to_string_lossy(),
-                "-c:v", "libx264",
-                "-crf", "23",
-                "-preset", "fast",
-                "-c:a", "aac",
-                "-b:a", "128k",
-                &output_path.to_string_lossy(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn();
-
-        let child = match child {
-            Ok(c) => c,
-            Err(e) => return err_result("ffmpeg_spawn_failed", &format!("Failed to start ffmpeg: {}", e)),
-        };
+    let encode = encode_export(
+        segments, &format, frame, subtitles, loudness_target, &extra_args,
+        &exports_dir, &output_path, &timestamp, task_id, state, app_handle,
+    );
+    let applied = encode.await?;
+    record.loudness_target_lufs = applied;
+    if loudness_target.is_some() && applied.is_none() {
+        append_task_event(state, task_id, "warn", "Audio measured as silent; exported without loudness normalization", app_handle).await;
+    }
 
-        let output = match child.wait_with_output().await {
-            Ok(o) => o,
-            Err(e) => return err_result("ffmpeg_wait_failed", &format!("ffmpeg process error: {}", e)),
-        };
+    update_progress(state, task_id, TaskProgress {
+        phase: "finalizing".to_string(),
+        percent: Some(95.0),
+        message: None,
+    }, app_handle).await;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return err_result("ffmpeg_failed", &format!("ffmpeg exited {:?}: {}", output.status.code(), &stderr[..stderr.len().min(512)]));
+    record.file_size_bytes = std::fs::metadata(&output_path).ok().map(|m| m.len());
+    match crate::media::probe::ffprobe(&output_path) {
+        Ok(probe) => {
+            record_output_probe(record, &probe, !format.is_loop());
+            if record.duration_ms.unwrap_or(0) <= 0 {
+                append_task_event(state, task_id, "warn", "Exported file reports no duration", app_handle).await;
+            }
         }
-    } else {
-        // Multiple clips: write concat list and use ffmpeg concat
-        let concat_list_path = exports_dir.join(format!("concat_{}.txt", timestamp));
-        let mut concat_content = String::new();
-        for p in &clip_paths {
-            let escaped = p.to_string_lossy().replace('\'', "'\\''");
-            concat_content.push_str(&format!("file '{}'\n", escaped));
-        }
-        if let Err(e) = std::fs::write(&concat_list_path, &concat_content) {
-            return err_result("io_error", &format!("Failed to write concat list: {}", e));
-        }
-
-        // Try concat copy first; fall back to re-encode on failure
-        let child = Command::new("ffmpeg")
-            .args([
-                "-y",
-                "-f", "concat",
-                "-safe", "0",
-                "-i", &concat_list_path.to_string_lossy(),
-                "-c:v", "libx264",
-                "-crf", "23",
-                "-preset", "fast",
-                "-c:a", "aac",
-                "-b:a", "128k",
-                &output_path.to_string_lossy(),
-            ])
-            .stdout(Stdio::null())
-            .stderr(Stdio::piped())
-            .spawn();
-
-        let child = match child {
-            Ok(c) => c,
-            Err(e) => {
-                let _ = std::fs::remove_file(&concat_list_path);
-                return err_result("ffmpeg_spawn_failed", &format!("Failed to start ffmpeg: {}", e));
-            }
-        };
+        Err(e) => {
+            append_task_event(state, task_id, "warn", &format!("Could not probe exported file: {}", e), app_handle).await;
+        }
+    }
 
-        let output = match child.wait_with_output().await {
-            Ok(o) => o,
-            Err(e) => {
-                let _ = std::fs::remove_file(&concat_list_path);
-                return err_result("ffmpeg_wait_failed", &format!("ffmpeg process error: {}", e));
-            }
+    Ok(serde_json::json!({
+        "exportPath": output_relative,
+        "startMs": start_ms,
+        "endMs": end_ms,
+        "loudnessTargetLufs": record.loudness_target_lufs,
+    }))
+}
+
+// ---------------------------------------------------------------------------
+// contact sheet handler
+// ---------------------------------------------------------------------------
+
+pub const CONTACT_SHEET_FILENAME: &str = "contact_sheet.png";
+const CONTACT_SHEET_DEFAULT_COLUMNS: u64 = 6;
+const CONTACT_SHEET_DEFAULT_CELL: (u64, u64) = (320, 180);
+
+/// Tile every video/image thumbnail into one PNG in the exports dir, grouped
+/// by image format and in asset order within each. Thumbnails still missing
+/// when it runs (including ones whose thumb task failed) are left out.
+async fn handle_contact_sheet(
+    task_id: &str,
+    input: &serde_json::Value,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> HandlerResult {
+    let columns = input.get("columns").and_then(|v| v.as_u64()).unwrap_or(CONTACT_SHEET_DEFAULT_COLUMNS);
+    let cell_width = input.get("cellWidth").and_then(|v| v.as_u64()).unwrap_or(CONTACT_SHEET_DEFAULT_CELL.0);
+    let cell_height = input.get("cellHeight").and_then(|v| v.as_u64()).unwrap_or(CONTACT_SHEET_DEFAULT_CELL.1);
+    if !(1..=20).contains(&columns) {
+        return err_result("invalid_input", &format!("columns must be between 1 and 20, got {}", columns));
+    }
+    for (name, value) in [("cellWidth", cell_width), ("cellHeight", cell_height)] {
+        if !(16..=1920).contains(&value) {
+            return err_result("invalid_input", &format!("{} must be between 16 and 1920, got {}", name, value));
+        }
+    }
+
+    let (thumbs, layout) = {
+        let guard = state.inner.lock().await;
+        let loaded = match guard.as_ref() {
+            Some(l) => l,
+            None => return err_result("no_project", "No project loaded"),
         };
+        let thumbs: Vec<std::path::PathBuf> = loaded
+            .project
+            .assets
+            .iter()
+            .filter(|a| a.asset_type == "video" || a.asset_type == "image")
+            .filter_map(|a| a.meta.get("thumbUri").and_then(|v| v.as_str()))
+            .map(|uri| loaded.resolve_path(uri))
+            .filter(|path| path.exists())
+            .collect();
+        (thumbs, loaded.layout())
+    };
+    if thumbs.is_empty() {
+        return err_result("no_thumbnails", "No video or image assets with thumbnails");
+    }
 
-        let _ = std::fs::remove_file(&concat_list_path);
+    update_progress(state, task_id, TaskProgress {
+        phase: "tiling".to_string(),
+        percent: Some(20.0),
+        message: Some(format!("Tiling {} thumbnail(s)", thumbs.len())),
+    }, app_handle).await;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return err_result("ffmpeg_failed", &format!("ffmpeg exited {:?}: {}", output.status.code(), &stderr[..stderr.len().min(512)]));
+    let exports_dir = layout.dir(WorkspaceSubdir::Exports);
+    let _ = std::fs::create_dir_all(&exports_dir);
+    let output_path = exports_dir.join(CONTACT_SHEET_FILENAME);
+    let tmp_path = output_path.with_extension("png.tmp");
+    let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
+
+    // One list file per image format rather than one -i per thumbnail, which
+    // would hit command-line and open-file limits on a large media bin.
+    let mut lists = Vec::new();
+    let mut _list_guards = Vec::new();
+    for (i, group) in group_by_extension(&thumbs).iter().enumerate() {
+        let list_path = exports_dir.join(format!("contact_sheet_{}_{}.txt", task_id, i));
+        _list_guards.push(TempFileGuard::new(list_path.clone()));
+        if let Err(e) = std::fs::write(&list_path, image_list(group)) {
+            return err_result("io_error", &format!("Failed to write thumbnail list: {}", e));
         }
+        lists.push(list_path);
+    }
+
+    let (columns, cell) = (columns as u32, (cell_width as u32, cell_height as u32));
+    let args = contact_sheet_args(&lists, thumbs.len(), columns, cell, &tmp_path);
+    if let Err(e) = run_ffmpeg(&args, task_id, state).await {
+        return HandlerResult { output: None, error: Some(e) };
+    }
+    if let Err(e) = std::fs::rename(&tmp_path, &output_path) {
+        return err_result("io_error", &format!("Failed to finalize contact sheet: {}", e));
     }
+    tmp_guard.disarm();
+
+    HandlerResult {
+        output: Some(serde_json::json!({
+            "path": layout.stored_path(WorkspaceSubdir::Exports, CONTACT_SHEET_FILENAME),
+            "assetCount": thumbs.len(),
+            "columns": columns,
+            "rows": (thumbs.len() as u32).div_ceil(columns),
+        })),
+        error: None,
+    }
+}
+
+/// Paths grouped by extension, groups in first-seen order. The concat
+/// demuxer can't switch image codecs mid-list, so each format is its own input.
+fn group_by_extension(paths: &[std::path::PathBuf]) -> Vec<Vec<&std::path::Path>> {
+    let mut groups: Vec<(String, Vec<&std::path::Path>)> = Vec::new();
+    for path in paths {
+        let ext = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match groups.iter_mut().find(|(e, _)| *e == ext) {
+            Some((_, group)) => group.push(path),
+            None => groups.push((ext, vec![path])),
+        }
+    }
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// Concat demuxer script showing each image for one frame.
+fn image_list(paths: &[&std::path::Path]) -> String {
+    let mut content = String::new();
+    for path in paths {
+        let escaped = path.to_string_lossy().replace('\'', "'\\''");
+        content.push_str(&format!("file '{}'\nduration 0.04\n", escaped));
+    }
+    content
+}
+
+/// One concat input per thumbnail list, each frame letterboxed into a
+/// `cell` (w, h), then joined into a single frame of `count` cells by
+/// `tile`. The last row is padded with black.
+fn contact_sheet_args(
+    lists: &[std::path::PathBuf],
+    count: usize,
+    columns: u32,
+    cell: (u32, u32),
+    tmp_path: &std::path::Path,
+) -> Vec<String> {
+    let (w, h) = cell;
+    let rows = (count as u32).div_ceil(columns);
+    let mut args = vec!["-y".to_string()];
+    let mut graph = String::new();
+    for (i, list) in lists.iter().enumerate() {
+        // Thumbnails differ in size; scale and pad per frame instead of
+        // rebuilding the graph (and losing tiled frames) on each change.
+        args.extend(["-f", "concat", "-safe", "0", "-reinit_filter", "0", "-i"].iter().map(|s| s.to_string()));
+        args.push(list.to_string_lossy().to_string());
+        graph.push_str(&format!(
+            "[{i}:v]scale={w}:{h}:force_original_aspect_ratio=decrease:eval=frame,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:eval=frame,setsar=1,format=rgb24[c{i}];"
+        ));
+    }
+    for i in 0..lists.len() {
+        graph.push_str(&format!("[c{}]", i));
+    }
+    graph.push_str(&format!("concat=n={}:v=1:a=0,tile={}x{}[out]", lists.len(), columns, rows));
+    args.extend([
+        "-filter_complex".to_string(), graph,
+        "-map".to_string(), "[out]".to_string(),
+        "-frames:v".to_string(), "1".to_string(),
+        "-c:v".to_string(), "png".to_string(),
+        // The .tmp extension hides the format from ffmpeg
+        "-f".to_string(), "image2".to_string(),
+        tmp_path.to_string_lossy().to_string(),
+    ]);
+    args
+}
+
+// ---------------------------------------------------------------------------
+// waveform handler
+// ---------------------------------------------------------------------------
+
+const WAVEFORM_SIZE: (u32, u32) = (1200, 120);
+
+/// Render the asset's audio as a mono waveform PNG next to its thumbnail and
+/// record it as `meta.waveformUri`.
+async fn handle_waveform(
+    task_id: &str,
+    input: &serde_json::Value,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> HandlerResult {
+    let asset_id = match input.get("assetId").and_then(|v| v.as_str()) {
+        Some(id) => id.to_string(),
+        None => return err_result("missing_input", "Missing assetId in input"),
+    };
+
+    let (abs_path, layout) = {
+        let guard = state.inner.lock().await;
+        let loaded = match guard.as_ref() {
+            Some(l) => l,
+            None => return err_result("no_project", "No project loaded"),
+        };
+        match loaded.project.assets.iter().find(|a| a.asset_id == asset_id) {
+            Some(a) => (loaded.resolve_path(&a.path), loaded.layout()),
+            None => return err_result("asset_not_found", &format!("Asset {} not found", asset_id)),
+        }
+    };
 
     update_progress(state, task_id, TaskProgress {
-        phase: "finalizing".to_string(),
-        percent: Some(95.0),
+        phase: "rendering_waveform".to_string(),
+        percent: Some(10.0),
         message: None,
     }, app_handle).await;
 
-    // Register export record
-    {
+    let thumb_dir = layout.dir(WorkspaceSubdir::Thumbs);
+    let _ = std::fs::create_dir_all(&thumb_dir);
+    let file_name = format!("{}_waveform.png", asset_id);
+    let output_path = thumb_dir.join(&file_name);
+    let tmp_path = output_path.with_extension("png.tmp");
+    let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
+
+    if let Err(e) = run_ffmpeg(&waveform_args(&abs_path, WAVEFORM_SIZE, &tmp_path), task_id, state).await {
+        return HandlerResult { output: None, error: Some(e) };
+    }
+    if let Err(e) = std::fs::rename(&tmp_path, &output_path) {
+        return err_result("io_error", &format!("Failed to finalize waveform: {}", e));
+    }
+    tmp_guard.disarm();
+
+    let relative = layout.stored_path(WorkspaceSubdir::Thumbs, &file_name);
+    let updated_meta = {
         let mut guard = state.inner.lock().await;
+        let mut updated = None;
         if let Some(loaded) = guard.as_mut() {
-            let export_record = crate::project::model::ExportRecord {
-                export_id: format!("exp_{}", &uuid::Uuid::new_v4().to_string().replace("-", "")[..8]),
-                status: "completed".to_string(),
-                preset: crate::project::model::ExportPreset {
-                    container: "mp4".to_string(),
-                    codec: "h264".to_string(),
-                    bitrate_kbps: 0,
-                },
-                start_ms: 0,
-                end_ms: 0,
-                output_uri: output_relative.clone(),
-                created_at: chrono::Utc::now().to_rfc3339(),
-            };
-            loaded.project.exports.push(export_record);
+            if let Some(asset) = loaded.project.assets.iter_mut().find(|a| a.asset_id == asset_id) {
+                if let Some(meta) = asset.meta.as_object_mut() {
+                    meta.insert("waveformUri".to_string(), serde_json::json!(relative));
+                }
+                updated = Some(asset.meta.clone());
+            }
             loaded.dirty = true;
         }
-    }
-
+        updated
+    };
+    emit_asset_updated(app_handle, &asset_id, updated_meta);
     let _ = app_handle.emit("project:updated", serde_json::json!({}));
 
     HandlerResult {
         output: Some(serde_json::json!({
-            "exportPath": output_relative,
+            "assetId": asset_id,
+            "waveformUri": relative,
         })),
         error: None,
     }
 }
+
+fn waveform_args(input: &std::path::Path, size: (u32, u32), tmp_path: &std::path::Path) -> Vec<String> {
+    vec![
+        "-y".to_string(),
+        "-i".to_string(), input.to_string_lossy().to_string(),
+        "-filter_complex".to_string(),
+        format!("[0:a:0]aformat=channel_layouts=mono,showwavespic=s={}x{}:colors=0x60a5fa[out]", size.0, size.1),
+        "-map".to_string(), "[out]".to_string(),
+        "-frames:v".to_string(), "1".to_string(),
+        "-c:v".to_string(), "png".to_string(),
+        // The .tmp extension hides the format from ffmpeg
+        "-f".to_string(), "image2".to_string(),
+        tmp_path.to_string_lossy().to_string(),
+    ]
+}
+
+const EXPORT_CONTAINER: &str = "mp4";
+const EXPORT_VIDEO_CODEC: &str = "h264";
+const AUDIO_EXPORT_CONTAINER: &str = "m4a";
+const LOOP_EXPORT_DEFAULT_FPS: u32 = 15;
+const LOOP_EXPORT_DEFAULT_WIDTH: u32 = 480;
+
+/// Output format for an export. GIF/WebP are silent loops; any audio in the
+/// source is dropped. MP3/M4A are audio-only and drop any video.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ExportFormat {
+    Mp4,
+    Gif { fps: u32, width: u32 },
+    Webp { fps: u32, width: u32 },
+    Mp3,
+    M4a,
+}
+
+impl ExportFormat {
+    /// Audio tracks default to M4A and only accept audio formats; other
+    /// tracks default to MP4.
+    fn from_input(input: &serde_json::Value, track_type: &str) -> Result<Self, String> {
+        let fps = input.get("fps").and_then(|v| v.as_u64()).unwrap_or(LOOP_EXPORT_DEFAULT_FPS as u64);
+        let width = input.get("width").and_then(|v| v.as_u64()).unwrap_or(LOOP_EXPORT_DEFAULT_WIDTH as u64);
+        if !(1..=60).contains(&fps) {
+            return Err(format!("fps must be between 1 and 60, got {}", fps));
+        }
+        if !(16..=3840).contains(&width) {
+            return Err(format!("width must be between 16 and 3840, got {}", width));
+        }
+        let (fps, width) = (fps as u32, width as u32);
+        let audio_track = track_type == "audio";
+        let default = if audio_track { AUDIO_EXPORT_CONTAINER } else { EXPORT_CONTAINER };
+        let format = match input.get("format").and_then(|v| v.as_str()).unwrap_or(default) {
+            "mp4" => ExportFormat::Mp4,
+            "gif" => ExportFormat::Gif { fps, width },
+            "webp" => ExportFormat::Webp { fps, width },
+            "mp3" => ExportFormat::Mp3,
+            "m4a" => ExportFormat::M4a,
+            other => return Err(format!("Unsupported export format: {}", other)),
+        };
+        if audio_track && !format.is_audio() {
+            return Err(format!("Audio tracks export as mp3 or m4a, not {}", format.container()));
+        }
+        Ok(format)
+    }
+
+    fn is_loop(&self) -> bool {
+        matches!(self, ExportFormat::Gif { .. } | ExportFormat::Webp { .. })
+    }
+
+    fn is_audio(&self) -> bool {
+        matches!(self, ExportFormat::Mp3 | ExportFormat::M4a)
+    }
+
+    fn container(&self) -> &'static str {
+        match self {
+            ExportFormat::Mp4 => EXPORT_CONTAINER,
+            ExportFormat::Gif { .. } => "gif",
+            ExportFormat::Webp { .. } => "webp",
+            ExportFormat::Mp3 => "mp3",
+            ExportFormat::M4a => AUDIO_EXPORT_CONTAINER,
+        }
+    }
+
+    fn codec(&self) -> &'static str {
+        match self {
+            ExportFormat::Mp4 => EXPORT_VIDEO_CODEC,
+            ExportFormat::Gif { .. } => "gif",
+            ExportFormat::Webp { .. } => "libwebp",
+            ExportFormat::Mp3 => "mp3",
+            ExportFormat::M4a => "aac",
+        }
+    }
+}
+
+/// The project frame pictures are fitted into on export and in proxies.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OutputFrame {
+    width: u32,
+    height: u32,
+    mode: ScaleMode,
+}
+
+impl OutputFrame {
+    fn from_settings(settings: &ProjectSettings) -> Self {
+        OutputFrame {
+            width: settings.resolution.width,
+            height: settings.resolution.height,
+            mode: settings.effective_scale_mode(),
+        }
+    }
+
+    /// Same aspect with the longer side at `long_side`, for proxies. Sides
+    /// stay even for yuv420p.
+    fn scaled_to(&self, long_side: u32) -> Self {
+        let factor = long_side as f64 / self.width.max(self.height).max(1) as f64;
+        let even = |px: u32| (((px as f64 * factor) / 2.0).round() as u32 * 2).max(2);
+        OutputFrame { width: even(self.width), height: even(self.height), mode: self.mode }
+    }
+
+    /// Scale without distortion, then pad (`fit`) or crop (`fill`) to the frame.
+    fn filter(&self) -> String {
+        let (w, h) = (self.width, self.height);
+        match self.mode {
+            ScaleMode::Fit => format!(
+                "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2,setsar=1"
+            ),
+            ScaleMode::Fill => format!("scale={w}:{h}:force_original_aspect_ratio=increase,crop={w}:{h},setsar=1"),
+        }
+    }
+}
+
+/// How subtitle clips end up in an export: rendered into the picture, or
+/// muxed as a soft `mov_text` stream players can toggle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SubtitleMode {
+    Burn,
+    Mux,
+}
+
+impl SubtitleMode {
+    /// `subtitles` is optional; absent means no subtitles.
+    fn from_input(input: &serde_json::Value) -> Result<Option<Self>, String> {
+        match input.get("subtitles").and_then(|v| v.as_str()) {
+            None => Ok(None),
+            Some("burn") => Ok(Some(SubtitleMode::Burn)),
+            Some("mux") => Ok(Some(SubtitleMode::Mux)),
+            Some(other) => Err(format!("subtitles must be burn or mux, got {}", other)),
+        }
+    }
+
+    fn check_format(&self, format: &ExportFormat) -> Result<(), String> {
+        match (self, format) {
+            (_, f) if f.is_audio() => Err(format!("{} exports cannot carry subtitles", f.container())),
+            (SubtitleMode::Mux, f) if *f != ExportFormat::Mp4 => {
+                Err(format!("Only mp4 can mux subtitles; burn them into {} instead", f.container()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// `subtitles` filter for an SRT file. The path is quoted for the filtergraph;
+/// `:` and `'` still need escaping for the filter's own option parser.
+fn subtitles_filter(path: &std::path::Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "'\\\\\\''");
+    format!("subtitles='{}'", path)
+}
+
+/// ffmpeg invocations (run in order) that encode `input_args` into `tmp_path`.
+/// GIF uses the two-pass palette approach: pass 1 writes an optimized palette
+/// to `palette_path`, pass 2 maps frames onto it with `paletteuse`.
+/// `subtitles` is validated against `format` by `SubtitleMode::check_format`.
+/// `audio_filter` applies to formats that keep audio and is ignored otherwise.
+/// `frame` fits every picture into the project frame before anything else.
+#[allow(clippy::too_many_arguments)]
+fn export_passes(
+    format: &ExportFormat,
+    input_args: &[String],
+    subtitles: Option<(SubtitleMode, &std::path::Path)>,
+    audio_filter: Option<&str>,
+    frame: Option<&OutputFrame>,
+    palette_path: &std::path::Path,
+    tmp_path: &std::path::Path,
+) -> Vec<Vec<String>> {
+    let tmp = tmp_path.to_string_lossy().to_string();
+    let mut base = vec!["-y".to_string()];
+    base.extend(input_args.iter().cloned());
+    // Framed, then burned, so text sits in the project frame and loop exports
+    // scale it along with the picture
+    let burn = match subtitles {
+        Some((SubtitleMode::Burn, path)) => Some(subtitles_filter(path)),
+        _ => None,
+    };
+    let video_filter = [frame.map(OutputFrame::filter), burn]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(",");
+    let loop_filters = |fps: &u32, width: &u32| {
+        let scaled = format!("fps={},scale={}:-1:flags=lanczos", fps, width);
+        if video_filter.is_empty() {
+            scaled
+        } else {
+            format!("{},{}", video_filter, scaled)
+        }
+    };
+    // loudnorm upsamples internally; pin the output rate back down
+    let audio_args: Vec<String> = match audio_filter {
+        Some(filter) => vec!["-af".to_string(), filter.to_string(), "-ar".to_string(), "48000".to_string()],
+        None => Vec::new(),
+    };
+
+    match format {
+        ExportFormat::Mp4 => {
+            let mut args = base;
+            if let Some((SubtitleMode::Mux, path)) = subtitles {
+                args.extend([
+                    "-i".to_string(), path.to_string_lossy().to_string(),
+                    "-map".to_string(), "0:v?".to_string(),
+                    "-map".to_string(), "0:a?".to_string(),
+                    "-map".to_string(), "1:0".to_string(),
+                    "-c:s".to_string(), "mov_text".to_string(),
+                ]);
+            }
+            if !video_filter.is_empty() {
+                args.extend(["-vf".to_string(), video_filter.clone()]);
+            }
+            args.extend([
+                "-c:v", "libx264",
+                "-crf", "23",
+                "-preset", "fast",
+                // Stills decode to yuvj444p etc.; keep the output playable everywhere
+                "-pix_fmt", "yuv420p",
+                "-c:a", "aac",
+                "-b:a", "128k",
+            ].iter().map(|s| s.to_string()));
+            args.extend(audio_args);
+            // The .tmp extension hides the container from ffmpeg, so name it explicitly.
+            args.extend(["-f".to_string(), EXPORT_CONTAINER.to_string(), tmp]);
+            vec![args]
+        }
+        ExportFormat::Gif { fps, width } => {
+            let filters = loop_filters(fps, width);
+            let palette = palette_path.to_string_lossy().to_string();
+
+            let mut pass1 = base.clone();
+            pass1.extend([
+                "-vf".to_string(), format!("{},palettegen=stats_mode=diff", filters),
+                "-an".to_string(),
+                palette.clone(),
+            ]);
+
+            let mut pass2 = base;
+            pass2.extend([
+                "-i".to_string(), palette,
+                "-lavfi".to_string(), format!("{}[x];[x][1:v]paletteuse=dither=sierra2_4a", filters),
+                "-an".to_string(),
+                "-loop".to_string(), "0".to_string(),
+                "-f".to_string(), "gif".to_string(),
+                tmp,
+            ]);
+            vec![pass1, pass2]
+        }
+        ExportFormat::Webp { fps, width } => {
+            let mut args = base;
+            args.extend([
+                "-vf".to_string(), loop_filters(fps, width),
+                "-c:v".to_string(), "libwebp".to_string(),
+                "-quality".to_string(), "75".to_string(),
+                "-an".to_string(),
+                "-loop".to_string(), "0".to_string(),
+                "-f".to_string(), "webp".to_string(),
+                tmp,
+            ]);
+            vec![args]
+        }
+        ExportFormat::Mp3 | ExportFormat::M4a => {
+            let (codec, muxer) = match format {
+                ExportFormat::Mp3 => ("libmp3lame", "mp3"),
+                // ffmpeg's muxer for .m4a is "ipod"
+                _ => ("aac", "ipod"),
+            };
+            let mut args = base;
+            args.extend(["-vn", "-c:a", codec, "-b:a", "192k"].iter().map(|s| s.to_string()));
+            args.extend(audio_args);
+            args.extend(["-f".to_string(), muxer.to_string(), tmp]);
+            vec![args]
+        }
+    }
+}
+
+/// Copy the head of a failed download to `cache/gen/{task_id}.error.txt`,
+/// redacted, so a login page or JSON error served in place of the video can be
+/// inspected. Returns the `TaskError.detail` pointing at it, if anything was saved.
+fn save_error_artifact(
+    layout: &crate::project::layout::WorkspaceLayout,
+    file_path: &std::path::Path,
+    task_id: &str,
+) -> Option<String> {
+    let bytes = std::fs::read(file_path).ok().filter(|b| !b.is_empty())?;
+    let name = format!("{}.error.txt", task_id);
+    let path = layout.dir(WorkspaceSubdir::Generated).join(&name);
+    if let Err(e) = std::fs::write(&path, error_artifact_text(&bytes)) {
+        log::warn!("Failed to save download artifact {}: {}", path.display(), e);
+        return None;
+    }
+    Some(format!(
+        "First bytes of the response saved to {}",
+        layout.stored_path(WorkspaceSubdir::Generated, &name)
+    ))
+}
+
+fn error_artifact_text(bytes: &[u8]) -> String {
+    let head = &bytes[..bytes.len().min(crate::task::download::ERROR_BODY_BYTES)];
+    crate::provider::redact::redact(&String::from_utf8_lossy(head))
+}
+
+fn ms_to_secs(ms: i64) -> String {
+    format!("{:.3}", ms as f64 / 1000.0)
+}
+
+/// Encode the given segments (in order) into `output_path` in `format`.
+/// ffmpeg writes to a `.tmp` sibling which is renamed into place only on
+/// success, so a canceled or failed export never leaves a finished-looking file.
+/// With `loudness_target` the mix is measured first and normalized to it in
+/// the encode; returns the target actually applied (`None` for silent audio).
+/// `extra_args` (see `parse_extra_ffmpeg_args`) go before the output path of
+/// the final pass.
+#[allow(clippy::too_many_arguments)]
+async fn encode_export(
+    segments: &[PlanSegment],
+    format: &ExportFormat,
+    frame: &OutputFrame,
+    subtitles: Option<(SubtitleMode, &std::path::Path)>,
+    loudness_target: Option<f64>,
+    extra_args: &[String],
+    exports_dir: &std::path::Path,
+    output_path: &std::path::Path,
+    timestamp: &str,
+    task_id: &str,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> Result<Option<f64>, TaskError> {
+    let mut input_args: Vec<String> = Vec::new();
+    // Removed on every exit path, including unwinding.
+    let mut _concat_guard = None;
+
+    if segments.len() == 1 && segments[0].is_still() {
+        // Single image: loop it for the clip's length
+        input_args.extend([
+            "-loop".to_string(), "1".to_string(),
+            "-t".to_string(), ms_to_secs(segments[0].timeline_duration_ms()),
+        ]);
+        input_args.push("-i".to_string());
+        input_args.push(segments[0].path.to_string_lossy().to_string());
+    } else if segments.len() == 1 {
+        // Single clip: transcode, seeking on the input when cut
+        if let Some((in_ms, out_ms)) = segments[0].cut() {
+            input_args.extend(["-ss".to_string(), ms_to_secs(in_ms), "-to".to_string(), ms_to_secs(out_ms)]);
+        }
+        input_args.push("-i".to_string());
+        input_args.push(segments[0].path.to_string_lossy().to_string());
+    } else {
+        // Multiple clips: write concat list and use ffmpeg concat
+        let list_path = exports_dir.join(format!("concat_{}.txt", timestamp));
+        let concat_content = concat_list(segments);
+        _concat_guard = Some(TempFileGuard::new(list_path.clone()));
+        if let Err(e) = std::fs::write(&list_path, &concat_content) {
+            return Err(TaskError::new("io_error", format!("Failed to write concat list: {}", e)));
+        }
+        input_args.extend(["-f", "concat", "-safe", "0", "-i"].iter().map(|s| s.to_string()));
+        input_args.push(list_path.to_string_lossy().to_string());
+    }
+
+    let tmp_path = output_path.with_extension(format!("{}.tmp", format.container()));
+    let mut tmp_guard = TempFileGuard::new(tmp_path.clone());
+    let palette_path = exports_dir.join(format!("palette_{}.png", timestamp));
+    let _palette_guard = TempFileGuard::new(palette_path.clone());
+
+    // Measure the whole mix in one pass so every clip gets the same gain curve
+    let mut audio_filter = None;
+    if let Some(target) = loudness_target {
+        let stderr = run_ffmpeg_stderr(&loudnorm_measure_args(&input_args, target), task_id, state).await?;
+        audio_filter = parse_loudnorm_measurement(&stderr).map(|m| loudnorm_apply_filter(target, &m));
+    }
+
+    // Remux instead of re-encoding when the source already matches the target.
+    // If the copy fails (e.g. audio the mp4 muxer won't take) fall back to encoding.
+    let mut copied = false;
+    if subtitles.is_none() && audio_filter.is_none() && extra_args.is_empty() && can_stream_copy(segments, format, frame) {
+        match run_ffmpeg(&stream_copy_args(&input_args, &tmp_path), task_id, state).await {
+            Ok(()) => {
+                copied = true;
+                append_task_event(state, task_id, "info", "Sources match the export; streams copied without re-encoding", app_handle).await;
+            }
+            Err(e) if e.code == "ffmpeg_failed" => {
+                log::warn!("Stream copy export failed, re-encoding: {} {}", e.message, e.detail.unwrap_or_default());
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    if !copied {
+        let mut passes = export_passes(format, &input_args, subtitles, audio_filter.as_deref(), Some(frame), &palette_path, &tmp_path);
+        if !extra_args.is_empty() {
+            if let Some(last) = passes.last_mut() {
+                insert_before_output(last, extra_args);
+            }
+        }
+        for pass in passes {
+            if !extra_args.is_empty() {
+                let command = crate::provider::redact::redact(&format!("ffmpeg {}", pass.join(" ")));
+                append_task_event(state, task_id, "info", &command, app_handle).await;
+            }
+            run_ffmpeg(&pass, task_id, state).await?;
+        }
+    }
+
+    std::fs::rename(&tmp_path, output_path)
+        .map_err(|e| TaskError::new("io_error", format!("Failed to finalize export: {}", e)))?;
+    tmp_guard.disarm();
+    Ok(audio_filter.and(loudness_target))
+}
+
+/// Flags that would add inputs, change the muxer or overwrite policy, write
+/// files of their own, or otherwise fight the args the export builds itself.
+/// Filters are blocked too: ffmpeg keeps only the last `-vf`/`-af`, so a user
+/// filter would silently drop the export's framing, subtitles and loudnorm.
+const BLOCKED_FFMPEG_FLAGS: &[&str] = &[
+    "-i", "-f", "-y", "-n", "-vf", "-af", "-filter", "-filter_complex", "-lavfi",
+    "-map", "-progress", "-filter_script", "-filter_complex_script", "-attach",
+    "-dump_attachment", "-vstats_file", "-passlogfile",
+];
+
+/// Flags allowed in `extraFfmpegArgs` that take the next token as their
+/// value. Matched without a stream specifier (`-b:v` is `-b`). Output
+/// options only: they are spliced in front of the output path.
+const VALUE_FFMPEG_FLAGS: &[&str] = &[
+    "-tune", "-preset", "-crf", "-qp", "-q", "-qscale", "-g", "-keyint_min",
+    "-sc_threshold", "-bf", "-refs", "-b", "-maxrate", "-minrate", "-bufsize",
+    "-profile", "-level", "-pix_fmt", "-x264-params", "-x264opts", "-x265-params",
+    "-r", "-ac", "-ar", "-c", "-codec", "-metadata",
+    "-threads", "-movflags", "-aspect", "-color_primaries", "-colorspace",
+    "-color_trc", "-color_range", "-tag", "-bsf", "-fps_mode", "-vsync",
+];
+
+/// Flags allowed in `extraFfmpegArgs` that take no value.
+const SWITCH_FFMPEG_FLAGS: &[&str] = &["-an", "-vn", "-sn", "-dn", "-shortest"];
+
+/// Optional `extraFfmpegArgs`: encoder knobs (`-tune film`, `-g 48`, ...)
+/// appended to the export command. Only allowlisted flags pass; a value is
+/// taken only after a flag known to need one, so any other positional arg (a
+/// second output file) is rejected, as is anything naming a path in
+/// `forbidden_paths`.
+fn parse_extra_ffmpeg_args(
+    input: &serde_json::Value,
+    forbidden_paths: &[&std::path::Path],
+) -> Result<Vec<String>, String> {
+    let args: Vec<String> = match input.get("extraFfmpegArgs") {
+        None | Some(serde_json::Value::Null) => return Ok(Vec::new()),
+        Some(v) => serde_json::from_value(v.clone()).map_err(|_| "extraFfmpegArgs must be an array of strings")?,
+    };
+    let forbidden: Vec<String> = forbidden_paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+    let mut value_for: Option<&str> = None;
+    for arg in &args {
+        if arg.is_empty() || arg.chars().any(|c| c.is_control()) {
+            return Err(format!("Invalid ffmpeg argument {:?}", arg));
+        }
+        if forbidden.iter().any(|p| arg.contains(p.as_str())) {
+            return Err(format!("ffmpeg argument {:?} names an export path", arg));
+        }
+        if value_for.take().is_some() {
+            continue;
+        }
+        if !arg.starts_with('-') {
+            return Err(format!("ffmpeg argument {:?} is not a flag value", arg));
+        }
+        let base = arg.split(':').next().unwrap_or(arg);
+        if BLOCKED_FFMPEG_FLAGS.contains(&base) {
+            return Err(format!("ffmpeg flag {} is not allowed in extraFfmpegArgs", arg));
+        }
+        if VALUE_FFMPEG_FLAGS.contains(&base) {
+            value_for = Some(base);
+        } else if !SWITCH_FFMPEG_FLAGS.contains(&base) {
+            return Err(format!("ffmpeg flag {} is not supported in extraFfmpegArgs", arg));
+        }
+    }
+    if let Some(flag) = value_for {
+        return Err(format!("ffmpeg flag {} needs a value", flag));
+    }
+    Ok(args)
+}
+
+/// Splice `extra` in front of the output path, the last element of `pass`.
+fn insert_before_output(pass: &mut Vec<String>, extra: &[String]) {
+    let at = pass.len().saturating_sub(1);
+    pass.splice(at..at, extra.iter().cloned());
+}
+
+/// Integrated loudness exports normalize to unless `targetLufs` is given.
+const DEFAULT_EXPORT_LUFS: f64 = -14.0;
+const LOUDNORM_TRUE_PEAK: f64 = -1.5;
+const LOUDNORM_LRA: f64 = 11.0;
+
+/// `loudnorm: true` enables normalization; `targetLufs` defaults to -14.
+fn parse_loudness_target(input: &serde_json::Value) -> Result<Option<f64>, String> {
+    if !input.get("loudnorm").and_then(|v| v.as_bool()).unwrap_or(false) {
+        return Ok(None);
+    }
+    let target = match input.get("targetLufs") {
+        None | Some(serde_json::Value::Null) => DEFAULT_EXPORT_LUFS,
+        Some(v) => v.as_f64().ok_or("targetLufs must be a number")?,
+    };
+    if !(-70.0..=-5.0).contains(&target) {
+        return Err(format!("targetLufs must be between -70 and -5, got {}", target));
+    }
+    Ok(Some(target))
+}
+
+/// Values loudnorm's first pass prints for the second, linear pass.
+#[derive(Debug, Clone, PartialEq)]
+struct LoudnormMeasurement {
+    input_i: f64,
+    input_tp: f64,
+    input_lra: f64,
+    input_thresh: f64,
+    target_offset: f64,
+}
+
+/// Analysis-only pass: decode the audio through loudnorm and discard it.
+fn loudnorm_measure_args(input_args: &[String], target: f64) -> Vec<String> {
+    let mut args = vec!["-y".to_string()];
+    args.extend(input_args.iter().cloned());
+    args.extend([
+        "-vn".to_string(),
+        "-af".to_string(),
+        format!("loudnorm=I={}:TP={}:LRA={}:print_format=json", target, LOUDNORM_TRUE_PEAK, LOUDNORM_LRA),
+        "-f".to_string(), "null".to_string(),
+        "-".to_string(),
+    ]);
+    args
+}
+
+/// The JSON block loudnorm prints at the end of stderr. `None` when it is
+/// missing or the input is silent (`-inf`), which loudnorm can't normalize.
+fn parse_loudnorm_measurement(stderr: &str) -> Option<LoudnormMeasurement> {
+    let start = stderr.rfind('{')?;
+    let end = start + stderr[start..].find('}')?;
+    let json: serde_json::Value = serde_json::from_str(&stderr[start..=end]).ok()?;
+    let field = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .filter(|v| v.is_finite())
+    };
+    Some(LoudnormMeasurement {
+        input_i: field("input_i")?,
+        input_tp: field("input_tp")?,
+        input_lra: field("input_lra")?,
+        input_thresh: field("input_thresh")?,
+        target_offset: field("target_offset")?,
+    })
+}
+
+fn loudnorm_apply_filter(target: f64, m: &LoudnormMeasurement) -> String {
+    format!(
+        "loudnorm=I={}:TP={}:LRA={}:measured_I={}:measured_TP={}:measured_LRA={}:measured_thresh={}:offset={}:linear=true",
+        target, LOUDNORM_TRUE_PEAK, LOUDNORM_LRA, m.input_i, m.input_tp, m.input_lra, m.input_thresh, m.target_offset,
+    )
+}
+
+/// Assumed rate for stills held on screen, which have no source bitrate.
+const STILL_ESTIMATE_BYTES_PER_SEC: u64 = 1024 * 1024;
+
+/// Rough size of the encoded export: each source's share of its file size
+/// for the part that is used, stills at `STILL_ESTIMATE_BYTES_PER_SEC`.
+/// Only used to check free space up front.
+fn export_size_estimate(segments: &[PlanSegment]) -> u64 {
+    segments
+        .iter()
+        .map(|s| {
+            if s.is_still() {
+                return s.timeline_duration_ms().max(0) as u64 * STILL_ESTIMATE_BYTES_PER_SEC / 1000;
+            }
+            let len = std::fs::metadata(&s.path).map(|m| m.len()).unwrap_or(0);
+            match (s.cut(), s.source_duration_ms) {
+                (Some((in_ms, out_ms)), Some(total)) => {
+                    (len as f64 * ((out_ms - in_ms).max(0) as f64 / total as f64).min(1.0)) as u64
+                }
+                _ => len,
+            }
+        })
+        .sum()
+}
+
+/// Whole constant-frame-rate clips that are already h264 in an mp4/mov
+/// container at the project frame size can be remuxed into an mp4 export
+/// without re-encoding. Several clips are joined by the concat demuxer, which
+/// needs them to share frame rate and audio layout (sample rate and channels)
+/// too; ffmpeg doesn't fail on a mismatch, it writes broken audio. Cut clips
+/// would snap to keyframes, so they always re-encode.
+fn can_stream_copy(segments: &[PlanSegment], format: &ExportFormat, frame: &OutputFrame) -> bool {
+    let Some(first) = segments.first() else { return false };
+    *format == ExportFormat::Mp4
+        && segments.iter().all(|seg| {
+            !seg.is_still()
+                && !seg.vfr
+                && seg.cut().is_none()
+                && seg.dimensions == Some((frame.width, frame.height))
+                && seg.codec.as_deref() == Some(EXPORT_VIDEO_CODEC)
+                && seg
+                    .container
+                    .as_deref()
+                    .is_some_and(|c| c.split(',').any(|name| name == EXPORT_CONTAINER))
+                && seg.has_audio == first.has_audio
+        })
+        && (segments.len() == 1
+            || segments.iter().all(|seg| {
+                seg.fps.is_some()
+                    && seg.fps == first.fps
+                    && (!seg.has_audio || seg.audio_layout.is_some())
+                    && seg.audio_layout == first.audio_layout
+            }))
+}
+
+fn stream_copy_args(input_args: &[String], tmp_path: &std::path::Path) -> Vec<String> {
+    let mut args = vec!["-y".to_string()];
+    args.extend(input_args.iter().cloned());
+    args.extend([
+        "-c", "copy",
+        "-movflags", "+faststart",
+        "-f", EXPORT_CONTAINER,
+    ].iter().map(|s| s.to_string()));
+    args.push(tmp_path.to_string_lossy().to_string());
+    args
+}
+
+/// ffmpeg concat demuxer script; cut segments get inpoint/outpoint directives
+/// and stills get a `duration` equal to their timeline length.
+fn concat_list(segments: &[PlanSegment]) -> String {
+    let mut content = String::new();
+    for seg in segments {
+        let escaped = seg.path.to_string_lossy().replace('\'', "'\\''");
+        content.push_str(&format!("file '{}'\n", escaped));
+        if seg.is_still() {
+            content.push_str(&format!("duration {}\n", ms_to_secs(seg.timeline_duration_ms())));
+        } else if let Some((in_ms, out_ms)) = seg.cut() {
+            content.push_str(&format!("inpoint {}\noutpoint {}\n", ms_to_secs(in_ms), ms_to_secs(out_ms)));
+        }
+    }
+    // The demuxer ignores the last entry's duration unless the file is repeated
+    if let Some(last) = segments.last().filter(|s| s.is_still()) {
+        let escaped = last.path.to_string_lossy().replace('\'', "'\\''");
+        content.push_str(&format!("file '{}'\n", escaped));
+    }
+    content
+}
+
+/// Deletes a file when dropped unless `disarm` was called.
+pub(crate) struct TempFileGuard {
+    path: std::path::PathBuf,
+    armed: bool,
+}
+
+impl TempFileGuard {
+    pub(crate) fn new(path: std::path::PathBuf) -> Self {
+        Self { path, armed: true }
+    }
+
+    pub(crate) fn disarm(&mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for TempFileGuard {
+    fn drop(&mut self) {
+        if self.armed {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+const CANCEL_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Removes a child from `AppState::children` however `run_ffmpeg` returns.
+struct ChildRegistration<'a> {
+    task_id: &'a str,
+    state: &'a Arc<AppState>,
+}
+
+impl Drop for ChildRegistration<'_> {
+    fn drop(&mut self) {
+        self.state.unregister_child(self.task_id);
+    }
+}
+
+/// Run ffmpeg to completion, killing it if the task's cancel flag is set
+/// or the app is shutting down.
+pub(crate) async fn run_ffmpeg(args: &[String], task_id: &str, state: &Arc<AppState>) -> Result<(), TaskError> {
+    run_ffmpeg_stderr(args, task_id, state).await.map(|_| ())
+}
+
+/// `run_ffmpeg`, returning ffmpeg's stderr on success (filters such as
+/// `loudnorm` report their measurements there).
+async fn run_ffmpeg_stderr(args: &[String], task_id: &str, state: &Arc<AppState>) -> Result<String, TaskError> {
+    let mut child = Command::new(crate::paths::ffmpeg())
+        .args(args)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| TaskError {
+            code: "ffmpeg_spawn_failed".to_string(),
+            message: format!("Failed to start ffmpeg: {}", e),
+            detail: Some(FFMPEG_INSTALL_HINT.to_string()),
+        })?;
+
+    if let Some(pid) = child.id() {
+        state.register_child(task_id, pid);
+    }
+    let _registration = ChildRegistration { task_id, state };
+
+    // Drain stderr concurrently so a chatty ffmpeg can't block on a full pipe.
+    let stderr_reader = child.stderr.take().map(|mut stderr| {
+        tokio::spawn(async move {
+            use tokio::io::AsyncReadExt;
+            let mut buf = Vec::new();
+            let _ = stderr.read_to_end(&mut buf).await;
+            buf
+        })
+    });
+
+    let status = loop {
+        tokio::select! {
+            res = child.wait() => {
+                break res.map_err(|e| TaskError::new("ffmpeg_wait_failed", format!("ffmpeg process error: {}", e)))?;
+            }
+            _ = tokio::time::sleep(CANCEL_POLL_INTERVAL) => {
+                if state.cancel_flags.lock().await.contains(task_id) {
+                    let _ = child.kill().await;
+                    return Err(TaskError::new("canceled", "Canceled while running ffmpeg"));
+                }
+                if state.is_shutting_down() {
+                    let _ = child.kill().await;
+                    return Err(TaskError::new("interrupted", "App shut down while running ffmpeg"));
+                }
+            }
+        }
+    };
+
+    let stderr = match stderr_reader {
+        Some(h) => h.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let stderr = String::from_utf8_lossy(&stderr).into_owned();
+    if !status.success() {
+        return Err(TaskError {
+            code: "ffmpeg_failed".to_string(),
+            message: format!("ffmpeg exited with code {:?}", status.code()),
+            detail: Some(stderr_tail(&stderr, STDERR_DETAIL_CHARS).to_string()),
+        });
+    }
+    Ok(stderr)
+}
+
+pub(crate) const FFMPEG_INSTALL_HINT: &str = "Ensure ffmpeg is installed and in PATH, or set CUTLINE_FFMPEG";
+/// Stderr kept in an ffmpeg error's detail.
+pub(crate) const STDERR_DETAIL_CHARS: usize = 2048;
+
+/// The last `max_chars` chars of ffmpeg's stderr, where the error is (the
+/// banner comes first). Cut on a char boundary: localized messages and
+/// non-ASCII paths are multibyte.
+pub(crate) fn stderr_tail(stderr: &str, max_chars: usize) -> &str {
+    match stderr.char_indices().rev().nth(max_chars.saturating_sub(1)) {
+        Some((i, _)) if max_chars > 0 => &stderr[i..],
+        Some(_) => "",
+        None => stderr,
+    }
+}
+
+/// Copy the produced resolution, codec and duration from ffprobe output into
+/// `record`. Loop exports (GIF/WebP) report no meaningful overall bitrate.
+fn record_output_probe(record: &mut crate::project::model::ExportRecord, probe: &serde_json::Value, with_bitrate: bool) {
+    let meta = crate::media::probe::extract_video_meta(probe);
+    let dim = |key: &str| meta.get(key).and_then(|v| v.as_u64()).filter(|&v| v > 0).map(|v| v as u32);
+    record.width = dim("width");
+    record.height = dim("height");
+    record.codec = meta.get("codec").and_then(|c| c.as_str()).map(String::from);
+    record.duration_ms = crate::project::model::meta_duration_ms(&meta);
+    if with_bitrate {
+        record.preset.bitrate_kbps = probe
+            .get("format")
+            .and_then(|f| f.get("bit_rate"))
+            .and_then(|b| b.as_str())
+            .and_then(|b| b.parse::<u64>().ok())
+            .map(|bps| (bps / 1000) as u32)
+            .unwrap_or(0);
+    }
+}
+
+async fn push_export_record(state: &Arc<AppState>, record: crate::project::model::ExportRecord) {
+    let mut guard = state.inner.lock().await;
+    if let Some(loaded) = guard.as_mut() {
+        loaded.project.exports.push(record);
+        loaded.dirty = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn stderr_tail_cuts_on_char_boundaries() {
+        let stderr = format!("{}无法打开文件 /素材/视频.mp4", "x".repeat(600));
+        assert_eq!(stderr_tail(&stderr, 12), "件 /素材/视频.mp4");
+        assert_eq!(stderr_tail("short", 2048), "short");
+        assert_eq!(stderr_tail("abc", 0), "");
+    }
+
+    const FRAME_1080P: OutputFrame = OutputFrame { width: 1920, height: 1080, mode: ScaleMode::Fit };
+
+    fn segment(path: &str, cut: Option<(i64, i64)>) -> PlanSegment {
+        PlanSegment {
+            clip_id: "clip_x".to_string(),
+            asset_id: "ast_x".to_string(),
+            asset_type: "video".to_string(),
+            codec: Some("h264".to_string()),
+            container: Some("mov,mp4,m4a,3gp,3g2,mj2".to_string()),
+            dimensions: Some((1920, 1080)),
+            fps: Some(30.0),
+            path: path.into(),
+            timeline_start_ms: 0,
+            timeline_end_ms: 0,
+            source_in_ms: cut.map(|c| c.0),
+            source_out_ms: cut.map(|c| c.1),
+            source_duration_ms: None,
+            has_audio: true,
+            audio_layout: Some((48_000, 2)),
+            vfr: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn sleep_unless_canceled_returns_early_on_cancel() {
+        let state = AppState::new();
+        sleep_unless_canceled(std::time::Duration::from_millis(10), "task_a", &state)
+            .await
+            .unwrap();
+
+        state.cancel_flags.lock().await.insert("task_a".to_string());
+        let started = std::time::Instant::now();
+        let err = sleep_unless_canceled(std::time::Duration::from_secs(30), "task_a", &state)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, "canceled");
+        assert!(started.elapsed() < std::time::Duration::from_secs(1));
+    }
+
+    #[test]
+    fn concat_list_writes_in_and_out_points() {
+        let segments = vec![
+            segment("/a.mp4", Some((1500, 2000))),
+            segment("/b.mp4", None),
+        ];
+        assert_eq!(
+            concat_list(&segments),
+            "file '/a.mp4'\ninpoint 1.500\noutpoint 2.000\nfile '/b.mp4'\n"
+        );
+    }
+
+    #[test]
+    fn export_estimate_scales_cut_sources_and_prices_stills() {
+        let dir = std::env::temp_dir().join(format!("cutline_estimate_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let video = dir.join("a.mp4");
+        std::fs::write(&video, vec![0u8; 10_000]).unwrap();
+        let path = video.to_str().unwrap();
+
+        let whole = segment(path, None);
+        let quarter = PlanSegment { source_duration_ms: Some(8000), ..segment(path, Some((2000, 4000))) };
+        let unknown_length = segment(path, Some((2000, 4000)));
+        let still = PlanSegment {
+            asset_type: "image".to_string(),
+            timeline_start_ms: 0,
+            timeline_end_ms: 2000,
+            ..segment("/missing.png", None)
+        };
+        assert_eq!(export_size_estimate(&[whole]), 10_000);
+        assert_eq!(export_size_estimate(&[quarter]), 2500);
+        assert_eq!(export_size_estimate(&[unknown_length]), 10_000);
+        assert_eq!(export_size_estimate(&[still]), 2 * STILL_ESTIMATE_BYTES_PER_SEC);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn concat_list_holds_stills_for_their_timeline_length() {
+        let still = |path: &str, start: i64, end: i64| PlanSegment {
+            asset_type: "image".to_string(),
+            timeline_start_ms: start,
+            timeline_end_ms: end,
+            ..segment(path, None)
+        };
+        let segments = vec![still("/a.png", 0, 2000), still("/b.png", 2000, 2500)];
+        assert_eq!(
+            concat_list(&segments),
+            "file '/a.png'\nduration 2.000\nfile '/b.png'\nduration 0.500\nfile '/b.png'\n"
+        );
+
+        // A mixed video track: the still is held between the clips
+        let mixed = vec![segment("/a.mp4", None), still("/b.png", 0, 1000), segment("/c.mp4", None)];
+        assert_eq!(concat_list(&mixed), "file '/a.mp4'\nfile '/b.png'\nduration 1.000\nfile '/c.mp4'\n");
+    }
+
+    #[test]
+    fn stream_copy_only_for_whole_h264_mp4_at_frame_size() {
+        let whole = segment("/a.mp4", None);
+        assert!(can_stream_copy(std::slice::from_ref(&whole), &ExportFormat::Mp4, &FRAME_1080P));
+        assert_eq!(
+            stream_copy_args(&["-i".to_string(), "/a.mp4".to_string()], Path::new("/o.tmp")),
+            vec!["-y", "-i", "/a.mp4", "-c", "copy", "-movflags", "+faststart", "-f", "mp4", "/o.tmp"]
+        );
+
+        // Trimmed clips, other formats or codecs all re-encode
+        assert!(!can_stream_copy(&[segment("/a.mp4", Some((0, 1000)))], &ExportFormat::Mp4, &FRAME_1080P));
+        assert!(!can_stream_copy(std::slice::from_ref(&whole), &ExportFormat::Webp { fps: 15, width: 480 }, &FRAME_1080P));
+        let hevc = PlanSegment { codec: Some("hevc".to_string()), ..whole.clone() };
+        assert!(!can_stream_copy(&[hevc], &ExportFormat::Mp4, &FRAME_1080P));
+        let webm = PlanSegment { container: Some("matroska,webm".to_string()), ..whole.clone() };
+        assert!(!can_stream_copy(&[webm], &ExportFormat::Mp4, &FRAME_1080P));
+        // A source of another size has to be fitted into the project frame
+        let vertical = PlanSegment { dimensions: Some((1080, 1920)), ..whole };
+        assert!(!can_stream_copy(&[vertical], &ExportFormat::Mp4, &FRAME_1080P));
+        let vfr = PlanSegment { vfr: true, ..segment("/phone.mp4", None) };
+        assert!(!can_stream_copy(&[vfr], &ExportFormat::Mp4, &FRAME_1080P));
+    }
+
+    #[test]
+    fn uniform_clips_concat_by_stream_copy() {
+        let a = segment("/a.mp4", None);
+        let b = segment("/b.mp4", None);
+        assert!(can_stream_copy(&[a.clone(), b.clone()], &ExportFormat::Mp4, &FRAME_1080P));
+
+        // Any mismatch in frame rate, audio layout or trim falls back to re-encoding
+        let b_25fps = PlanSegment { fps: Some(25.0), ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_25fps], &ExportFormat::Mp4, &FRAME_1080P));
+        let b_unknown_fps = PlanSegment { fps: None, ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_unknown_fps], &ExportFormat::Mp4, &FRAME_1080P));
+        let b_silent = PlanSegment { has_audio: false, ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_silent], &ExportFormat::Mp4, &FRAME_1080P));
+        let b_44k = PlanSegment { audio_layout: Some((44_100, 2)), ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_44k], &ExportFormat::Mp4, &FRAME_1080P));
+        let b_mono = PlanSegment { audio_layout: Some((48_000, 1)), ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_mono], &ExportFormat::Mp4, &FRAME_1080P));
+        let b_unknown_layout = PlanSegment { audio_layout: None, ..b.clone() };
+        assert!(!can_stream_copy(&[a.clone(), b_unknown_layout], &ExportFormat::Mp4, &FRAME_1080P));
+        assert!(!can_stream_copy(&[a, segment("/b.mp4", Some((0, 500)))], &ExportFormat::Mp4, &FRAME_1080P));
+    }
+
+    #[test]
+    fn exports_fit_or_fill_the_project_frame() {
+        let vertical = OutputFrame { width: 1080, height: 1920, mode: ScaleMode::Fit };
+        assert_eq!(
+            vertical.filter(),
+            "scale=1080:1920:force_original_aspect_ratio=decrease,pad=1080:1920:(ow-iw)/2:(oh-ih)/2,setsar=1"
+        );
+        let fill = OutputFrame { mode: ScaleMode::Fill, ..vertical };
+        assert_eq!(fill.filter(), "scale=1080:1920:force_original_aspect_ratio=increase,crop=1080:1920,setsar=1");
+
+        let input = vec!["-i".to_string(), "/in.mp4".to_string()];
+        let subs = Path::new("/out/subs.srt");
+        let mp4 = export_passes(&ExportFormat::Mp4, &input, Some((SubtitleMode::Burn, subs)), None, Some(&fill), Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(mp4[0].join(" ").contains("-vf scale=1080:1920:force_original_aspect_ratio=increase,crop=1080:1920,setsar=1,subtitles="));
+        let gif = export_passes(&ExportFormat::Gif { fps: 12, width: 400 }, &input, None, None, Some(&vertical), Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(gif.iter().all(|pass| pass.join(" ").contains("setsar=1,fps=12,scale=400:-1")));
+        let mp3 = export_passes(&ExportFormat::Mp3, &input, None, None, Some(&vertical), Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(!mp3[0].iter().any(|a| a == "-vf"));
+
+        // Proxies keep the project aspect with the long side at the proxy width
+        assert_eq!((vertical.scaled_to(960).width, vertical.scaled_to(960).height), (540, 960));
+        assert_eq!((FRAME_1080P.scaled_to(960).width, FRAME_1080P.scaled_to(960).height), (960, 540));
+    }
+
+    #[test]
+    fn export_format_parses_and_validates() {
+        let parse = |v: serde_json::Value| ExportFormat::from_input(&v, "video");
+        assert_eq!(parse(serde_json::json!({})).unwrap(), ExportFormat::Mp4);
+        assert_eq!(
+            parse(serde_json::json!({ "format": "gif", "fps": 10, "width": 320 })).unwrap(),
+            ExportFormat::Gif { fps: 10, width: 320 }
+        );
+        assert!(parse(serde_json::json!({ "format": "avi" })).is_err());
+        assert!(parse(serde_json::json!({ "format": "gif", "fps": 0 })).is_err());
+    }
+
+    #[test]
+    fn audio_tracks_export_audio_formats_only() {
+        let parse = |v: serde_json::Value| ExportFormat::from_input(&v, "audio");
+        assert_eq!(parse(serde_json::json!({})).unwrap(), ExportFormat::M4a);
+        assert_eq!(parse(serde_json::json!({ "format": "mp3" })).unwrap(), ExportFormat::Mp3);
+        assert!(parse(serde_json::json!({ "format": "mp4" })).is_err());
+        assert!(parse(serde_json::json!({ "format": "gif" })).is_err());
+    }
+
+    #[test]
+    fn audio_exports_drop_video() {
+        let input = vec!["-i".to_string(), "/in.wav".to_string()];
+        let mp3 = export_passes(&ExportFormat::Mp3, &input, None, None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert_eq!(mp3, vec![vec![
+            "-y", "-i", "/in.wav", "-vn", "-c:a", "libmp3lame", "-b:a", "192k", "-f", "mp3", "/o.tmp",
+        ]]);
+        let m4a = export_passes(&ExportFormat::M4a, &input, None, None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(m4a[0].join(" ").contains("-vn -c:a aac -b:a 192k -f ipod"));
+        assert_eq!((ExportFormat::M4a.container(), ExportFormat::M4a.codec()), ("m4a", "aac"));
+    }
+
+    #[test]
+    fn gif_export_builds_two_pass_palette_commands() {
+        let input = vec!["-i".to_string(), "/in.mp4".to_string()];
+        let passes = export_passes(
+            &ExportFormat::Gif { fps: 12, width: 400 },
+            &input,
+            None,
+            None,
+            None,
+            Path::new("/out/palette.png"),
+            Path::new("/out/export.gif.tmp"),
+        );
+        assert_eq!(passes.len(), 2);
+
+        assert_eq!(passes[0], vec![
+            "-y", "-i", "/in.mp4",
+            "-vf", "fps=12,scale=400:-1:flags=lanczos,palettegen=stats_mode=diff",
+            "-an", "/out/palette.png",
+        ]);
+        assert_eq!(passes[1], vec![
+            "-y", "-i", "/in.mp4",
+            "-i", "/out/palette.png",
+            "-lavfi", "fps=12,scale=400:-1:flags=lanczos[x];[x][1:v]paletteuse=dither=sierra2_4a",
+            "-an", "-loop", "0", "-f", "gif", "/out/export.gif.tmp",
+        ]);
+    }
+
+    #[test]
+    fn loop_exports_drop_audio() {
+        let input = vec!["-i".to_string(), "/in.mp4".to_string()];
+        for format in [ExportFormat::Gif { fps: 15, width: 480 }, ExportFormat::Webp { fps: 15, width: 480 }] {
+            for pass in export_passes(&format, &input, None, None, None, Path::new("/p.png"), Path::new("/o.tmp")) {
+                assert!(pass.iter().any(|a| a == "-an"));
+                assert!(!pass.iter().any(|a| a == "-c:a" || a == "-b:a"));
+            }
+        }
+        let mp4 = export_passes(&ExportFormat::Mp4, &input, None, None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert_eq!(mp4.len(), 1);
+        assert!(mp4[0].iter().any(|a| a == "aac"));
+    }
+
+    #[test]
+    fn subtitles_burn_or_mux_into_exports() {
+        let input = vec!["-i".to_string(), "/in.mp4".to_string()];
+        let subs = Path::new("/out/subs.srt");
+        let burn = export_passes(&ExportFormat::Mp4, &input, Some((SubtitleMode::Burn, subs)), None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(burn[0].join(" ").contains("-vf subtitles='/out/subs.srt' -c:v libx264"));
+
+        let mux = export_passes(&ExportFormat::Mp4, &input, Some((SubtitleMode::Mux, subs)), None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(mux[0].join(" ").contains("-i /out/subs.srt -map 0:v? -map 0:a? -map 1:0 -c:s mov_text"));
+
+        let gif = export_passes(&ExportFormat::Gif { fps: 12, width: 400 }, &input, Some((SubtitleMode::Burn, subs)), None, None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(gif.iter().all(|pass| pass.join(" ").contains("subtitles='/out/subs.srt',fps=12,scale=400")));
+
+        assert_eq!(subtitles_filter(Path::new("C:\\a'b\\s.srt")), "subtitles='C\\:/a'\\\\\\''b/s.srt'");
+        assert!(SubtitleMode::Mux.check_format(&ExportFormat::Webp { fps: 15, width: 480 }).is_err());
+        assert!(SubtitleMode::Burn.check_format(&ExportFormat::Mp3).is_err());
+        assert!(SubtitleMode::Burn.check_format(&ExportFormat::Gif { fps: 15, width: 480 }).is_ok());
+        assert_eq!(SubtitleMode::from_input(&serde_json::json!({})).unwrap(), None);
+        assert!(SubtitleMode::from_input(&serde_json::json!({ "subtitles": "soft" })).is_err());
+    }
+
+    #[test]
+    fn loudnorm_measures_then_applies_linear_pass() {
+        let input = vec!["-i".to_string(), "/in.mp4".to_string()];
+        assert_eq!(loudnorm_measure_args(&input, -14.0), vec![
+            "-y", "-i", "/in.mp4", "-vn",
+            "-af", "loudnorm=I=-14:TP=-1.5:LRA=11:print_format=json",
+            "-f", "null", "-",
+        ]);
+
+        let stderr = "size=N/A time=00:00:05.00\n[Parsed_loudnorm_0 @ 0x1]\n{\n\t\"input_i\" : \"-23.45\",\n\t\"input_tp\" : \"-5.10\",\n\t\"input_lra\" : \"6.40\",\n\t\"input_thresh\" : \"-33.78\",\n\t\"output_i\" : \"-14.02\",\n\t\"normalization_type\" : \"dynamic\",\n\t\"target_offset\" : \"0.43\"\n}\n";
+        let m = parse_loudnorm_measurement(stderr).unwrap();
+        assert_eq!(m, LoudnormMeasurement {
+            input_i: -23.45, input_tp: -5.1, input_lra: 6.4, input_thresh: -33.78, target_offset: 0.43,
+        });
+        let filter = loudnorm_apply_filter(-14.0, &m);
+        assert_eq!(filter, "loudnorm=I=-14:TP=-1.5:LRA=11:measured_I=-23.45:measured_TP=-5.1:measured_LRA=6.4:measured_thresh=-33.78:offset=0.43:linear=true");
+
+        let silent = stderr.replace("\"-23.45\"", "\"-inf\"");
+        assert_eq!(parse_loudnorm_measurement(&silent), None);
+        assert_eq!(parse_loudnorm_measurement("no json here"), None);
+
+        let mp3 = export_passes(&ExportFormat::Mp3, &input, None, Some(&filter), None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(mp3[0].join(" ").contains(&format!("-b:a 192k -af {} -ar 48000 -f mp3", filter)));
+        let mp4 = export_passes(&ExportFormat::Mp4, &input, None, Some(&filter), None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(mp4[0].join(" ").contains("-b:a 128k -af loudnorm="));
+        let gif = export_passes(&ExportFormat::Gif { fps: 12, width: 400 }, &input, None, Some(&filter), None, Path::new("/p.png"), Path::new("/o.tmp"));
+        assert!(gif.iter().all(|pass| !pass.iter().any(|a| a == "-af")));
+    }
+
+    #[test]
+    fn remux_copies_streams_or_transcodes_into_faststart_mp4() {
+        let copy = remux_args(Path::new("/a/in.mkv"), Path::new("/p/out.mp4.tmp"), true);
+        assert_eq!(copy, vec![
+            "-y", "-i", "/a/in.mkv", "-map", "0:v:0", "-map", "0:a?",
+            "-c", "copy", "-movflags", "+faststart", "-f", "mp4", "/p/out.mp4.tmp",
+        ]);
+        let transcode = remux_args(Path::new("/a/in.wmv"), Path::new("/p/out.mp4.tmp"), false).join(" ");
+        assert!(transcode.contains("-c:v libx264") && transcode.contains("-c:a aac"));
+        assert!(transcode.ends_with("-movflags +faststart -f mp4 /p/out.mp4.tmp"));
+        assert!(!transcode.contains("-c copy"));
+    }
+
+    #[test]
+    fn waveform_renders_first_audio_stream_as_png() {
+        let args = waveform_args(Path::new("/a/song.mp3"), (1200, 120), Path::new("/t/w.png.tmp"));
+        assert_eq!(args, vec![
+            "-y", "-i", "/a/song.mp3",
+            "-filter_complex", "[0:a:0]aformat=channel_layouts=mono,showwavespic=s=1200x120:colors=0x60a5fa[out]",
+            "-map", "[out]", "-frames:v", "1", "-c:v", "png", "-f", "image2", "/t/w.png.tmp",
+        ]);
+    }
+
+    #[test]
+    fn error_artifact_is_redacted_and_bounded() {
+        let page = b"<html>login required; sessionid=abc123\nAuthorization: Bearer sk-live</html>";
+        let text = error_artifact_text(page);
+        assert!(text.starts_with("<html>login required"));
+        assert!(!text.contains("abc123") && !text.contains("sk-live"));
+
+        let binary = vec![0xffu8; crate::task::download::ERROR_BODY_BYTES * 4];
+        assert!(error_artifact_text(&binary).chars().count() <= crate::task::download::ERROR_BODY_BYTES + 20);
+    }
+
+    #[test]
+    fn extra_ffmpeg_args_are_validated_and_spliced_before_output() {
+        let out = Path::new("/w/exports/export_1.mp4");
+        let parse = |v: serde_json::Value| parse_extra_ffmpeg_args(&v, &[Path::new("/w/exports"), out]);
+
+        assert_eq!(parse(serde_json::json!({})), Ok(vec![]));
+        let args = parse(serde_json::json!({ "extraFfmpegArgs": ["-tune", "film", "-g", "48", "-an", "-sc_threshold", "-1"] })).unwrap();
+        assert_eq!(args.len(), 7);
+        // Input-only: ffmpeg refuses it in front of the output
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-itsoffset", "-0.5"] })).is_err());
+
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-i", "/etc/passwd"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-tune", "film", "/tmp/other.mp4"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["/w/exports/export_1.mp4"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-metadata", "title=/w/exports/x"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-vf", "scale=1:1\n-i"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": "-tune film" })).is_err());
+        // A switch takes no value, so the path after it is a second output
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-an", "/tmp/x.mp4"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-progress", "/tmp/p.txt"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-vf", "movie=/tmp/a.mp4[m];[in][m]overlay"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-filter:a", "AMOVIE=/tmp/a.wav"] })).is_err());
+        // Would replace the export's own -vf/-af chains (framing, subtitles, loudnorm)
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-vf", "eq=contrast=1.1"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-af", "volume=2"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-filter:v", "hflip"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-vstats_file", "/tmp/v"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-unknown_flag", "x"] })).is_err());
+        assert!(parse(serde_json::json!({ "extraFfmpegArgs": ["-crf"] })).is_err());
+        assert_eq!(
+            parse(serde_json::json!({ "extraFfmpegArgs": ["-b:v", "4M", "-metadata", "title=-cut", "-shortest"] })).unwrap().len(),
+            5
+        );
+
+        let mut pass: Vec<String> = ["-y", "-i", "/in.mp4", "-c:v", "libx264", "/o.tmp"].iter().map(|s| s.to_string()).collect();
+        insert_before_output(&mut pass, &["-tune".to_string(), "film".to_string()]);
+        assert_eq!(pass, vec!["-y", "-i", "/in.mp4", "-c:v", "libx264", "-tune", "film", "/o.tmp"]);
+    }
+
+    #[test]
+    fn loudness_target_defaults_and_range() {
+        let parse = |v: serde_json::Value| parse_loudness_target(&v);
+        assert_eq!(parse(serde_json::json!({})), Ok(None));
+        assert_eq!(parse(serde_json::json!({ "loudnorm": false, "targetLufs": -16 })), Ok(None));
+        assert_eq!(parse(serde_json::json!({ "loudnorm": true })), Ok(Some(-14.0)));
+        assert_eq!(parse(serde_json::json!({ "loudnorm": true, "targetLufs": -23 })), Ok(Some(-23.0)));
+        assert!(parse(serde_json::json!({ "loudnorm": true, "targetLufs": 0 })).is_err());
+        assert!(parse(serde_json::json!({ "loudnorm": true, "targetLufs": "loud" })).is_err());
+    }
+
+    #[test]
+    fn vfr_video_proxies_are_converted_to_cfr() {
+        let vfr = serde_json::json!({ "kind": "video", "fps": 60.0, "avgFps": 29.92, "vfr": true });
+        assert_eq!(vfr_target_fps(&vfr), Some(29.92));
+        assert_eq!(vfr_target_fps(&serde_json::json!({ "kind": "video", "fps": 24.0, "vfr": false })), None);
+        assert_eq!(vfr_target_fps(&serde_json::json!({ "kind": "video", "fps": 24.0 })), None);
+
+        let frame = FRAME_1080P.scaled_to(960);
+        let args = video_proxy_args(Path::new("/in/a.mp4"), Path::new("/out/a.mp4"), &frame, 28, Some(29.92), 15);
+        assert!(args.join(" ").contains("-vf scale=960:540:force_original_aspect_ratio=decrease,pad=960:540:(ow-iw)/2:(oh-ih)/2,setsar=1 -vsync cfr -r 29.92 -crf 28"));
+        let plain = video_proxy_args(Path::new("/in/a.mp4"), Path::new("/out/a.mp4"), &frame, 28, None, 15);
+        assert!(!plain.iter().any(|a| a == "-vsync"));
+        assert_eq!(plain.last().unwrap(), "/out/a.mp4");
+    }
+
+    #[test]
+    fn proxies_get_a_fixed_keyframe_interval_and_faststart() {
+        assert_eq!(proxy_gop_frames(30.0, 500), 15);
+        assert_eq!(proxy_gop_frames(29.97, 1000), 30);
+        assert_eq!(proxy_gop_frames(1.0, 100), 1);
+
+        let frame = FRAME_1080P.scaled_to(960);
+        let args = video_proxy_args(Path::new("/in/a.mp4"), Path::new("/out/a.mp4"), &frame, 28, None, 12).join(" ");
+        assert!(args.contains("-g 12 -keyint_min 12 -sc_threshold 0"), "{}", args);
+        assert!(args.ends_with("-movflags +faststart /out/a.mp4"), "{}", args);
+    }
+
+    #[test]
+    fn contact_sheet_tiles_letterboxed_thumbs() {
+        let thumbs: Vec<std::path::PathBuf> = ["/t/a.jpg", "/t/b.webp", "/t/it's.JPG"].iter().map(|p| p.into()).collect();
+        let groups = group_by_extension(&thumbs);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(image_list(&groups[0]), "file '/t/a.jpg'\nduration 0.04\nfile '/t/it'\\''s.JPG'\nduration 0.04\n");
+
+        let lists: Vec<std::path::PathBuf> = ["/out/l0.txt", "/out/l1.txt"].iter().map(|p| p.into()).collect();
+        let args = contact_sheet_args(&lists, thumbs.len(), 2, (320, 180), Path::new("/out/sheet.png.tmp"));
+        assert_eq!(args.iter().filter(|a| *a == "-i").count(), 2);
+        assert_eq!(&args[1..9], &["-f", "concat", "-safe", "0", "-reinit_filter", "0", "-i", "/out/l0.txt"]);
+        let graph = &args[args.iter().position(|a| a == "-filter_complex").unwrap() + 1];
+        assert!(graph.starts_with("[0:v]scale=320:180:force_original_aspect_ratio=decrease:eval=frame,pad=320:180:"), "{}", graph);
+        assert!(graph.ends_with("[c0][c1]concat=n=2:v=1:a=0,tile=2x2[out]"), "{}", graph);
+        assert_eq!(args.last().unwrap(), "/out/sheet.png.tmp");
+    }
+
+    #[test]
+    fn audio_proxy_args_normalize_and_resample() {
+        let args = audio_proxy_args(Path::new("/in/a.wav"), Path::new("/out/a.m4a"), true);
+        let joined = args.join(" ");
+        assert!(joined.contains("-vn"));
+        assert!(joined.contains(&format!("-af {}", PROXY_LOUDNORM_FILTER)));
+        assert!(joined.contains("-c:a aac -b:a 128k -ar 48000 -ac 2"));
+        assert_eq!(args.last().unwrap(), "/out/a.m4a");
+
+        let plain = audio_proxy_args(Path::new("/in/a.wav"), Path::new("/out/a.m4a"), false);
+        assert!(!plain.iter().any(|a| a == "-af"));
+    }
+
+    #[test]
+    fn export_record_takes_produced_format_from_probe() {
+        let mut record: crate::project::model::ExportRecord = serde_json::from_value(serde_json::json!({
+            "exportId": "exp_1",
+            "status": "completed",
+            "preset": { "container": "mp4", "codec": "h264", "bitrateKbps": 0 },
+            "startMs": 0,
+            "endMs": 4000,
+            "outputUri": "exports/a.mp4",
+            "createdAt": "2026-01-01T00:00:00Z"
+        }))
+        .unwrap();
+        let probe = serde_json::json!({
+            "streams": [
+                { "codec_type": "video", "codec_name": "hevc", "width": 1280, "height": 720,
+                  "r_frame_rate": "30/1", "avg_frame_rate": "30/1" },
+                { "codec_type": "audio", "codec_name": "aac", "sample_rate": "48000", "channels": 2 }
+            ],
+            "format": { "format_name": "mov,mp4", "duration": "3.960", "bit_rate": "2500000" }
+        });
+
+        record_output_probe(&mut record, &probe, true);
+        assert_eq!((record.width, record.height), (Some(1280), Some(720)));
+        assert_eq!(record.codec.as_deref(), Some("hevc"));
+        assert_eq!(record.duration_ms, Some(3960));
+        assert_eq!(record.preset.bitrate_kbps, 2500);
+
+        let audio_only = serde_json::json!({
+            "streams": [{ "codec_type": "audio", "codec_name": "mp3", "sample_rate": "44100", "channels": 2 }],
+            "format": { "format_name": "mp3", "duration": "2.5" }
+        });
+        record_output_probe(&mut record, &audio_only, false);
+        assert_eq!((record.width, record.height), (None, None));
+        assert_eq!(record.codec.as_deref(), Some("mp3"));
+        assert_eq!(record.duration_ms, Some(2500));
+    }
+}
@@ -1,2 +1,4 @@
+pub mod download;
+pub mod export_plan;
 pub mod handlers;
 pub mod runner;
@@ -2,11 +2,18 @@ use std::sync::Arc;
 use tauri::Emitter;
 
 use crate::project::io;
-use crate::state::AppState;
+use crate::project::model::Task;
+use crate::provider::model::DEFAULT_MAX_CONCURRENT_GENERATIONS;
+use crate::state::{AppState, ProviderGate};
 use crate::task::handlers;
 
-/// Single-worker serial task runner loop.
-/// Picks the first queued task whose deps are all succeeded, runs it, repeats.
+/// Task runner loop.
+/// Picks the first queued task whose deps are met (see `deps_met`) and runs it.
+/// Nothing is picked while the queue is paused, and generations waiting on
+/// their gate are held; resuming notifies the loop.
+/// Local tasks run one at a time on this loop; remote generations are spawned
+/// so they don't block local work, throttled per provider profile (see
+/// `ProviderGate`); they stay queued until their gate lets them start.
 pub async fn task_runner_loop(state: Arc<AppState>, app_handle: tauri::AppHandle) {
     loop {
         state.task_notify.notified().await;
@@ -27,65 +34,274 @@ pub async fn task_runner_loop(state: Arc<AppState>, app_handle: tauri::AppHandle
                 }
             }
 
-            mark_running(&state, &task_id, &app_handle).await;
-
-            let result = handlers::dispatch(&kind, &task_id, &input, &state, &app_handle).await;
-
-            // Check cancel after execution
-            {
-                let mut flags = state.cancel_flags.lock().await;
-                if flags.remove(&task_id) {
-                    mark_canceled(&state, &task_id, &app_handle).await;
-                    continue;
-                }
+            if kind == "gen_video" {
+                let gate = generation_gate(&input, &state, &app_handle);
+                state.throttled.lock().unwrap_or_else(|e| e.into_inner()).insert(task_id.clone());
+                let state = state.clone();
+                let app_handle = app_handle.clone();
+                tokio::spawn(async move {
+                    let permit = match gate {
+                        Some(gate) => acquire_generation_slot(&gate, &task_id, &state, &app_handle).await,
+                        None => Some(None),
+                    };
+                    match permit {
+                        Some(permit) => {
+                            if let Some(snapshot) = start_throttled(&state, &task_id).await {
+                                let _ = app_handle.emit("task:updated", serde_json::json!({ "task": snapshot }));
+                                run_task(&state, &task_id, &kind, &input, &app_handle).await;
+                            }
+                            drop(permit);
+                        }
+                        None => {
+                            release_throttled(&state, &task_id);
+                            if state.cancel_flags.lock().await.remove(&task_id) {
+                                mark_canceled(&state, &task_id, &app_handle).await;
+                            }
+                        }
+                    }
+                    // Dependents of this task (or the next throttled one) may now be runnable
+                    state.task_notify.notify_one();
+                });
+                continue;
             }
 
-            if let Some(err) = result.error {
-                mark_failed(&state, &task_id, err, &app_handle).await;
-            } else {
-                mark_succeeded(&state, &task_id, result.output, &app_handle).await;
+            if !mark_running(&state, &task_id, &app_handle).await {
+                continue;
             }
+            run_task(&state, &task_id, &kind, &input, &app_handle).await;
+        }
+    }
+}
+
+/// Dispatch a task already marked running and record its outcome.
+async fn run_task(
+    state: &Arc<AppState>,
+    task_id: &str,
+    kind: &str,
+    input: &serde_json::Value,
+    app_handle: &tauri::AppHandle,
+) {
+    let result = handlers::dispatch(kind, task_id, input, state, app_handle).await;
+
+    // Check cancel after execution
+    {
+        let mut flags = state.cancel_flags.lock().await;
+        if flags.remove(task_id) {
+            mark_canceled(state, task_id, app_handle).await;
+            return;
+        }
+    }
+
+    if let Some(err) = result.error {
+        mark_failed(state, task_id, err, app_handle).await;
+    } else {
+        mark_succeeded(state, task_id, result.output, app_handle).await;
+    }
+
+    // Force save on state transition
+    let _ = io::force_save(state).await;
+}
+
+/// The throttle for a generation task's provider profile, using the limits
+/// from that profile. `None` if the provider can't be resolved (the handler reports that).
+fn generation_gate(
+    input: &serde_json::Value,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> Option<Arc<ProviderGate>> {
+    let provider_name = input.get("providerName")?.as_str()?;
+    let profile_name = input.get("profileName")?.as_str()?;
+    let path = crate::provider::io::providers_path(app_handle).ok()?;
+    let file = crate::provider::io::load_providers(&path).ok()?;
+    let profile = file.providers.get(provider_name)?.profiles.get(profile_name)?;
+
+    Some(state.provider_gate(
+        provider_name,
+        profile_name,
+        profile.max_concurrent.unwrap_or(DEFAULT_MAX_CONCURRENT_GENERATIONS),
+        std::time::Duration::from_millis(profile.min_submit_interval_ms.unwrap_or(0)),
+    ))
+}
+
+/// How often a throttled generation checks whether it was canceled.
+const WITHDRAW_POLL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Wait for an in-flight slot and the submit interval while the task is still
+/// `queued`. The permit is held until the generation finishes. `None` means
+/// the task was canceled (or the project closed) while waiting.
+async fn acquire_generation_slot(
+    gate: &ProviderGate,
+    task_id: &str,
+    state: &Arc<AppState>,
+    app_handle: &tauri::AppHandle,
+) -> Option<Option<tokio::sync::OwnedSemaphorePermit>> {
+    if gate.slots.available_permits() == 0 {
+        handlers::append_task_event(state, task_id, "info", "Waiting for a provider slot", app_handle).await;
+    }
+    wait_for_slot(gate, task_id, state).await
+}
+
+/// `acquire_generation_slot` without the task event. A paused queue holds
+/// the task after it gets its slot, before the submit interval is spent.
+async fn wait_for_slot(
+    gate: &ProviderGate,
+    task_id: &str,
+    state: &Arc<AppState>,
+) -> Option<Option<tokio::sync::OwnedSemaphorePermit>> {
+    let permit = tokio::select! {
+        p = gate.slots.clone().acquire_owned() => p.ok(),
+        _ = wait_until_withdrawn(state, task_id) => return None,
+    };
+    while state.is_queue_paused() {
+        let resumed = state.queue_resumed.notified();
+        tokio::pin!(resumed);
+        resumed.as_mut().enable();
+        if !state.is_queue_paused() {
+            break;
+        }
+        tokio::select! {
+            _ = resumed => {}
+            _ = wait_until_withdrawn(state, task_id) => return None,
+        }
+    }
+    tokio::select! {
+        _ = gate.wait_for_submit() => {}
+        _ = wait_until_withdrawn(state, task_id) => return None,
+    }
+    Some(permit)
+}
+
+/// Resolves once a throttled task should no longer start: it was flagged for
+/// cancel, left the `queued` state, or the app is shutting down.
+async fn wait_until_withdrawn(state: &Arc<AppState>, task_id: &str) {
+    loop {
+        if is_withdrawn(state, task_id).await {
+            return;
+        }
+        tokio::time::sleep(WITHDRAW_POLL).await;
+    }
+}
+
+async fn is_withdrawn(state: &Arc<AppState>, task_id: &str) -> bool {
+    if state.is_shutting_down() || state.cancel_flags.lock().await.contains(task_id) {
+        return true;
+    }
+    let guard = state.inner.lock().await;
+    guard
+        .as_ref()
+        .and_then(|l| l.project.tasks.iter().find(|t| t.task_id == task_id))
+        .is_none_or(|t| t.state != "queued")
+}
+
+/// How long shutdown waits for the in-flight handler to wind down.
+const SHUTDOWN_GRACE: std::time::Duration = std::time::Duration::from_secs(3);
 
-            // Force save on state transition
-            let _ = io::force_save(&state).await;
+/// Stop taking new tasks, kill running ffmpeg children, give the in-flight
+/// handler a moment to record its result, then flush the project to disk.
+pub async fn shutdown(state: &Arc<AppState>) {
+    state.shutting_down.store(true, std::sync::atomic::Ordering::SeqCst);
+    log::info!("Shutting down: {} ffmpeg child(ren) running", state.active_children());
+
+    // run_ffmpeg polls the shutdown flag, kills its child and returns an error,
+    // which lets the runner mark the task before we save.
+    let deadline = tokio::time::Instant::now() + SHUTDOWN_GRACE;
+    while tokio::time::Instant::now() < deadline {
+        if state.active_children() == 0 && !has_running_task(state).await {
+            break;
         }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+
+    if let Err(e) = io::force_save(state).await {
+        log::error!("Final save on shutdown failed: {}", e);
+    }
+    if let Some(loaded) = state.inner.lock().await.as_ref() {
+        crate::project::lock::release(&loaded.json_path);
     }
 }
 
+async fn has_running_task(state: &Arc<AppState>) -> bool {
+    let guard = state.inner.lock().await;
+    guard
+        .as_ref()
+        .map(|l| l.project.tasks.iter().any(|t| t.state == "running"))
+        .unwrap_or(false)
+}
+
 async fn pick_next_task(state: &Arc<AppState>) -> Option<(String, String, serde_json::Value)> {
+    if state.is_shutting_down() || state.is_queue_paused() {
+        return None;
+    }
     let guard = state.inner.lock().await;
-    let loaded = guard.as_ref()?;
+    let loaded = guard.as_ref().filter(|l| !l.read_only)?;
     let tasks = &loaded.project.tasks;
 
+    let throttled = state.throttled.lock().unwrap_or_else(|e| e.into_inner());
     for task in tasks {
-        if task.state != "queued" {
+        if task.state != "queued" || throttled.contains(&task.task_id) {
             continue;
         }
-        let deps_met = task.deps.iter().all(|dep_id| {
-            tasks.iter().any(|t| t.task_id == *dep_id && t.state == "succeeded")
-        });
-        if deps_met {
+        if deps_met(task, tasks) {
             return Some((task.task_id.clone(), task.kind.clone(), task.input.clone()));
         }
     }
     None
 }
 
-async fn mark_running(state: &Arc<AppState>, task_id: &str, app_handle: &tauri::AppHandle) {
-    let mut guard = state.inner.lock().await;
-    if let Some(loaded) = guard.as_mut() {
-        if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
-            task.state = "running".to_string();
-            task.updated_at = chrono::Utc::now().to_rfc3339();
-            task.append_event("info", "Task started");
-            loaded.dirty = true;
-            let snapshot = task.clone();
-            drop(guard);
+/// Kinds that only wait for their deps to finish, not to succeed: a contact
+/// sheet leaves out thumbnails that failed instead of waiting forever.
+const BEST_EFFORT_DEP_KINDS: &[&str] = &["contact_sheet"];
+
+fn deps_met(task: &Task, tasks: &[Task]) -> bool {
+    let best_effort = BEST_EFFORT_DEP_KINDS.contains(&task.kind.as_str());
+    task.deps.iter().all(|dep_id| match tasks.iter().find(|t| t.task_id == *dep_id) {
+        Some(dep) => dep.state == "succeeded" || (best_effort && dep.is_finished()),
+        None => best_effort,
+    })
+}
+
+/// Move a queued task to running. Returns false if it left the queue in the
+/// meantime (e.g. canceled), in which case it must not be started.
+async fn mark_running(state: &Arc<AppState>, task_id: &str, app_handle: &tauri::AppHandle) -> bool {
+    match claim_running(state, task_id).await {
+        Some(snapshot) => {
             let _ = app_handle.emit("task:updated", serde_json::json!({ "task": snapshot }));
+            true
         }
+        None => false,
     }
 }
 
+/// The state change behind `mark_running`; returns the updated task.
+async fn claim_running(state: &Arc<AppState>, task_id: &str) -> Option<Task> {
+    let mut guard = state.inner.lock().await;
+    let loaded = guard.as_mut()?;
+    let cap = loaded.project.project.settings.task_event_cap();
+    let task = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id && t.state == "queued")?;
+    let now = crate::clock::now_rfc3339();
+    task.state = "running".to_string();
+    task.started_at = Some(now.clone());
+    task.finished_at = None;
+    task.updated_at = now;
+    task.append_event("info", "Task started", cap);
+    let snapshot = task.clone();
+    loaded.dirty = true;
+    Some(snapshot)
+}
+
+/// Start a throttled generation that got its slot. It leaves `throttled` only
+/// once it is `running` (or no longer queued), so the runner can't pick and
+/// spawn it a second time in between.
+async fn start_throttled(state: &Arc<AppState>, task_id: &str) -> Option<Task> {
+    let snapshot = claim_running(state, task_id).await;
+    release_throttled(state, task_id);
+    snapshot
+}
+
+fn release_throttled(state: &AppState, task_id: &str) {
+    state.throttled.lock().unwrap_or_else(|e| e.into_inner()).remove(task_id);
+}
+
 async fn mark_succeeded(
     state: &Arc<AppState>,
     task_id: &str,
@@ -94,16 +310,19 @@ async fn mark_succeeded(
 ) {
     let mut guard = state.inner.lock().await;
     if let Some(loaded) = guard.as_mut() {
+        let cap = loaded.project.project.settings.task_event_cap();
         if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
+            let now = crate::clock::now_rfc3339();
             task.state = "succeeded".to_string();
-            task.updated_at = chrono::Utc::now().to_rfc3339();
+            task.finished_at = Some(now.clone());
+            task.updated_at = now;
             task.output = output;
             task.progress = Some(crate::project::model::TaskProgress {
                 phase: "done".to_string(),
                 percent: Some(100.0),
                 message: None,
             });
-            task.append_event("info", "Task succeeded");
+            task.append_event("info", "Task succeeded", cap);
             loaded.dirty = true;
             let snapshot = task.clone();
             drop(guard);
@@ -123,12 +342,15 @@ async fn mark_failed(
 ) {
     let mut guard = state.inner.lock().await;
     if let Some(loaded) = guard.as_mut() {
+        let cap = loaded.project.project.settings.task_event_cap();
         if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
             let msg = format!("Task failed: {} - {}", error.code, error.message);
+            let now = crate::clock::now_rfc3339();
             task.state = "failed".to_string();
-            task.updated_at = chrono::Utc::now().to_rfc3339();
+            task.finished_at = Some(now.clone());
+            task.updated_at = now;
             task.error = Some(error);
-            task.append_event("error", &msg);
+            task.append_event("error", &msg, cap);
             loaded.dirty = true;
             let snapshot = task.clone();
             drop(guard);
@@ -140,10 +362,13 @@ async fn mark_failed(
 async fn mark_canceled(state: &Arc<AppState>, task_id: &str, app_handle: &tauri::AppHandle) {
     let mut guard = state.inner.lock().await;
     if let Some(loaded) = guard.as_mut() {
+        let cap = loaded.project.project.settings.task_event_cap();
         if let Some(task) = loaded.project.tasks.iter_mut().find(|t| t.task_id == task_id) {
+            let now = crate::clock::now_rfc3339();
             task.state = "canceled".to_string();
-            task.updated_at = chrono::Utc::now().to_rfc3339();
-            task.append_event("warn", "Task canceled");
+            task.finished_at = Some(now.clone());
+            task.updated_at = now;
+            task.append_event("warn", "Task canceled", cap);
             loaded.dirty = true;
             let snapshot = task.clone();
             drop(guard);
@@ -151,3 +376,175 @@ async fn mark_canceled(state: &Arc<AppState>, task_id: &str, app_handle: &tauri:
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::project::model::tests::{make_empty_project, make_task};
+    use crate::project::model::ProjectFile;
+    use crate::state::LoadedProject;
+
+    fn loaded(project: ProjectFile, read_only: bool) -> LoadedProject {
+        LoadedProject {
+            project,
+            json_path: "/tmp/project.json".into(),
+            project_dir: "/tmp".into(),
+            dirty: false,
+            read_only,
+        }
+    }
+
+    #[tokio::test]
+    async fn shutdown_flushes_dirty_project_and_stops_picking() {
+        let dir = std::env::temp_dir().join(format!("cutline_shutdown_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("project.json");
+
+        let state = AppState::new();
+        *state.inner.lock().await = Some(LoadedProject {
+            json_path: json_path.clone(),
+            project_dir: dir.clone(),
+            dirty: true,
+            ..loaded(make_empty_project(), false)
+        });
+
+        shutdown(&state).await;
+
+        assert!(state.is_shutting_down());
+        assert!(!state.inner.lock().await.as_ref().unwrap().dirty);
+        assert!(json_path.exists());
+        assert!(pick_next_task(&state).await.is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn shutdown_never_writes_read_only_projects() {
+        let dir = std::env::temp_dir().join(format!("cutline_shutdown_ro_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("project.json");
+
+        let state = AppState::new();
+        *state.inner.lock().await = Some(LoadedProject {
+            json_path: json_path.clone(),
+            project_dir: dir.clone(),
+            dirty: true,
+            ..loaded(make_empty_project(), true)
+        });
+
+        shutdown(&state).await;
+
+        assert!(!json_path.exists());
+        let guard = state.inner.lock().await;
+        assert_eq!(guard.as_ref().unwrap().project.project.updated_at, make_empty_project().project.updated_at);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[tokio::test]
+    async fn paused_queue_picks_nothing_until_resumed() {
+        let state = AppState::new();
+        let mut project = make_empty_project();
+        project.tasks.push(make_task("task_thumb_1", "thumb", "queued", &[]));
+        *state.inner.lock().await = Some(loaded(project, false));
+
+        assert!(state.set_queue_paused(true));
+        assert!(!state.set_queue_paused(true));
+        assert!(pick_next_task(&state).await.is_none());
+
+        assert!(state.set_queue_paused(false));
+        let (task_id, _, _) = pick_next_task(&state).await.unwrap();
+        assert_eq!(task_id, "task_thumb_1");
+    }
+
+    #[tokio::test]
+    async fn read_only_projects_are_never_picked() {
+        let state = AppState::new();
+        let mut project = make_empty_project();
+        project.tasks.push(make_task("task_thumb_1", "thumb", "queued", &[]));
+        *state.inner.lock().await = Some(loaded(project, true));
+
+        assert!(pick_next_task(&state).await.is_none());
+        state.inner.lock().await.as_mut().unwrap().read_only = false;
+        assert!(pick_next_task(&state).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn throttled_generations_stay_queued_and_see_cancels() {
+        let state = AppState::new();
+        let mut project = make_empty_project();
+        project.tasks.push(make_task("task_gen_1", "gen_video", "queued", &[]));
+        project.tasks.push(make_task("task_gen_2", "gen_video", "queued", &[]));
+        *state.inner.lock().await = Some(loaded(project, false));
+
+        state.throttled.lock().unwrap().insert("task_gen_1".to_string());
+        let (task_id, _, _) = pick_next_task(&state).await.unwrap();
+        assert_eq!(task_id, "task_gen_2");
+
+        assert!(!is_withdrawn(&state, "task_gen_1").await);
+        state.cancel_flags.lock().await.insert("task_gen_1".to_string());
+        assert!(is_withdrawn(&state, "task_gen_1").await);
+
+        // task_cancel moves a queued task straight to canceled
+        state.inner.lock().await.as_mut().unwrap().project.tasks[1].state = "canceled".to_string();
+        assert!(is_withdrawn(&state, "task_gen_2").await);
+    }
+
+    #[tokio::test]
+    async fn paused_queue_holds_generations_that_get_a_slot() {
+        let state = AppState::new();
+        let mut project = make_empty_project();
+        project.tasks.push(make_task("task_gen_1", "gen_video", "queued", &[]));
+        *state.inner.lock().await = Some(loaded(project, false));
+
+        let gate = state.provider_gate("jimeng", "default", 1, std::time::Duration::ZERO);
+        let held = gate.slots.clone().try_acquire_owned().unwrap();
+        state.set_queue_paused(true);
+
+        let waiter = {
+            let (state, gate) = (state.clone(), gate.clone());
+            tokio::spawn(async move { wait_for_slot(&gate, "task_gen_1", &state).await })
+        };
+        drop(held);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!waiter.is_finished());
+        assert_eq!(state.inner.lock().await.as_ref().unwrap().project.tasks[0].state, "queued");
+
+        state.set_queue_paused(false);
+        let permit = tokio::time::timeout(std::time::Duration::from_secs(1), waiter).await.unwrap().unwrap();
+        assert!(matches!(permit, Some(Some(_))));
+    }
+
+    #[tokio::test]
+    async fn started_generation_is_never_picked_again() {
+        let state = AppState::new();
+        let mut project = make_empty_project();
+        project.tasks.push(make_task("task_gen_1", "gen_video", "queued", &[]));
+        project.tasks.push(make_task("task_gen_2", "gen_video", "canceled", &[]));
+        *state.inner.lock().await = Some(loaded(project, false));
+        state.throttled.lock().unwrap().extend(["task_gen_1".to_string(), "task_gen_2".to_string()]);
+
+        let snapshot = start_throttled(&state, "task_gen_1").await.unwrap();
+        assert_eq!(snapshot.state, "running");
+        assert!(pick_next_task(&state).await.is_none());
+
+        // Canceled while waiting: not started, but no longer held either
+        assert!(start_throttled(&state, "task_gen_2").await.is_none());
+        assert!(state.throttled.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn best_effort_kinds_run_once_deps_finish() {
+        let mut tasks = vec![
+            make_task("thumb_ok", "thumb", "succeeded", &[]),
+            make_task("thumb_bad", "thumb", "failed", &[]),
+            make_task("sheet", "contact_sheet", "queued", &["thumb_ok", "thumb_bad"]),
+            make_task("proxy", "proxy", "queued", &["thumb_bad"]),
+        ];
+        assert!(deps_met(&tasks[2], &tasks));
+        assert!(!deps_met(&tasks[3], &tasks));
+
+        tasks[1].state = "running".to_string();
+        assert!(!deps_met(&tasks[2], &tasks));
+    }
+}
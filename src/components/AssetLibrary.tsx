@@ -31,8 +31,8 @@ function getThumbRelativePath(asset: Asset): string | null {
 
 function getDuration(asset: Asset): number | null {
   const meta = asset.meta as unknown as Record<string, unknown>;
-  if (meta?.kind === "video") return (meta as unknown as VideoMeta).durationSec;
-  if (meta?.kind === "audio") return (meta as unknown as AudioMeta).durationSec;
+  if (meta?.kind === "video") return (meta as unknown as VideoMeta).durationMs / 1000;
+  if (meta?.kind === "audio") return (meta as unknown as AudioMeta).durationMs / 1000;
   return null;
 }
 
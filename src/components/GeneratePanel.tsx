@@ -85,17 +85,18 @@ export function GeneratePanel() {
     if (!prompt.trim() || !projectFile) return;
     setNoteSaved(false);
     try {
-      const asset = await commands.createNote(prompt.trim());
       const textTrack = projectFile.timeline.tracks.find((t) => t.type === "text");
       if (textTrack) {
         const cursorMs = useTimelineViewStore.getState().playheadMs;
-        await commands.timelineAddClip(textTrack.trackId, asset.assetId, cursorMs);
+        await commands.timelineAddPrompt(textTrack.trackId, cursorMs, prompt.trim());
+      } else {
+        await commands.createNote(prompt.trim());
       }
       setNoteSaved(true);
       refreshProject();
       setTimeout(() => setNoteSaved(false), 2000);
     } catch (e) {
-      setError(String(e));
+      setError(commands.isTaskError(e) ? e.message : String(e));
     }
   };
 
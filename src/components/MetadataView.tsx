@@ -16,9 +16,14 @@ function VideoMetaView({ meta }: { meta: VideoMeta }) {
     <>
       <MetaField label="容器" value={meta.container} />
       <MetaField label="编码" value={meta.codec} />
-      <MetaField label="时长" value={`${meta.durationSec.toFixed(2)}s`} />
+      <MetaField label="时长" value={`${(meta.durationMs / 1000).toFixed(2)}s`} />
       <MetaField label="分辨率" value={`${meta.width}×${meta.height}`} />
       <MetaField label="帧率" value={`${meta.fps} fps`} />
+      {meta.vfr && (
+        <div className="py-1 border-b border-zinc-800 text-amber-400 text-xs">
+          可变帧率（平均 {meta.avgFps} fps），导出可能音画不同步；代理已转为恒定帧率
+        </div>
+      )}
       {meta.audio && (
         <>
           <MetaField label="音频" value={meta.audio.present ? "有" : "无"} />
@@ -34,7 +39,7 @@ function AudioMetaView({ meta }: { meta: AudioMeta }) {
   return (
     <>
       <MetaField label="编码" value={meta.codec} />
-      <MetaField label="时长" value={`${meta.durationSec.toFixed(2)}s`} />
+      <MetaField label="时长" value={`${(meta.durationMs / 1000).toFixed(2)}s`} />
       <MetaField label="采样率" value={`${meta.sampleRate} Hz`} />
       <MetaField label="声道" value={meta.channels} />
     </>
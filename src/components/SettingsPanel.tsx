@@ -477,7 +477,7 @@ export function SettingsPanel({ onClose }: { onClose: () => void }) {
                       onChange={(e) =>
                         setEditConfig((c) => ({
                           ...c,
-                          test: { method: e.target.value, path: c.test?.path || "/health" },
+                          test: { ...c.test, method: e.target.value, path: c.test?.path || "/health" },
                         }))
                       }
                       className="w-full px-2 py-1.5 text-xs bg-zinc-800 border border-zinc-700 rounded text-zinc-200"
@@ -495,13 +495,55 @@ export function SettingsPanel({ onClose }: { onClose: () => void }) {
                       onChange={(e) =>
                         setEditConfig((c) => ({
                           ...c,
-                          test: { method: c.test?.method || "GET", path: e.target.value },
+                          test: { ...c.test, method: c.test?.method || "GET", path: e.target.value },
                         }))
                       }
                       className="w-full px-2 py-1.5 text-xs bg-zinc-800 border border-zinc-700 rounded text-zinc-200"
                       placeholder="/health"
                     />
                   </label>
+                  <label className="block">
+                    <span className="text-[10px] text-zinc-400 block mb-1">成功状态码</span>
+                    <input
+                      data-testid="input-test-statuses"
+                      value={editConfig.test?.success?.statuses?.join(", ") || ""}
+                      onChange={(e) => {
+                        const statuses = e.target.value
+                          .split(",")
+                          .map((v) => Number(v.trim()))
+                          .filter((v) => Number.isInteger(v) && v > 0);
+                        setEditConfig((c) => ({
+                          ...c,
+                          test: {
+                            method: c.test?.method || "GET",
+                            path: c.test?.path || "/health",
+                            success: { ...c.test?.success, statuses },
+                          },
+                        }));
+                      }}
+                      className="w-full px-2 py-1.5 text-xs bg-zinc-800 border border-zinc-700 rounded text-zinc-200"
+                      placeholder="留空为任意 2xx，如 200, 401"
+                    />
+                  </label>
+                  <label className="block">
+                    <span className="text-[10px] text-zinc-400 block mb-1">响应需包含</span>
+                    <input
+                      data-testid="input-test-body-contains"
+                      value={editConfig.test?.success?.bodyContains || ""}
+                      onChange={(e) =>
+                        setEditConfig((c) => ({
+                          ...c,
+                          test: {
+                            method: c.test?.method || "GET",
+                            path: c.test?.path || "/health",
+                            success: { ...c.test?.success, bodyContains: e.target.value || undefined },
+                          },
+                        }))
+                      }
+                      className="w-full px-2 py-1.5 text-xs bg-zinc-800 border border-zinc-700 rounded text-zinc-200"
+                      placeholder="可选"
+                    />
+                  </label>
                 </div>
 
                 {/* Profiles */}
@@ -607,6 +649,17 @@ export function SettingsPanel({ onClose }: { onClose: () => void }) {
                             />
                           </label>
                         </div>
+                        <label className="block col-span-2">
+                          <span className="text-[10px] text-zinc-500 block mb-0.5">Base URL 覆盖（可选）</span>
+                          <input
+                            value={prof.baseUrlOverride ?? ""}
+                            onChange={(e) =>
+                              updateProfile(profName, { baseUrlOverride: e.target.value || undefined })
+                            }
+                            className="w-full px-2 py-1 text-xs bg-zinc-800 border border-zinc-700 rounded text-zinc-200"
+                            placeholder="留空则使用 Provider 的 Base URL"
+                          />
+                        </label>
                       </div>
 
                       {/* Connect / Disconnect / Test */}
@@ -9,7 +9,7 @@ import {
 } from "../store/timelineViewStore";
 import { useThumbnail } from "../hooks/useThumbnail";
 import * as commands from "../services/commands";
-import type { Clip, Asset } from "../models/project";
+import type { Clip, Asset, TrackType } from "../models/project";
 
 const TRACK_HEIGHT = 56;
 const RULER_HEIGHT = 28;
@@ -480,6 +480,7 @@ export function TimelineView() {
   const scrollRef = useRef<HTMLDivElement>(null);
   const tracksAreaRef = useRef<HTMLDivElement>(null);
   const [marquee, setMarquee] = useState<MarqueeRect | null>(null);
+  const [addError, setAddError] = useState<string | null>(null);
   const marqueeOrigin = useRef<{ x: number; y: number; scrollLeft: number } | null>(null);
 
   const timeline = projectFile?.timeline;
@@ -617,16 +618,27 @@ export function TimelineView() {
     const asset = projectFile.assets.find((a) => a.assetId === selectedAssetId);
     if (!asset) return;
 
-    const targetTrackType = asset.type === "prompt" ? "text" : asset.type === "audio" ? "audio" : "video";
-    const targetTrack = projectFile.timeline.tracks.find(
-      (t) => t.type === targetTrackType
-    );
-    if (!targetTrack) return;
+    const targetTrackType: TrackType =
+      asset.type === "prompt"
+        ? "text"
+        : asset.type === "audio"
+          ? "audio"
+          : asset.type === "subtitle"
+            ? "subtitle"
+            : "video";
 
+    setAddError(null);
     try {
+      let targetTrack = projectFile.timeline.tracks.find((t) => t.type === targetTrackType);
+      // Projects start without a subtitle track; add one on first use
+      if (!targetTrack && targetTrackType === "subtitle") {
+        targetTrack = await commands.timelineAddTrack("subtitle");
+      }
+      if (!targetTrack) return;
       await commands.timelineAddClip(targetTrack.trackId, selectedAssetId, playheadMs);
     } catch (err) {
       console.error("Failed to add clip:", err);
+      setAddError(commands.isTaskError(err) ? err.message : String(err));
     }
   }, [projectFile, selectedAssetId, playheadMs]);
 
@@ -689,6 +701,12 @@ export function TimelineView() {
           + 添加到时间轴
         </button>
 
+        {addError && (
+          <span data-testid="timeline-add-error" className="text-[10px] text-red-400">
+            {addError}
+          </span>
+        )}
+
         {selCount > 0 && (
           <button
             data-testid="btn-delete-clip"
@@ -27,7 +27,7 @@ export function Toolbar({ onOpenSettings }: ToolbarProps) {
   const handleOpen = async () => {
     const file = await open({
       title: "打开项目",
-      filters: [{ name: "Project", extensions: ["json"] }],
+      filters: [{ name: "Project", extensions: ["json", "gz"] }],
     });
     if (!file) return;
     await openProject(file as string);
@@ -50,6 +50,7 @@ export function Toolbar({ onOpenSettings }: ToolbarProps) {
             "mp4", "mov", "avi", "mkv", "webm",
             "mp3", "wav", "aac", "flac",
             "png", "jpg", "jpeg", "webp", "bmp",
+            "srt", "vtt",
           ],
         },
       ],
@@ -5,6 +5,14 @@
 // ============================================================
 
 // --- 顶层 ---
+/** Tasks `openProject` failed because the app exited while they ran */
+export interface RecoverySummary {
+  crashRecoveredTaskIds: string[];
+}
+
+/** What `openProject`/`openProjectDir` return */
+export type OpenedProject = ProjectFile & { recovery: RecoverySummary };
+
 export interface ProjectFile {
   schemaVersion: string;
   project: ProjectMeta;
@@ -29,14 +37,65 @@ export interface ProjectMeta {
     audio: string;
     text: string;
   };
+  viewState?: ViewState;
+}
+
+/** Editor UI state persisted with the project. */
+export interface ViewState {
+  playheadMs: number;
+  selectedTrackId?: string;
+  zoom?: number;
 }
 
+export type ScaleMode = "fit" | "fill";
+
 export interface ProjectSettings {
   fps: number;
   resolution: { width: number; height: number };
   aspectRatio: string;
   sampleRate: number;
   generation?: GenerationSettings;
+  snapToFrame?: boolean;
+  saveDebounceMs?: number;
+  maxTaskEvents?: number;
+  /** Store project.json gzipped as project.json.gz */
+  compressOnDisk?: boolean;
+  /** Tasks `importAssets` enqueues when a call passes no options */
+  importDefaults?: ImportOptions;
+  /** How clips of another aspect fill the frame: letterbox (default) or crop */
+  scaleMode?: ScaleMode;
+  /** Length of a new clip made from an image, in ms (default 3000) */
+  stillDurationMs?: number;
+  /** Keyframe spacing of video proxies, in ms (default 500); smaller scrubs more precisely */
+  proxyKeyframeIntervalMs?: number;
+  /** Caps on tasks and clips; on by default */
+  limits?: ProjectLimits;
+}
+
+/**
+ * Soft caps that keep project.json from growing without bound. Defaults:
+ * enabled, 5000 tasks, 10000 clips, `prune`. Set `enabled: false` to opt out.
+ */
+export interface ProjectLimits {
+  enabled: boolean;
+  maxTasks: number;
+  maxClips: number;
+  /** `prune` drops the oldest finished tasks; `reject` fails with `limit_exceeded` */
+  onTaskLimit: TaskLimitAction;
+}
+
+export type TaskLimitAction = "prune" | "reject";
+
+/** Background tasks enqueued per imported asset. */
+export interface ImportOptions {
+  /** video/image; default true */
+  generateThumb: boolean;
+  /** video/audio; default true */
+  generateProxy: boolean;
+  /** audio and video with sound; default false */
+  generateWaveform: boolean;
+  /** enqueue nothing; default false */
+  probeOnly: boolean;
 }
 
 export interface GenerationSettings {
@@ -52,7 +111,7 @@ export interface ProjectPaths {
 }
 
 // --- 资产（仅元数据 + 相对路径，不含数据本体）---
-export type AssetType = "video" | "audio" | "image" | "prompt";
+export type AssetType = "video" | "audio" | "image" | "prompt" | "subtitle";
 export type AssetSource = "uploaded" | "generated" | "authored";
 export type FingerprintBasis = "file_bytes" | "content_json" | "model_output_bytes";
 
@@ -68,21 +127,32 @@ export interface Asset {
   source: AssetSource;
   fingerprint: Fingerprint;
   path: string;
-  meta: VideoMeta | AudioMeta | ImageMeta | PromptMeta;
+  meta: (VideoMeta | AudioMeta | ImageMeta | PromptMeta | SubtitleMeta) & RatingMeta;
   generation?: GenerationInfo;
   tags: string[];
   createdAt: string;
 }
 
 // --- Meta 子类型 ---
+/** Keys any asset's meta may carry for picking between variants */
+export interface RatingMeta {
+  /** 1-5 stars; absent means unrated */
+  rating?: number;
+  favorite?: boolean;
+}
+
 export interface VideoMeta {
   kind: "video";
   container: string;
   codec: string;
-  durationSec: number;
+  durationMs: number;
   width: number;
   height: number;
   fps: number;
+  /** avg_frame_rate; differs from `fps` (r_frame_rate) for VFR sources */
+  avgFps?: number;
+  /** variable frame rate; the proxy is converted to CFR at `avgFps` */
+  vfr?: boolean;
   audio?: {
     present: boolean;
     sampleRate: number;
@@ -93,7 +163,7 @@ export interface VideoMeta {
 export interface AudioMeta {
   kind: "audio";
   codec: string;
-  durationSec: number;
+  durationMs: number;
   sampleRate: number;
   channels: number;
 }
@@ -111,6 +181,14 @@ export interface PromptMeta {
   format: string;
 }
 
+export interface SubtitleMeta {
+  kind: "subtitle";
+  format: "srt" | "vtt";
+  cueCount: number;
+  /** end of the last cue */
+  durationMs: number;
+}
+
 // --- AI 生成信息 ---
 export interface GenerationInfo {
   taskId: string;
@@ -119,7 +197,7 @@ export interface GenerationInfo {
 }
 
 // --- 任务 v1 ---
-export type TaskKind = "probe" | "thumb" | "proxy" | "generate" | "export" | "capture_frame" | "gen_video";
+export type TaskKind = "probe" | "thumb" | "proxy" | "generate" | "export" | "capture_frame" | "gen_video" | "contact_sheet" | "waveform" | "remux";
 export type TaskState = "queued" | "running" | "succeeded" | "failed" | "canceled";
 
 export interface TaskProgress {
@@ -159,6 +237,8 @@ export interface Task {
   deps: string[];
   events: TaskEvent[];
   dedupeKey?: string;
+  startedAt?: string;
+  finishedAt?: string;
 }
 
 export interface TaskSummary {
@@ -170,6 +250,9 @@ export interface TaskSummary {
   progress?: TaskProgress;
   error?: TaskError;
   retries: TaskRetries;
+  startedAt: string | null;
+  finishedAt: string | null;
+  durationMs: number | null;
 }
 
 // --- 时间轴 v2 (normalized, ms integers) ---
@@ -182,7 +265,7 @@ export interface Timeline {
   durationMs: number;
 }
 
-export type TrackType = "video" | "audio" | "text";
+export type TrackType = "video" | "audio" | "text" | "subtitle";
 
 export interface Track {
   trackId: string;
@@ -199,6 +282,9 @@ export interface Clip {
   durationMs: number;
   inMs: number;
   outMs: number;
+  /** Timeline color tag: `#rgb`, `#rrggbb` or a color name. Not exported. */
+  labelColor?: string;
+  note?: string;
 }
 
 export interface Marker {
@@ -207,12 +293,14 @@ export interface Marker {
   label: string;
   promptText: string;
   createdAt: string;
+  /** Present for range markers; absent for point markers. */
+  endMs?: number;
 }
 
 // --- 导出 ---
 export interface ExportRecord {
   exportId: string;
-  status: "planned" | "running" | "completed" | "failed";
+  status: "planned" | "running" | "completed" | "failed" | "canceled";
   preset: {
     container: string;
     codec: string;
@@ -222,6 +310,16 @@ export interface ExportRecord {
   endMs: number;
   outputUri: string;
   createdAt: string;
+  trackId?: string;
+  clipCount?: number;
+  /** Set when the export was loudness-normalized */
+  loudnessTargetLufs?: number;
+  /** Probed from the finished file; absent on failed exports */
+  width?: number;
+  height?: number;
+  codec?: string;
+  durationMs?: number;
+  fileSizeBytes?: number;
 }
 
 // --- 索引 ---
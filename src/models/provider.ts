@@ -5,10 +5,21 @@ export interface ProvidersFile {
 
 export interface ProviderConfig {
   displayName: string;
+  providerType?: string;
   baseUrl: string;
   auth: AuthConfig;
   test?: TestEndpoint;
   profiles: Record<string, ProfileConfig>;
+  debugLogging?: boolean;
+  /** Overrides for request signing; unset fields use the built-in values */
+  sign?: SignConfig;
+}
+
+export interface SignConfig {
+  prefix?: string;
+  suffix?: string;
+  appVersion?: string;
+  platformCode?: string;
 }
 
 export type AuthKind = "api_key" | "session_cookie";
@@ -23,6 +34,15 @@ export interface AuthConfig {
 export interface TestEndpoint {
   method: string;
   path: string;
+  /** What a working provider answers; any 2xx when omitted */
+  success?: TestSuccess;
+}
+
+export interface TestSuccess {
+  /** Accepted statuses instead of 2xx, e.g. [200, 401] */
+  statuses?: number[];
+  /** Text the response body must contain */
+  bodyContains?: string;
 }
 
 export interface ProfileConfig {
@@ -30,6 +50,12 @@ export interface ProfileConfig {
   timeoutMs: number;
   retry: RetryConfig;
   credentialRef: string;
+  /** Max generations in flight against this provider (default 1) */
+  maxConcurrent?: number;
+  /** Minimum gap between generation submits, in ms */
+  minSubmitIntervalMs?: number;
+  /** Replaces the provider's baseUrl for this profile (staging, regional mirror) */
+  baseUrlOverride?: string;
 }
 
 export interface RetryConfig {
@@ -49,3 +75,14 @@ export interface TestResult {
   latencyMs?: number;
   error?: string;
 }
+
+export interface ProfileRef {
+  provider: string;
+  profile: string;
+}
+
+/** A credential ref used by more than one profile; its secret is shared. */
+export interface SharedCredential {
+  credentialRef: string;
+  profiles: ProfileRef[];
+}
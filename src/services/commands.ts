@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ProjectFile, Asset, Clip, Marker, TaskSummary } from "../models/project";
+import type { ProjectFile, OpenedProject, Asset, Clip, ImportOptions, Marker, ProjectLimits, ProjectSettings, TaskError, TaskKind, TaskState, TaskSummary, Track, TrackType, ViewState } from "../models/project";
 
 export async function createProject(
   dirPath: string,
@@ -8,10 +8,30 @@ export async function createProject(
   return invoke("create_project", { dirPath, name });
 }
 
+/** `readOnly` opens for inspection: no lock, no saves, edits are refused. */
 export async function openProject(
-  projectJsonPath: string
-): Promise<ProjectFile> {
-  return invoke("open_project", { projectJsonPath });
+  projectJsonPath: string,
+  force?: boolean,
+  readOnly?: boolean
+): Promise<OpenedProject> {
+  return invoke("open_project", { projectJsonPath, force, readOnly });
+}
+
+/** Open the project inside a picked folder (or its newest backup). */
+export async function openProjectDir(
+  dirPath: string,
+  force?: boolean,
+  readOnly?: boolean
+): Promise<OpenedProject> {
+  return invoke("open_project_dir", { dirPath, force, readOnly });
+}
+
+export async function reloadProject(discardUnsaved?: boolean): Promise<ProjectFile> {
+  return invoke("reload_project", { discardUnsaved });
+}
+
+export async function closeProject(): Promise<void> {
+  return invoke("close_project");
 }
 
 export async function saveProject(): Promise<void> {
@@ -22,10 +42,58 @@ export async function getProject(): Promise<ProjectFile> {
   return invoke("get_project");
 }
 
+export interface ProjectQuery {
+  text?: string;
+  assetType?: string;
+  assetTag?: string;
+  clipAssetId?: string;
+  clipTrackId?: string;
+  taskState?: string;
+  taskKind?: string;
+  markerLabel?: string;
+}
+
+export interface SearchResults {
+  assetIds: string[];
+  clipIds: string[];
+  taskIds: string[];
+  markerIds: string[];
+}
+
+export async function projectSearch(query: ProjectQuery): Promise<SearchResults> {
+  return invoke("project_search", { query });
+}
+
+export interface EntityDiff {
+  added: string[];
+  removed: string[];
+  changed: string[];
+}
+
+export interface ProjectDiff {
+  assets: EntityDiff;
+  clips: EntityDiff;
+  tracks: EntityDiff;
+  markers: EntityDiff;
+}
+
+export async function projectDiff(otherJsonPath: string): Promise<ProjectDiff> {
+  return invoke("project_diff", { otherJsonPath });
+}
+
+/** Per-file outcome of `importAssets`; one bad path doesn't abort the batch. */
+export type ImportResult = { path: string } & (
+  | { status: "imported"; asset: Asset }
+  | { status: "duplicate"; duplicateOf: string }
+  | { status: "error"; error: string }
+);
+
+/** Unset `options` fields fall back to the project's `importDefaults`. */
 export async function importAssets(
-  filePaths: string[]
-): Promise<Asset[]> {
-  return invoke("import_assets", { filePaths });
+  filePaths: string[],
+  options?: Partial<ImportOptions>
+): Promise<ImportResult[]> {
+  return invoke("import_assets", { filePaths, options });
 }
 
 export async function probeMedia(
@@ -51,8 +119,135 @@ export async function taskCancel(taskId: string): Promise<void> {
   return invoke("task_cancel", { taskId });
 }
 
-export async function taskList(): Promise<TaskSummary[]> {
-  return invoke("task_list");
+/** Cancel a task and everything downstream of it; returns the canceled ids. */
+export async function taskCancelCascade(taskId: string): Promise<string[]> {
+  return invoke("task_cancel_cascade", { taskId });
+}
+
+export interface CancelAllResult {
+  canceled: number;
+  runningKilled: number;
+  /** Canceled tasks removed from the project; 0 for `taskCancelAll` */
+  cleared: number;
+}
+
+/** Cancel every queued task and kill every running one. */
+export async function taskCancelAll(): Promise<CancelAllResult> {
+  return invoke("task_cancel_all");
+}
+
+/** `taskCancelAll`, then drop the canceled tasks from the project. */
+export async function taskClearQueue(): Promise<CancelAllResult> {
+  return invoke("task_clear_queue");
+}
+
+export interface TaskListQuery {
+  state?: TaskState;
+  kind?: TaskKind;
+  /** Insertion order when omitted */
+  sortBy?: "created" | "updated";
+  order?: "asc" | "desc";
+  limit?: number;
+  offset?: number;
+}
+
+export async function taskList(query?: TaskListQuery): Promise<TaskSummary[]> {
+  return invoke("task_list", { query });
+}
+
+export interface AssetListQuery {
+  assetType?: Asset["type"];
+  tag?: string;
+  /** Only assets rated at least this many stars */
+  minRating?: number;
+  favorite?: boolean;
+  /** Project order when omitted; unrated sorts below one star */
+  sortBy?: "rating" | "created";
+  order?: "asc" | "desc";
+  limit?: number;
+  offset?: number;
+}
+
+export async function assetList(query?: AssetListQuery): Promise<Asset[]> {
+  return invoke("asset_list", { query });
+}
+
+/** Star an asset 1-5 (0 clears); omitted or null fields are left alone. */
+export async function assetSetRating(
+  assetId: string,
+  rating?: number | null,
+  favorite?: boolean
+): Promise<Asset> {
+  return invoke("asset_set_rating", { assetId, rating, favorite });
+}
+
+/** The task's output blob (e.g. `{ assetId, clipId }`); null until it has one. */
+export async function taskOutput(taskId: string): Promise<Record<string, unknown> | null> {
+  return invoke("task_output", { taskId });
+}
+
+export interface TaskStats {
+  queued: number;
+  running: number;
+  succeeded: number;
+  failed: number;
+  canceled: number;
+  paused: boolean;
+}
+
+export async function taskStats(): Promise<TaskStats> {
+  return invoke("task_stats");
+}
+
+/** Stop starting queued tasks; running ones finish. */
+export async function queuePause(): Promise<void> {
+  return invoke("queue_pause");
+}
+
+export async function queueResume(): Promise<void> {
+  return invoke("queue_resume");
+}
+
+export async function regenerateThumb(
+  assetId: string,
+  tMs: number
+): Promise<string> {
+  return invoke("regenerate_thumb", { assetId, tMs });
+}
+
+export async function regenerateProxy(
+  assetId: string,
+  width?: number,
+  crf?: number
+): Promise<string> {
+  return invoke("regenerate_proxy", { assetId, width, crf });
+}
+
+export interface AssetPaths {
+  path: string;
+  proxyPath: string | null;
+  thumbPath: string | null;
+  /** mp4 rewrap of an mkv/avi/flv/wmv source */
+  playablePath: string | null;
+}
+
+export interface AssetUsages {
+  assetId: string;
+  clips: { clipId: string; trackId: string; startMs: number; endMs: number }[];
+  taskIds: string[];
+}
+
+export async function assetUsages(assetId: string): Promise<AssetUsages> {
+  return invoke("asset_usages", { assetId });
+}
+
+export async function assetAbsPath(assetId: string): Promise<AssetPaths> {
+  return invoke("asset_abs_path", { assetId });
+}
+
+/** Origins allowed to read media:// responses; "*" allows any. */
+export async function setMediaAllowedOrigins(origins: string[]): Promise<void> {
+  return invoke("set_media_allowed_origins", { origins });
 }
 
 export async function readFileBase64(
@@ -61,16 +256,66 @@ export async function readFileBase64(
   return invoke("read_file_base64", { relativePath });
 }
 
+export interface ArtifactCacheStats {
+  hits: number;
+  misses: number;
+  /** Reads that skipped the cache because the file was too large */
+  bypassed: number;
+  entries: number;
+  bytes: number;
+  budgetBytes: number;
+}
+
+/** Debug counters of the in-memory thumbnail/artifact cache. */
+export async function artifactCacheStats(): Promise<ArtifactCacheStats> {
+  return invoke("artifact_cache_stats");
+}
+
 // ============================================================
 // Timeline Commands
 // ============================================================
 
+/**
+ * timelineAddClip / timelineTrimClip / timelineReorderClips /
+ * timelineExtendClipTo / timelineSetClipAsset / timelineAddPrompt reject with
+ * a `TaskError` (e.g. `invalid_trim_out_le_in`, `out_of_bounds`,
+ * `type_mismatch`, `unknown_duration`) rather than a plain string.
+ */
+export function isTaskError(err: unknown): err is TaskError {
+  return typeof err === "object" && err !== null && "code" in err && "message" in err;
+}
+
+/**
+ * A timeline position other than plain ms. `percent` is a fraction (0-1) of
+ * the timeline's duration when the command runs, before the edit itself.
+ */
+export type TimelinePosition =
+  | { kind: "ms"; value: number }
+  | { kind: "percent"; value: number };
+
+function positionArgs(at: number | TimelinePosition, msKey: string) {
+  return typeof at === "number" ? { [msKey]: at } : { position: at };
+}
+
 export async function timelineAddClip(
   trackId: string,
   assetId: string,
-  startMs: number
+  startMs: number | TimelinePosition,
+  allowTypeMismatch?: boolean
 ): Promise<Clip> {
-  return invoke("timeline_add_clip", { trackId, assetId, startMs });
+  return invoke("timeline_add_clip", { trackId, assetId, ...positionArgs(startMs, "startMs"), allowTypeMismatch });
+}
+
+export type TimelineOp =
+  | { op: "add"; trackId: string; assetId: string; startMs: number; clipId?: string; allowTypeMismatch?: boolean }
+  | { op: "move"; clipId: string; newStartMs: number }
+  | { op: "trim"; clipId: string; inMs?: number; outMs?: number }
+  | { op: "remove"; clipId: string }
+  | { op: "reorder"; trackId: string; clipIds: string[] };
+
+/** Apply all ops atomically; returns the ids of clips added by the batch. */
+export async function timelineBatch(ops: TimelineOp[]): Promise<string[]> {
+  return invoke("timeline_batch", { ops });
 }
 
 export async function timelineMoveClip(
@@ -88,6 +333,27 @@ export async function timelineTrimClip(
   return invoke("timeline_trim_clip", { clipId, inMs, outMs });
 }
 
+/** Move the clip's out-point so it ends at `targetMs`; rejects with `asset_too_short` if the source can't reach. */
+export async function timelineExtendClipTo(clipId: string, targetMs: number): Promise<Clip> {
+  return invoke("timeline_extend_clip_to", { clipId, targetMs });
+}
+
+export async function timelineSetClipAsset(
+  clipId: string,
+  assetId: string
+): Promise<Clip> {
+  return invoke("timeline_set_clip_asset", { clipId, assetId });
+}
+
+/** Set (or, with undefined/blank, clear) a clip's label color and note. */
+export async function timelineSetClipMeta(
+  clipId: string,
+  labelColor?: string,
+  note?: string
+): Promise<Clip> {
+  return invoke("timeline_set_clip_meta", { clipId, labelColor, note });
+}
+
 export async function timelineRemoveClip(clipId: string): Promise<void> {
   return invoke("timeline_remove_clip", { clipId });
 }
@@ -99,31 +365,57 @@ export async function timelineReorderClips(
   return invoke("timeline_reorder_clips", { trackId, clipIds });
 }
 
+/** Append an empty track; `name` defaults to the capitalized type. */
+export async function timelineAddTrack(trackType: TrackType, name?: string): Promise<Track> {
+  return invoke("timeline_add_track", { trackType, name });
+}
+
+/** Copies the track and its clips onto a new track placed after it; a blank name becomes "<name> copy". */
+export async function timelineDuplicateTrack(trackId: string, newName: string): Promise<Track> {
+  return invoke("timeline_duplicate_track", { trackId, newName });
+}
+
+/** `trackIds` must be a permutation of the current tracks; later ones draw on top. */
+export async function timelineReorderTracks(trackIds: string[]): Promise<void> {
+  return invoke("timeline_reorder_tracks", { trackIds });
+}
+
 // ============================================================
 // Marker Commands
 // ============================================================
 
 export async function markerAdd(
-  tMs: number,
+  tMs: number | TimelinePosition,
   label?: string,
-  promptText?: string
+  promptText?: string,
+  endMs?: number
 ): Promise<Marker> {
-  return invoke("marker_add", { tMs, label, promptText });
+  return invoke("marker_add", { ...positionArgs(tMs, "tMs"), label, promptText, endMs });
 }
 
+/** Omitted fields are left unchanged; `clearEnd` makes a range marker a point again. */
 export async function markerUpdate(
   markerId: string,
   label?: string,
   promptText?: string,
-  tMs?: number
+  tMs?: number,
+  endMs?: number,
+  clearEnd?: boolean
 ): Promise<void> {
-  return invoke("marker_update", { markerId, label, promptText, tMs });
+  return invoke("marker_update", { markerId, label, promptText, tMs, endMs, clearEnd });
 }
 
 export async function markerRemove(markerId: string): Promise<void> {
   return invoke("marker_remove", { markerId });
 }
 
+export async function markersShift(
+  afterMs: number,
+  deltaMs: number
+): Promise<number> {
+  return invoke("markers_shift", { afterMs, deltaMs });
+}
+
 // ============================================================
 // Project Settings Commands
 // ============================================================
@@ -135,6 +427,40 @@ export async function updateGenerationSettings(
   return invoke("update_generation_settings", { videoProvider, videoProfile });
 }
 
+/**
+ * Replace the project settings. fps must be 1-240, resolution even and
+ * 16-8192px, aspectRatio one of 16:9, 9:16, 1:1, 4:3, 3:4, 21:9, 3:2, 2:3.
+ * `resnap` (default: settings.snapToFrame) re-snaps clips when fps changes.
+ */
+export async function updateProjectSettings(
+  settings: ProjectSettings,
+  resnap?: boolean
+): Promise<void> {
+  return invoke("update_project_settings", { settings, resnap });
+}
+
+export async function updateViewState(viewState: ViewState): Promise<void> {
+  return invoke("update_view_state", { viewState });
+}
+
+/** Omitted settings keep their current value. */
+export async function updatePersistenceSettings(
+  saveDebounceMs?: number,
+  maxTaskEvents?: number,
+  compressOnDisk?: boolean
+): Promise<void> {
+  return invoke("update_persistence_settings", { saveDebounceMs, maxTaskEvents, compressOnDisk });
+}
+
+export async function updateImportDefaults(options: ImportOptions): Promise<void> {
+  return invoke("update_import_defaults", { options });
+}
+
+/** `null` restores the default limits. */
+export async function updateProjectLimits(limits: ProjectLimits | null): Promise<void> {
+  return invoke("update_project_limits", { limits });
+}
+
 // ============================================================
 // Note / Prompt Asset Commands
 // ============================================================
@@ -147,6 +473,20 @@ export async function createNote(
   return invoke("create_note", { text, label, language });
 }
 
+export interface PromptClip {
+  asset: Asset;
+  clip: Clip;
+}
+
+export async function timelineAddPrompt(
+  trackId: string,
+  tMs: number,
+  text: string,
+  label?: string
+): Promise<PromptClip> {
+  return invoke("timeline_add_prompt", { trackId, tMs, text, label });
+}
+
 export async function updateNote(
   assetId: string,
   text: string,
@@ -164,11 +504,14 @@ export async function readNote(assetId: string): Promise<string> {
 // ============================================================
 
 export interface GenVideoParams {
-  providerName: string;
-  profileName: string;
+  /** Defaults to the project's generation settings */
+  providerName?: string;
+  profileName?: string;
   prompt: string;
+  /** Defaults to the profile's configured model */
   model?: string;
   ratio?: string;
+  /** Snapped to the nearest duration the model supports; far-off values are rejected */
   durationMs?: number;
   startMs?: number;
 }
@@ -179,8 +522,124 @@ export async function genVideoEnqueue(
   return invoke("gen_video_enqueue", { ...params });
 }
 
+/** Audio tracks default to "m4a" and accept only "mp3"/"m4a"; other tracks default to "mp4". */
+export type ExportFormat = "mp4" | "gif" | "webp" | "mp3" | "m4a";
+
+export interface ExportOptions {
+  startMs?: number;
+  endMs?: number;
+  format?: ExportFormat;
+  /** gif/webp only */
+  fps?: number;
+  /** gif/webp only */
+  width?: number;
+  /** burn: render into the picture; mux: soft mov_text stream (mp4 only) */
+  subtitles?: "burn" | "mux";
+  /** defaults to the first subtitle track */
+  subtitleTrackId?: string;
+  /** Two-pass loudness normalization of the whole mix; ignored for gif/webp */
+  loudnorm?: boolean;
+  /** Integrated loudness in LUFS (-70 to -5), default -14 */
+  targetLufs?: number;
+  /** Appended before the output path, e.g. ["-tune", "film"]; disables stream copy. No -i/-f/-map or filters (-vf/-af). */
+  extraFfmpegArgs?: string[];
+}
+
+/** `trackId` defaults to the project's draft video track. */
 export async function exportDraft(
-  trackId?: string
+  trackId?: string,
+  options: ExportOptions = {}
 ): Promise<{ taskId: string }> {
-  return invoke("export_draft", { trackId });
+  return invoke("export_draft", { trackId, ...options });
+}
+
+export interface ContactSheetOptions {
+  /** 1-20, default 6 */
+  columns?: number;
+  /** 16-1920, default 320x180 */
+  cellWidth?: number;
+  cellHeight?: number;
+}
+
+/** "filmstrip" is accepted but not supported yet: it always counts 0. */
+export type CacheKind = "thumb" | "proxy" | "waveform" | "filmstrip";
+
+/** Queue tasks for every missing thumb/proxy/waveform (all kinds when empty); counts per kind. */
+export async function rebuildCaches(kinds: CacheKind[] = []): Promise<Record<CacheKind, number>> {
+  return invoke("rebuild_caches", { kinds });
+}
+
+/** Tile all video/image thumbnails into `path` (exports dir) once the task succeeds. */
+export async function contactSheet(
+  options: ContactSheetOptions = {}
+): Promise<{ taskId: string; path: string }> {
+  return invoke("contact_sheet", { ...options });
+}
+
+export interface ExportPlanSegment {
+  clipId: string;
+  assetId: string;
+  assetType: string;
+  codec: string | null;
+  container: string | null;
+  path: string;
+  timelineStartMs: number;
+  timelineEndMs: number;
+  /** null means the whole source file is read */
+  sourceInMs: number | null;
+  sourceOutMs: number | null;
+  sourceDurationMs: number | null;
+  hasAudio: boolean;
+  /** [sampleRate, channels] */
+  audioLayout: [number, number] | null;
+  vfr: boolean;
+}
+
+export interface ExportPlan {
+  trackId: string;
+  trackType: string;
+  startMs: number;
+  endMs: number;
+  segments: ExportPlanSegment[];
+  /** Not rendered; segments are concatenated back to back */
+  gaps: { startMs: number; endMs: number }[];
+  outputDurationMs: number;
+}
+
+export async function exportPlan(
+  trackId?: string,
+  startMs?: number,
+  endMs?: number
+): Promise<ExportPlan> {
+  return invoke("export_plan", { trackId, startMs, endMs });
+}
+
+/** Export the frame visible at `tMs` as a PNG; `outputPath` must be a .png in the exports folder. Returns the written path. */
+export async function exportFrame(
+  tMs: number,
+  outputPath?: string
+): Promise<string> {
+  return invoke("export_frame", { tMs, outputPath });
+}
+
+// ============================================================
+// Diagnostics
+// ============================================================
+
+export interface SelfTestCheck {
+  name: "ffmpeg" | "ffprobe" | "workspace" | "secrets" | "thumbnail";
+  status: "pass" | "fail" | "skip";
+  detail: string;
+  elapsedMs: number;
+}
+
+export interface SelfTestReport {
+  /** False if any check failed; skipped checks don't count */
+  ok: boolean;
+  checks: SelfTestCheck[];
+}
+
+/** Check ffmpeg/ffprobe, workspace access, secret storage and thumbnailing. */
+export async function selfTest(): Promise<SelfTestReport> {
+  return invoke("self_test");
 }
@@ -1,5 +1,5 @@
 import { listen, type UnlistenFn } from "@tauri-apps/api/event";
-import type { Task } from "../models/project";
+import type { Task, TaskError, TaskEvent } from "../models/project";
 
 export function subscribeTaskUpdates(
   onUpdate: (task: Task) => void
@@ -9,6 +9,16 @@ export function subscribeTaskUpdates(
   });
 }
 
+/** One new log line per task event, emitted as it is appended. */
+export function subscribeTaskEvents(
+  onEvent: (taskId: string, event: TaskEvent) => void
+): Promise<UnlistenFn> {
+  return listen<{ taskId: string } & TaskEvent>("task:event", (event) => {
+    const { taskId, ...taskEvent } = event.payload;
+    onEvent(taskId, taskEvent);
+  });
+}
+
 export function subscribeProjectUpdates(
   onUpdate: () => void
 ): Promise<UnlistenFn> {
@@ -16,3 +26,47 @@ export function subscribeProjectUpdates(
     onUpdate();
   });
 }
+
+export function subscribeAssetUpdates(
+  onUpdate: (assetId: string, meta: Record<string, unknown>) => void
+): Promise<UnlistenFn> {
+  return listen<{ assetId: string; meta: Record<string, unknown> }>(
+    "asset:updated",
+    (event) => {
+      onUpdate(event.payload.assetId, event.payload.meta);
+    }
+  );
+}
+
+export interface ImportProgress {
+  done: number;
+  total: number;
+  /** null once the whole batch is finished */
+  currentFile: string | null;
+}
+
+export function subscribeImportProgress(
+  onProgress: (progress: ImportProgress) => void
+): Promise<UnlistenFn> {
+  return listen<ImportProgress>("import:progress", (event) => {
+    onProgress(event.payload);
+  });
+}
+
+/** A task or clip cap in `settings.limits` was hit. */
+export interface ProjectLimitEvent {
+  kind: "tasks" | "clips";
+  limit: number;
+  /** Finished task ids dropped to make room */
+  pruned?: string[];
+  /** Set when the addition was refused */
+  error?: TaskError;
+}
+
+export function subscribeProjectLimits(
+  onLimit: (event: ProjectLimitEvent) => void
+): Promise<UnlistenFn> {
+  return listen<ProjectLimitEvent>("project:limit", (event) => {
+    onLimit(event.payload);
+  });
+}
@@ -1,5 +1,5 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { ProviderConfig, ProviderSummary, TestResult } from "../models/provider";
+import type { ProfileRef, ProviderConfig, ProviderSummary, SharedCredential, TestResult } from "../models/provider";
 
 export async function providersList(): Promise<ProviderSummary[]> {
   return invoke<ProviderSummary[]>("providers_list");
@@ -9,10 +9,11 @@ export async function providersGet(name: string): Promise<ProviderConfig> {
   return invoke<ProviderConfig>("providers_get", { name });
 }
 
+/** Resolves to the credential refs this provider shares with other profiles. */
 export async function providersUpsert(
   name: string,
   config: ProviderConfig
-): Promise<void> {
+): Promise<SharedCredential[]> {
   return invoke("providers_upsert", { name, config });
 }
 
@@ -20,6 +21,11 @@ export async function providersDelete(name: string): Promise<void> {
   return invoke("providers_delete", { name });
 }
 
+/** Log this provider's request/response bodies (redacted). Off by default. */
+export async function setProviderDebugLogging(name: string, enabled: boolean): Promise<void> {
+  return invoke("set_provider_debug_logging", { name, enabled });
+}
+
 export async function secretsSet(
   credentialRef: string,
   secret: string
@@ -31,6 +37,11 @@ export async function secretsExists(credentialRef: string): Promise<boolean> {
   return invoke<boolean>("secrets_exists", { credentialRef });
 }
 
+/** Every profile whose secret is stored under `credentialRef`. */
+export async function secretsUsage(credentialRef: string): Promise<ProfileRef[]> {
+  return invoke<ProfileRef[]>("secrets_usage", { credentialRef });
+}
+
 export async function secretsDelete(credentialRef: string): Promise<void> {
   return invoke("secrets_delete", { credentialRef });
 }
@@ -41,3 +52,9 @@ export async function providersTest(
 ): Promise<TestResult> {
   return invoke<TestResult>("providers_test", { providerName, profileName });
 }
+
+export async function providersTestAll(
+  providerName: string
+): Promise<Record<string, TestResult>> {
+  return invoke<Record<string, TestResult>>("providers_test_all", { providerName });
+}
@@ -1,7 +1,7 @@
 import { create } from "zustand";
-import type { ProjectFile, Task } from "../models/project";
+import type { ProjectFile, Task, TaskEvent } from "../models/project";
 import * as commands from "../services/commands";
-import { subscribeTaskUpdates, subscribeProjectUpdates } from "../services/events";
+import { subscribeTaskUpdates, subscribeTaskEvents, subscribeProjectUpdates } from "../services/events";
 
 interface ProjectState {
   projectFile: ProjectFile | null;
@@ -11,6 +11,8 @@ interface ProjectState {
   isDirty: boolean;
   loading: boolean;
   error: string | null;
+  /** Tasks interrupted by a crash, reported when the project was opened */
+  interruptedTaskIds: string[];
 
   createProject: (dirPath: string, name: string) => Promise<void>;
   openProject: (projectJsonPath: string) => Promise<void>;
@@ -20,6 +22,7 @@ interface ProjectState {
   selectAsset: (assetId: string | null) => void;
   clearError: () => void;
   updateTask: (task: Task) => void;
+  appendTaskEvent: (taskId: string, event: TaskEvent) => void;
 }
 
 function rebuildIndexes(pf: ProjectFile): ProjectFile {
@@ -48,6 +51,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
   isDirty: false,
   loading: false,
   error: null,
+  interruptedTaskIds: [],
 
   createProject: async (dirPath, name) => {
     set({ loading: true, error: null });
@@ -61,6 +65,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
         isDirty: false,
         loading: false,
         selectedAssetId: null,
+        interruptedTaskIds: [],
       });
     } catch (e) {
       set({ error: String(e), loading: false });
@@ -70,7 +75,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
   openProject: async (projectJsonPath) => {
     set({ loading: true, error: null });
     try {
-      const pf = await commands.openProject(projectJsonPath);
+      const { recovery, ...pf } = await commands.openProject(projectJsonPath);
       const parts = projectJsonPath.replace(/\//g, "\\").split("\\");
       parts.pop();
       const projectDir = parts.join("\\");
@@ -81,6 +86,7 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
         isDirty: false,
         loading: false,
         selectedAssetId: null,
+        interruptedTaskIds: recovery.crashRecoveredTaskIds,
       });
     } catch (e) {
       set({ error: String(e), loading: false });
@@ -105,9 +111,15 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
     if (!projectFile) return;
     set({ loading: true, error: null });
     try {
-      await commands.importAssets(filePaths);
+      const results = await commands.importAssets(filePaths);
+      const failed = results.flatMap((r) => (r.status === "error" ? [`${r.path}: ${r.error}`] : []));
       const fresh = await commands.getProject();
-      set({ projectFile: fresh, isDirty: false, loading: false });
+      set({
+        projectFile: fresh,
+        isDirty: false,
+        loading: false,
+        error: failed.length > 0 ? `导入失败 (${failed.length}):\n${failed.join("\n")}` : null,
+      });
     } catch (e) {
       set({ error: String(e), loading: false });
     }
@@ -141,6 +153,16 @@ export const useProjectStore = create<ProjectState>((set, get) => ({
       projectFile: rebuildIndexes({ ...projectFile, tasks: newTasks }),
     });
   },
+
+  appendTaskEvent: (taskId: string, event: TaskEvent) => {
+    const { projectFile } = get();
+    if (!projectFile) return;
+
+    const newTasks = projectFile.tasks.map((t) =>
+      t.taskId === taskId ? { ...t, events: [...t.events, event] } : t
+    );
+    set({ projectFile: { ...projectFile, tasks: newTasks } });
+  },
 }));
 
 // Global event subscriptions (set up once)
@@ -156,6 +178,11 @@ export async function initEventSubscriptions() {
   });
   _unsubscribers.push(unsubTask);
 
+  const unsubTaskEvent = await subscribeTaskEvents((taskId, event) => {
+    useProjectStore.getState().appendTaskEvent(taskId, event);
+  });
+  _unsubscribers.push(unsubTaskEvent);
+
   const unsubProject = await subscribeProjectUpdates(() => {
     useProjectStore.getState().refreshProject();
   });
@@ -80,10 +80,10 @@ export function buildMockScript(): string {
 
   function makeMeta(type) {
     if (type === "video") {
-      return { kind: "video", container: "mp4", codec: "h264", durationSec: 1.0, width: 320, height: 240, fps: 24, audio: { present: false, sampleRate: 0, channels: 0 } };
+      return { kind: "video", container: "mp4", codec: "h264", durationMs: 1000, width: 320, height: 240, fps: 24, audio: { present: false, sampleRate: 0, channels: 0 } };
     }
     if (type === "audio") {
-      return { kind: "audio", codec: "mp3", durationSec: 1.0, sampleRate: 44100, channels: 1 };
+      return { kind: "audio", codec: "mp3", durationMs: 1000, sampleRate: 44100, channels: 1 };
     }
     return { kind: "image", format: "png", width: 4, height: 4 };
   }
@@ -171,7 +171,11 @@ export function buildMockScript(): string {
     },
     open_project: function () {
       if (!project) project = makeProject("Opened Project");
-      return project;
+      return Object.assign({}, project, { recovery: { crashRecoveredTaskIds: [] } });
+    },
+    open_project_dir: function () {
+      if (!project) project = makeProject("Opened Project");
+      return Object.assign({}, project, { recovery: { crashRecoveredTaskIds: [] } });
     },
     save_project: function () { return null; },
     get_project: function () { return project; },
@@ -192,7 +196,7 @@ export function buildMockScript(): string {
     timeline_add_clip: function (args) {
       var clipId = makeId();
       var assetObj = assets.find(function(a) { return a.assetId === args.assetId; });
-      var dur = (assetObj && assetObj.meta && assetObj.meta.durationSec) ? Math.round(assetObj.meta.durationSec * 1000) : 1000;
+      var dur = (assetObj && assetObj.meta && assetObj.meta.durationMs) ? assetObj.meta.durationMs : 1000;
       var clip = { clipId: clipId, assetId: args.assetId, trackId: args.trackId, startMs: args.startMs || 0, durationMs: dur, inMs: 0, outMs: dur };
       project.timeline.clips[clipId] = clip;
       project.indexes.clipById[clipId] = clip;